@@ -18,6 +18,8 @@ pub enum Command {
     Users(UsersArgs),
     Sessions(SessionsArgs),
     Contracts(ContractsArgs),
+    Tokens(TokensArgs),
+    Usage(UsageArgs),
 }
 
 #[derive(Args)]
@@ -44,6 +46,18 @@ pub struct ContractsArgs {
     pub command: ContractsCommand,
 }
 
+#[derive(Args)]
+pub struct TokensArgs {
+    #[command(subcommand)]
+    pub command: TokensCommand,
+}
+
+#[derive(Args)]
+pub struct UsageArgs {
+    #[command(subcommand)]
+    pub command: UsageCommand,
+}
+
 #[derive(Subcommand)]
 pub enum ApiKeysCommand {
     Create(ApiKeyCreateArgs),
@@ -72,6 +86,20 @@ pub enum ContractsCommand {
     Delete(ContractDeleteArgs),
 }
 
+#[derive(Subcommand)]
+pub enum TokensCommand {
+    Import(TokenImportArgs),
+    List(TokenListArgs),
+}
+
+#[derive(Subcommand)]
+pub enum UsageCommand {
+    Report(UsageReportArgs),
+    SetQuota(QuotaSetArgs),
+    ListQuotas(QuotaListArgs),
+    DeleteQuota(QuotaDeleteArgs),
+}
+
 #[derive(Args, Clone)]
 pub struct ApiKeyCreateArgs {
     /// Namespaces for this key (can be specified multiple times, e.g. -n ns1 -n ns2)
@@ -331,3 +359,105 @@ pub struct ContractDeleteArgs {
     #[arg(short = 'a', long)]
     pub address: String,
 }
+
+#[derive(Args, Clone)]
+pub struct TokenImportArgs {
+    /// Path to a token-list JSON file (tokenlists.org format)
+    #[arg(short = 'f', long)]
+    pub file: std::path::PathBuf,
+}
+
+#[derive(Args, Clone)]
+pub struct TokenListArgs {
+    /// Filter by chain id
+    #[arg(short = 'c', long)]
+    pub chain_id: Option<i32>,
+
+    /// Filter by symbol (case-insensitive)
+    #[arg(short = 's', long)]
+    pub symbol: Option<String>,
+
+    /// Max rows to return
+    #[arg(short = 'l', long)]
+    pub limit: Option<i64>,
+
+    /// Offset for pagination
+    #[arg(short = 'o', long)]
+    pub offset: Option<i64>,
+}
+
+#[derive(Args, Clone)]
+pub struct UsageReportArgs {
+    /// Group by api-key, public-key or session
+    #[arg(short = 'g', long, default_value = "api-key")]
+    pub group_by: String,
+
+    /// Filter by API key
+    #[arg(short = 'k', long)]
+    pub api_key: Option<String>,
+
+    /// Filter by user public key
+    #[arg(short = 'p', long)]
+    pub public_key: Option<String>,
+
+    /// Filter by session id
+    #[arg(short = 'i', long)]
+    pub session_id: Option<String>,
+
+    /// Only include usage from this UTC date on (YYYY-MM-DD)
+    #[arg(short = 's', long)]
+    pub since: Option<String>,
+
+    /// Only include usage before this UTC date (YYYY-MM-DD)
+    #[arg(short = 'u', long)]
+    pub until: Option<String>,
+
+    /// Max rows to return
+    #[arg(short = 'l', long)]
+    pub limit: Option<i64>,
+
+    /// Offset for pagination
+    #[arg(short = 'o', long)]
+    pub offset: Option<i64>,
+}
+
+#[derive(Args, Clone)]
+pub struct QuotaSetArgs {
+    /// API key the quota applies to
+    #[arg(short = 'k', long)]
+    pub api_key: String,
+
+    /// Max LLM tokens (input + output) per UTC day
+    #[arg(long)]
+    pub daily_tokens: Option<i64>,
+
+    /// Max LLM tokens (input + output) per UTC month
+    #[arg(long)]
+    pub monthly_tokens: Option<i64>,
+
+    /// Max tool invocations per UTC day
+    #[arg(long)]
+    pub daily_tool_calls: Option<i64>,
+
+    /// Max tool invocations per UTC month
+    #[arg(long)]
+    pub monthly_tool_calls: Option<i64>,
+}
+
+#[derive(Args, Clone)]
+pub struct QuotaListArgs {
+    /// Max rows to return
+    #[arg(short = 'l', long)]
+    pub limit: Option<i64>,
+
+    /// Offset for pagination
+    #[arg(short = 'o', long)]
+    pub offset: Option<i64>,
+}
+
+#[derive(Args, Clone)]
+pub struct QuotaDeleteArgs {
+    /// API key whose quota is removed
+    #[arg(short = 'k', long)]
+    pub api_key: String,
+}
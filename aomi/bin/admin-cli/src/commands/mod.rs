@@ -1,12 +1,16 @@
 mod api_keys;
 mod contracts;
 mod sessions;
+mod tokens;
+mod usage;
 mod users;
 
 use anyhow::Result;
 use sqlx::AnyPool;
 
-use crate::cli::{ApiKeysCommand, ContractsCommand, SessionsCommand, UsersCommand};
+use crate::cli::{
+    ApiKeysCommand, ContractsCommand, SessionsCommand, TokensCommand, UsageCommand, UsersCommand,
+};
 
 pub async fn handle_api_keys(cmd: ApiKeysCommand, pool: &AnyPool) -> Result<()> {
     match cmd {
@@ -39,3 +43,19 @@ pub async fn handle_contracts(cmd: ContractsCommand, pool: &AnyPool) -> Result<(
         ContractsCommand::Delete(args) => contracts::delete_contract(args, pool).await,
     }
 }
+
+pub async fn handle_tokens(cmd: TokensCommand, pool: &AnyPool) -> Result<()> {
+    match cmd {
+        TokensCommand::Import(args) => tokens::import_tokens(args, pool).await,
+        TokensCommand::List(args) => tokens::list_tokens(args, pool).await,
+    }
+}
+
+pub async fn handle_usage(cmd: UsageCommand, pool: &AnyPool) -> Result<()> {
+    match cmd {
+        UsageCommand::Report(args) => usage::usage_report(args, pool).await,
+        UsageCommand::SetQuota(args) => usage::set_quota(args, pool).await,
+        UsageCommand::ListQuotas(args) => usage::list_quotas(args, pool).await,
+        UsageCommand::DeleteQuota(args) => usage::delete_quota(args, pool).await,
+    }
+}
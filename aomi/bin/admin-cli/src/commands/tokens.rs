@@ -0,0 +1,44 @@
+use anyhow::Result;
+use serde_json::Value;
+
+use crate::cli::{TokenImportArgs, TokenListArgs};
+use crate::util::print_json;
+use aomi_tools::db::{Token, TokenList, TokenStore, TokenStoreApi};
+
+pub async fn import_tokens(args: TokenImportArgs, pool: &sqlx::AnyPool) -> Result<()> {
+    let list = TokenList::from_file(&args.file)?;
+    let store = TokenStore::new(pool.clone());
+    let imported = store.import_token_list(&list).await?;
+
+    print_json(&serde_json::json!({
+        "list": list.name,
+        "entries": list.tokens.len(),
+        "imported": imported,
+    }))?;
+    Ok(())
+}
+
+pub async fn list_tokens(args: TokenListArgs, pool: &sqlx::AnyPool) -> Result<()> {
+    let store = TokenStore::new(pool.clone());
+    let chain_id = args.chain_id.map(|value| value as u32);
+    let rows = match args.symbol {
+        Some(symbol) => store.resolve_symbol(&symbol, chain_id).await?,
+        None => store.list_tokens(chain_id, args.limit, args.offset).await?,
+    };
+
+    let json_rows = rows.iter().map(token_to_json).collect::<Vec<_>>();
+    print_json(&Value::from(json_rows))?;
+    Ok(())
+}
+
+fn token_to_json(token: &Token) -> Value {
+    serde_json::json!({
+        "chain_id": token.chain_id,
+        "address": token.address,
+        "symbol": token.symbol,
+        "name": token.name,
+        "decimals": token.decimals,
+        "source": token.source,
+        "updated_at": token.updated_at.unwrap_or_default(),
+    })
+}
@@ -0,0 +1,138 @@
+use anyhow::{Context, Result, bail};
+use chrono::{NaiveDate, Utc};
+use serde_json::Value;
+
+use crate::cli::{QuotaDeleteArgs, QuotaListArgs, QuotaSetArgs, UsageReportArgs};
+use crate::util::print_json;
+use aomi_tools::db::{
+    ApiKeyQuota, QuotaPeriod, UsageGroupBy, UsageReportParams, UsageReportRow, UsageStore,
+    UsageStoreApi, UsageTotals,
+};
+
+pub async fn usage_report(args: UsageReportArgs, pool: &sqlx::AnyPool) -> Result<()> {
+    let group_by: UsageGroupBy = args.group_by.parse()?;
+    let params = UsageReportParams {
+        api_key: args.api_key,
+        public_key: args.public_key,
+        session_id: args.session_id,
+        since: args.since.as_deref().map(parse_date).transpose()?,
+        until: args.until.as_deref().map(parse_date).transpose()?,
+        limit: args.limit,
+        offset: args.offset,
+    };
+
+    let store = UsageStore::new(pool.clone());
+    let rows = store.usage_report(group_by, params).await?;
+    let json_rows = rows
+        .iter()
+        .map(|row| report_row_to_json(group_by, row))
+        .collect::<Vec<_>>();
+
+    print_json(&Value::from(json_rows))?;
+    Ok(())
+}
+
+pub async fn set_quota(args: QuotaSetArgs, pool: &sqlx::AnyPool) -> Result<()> {
+    let limits = [
+        args.daily_tokens,
+        args.monthly_tokens,
+        args.daily_tool_calls,
+        args.monthly_tool_calls,
+    ];
+    if limits.iter().flatten().any(|limit| *limit < 0) {
+        bail!("quota limits cannot be negative");
+    }
+
+    let store = UsageStore::new(pool.clone());
+    let quota = store
+        .set_quota(ApiKeyQuota {
+            api_key: args.api_key,
+            daily_token_limit: args.daily_tokens,
+            monthly_token_limit: args.monthly_tokens,
+            daily_tool_call_limit: args.daily_tool_calls,
+            monthly_tool_call_limit: args.monthly_tool_calls,
+            updated_at: None,
+        })
+        .await
+        .context("failed to set quota")?;
+
+    print_json(&quota_to_json(&store, &quota).await?)?;
+    Ok(())
+}
+
+pub async fn list_quotas(args: QuotaListArgs, pool: &sqlx::AnyPool) -> Result<()> {
+    let store = UsageStore::new(pool.clone());
+    let quotas = store.list_quotas(args.limit, args.offset).await?;
+
+    let mut json_rows = Vec::with_capacity(quotas.len());
+    for quota in &quotas {
+        json_rows.push(quota_to_json(&store, quota).await?);
+    }
+
+    print_json(&Value::from(json_rows))?;
+    Ok(())
+}
+
+pub async fn delete_quota(args: QuotaDeleteArgs, pool: &sqlx::AnyPool) -> Result<()> {
+    let store = UsageStore::new(pool.clone());
+    let deleted = store.delete_quota(&args.api_key).await?;
+
+    print_json(&serde_json::json!({
+        "api_key": args.api_key,
+        "deleted": deleted,
+    }))?;
+    Ok(())
+}
+
+/// Parses a YYYY-MM-DD date as midnight UTC
+fn parse_date(value: &str) -> Result<i64> {
+    let date = NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .with_context(|| format!("invalid date '{value}', expected YYYY-MM-DD"))?;
+    Ok(date
+        .and_hms_opt(0, 0, 0)
+        .context("invalid date")?
+        .and_utc()
+        .timestamp())
+}
+
+/// Quota with the key's usage in the current day and month
+async fn quota_to_json(store: &UsageStore, quota: &ApiKeyQuota) -> Result<Value> {
+    let now = Utc::now().timestamp();
+    let today = store
+        .usage_totals(&quota.api_key, QuotaPeriod::Daily.start(now))
+        .await?;
+    let this_month = store
+        .usage_totals(&quota.api_key, QuotaPeriod::Monthly.start(now))
+        .await?;
+    let exceeded = quota.exceeded(&today, &this_month, now);
+
+    Ok(serde_json::json!({
+        "api_key": quota.api_key,
+        "daily_token_limit": quota.daily_token_limit,
+        "monthly_token_limit": quota.monthly_token_limit,
+        "daily_tool_call_limit": quota.daily_tool_call_limit,
+        "monthly_tool_call_limit": quota.monthly_tool_call_limit,
+        "today": totals_to_json(&today),
+        "this_month": totals_to_json(&this_month),
+        "exceeded": exceeded.map(|e| e.to_string()),
+        "updated_at": quota.updated_at,
+    }))
+}
+
+fn totals_to_json(totals: &UsageTotals) -> Value {
+    serde_json::json!({
+        "llm_requests": totals.llm_requests,
+        "input_tokens": totals.input_tokens,
+        "output_tokens": totals.output_tokens,
+        "total_tokens": totals.total_tokens(),
+        "tool_calls": totals.tool_calls,
+    })
+}
+
+fn report_row_to_json(group_by: UsageGroupBy, row: &UsageReportRow) -> Value {
+    let mut value = totals_to_json(&row.totals);
+    value[group_by.column()] = serde_json::json!(row.key);
+    value["first_at"] = row.first_at.into();
+    value["last_at"] = row.last_at.into();
+    value
+}
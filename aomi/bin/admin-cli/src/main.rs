@@ -19,6 +19,8 @@ async fn main() -> Result<()> {
         Command::Users(cmd) => commands::handle_users(cmd.command, &pool).await?,
         Command::Sessions(cmd) => commands::handle_sessions(cmd.command, &pool).await?,
         Command::Contracts(cmd) => commands::handle_contracts(cmd.command, &pool).await?,
+        Command::Tokens(cmd) => commands::handle_tokens(cmd.command, &pool).await?,
+        Command::Usage(cmd) => commands::handle_usage(cmd.command, &pool).await?,
     }
 
     Ok(())
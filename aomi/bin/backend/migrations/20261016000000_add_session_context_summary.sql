@@ -0,0 +1,3 @@
+-- Rolling conversation summary for sessions with context compaction enabled
+
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS context_summary TEXT;
@@ -0,0 +1,16 @@
+-- Token registry: symbol -> address/decimals per chain, seeded from token lists
+-- and auto-populated from stored ERC20 contracts
+
+CREATE TABLE IF NOT EXISTS tokens (
+    chain_id INTEGER NOT NULL,
+    address TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    name TEXT,
+    decimals INTEGER,
+    source TEXT NOT NULL,
+    created_at BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
+    updated_at BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
+    PRIMARY KEY (chain_id, address)
+);
+
+CREATE INDEX IF NOT EXISTS idx_tokens_symbol ON tokens(LOWER(symbol));
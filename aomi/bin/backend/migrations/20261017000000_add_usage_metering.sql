@@ -0,0 +1,28 @@
+-- Usage metering: LLM tokens and tool invocations per api_key, public_key and session,
+-- plus optional daily/monthly quotas per API key
+
+CREATE TABLE IF NOT EXISTS usage_events (
+    id BIGSERIAL PRIMARY KEY,
+    api_key TEXT,
+    public_key TEXT,
+    session_id TEXT NOT NULL,
+    namespace TEXT,
+    kind TEXT NOT NULL,
+    tool_name TEXT,
+    input_tokens BIGINT NOT NULL DEFAULT 0,
+    output_tokens BIGINT NOT NULL DEFAULT 0,
+    created_at BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT
+);
+
+CREATE INDEX IF NOT EXISTS idx_usage_events_api_key ON usage_events(api_key, created_at) WHERE api_key IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_usage_events_public_key ON usage_events(public_key, created_at) WHERE public_key IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_usage_events_session ON usage_events(session_id, created_at);
+
+CREATE TABLE IF NOT EXISTS api_key_quotas (
+    api_key TEXT PRIMARY KEY,
+    daily_token_limit BIGINT,
+    monthly_token_limit BIGINT,
+    daily_tool_call_limit BIGINT,
+    monthly_tool_call_limit BIGINT,
+    updated_at BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT
+);
@@ -0,0 +1,6 @@
+-- Forked sessions link back to the session and message they were branched from
+
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS parent_session_id TEXT REFERENCES sessions(id) ON DELETE SET NULL;
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS fork_message_index BIGINT;
+
+CREATE INDEX IF NOT EXISTS idx_sessions_parent ON sessions(parent_session_id);
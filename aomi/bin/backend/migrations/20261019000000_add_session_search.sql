@@ -0,0 +1,12 @@
+-- Keyword and semantic search over a user's past chat messages
+
+CREATE INDEX IF NOT EXISTS idx_messages_fts ON messages
+    USING GIN (to_tsvector('english', content->>'text'))
+    WHERE message_type = 'chat';
+
+-- Message embeddings (JSON float arrays), filled lazily by semantic search
+CREATE TABLE IF NOT EXISTS message_embeddings (
+    message_id BIGINT PRIMARY KEY REFERENCES messages(id) ON DELETE CASCADE,
+    embedding TEXT NOT NULL,
+    created_at BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT
+);
@@ -0,0 +1,12 @@
+-- Cached name service lookups (ENS, Basenames): forward, reverse and text records.
+-- A NULL value records a lookup that found nothing.
+
+CREATE TABLE IF NOT EXISTS name_records (
+    service TEXT NOT NULL,
+    lookup TEXT NOT NULL,
+    value TEXT,
+    expires_at BIGINT NOT NULL,
+    PRIMARY KEY (service, lookup)
+);
+
+CREATE INDEX IF NOT EXISTS idx_name_records_expires ON name_records(expires_at);
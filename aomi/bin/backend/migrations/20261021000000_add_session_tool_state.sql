@@ -0,0 +1,3 @@
+-- Tool handler snapshot (unconsumed results, resumable in-flight calls) for session rehydration
+
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS tool_state TEXT;
@@ -0,0 +1,10 @@
+-- Proxy layouts read on-chain, so contract lookups only re-probe after the cache TTL.
+-- A NULL layout records a contract that was not a proxy when checked.
+
+CREATE TABLE IF NOT EXISTS contract_proxies (
+    chain_id INTEGER NOT NULL,
+    address TEXT NOT NULL,
+    layout TEXT,
+    checked_at BIGINT NOT NULL,
+    PRIMARY KEY (chain_id, address)
+);
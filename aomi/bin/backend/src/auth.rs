@@ -2,14 +2,17 @@ use anyhow::{Context, Result};
 use axum::{
     body::Body,
     extract::State,
-    http::{Method, Request, StatusCode},
+    http::{header, Method, Request, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Json, Response},
 };
+use serde_json::json;
 use sqlx::{AnyPool, Row};
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use aomi_backend::{requires_api_key, AuthorizedKey, DEFAULT_NAMESPACE};
+use aomi_backend::{requires_api_key, AuthorizedKey, Namespace, DEFAULT_NAMESPACE};
+use aomi_tools::db::{QuotaExceeded, UsageStore, UsageStoreApi};
 
 // ============================================================================
 // Constants
@@ -25,12 +28,17 @@ pub const SESSION_ID_HEADER: &str = "X-Session-Id";
 #[derive(Clone)]
 pub struct ApiAuth {
     pool: AnyPool,
+    usage: UsageStore,
     /// Paths that require a session ID header.
     session_required_paths: Vec<String>,
     /// Path prefixes where session ID is required when followed by a non-empty suffix.
     session_required_prefixes: Vec<String>,
     /// Paths where API key is validated for non-default namespaces.
     apikey_checked_paths: Vec<String>,
+    /// Paths that consume LLM tokens and are subject to API key quotas.
+    metered_paths: Vec<String>,
+    /// Operator paths that need an API key authorized for the admin namespace.
+    admin_paths: Vec<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -43,6 +51,7 @@ pub struct SessionId(pub String);
 impl ApiAuth {
     pub async fn from_db(pool: AnyPool) -> Result<Arc<Self>> {
         Ok(Arc::new(Self {
+            usage: UsageStore::new(pool.clone()),
             pool,
             session_required_paths: vec![
                 "/api/chat".into(),
@@ -52,13 +61,16 @@ impl ApiAuth {
                 "/api/system".into(),
                 "/api/events".into(),
                 "/api/memory-mode".into(),
+                "/api/ws".into(),
             ],
             session_required_prefixes: vec![
                 "/api/sessions/".into(),
                 "/api/db/sessions/".into(),
                 "/api/control/".into(),
             ],
-            apikey_checked_paths: vec!["/api/chat".into()],
+            apikey_checked_paths: vec!["/api/chat".into(), "/api/ws".into()],
+            metered_paths: vec!["/api/chat".into(), "/api/ws".into()],
+            admin_paths: vec!["/api/metrics".into()],
         }))
     }
 
@@ -98,6 +110,18 @@ impl ApiAuth {
         )))
     }
 
+    /// Returns the quota limit the key has reached, if any.
+    /// Metering failures are logged and never block requests.
+    pub async fn check_quota(&self, key: &str) -> Option<QuotaExceeded> {
+        match self.usage.check_quota(key, unix_now()).await {
+            Ok(exceeded) => exceeded,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to check API key quota");
+                None
+            }
+        }
+    }
+
     /// Returns true if middleware should be skipped for this request.
     fn should_skip(&self, req: &Request<Body>) -> bool {
         req.method() == Method::OPTIONS || !req.uri().path().starts_with("/api/")
@@ -111,6 +135,11 @@ impl ApiAuth {
             return true;
         }
 
+        // Session search is keyed by public key, like GET /api/sessions
+        if path == "/api/sessions/search" {
+            return false;
+        }
+
         // POST /api/sessions requires session ID (frontend generates it)
         if path == "/api/sessions" && req.method() == Method::POST {
             return true;
@@ -123,6 +152,20 @@ impl ApiAuth {
         })
     }
 
+    /// Returns true if the request counts against API key quotas.
+    fn is_metered(&self, req: &Request<Body>) -> bool {
+        let path = req.uri().path();
+        // Editing a message regenerates the reply, so it costs as much as a chat turn
+        self.metered_paths.iter().any(|p| p == path)
+            || (path.starts_with("/api/sessions/") && path.ends_with("/edit"))
+    }
+
+    /// Returns true if the request is only open to admin API keys.
+    fn requires_admin(&self, req: &Request<Body>) -> bool {
+        let path = req.uri().path().trim_end_matches('/');
+        self.admin_paths.iter().any(|p| p == path)
+    }
+
     /// Returns true if the request requires API key validation.
     fn requires_api_key(&self, req: &Request<Body>) -> bool {
         let path = req.uri().path();
@@ -151,6 +194,7 @@ impl ApiAuth {
         req.headers()
             .get(SESSION_ID_HEADER)
             .and_then(|v| v.to_str().ok())
+            .or_else(|| websocket_query_param(req, "session_id"))
             .map(String::from)
     }
 
@@ -159,6 +203,7 @@ impl ApiAuth {
         req.headers()
             .get(API_KEY_HEADER)
             .and_then(|v| v.to_str().ok())
+            .or_else(|| websocket_query_param(req, "api_key"))
             .map(str::trim)
             .filter(|k| !k.is_empty())
     }
@@ -184,7 +229,16 @@ pub async fn api_key_middleware(
         req.extensions_mut().insert(SessionId(session_id));
     }
 
-    if auth.requires_api_key(&req) {
+    if auth.requires_admin(&req) {
+        let key = auth.extract_api_key(&req).ok_or(StatusCode::UNAUTHORIZED)?;
+        let authorized = auth
+            .authorize_key(key)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .filter(|authorized| authorized.allows_namespace(Namespace::Admin.as_str()))
+            .ok_or(StatusCode::FORBIDDEN)?;
+        req.extensions_mut().insert(authorized);
+    } else if auth.requires_api_key(&req) {
         let key = auth.extract_api_key(&req).ok_or(StatusCode::UNAUTHORIZED)?;
 
         let authorized = auth
@@ -201,13 +255,66 @@ pub async fn api_key_middleware(
         }
     }
 
+    if auth.is_metered(&req) {
+        let key = req
+            .extensions()
+            .get::<AuthorizedKey>()
+            .map(|authorized| authorized.key.clone());
+        if let Some(key) = key {
+            if let Some(exceeded) = auth.check_quota(&key).await {
+                return Ok(quota_exceeded_response(&exceeded));
+            }
+        }
+    }
+
     Ok(next.run(req).await)
 }
 
+/// 429 response naming the exhausted quota and when it resets.
+fn quota_exceeded_response(exceeded: &QuotaExceeded) -> Response {
+    let retry_after = exceeded.retry_after_secs(unix_now());
+    let body = json!({
+        "error": "quota_exceeded",
+        "message": format!("API key {exceeded}"),
+        "period": exceeded.period,
+        "metric": exceeded.metric,
+        "limit": exceeded.limit,
+        "used": exceeded.used,
+        "resets_at": exceeded.resets_at,
+    });
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(header::RETRY_AFTER, retry_after.to_string())],
+        Json(body),
+    )
+        .into_response()
+}
+
 // ============================================================================
 // Helpers
 // ============================================================================
 
+pub(crate) fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Query parameter fallback for WebSocket handshakes, where browsers cannot set
+/// custom headers.
+fn websocket_query_param<'a>(req: &'a Request<Body>, key: &str) -> Option<&'a str> {
+    let is_upgrade = req
+        .headers()
+        .get(header::UPGRADE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case("websocket"));
+    if !is_upgrade {
+        return None;
+    }
+    query_param(req.uri().query().unwrap_or(""), key).filter(|v| !v.is_empty())
+}
+
 /// Extract a query parameter value by key.
 fn query_param<'a>(query: &'a str, key: &str) -> Option<&'a str> {
     query.split('&').find_map(|pair| {
@@ -259,6 +366,42 @@ mod tests {
         .await
         .expect("failed to create api_keys table");
 
+        sqlx::query::<Any>(
+            r#"
+            CREATE TABLE api_key_quotas (
+                api_key TEXT PRIMARY KEY,
+                daily_token_limit INTEGER,
+                monthly_token_limit INTEGER,
+                daily_tool_call_limit INTEGER,
+                monthly_tool_call_limit INTEGER,
+                updated_at INTEGER
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("failed to create api_key_quotas table");
+
+        sqlx::query::<Any>(
+            r#"
+            CREATE TABLE usage_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                api_key TEXT,
+                public_key TEXT,
+                session_id TEXT NOT NULL,
+                namespace TEXT,
+                kind TEXT NOT NULL,
+                tool_name TEXT,
+                input_tokens INTEGER NOT NULL DEFAULT 0,
+                output_tokens INTEGER NOT NULL DEFAULT 0,
+                created_at INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("failed to create usage_events table");
+
         pool
     }
 
@@ -510,4 +653,126 @@ mod tests {
             .unwrap();
         assert_eq!(response.status(), StatusCode::OK);
     }
+
+    #[tokio::test]
+    async fn middleware_rejects_keys_over_quota() {
+        let pool = setup_pool().await;
+        insert_key(&pool, "metered-key", None, r#"["l2beat"]"#, true).await;
+
+        let usage = UsageStore::new(pool.clone());
+        usage
+            .set_quota(aomi_tools::db::ApiKeyQuota {
+                api_key: "metered-key".to_string(),
+                daily_token_limit: Some(100),
+                ..Default::default()
+            })
+            .await
+            .expect("failed to set quota");
+
+        let auth = ApiAuth::from_db(pool).await.expect("auth init failed");
+        let app = Router::new().route("/api/chat", post(chat_handler)).layer(
+            axum::middleware::from_fn_with_state(auth, api_key_middleware),
+        );
+        let chat_request = || {
+            Request::builder()
+                .method("POST")
+                .uri("/api/chat?namespace=l2beat")
+                .header(SESSION_ID_HEADER, "session-1")
+                .header(API_KEY_HEADER, "metered-key")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(chat_request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        usage
+            .record_usage(&aomi_tools::db::UsageEvent {
+                api_key: Some("metered-key".to_string()),
+                public_key: None,
+                session_id: "session-1".to_string(),
+                namespace: Some("l2beat".to_string()),
+                kind: aomi_tools::db::UsageKind::Llm,
+                tool_name: None,
+                input_tokens: 90,
+                output_tokens: 10,
+                created_at: unix_now(),
+            })
+            .await
+            .expect("failed to record usage");
+
+        let response = app.oneshot(chat_request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().contains_key(header::RETRY_AFTER));
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"], "quota_exceeded");
+        assert_eq!(body["period"], "daily");
+        assert_eq!(body["used"], 100);
+    }
+
+    #[tokio::test]
+    async fn middleware_reads_websocket_credentials_from_query() {
+        let pool = setup_pool().await;
+        insert_key(&pool, "ws-key", None, r#"["l2beat"]"#, true).await;
+
+        let auth = ApiAuth::from_db(pool).await.expect("auth init failed");
+        let app = Router::new().route("/api/ws", get(chat_handler)).layer(
+            axum::middleware::from_fn_with_state(auth, api_key_middleware),
+        );
+
+        let handshake = Request::builder()
+            .uri("/api/ws?namespace=l2beat&session_id=session-1&api_key=ws-key")
+            .header(header::UPGRADE, "websocket")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(handshake).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Query credentials are only honoured on WebSocket upgrades
+        let plain = Request::builder()
+            .uri("/api/ws?namespace=l2beat&session_id=session-1&api_key=ws-key")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(plain).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn middleware_restricts_metrics_to_admin_keys() {
+        let pool = setup_pool().await;
+        insert_key(&pool, "admin-key", None, r#"["admin"]"#, true).await;
+        insert_key(&pool, "l2beat-key", None, r#"["l2beat"]"#, true).await;
+
+        let auth = ApiAuth::from_db(pool).await.expect("auth init failed");
+        let app = Router::new()
+            .route("/api/metrics", get(|| async { StatusCode::OK }))
+            .layer(axum::middleware::from_fn_with_state(
+                auth,
+                api_key_middleware,
+            ));
+
+        let metrics = |key: Option<&str>| {
+            let mut request = Request::builder().uri("/api/metrics");
+            if let Some(key) = key {
+                request = request.header(API_KEY_HEADER, key);
+            }
+            request.body(Body::empty()).unwrap()
+        };
+
+        let response = app.clone().oneshot(metrics(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app
+            .clone()
+            .oneshot(metrics(Some("l2beat-key")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app.oneshot(metrics(Some("admin-key"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
 }
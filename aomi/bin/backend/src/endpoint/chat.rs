@@ -6,7 +6,7 @@ use axum::{
 };
 use serde_json::json;
 use std::{collections::HashMap, sync::Arc};
-use tracing::info;
+use tracing::{info, Instrument};
 
 use crate::auth::SessionId;
 use crate::endpoint::history;
@@ -70,7 +70,18 @@ pub async fn chat_endpoint(
         }
     }
 
-    if state.send_user_input(message).await.is_err() {
+    // Root span of the turn; the session's processing task and tool calls hang off it
+    let span = tracing::info_span!(
+        "chat_request",
+        session_id = session_id.as_str(),
+        namespace = requested_namespace.unwrap_or("default")
+    );
+    if state
+        .send_user_input(message)
+        .instrument(span)
+        .await
+        .is_err()
+    {
         return Err(StatusCode::INTERNAL_SERVER_ERROR);
     }
     let title = session_manager.get_session_title(&session_id);
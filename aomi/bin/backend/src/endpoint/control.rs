@@ -40,11 +40,16 @@ pub async fn get_namespaces_endpoint(
     Ok(Json(auth.current_authorization))
 }
 
-/// HTTP endpoint to get available models.
+/// HTTP endpoint to get available models (built-ins plus registry models).
 pub async fn get_model_endpoint(
     Extension(SessionId(_session_id)): Extension<SessionId>,
 ) -> Json<Vec<&'static str>> {
-    Json(AomiModel::rig_all().iter().map(|m| m.rig_label()).collect())
+    Json(
+        AomiModel::rig_available()
+            .into_iter()
+            .map(|m| m.rig_label())
+            .collect(),
+    )
 }
 
 /// HTTP endpoint to set the model selection for a session.
@@ -0,0 +1,81 @@
+use aomi_anvil::{provider_manager, ProviderManager};
+use aomi_core::{metrics, PrometheusText};
+use axum::{http::header, response::IntoResponse, routing::get, Router};
+use std::sync::Arc;
+
+use aomi_backend::SessionManager;
+
+type SharedSessionManager = Arc<SessionManager>;
+
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Tool, LLM usage, SSE and Anvil metrics in Prometheus text format.
+/// Gated to admin API keys by the auth middleware; API keys appear only as hashed ids.
+async fn metrics_endpoint() -> impl IntoResponse {
+    let mut out = PrometheusText::new();
+    metrics().render(&mut out);
+    if let Ok(manager) = provider_manager().await {
+        render_anvil_metrics(&manager, &mut out);
+    }
+    (
+        [(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)],
+        out.finish(),
+    )
+}
+
+fn render_anvil_metrics(manager: &ProviderManager, out: &mut PrometheusText) {
+    let instances = manager.list_instances();
+    let snapshots = manager.get_all_metrics();
+
+    out.family("aomi_anvil_instances", "gauge", "Managed chain instances");
+    out.sample("aomi_anvil_instances", &[], instances.len());
+
+    out.family(
+        "aomi_anvil_provider_requests_total",
+        "counter",
+        "Provider requests served per instance",
+    );
+    out.family(
+        "aomi_anvil_backend_requests_total",
+        "counter",
+        "Forked EVM backend requests served per instance",
+    );
+    out.family(
+        "aomi_anvil_last_access_age_seconds",
+        "gauge",
+        "Seconds since an instance was last used, by access kind",
+    );
+    for info in &instances {
+        let Some(snapshot) = snapshots.get(&info.id) else {
+            continue;
+        };
+        let chain_id = info.chain_id.to_string();
+        let labels = [("instance", info.name.as_str()), ("chain_id", &chain_id)];
+        out.sample(
+            "aomi_anvil_provider_requests_total",
+            &labels,
+            snapshot.provider_requests,
+        );
+        out.sample(
+            "aomi_anvil_backend_requests_total",
+            &labels,
+            snapshot.backend_requests,
+        );
+        for (kind, last_access) in [
+            ("provider", snapshot.last_provider_access),
+            ("backend", snapshot.last_backend_access),
+        ] {
+            if let Some(last_access) = last_access {
+                out.sample(
+                    "aomi_anvil_last_access_age_seconds",
+                    &[labels[0], labels[1], ("kind", kind)],
+                    last_access.elapsed().as_secs_f64(),
+                );
+            }
+        }
+    }
+}
+
+pub fn create_metrics_router() -> Router<SharedSessionManager> {
+    Router::new().route("/", get(metrics_endpoint))
+}
@@ -2,10 +2,12 @@ mod chat;
 mod control;
 mod db;
 mod history;
+mod metrics;
 mod sessions;
 mod system;
 mod types;
 mod wallet;
+mod ws;
 
 use crate::endpoint::chat::{
     chat_endpoint, health, interrupt_endpoint, state_endpoint, SharedSessionManager,
@@ -21,10 +23,296 @@ pub fn create_router(session_manager: SharedSessionManager) -> Router {
         .route("/api/chat", post(chat_endpoint))
         .route("/api/state", get(state_endpoint))
         .route("/api/interrupt", post(interrupt_endpoint))
+        .route("/api/ws", get(ws::ws_endpoint))
         .nest("/api/sessions", sessions::create_sessions_router())
         .nest("/api/control", control::create_control_router())
         .nest("/api/wallet", wallet::create_wallet_router())
         .nest("/api", system::create_system_router())
         .nest("/api/db", db::create_db_router())
+        .nest("/api/metrics", metrics::create_metrics_router())
         .with_state(session_manager)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{api_key_middleware, ApiAuth, SESSION_ID_HEADER};
+    use aomi_backend::{
+        history::HistoryBackend, AomiBackend, ChatMessage, SessionManager, SessionRecord,
+    };
+    use aomi_core::{
+        AomiModel, BuildOpts, CoreAppBuilder, MockCompletionModel, MockScript, ScriptedChunk,
+        ScriptedTurn, Selection,
+    };
+    use aomi_tools::{with_topic, AomiTool, AomiToolArgs, ToolCallCtx, ToolScheduler};
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+    };
+    use futures::StreamExt;
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+    use sqlx::any::AnyPoolOptions;
+    use std::{sync::Arc, time::Duration};
+    use tokio::sync::mpsc;
+    use tower::util::ServiceExt;
+
+    /// History backend that keeps nothing, so sessions stay in memory.
+    struct NoopHistoryBackend;
+
+    #[async_trait::async_trait]
+    impl HistoryBackend for NoopHistoryBackend {
+        async fn get_or_create_history(
+            &self,
+            _pubkey: &str,
+            _session_id: &str,
+        ) -> anyhow::Result<Option<ChatMessage>> {
+            Ok(None)
+        }
+
+        fn update_history(&self, _session_id: &str, _messages: &[ChatMessage]) {}
+
+        async fn flush_history(&self, _pubkey: &str, _session_id: &str) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn list_sessions(
+            &self,
+            _public_key: &str,
+            _limit: usize,
+        ) -> anyhow::Result<Vec<SessionRecord>> {
+            Ok(Vec::new())
+        }
+
+        async fn update_session_title(
+            &self,
+            _session_id: &str,
+            _title: &str,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[derive(Debug, Clone, Deserialize)]
+    struct LookupArgs {
+        input: String,
+    }
+
+    impl AomiToolArgs for LookupArgs {
+        fn schema() -> Value {
+            with_topic(json!({
+                "type": "object",
+                "properties": {
+                    "input": { "type": "string" }
+                },
+                "required": ["input"]
+            }))
+        }
+    }
+
+    /// Async tool, so its result reaches the client as an SSE update.
+    #[derive(Debug, Clone)]
+    struct LookupTool;
+
+    impl AomiTool for LookupTool {
+        const NAME: &'static str = "lookup_tool";
+
+        type Args = LookupArgs;
+        type Output = Value;
+        type Error = std::io::Error;
+
+        fn support_async(&self) -> bool {
+            true
+        }
+
+        fn description(&self) -> &'static str {
+            "Look the input up in the background"
+        }
+
+        fn run_async(
+            &self,
+            sender: mpsc::Sender<(eyre::Result<Value>, bool)>,
+            _ctx: ToolCallCtx,
+            args: Self::Args,
+        ) -> impl std::future::Future<Output = ()> + Send {
+            async move {
+                let _ = sender
+                    .send((Ok(json!({ "found": args.input })), false))
+                    .await;
+            }
+        }
+    }
+
+    /// The production router and auth middleware over a scripted mock model.
+    async fn mock_router(model: MockCompletionModel) -> Router {
+        let opts = BuildOpts {
+            no_docs: true,
+            skip_mcp: true,
+            no_tools: true,
+            selection: Selection {
+                rig: AomiModel::parse_rig("mock").expect("mock model is always registered"),
+                baml: AomiModel::ClaudeOpus4,
+            },
+        };
+        let scheduler = ToolScheduler::new_for_test().await.expect("scheduler");
+        let mut builder = CoreAppBuilder::new_mock("You are a test agent.", opts, model, scheduler)
+            .await
+            .expect("mock builder");
+        builder.add_tool(LookupTool).expect("register lookup tool");
+        let app = builder.build(opts, None).await.expect("build app");
+        let backend: Arc<AomiBackend> = Arc::new(app);
+        let manager = Arc::new(SessionManager::with_backend(
+            backend,
+            Arc::new(NoopHistoryBackend),
+        ));
+
+        sqlx::any::install_default_drivers();
+        let pool = AnyPoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("failed to open sqlite memory db");
+        let auth = ApiAuth::from_db(pool).await.expect("api auth");
+
+        create_router(manager).layer(axum::middleware::from_fn_with_state(
+            auth,
+            api_key_middleware,
+        ))
+    }
+
+    fn request(method: &str, uri: &str, session_id: &str) -> Request<Body> {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(SESSION_ID_HEADER, session_id)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn chat_streams_scripted_turn_and_tool_update_over_sse() {
+        let script = MockScript::new(vec![
+            ScriptedTurn::text("Looking it up. ").chunk(ScriptedChunk::ToolCall {
+                id: None,
+                name: "lookup_tool".to_string(),
+                arguments: json!({ "input": "gm", "topic": "lookup gm" }),
+            }),
+            ScriptedTurn::text("Lookup queued."),
+            ScriptedTurn::text("Lookup found gm."),
+        ]);
+        let router = mock_router(MockCompletionModel::scripted("mock", script)).await;
+        let session_id = "sse-session";
+
+        // Subscribe first, like the frontend, so the tool update is not missed
+        let updates = router
+            .clone()
+            .oneshot(request("GET", "/api/updates", session_id))
+            .await
+            .unwrap();
+        assert_eq!(updates.status(), StatusCode::OK);
+        assert_eq!(updates.headers()[header::CONTENT_TYPE], "text/event-stream");
+        let mut events = updates.into_body().into_data_stream();
+
+        let chat = router
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/api/chat?message=look%20up%20gm",
+                session_id,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(chat.status(), StatusCode::OK);
+
+        // Poll the session state until the scripted turn has streamed in
+        let mut state = Value::Null;
+        for _ in 0..200 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            let response = router
+                .clone()
+                .oneshot(request("GET", "/api/state", session_id))
+                .await
+                .unwrap();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            state = serde_json::from_slice(&body).unwrap();
+            if state["is_processing"] == false {
+                break;
+            }
+        }
+        assert_eq!(
+            state["is_processing"], false,
+            "turn did not finish: {state}"
+        );
+        let messages = state["messages"].as_array().expect("messages");
+        let text: String = messages
+            .iter()
+            .filter(|m| m["sender"] == "agent" && m["tool_result"].is_null())
+            .filter_map(|m| m["content"].as_str())
+            .collect();
+        assert!(
+            text.contains("Looking it up."),
+            "missing first turn: {text}"
+        );
+        assert!(text.contains("Lookup queued."), "missing follow-up: {text}");
+        assert!(
+            messages.iter().any(|m| m["tool_result"][0] == "lookup gm"),
+            "missing tool message: {state}"
+        );
+
+        // The async tool result is pushed over SSE by the background broadcaster
+        let data = tokio::time::timeout(Duration::from_secs(10), async {
+            let mut buffer = String::new();
+            while let Some(chunk) = events.next().await {
+                buffer.push_str(&String::from_utf8_lossy(&chunk.unwrap()));
+                if let Some(data) = buffer
+                    .split("\n\n")
+                    .flat_map(str::lines)
+                    .find_map(|line| line.strip_prefix("data:"))
+                {
+                    return data.trim().to_string();
+                }
+            }
+            panic!("update stream closed");
+        })
+        .await
+        .expect("tool update over SSE");
+        let event: Value = serde_json::from_str(&data).unwrap();
+        assert_eq!(event["type"], "tool_complete");
+        assert_eq!(event["tool_name"], "lookup_tool");
+        assert_eq!(event["result"]["found"], "gm");
+        assert_eq!(event["session_id"], session_id);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn fork_only_accepts_the_callers_own_session() {
+        let router = mock_router(MockCompletionModel::scripted(
+            "mock",
+            MockScript::new(Vec::new()),
+        ))
+        .await;
+        let fork = |path_session: &str, header_session: &str| {
+            Request::builder()
+                .method("POST")
+                .uri(format!("/api/sessions/{path_session}/fork"))
+                .header(SESSION_ID_HEADER, header_session)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(json!({ "message_index": 0 }).to_string()))
+                .unwrap()
+        };
+
+        let other = router
+            .clone()
+            .oneshot(fork("victim-session", "attacker-session"))
+            .await
+            .unwrap();
+        assert_eq!(other.status(), StatusCode::FORBIDDEN);
+
+        let own = router
+            .oneshot(fork("attacker-session", "attacker-session"))
+            .await
+            .unwrap();
+        assert_eq!(own.status(), StatusCode::NOT_FOUND);
+    }
+}
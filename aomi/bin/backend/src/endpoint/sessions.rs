@@ -1,16 +1,21 @@
 use axum::{
-    extract::{Query, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::Json,
     routing::{get, post},
     Extension, Router,
 };
+use serde::Deserialize;
 use serde_json::json;
 use std::{collections::HashMap, sync::Arc};
-use tracing::info;
+use tracing::{info, Instrument};
 
 use crate::auth::SessionId;
-use aomi_backend::{NamespaceAuth, SessionManager};
+use crate::endpoint::history;
+use aomi_backend::{
+    manager::generate_session_id, AuthorizedKey, NamespaceAuth, SessionManager, SessionResponse,
+};
+use aomi_tools::session_search::session_search;
 
 type SharedSessionManager = Arc<SessionManager>;
 
@@ -47,6 +52,48 @@ async fn session_list_endpoint(
     Ok(Json(result))
 }
 
+async fn session_search_endpoint(
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<serde_json::Value>>, StatusCode> {
+    let (Some(public_key), Some(query)) = (params.get("public_key"), params.get("q")) else {
+        return Err(StatusCode::BAD_REQUEST);
+    };
+    info!(public_key, "GET /api/sessions/search");
+
+    let limit = params
+        .get("limit")
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(10);
+    let semantic = params.get("semantic").is_some_and(|s| s == "true");
+
+    let search = session_search().ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    let hits = search
+        .search(public_key, query, limit, semantic)
+        .await
+        .map_err(|e| {
+            tracing::warn!(public_key, error = %e, "Session search failed");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let result = hits
+        .into_iter()
+        .map(|hit| {
+            json!({
+                "session_id": hit.session_id,
+                "title": hit.title,
+                "score": hit.score,
+                "message": {
+                    "id": hit.message_id,
+                    "sender": hit.sender,
+                    "text": hit.text,
+                    "timestamp": hit.timestamp,
+                },
+            })
+        })
+        .collect();
+    Ok(Json(result))
+}
+
 async fn session_create_endpoint(
     State(session_manager): State<SharedSessionManager>,
     Extension(SessionId(session_id)): Extension<SessionId>,
@@ -86,11 +133,16 @@ async fn session_get_endpoint(
         Some(m) => (m.title, m.is_archived),
         None => (String::new(), false),
     };
+    let parent = session_manager
+        .get_session_parent(&session_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(Json(json!({
         "session_id": session_id,
         "title": title,
         "is_archived": is_archived,
+        "parent": parent,
     })))
 }
 
@@ -140,12 +192,136 @@ async fn session_unarchive_endpoint(
     Ok(StatusCode::OK)
 }
 
+#[derive(Deserialize)]
+struct ForkRequest {
+    message_index: usize,
+    new_session_id: Option<String>,
+}
+
+async fn session_fork_endpoint(
+    State(session_manager): State<SharedSessionManager>,
+    api_key: Option<Extension<AuthorizedKey>>,
+    Path(path_session_id): Path<String>,
+    Extension(SessionId(session_id)): Extension<SessionId>,
+    Query(params): Query<HashMap<String, String>>,
+    Json(payload): Json<ForkRequest>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    info!(
+        session_id,
+        message_index = payload.message_index,
+        "POST /api/sessions/:id/fork"
+    );
+
+    // Only the caller's own session can be forked: the copy carries its wallet state,
+    // policy scope and tool results
+    if path_session_id != session_id {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    match session_manager.session_exists(&session_id).await {
+        Ok(true) => {}
+        Ok(false) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!(session_id, error = %e, "Failed to look up session");
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    let new_session_id = payload.new_session_id.unwrap_or_else(generate_session_id);
+    match session_manager.session_exists(&new_session_id).await {
+        Ok(false) => {}
+        Ok(true) => return Err(StatusCode::CONFLICT),
+        Err(e) => {
+            tracing::error!(new_session_id, error = %e, "Failed to look up session");
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    // A source that is only in storage is loaded like a chat request would load it
+    let mut auth = NamespaceAuth::new(
+        params.get("public_key").cloned(),
+        api_key.map(|e| e.0),
+        params.get("namespace").map(String::as_str),
+    );
+    if let Err(e) = session_manager
+        .fork_session(
+            &session_id,
+            &new_session_id,
+            payload.message_index,
+            &mut auth,
+        )
+        .await
+    {
+        tracing::warn!(session_id, error = %e, "Failed to fork session");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let title = session_manager.get_session_title(&new_session_id);
+    Ok(Json(json!({
+        "session_id": new_session_id,
+        "title": title,
+        "parent": {
+            "session_id": session_id,
+            "message_index": payload.message_index,
+        },
+    })))
+}
+
+#[derive(Deserialize)]
+struct EditRequest {
+    message_index: usize,
+    message: String,
+}
+
+async fn session_edit_endpoint(
+    State(session_manager): State<SharedSessionManager>,
+    Extension(SessionId(session_id)): Extension<SessionId>,
+    Json(payload): Json<EditRequest>,
+) -> Result<Json<SessionResponse>, StatusCode> {
+    info!(
+        session_id,
+        message_index = payload.message_index,
+        "POST /api/sessions/:id/edit"
+    );
+
+    if session_manager.get_session_if_exists(&session_id).is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let span = tracing::info_span!("chat_request", session_id = session_id.as_str());
+    let session_state = match session_manager
+        .edit_and_resend(&session_id, payload.message_index, payload.message)
+        .instrument(span)
+        .await
+    {
+        Ok(state) => state,
+        Err(e) => {
+            tracing::warn!(session_id, error = %e, "Failed to edit message");
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    };
+
+    let title = session_manager.get_session_title(&session_id);
+    let response = session_state.lock().await.format_session_response(title);
+
+    history::maybe_update_history(
+        &session_manager,
+        &session_id,
+        &response.messages,
+        response.is_processing,
+    )
+    .await;
+
+    Ok(Json(response))
+}
+
 pub fn create_sessions_router() -> Router<SharedSessionManager> {
     Router::new()
         .route(
             "/",
             get(session_list_endpoint).post(session_create_endpoint),
         )
+        .route("/search", get(session_search_endpoint))
         .route(
             "/:session_id",
             get(session_get_endpoint)
@@ -154,4 +330,6 @@ pub fn create_sessions_router() -> Router<SharedSessionManager> {
         )
         .route("/:session_id/archive", post(session_archive_endpoint))
         .route("/:session_id/unarchive", post(session_unarchive_endpoint))
+        .route("/:session_id/fork", post(session_fork_endpoint))
+        .route("/:session_id/edit", post(session_edit_endpoint))
 }
@@ -1,7 +1,9 @@
 use std::sync::Arc;
 
-use aomi_backend::SessionManager;
+use aomi_backend::{types::UserState, SessionManager};
+use aomi_tools::names::name_service;
 use axum::{
+    extract::State,
     http::{HeaderMap, StatusCode},
     routing::post,
     Extension, Json, Router,
@@ -26,6 +28,8 @@ pub struct WalletBindResponse {
     success: bool,
     wallet_address: String,
     session_key: String,
+    /// Primary ENS/Basenames name of the wallet, if it has one
+    ens_name: Option<String>,
 }
 
 fn err(status: StatusCode, message: &str) -> (StatusCode, Json<Value>) {
@@ -40,7 +44,20 @@ fn is_valid_evm_address(address: &str) -> bool {
             .all(|b| b.is_ascii_hexdigit())
 }
 
+/// Primary name of `wallet_address`; lookup failures only cost the name.
+async fn lookup_ens_name(wallet_address: &str) -> Option<String> {
+    let address = wallet_address.parse().ok()?;
+    match name_service().lookup_address(address).await {
+        Ok(resolved) => resolved.map(|resolved| resolved.name),
+        Err(e) => {
+            warn!(wallet_address, error = %e, "Name lookup for bound wallet failed");
+            None
+        }
+    }
+}
+
 pub async fn bind_wallet_endpoint(
+    State(session_manager): State<SharedSessionManager>,
     Extension(pool): Extension<AnyPool>,
     headers: HeaderMap,
     Json(payload): Json<WalletBindRequest>,
@@ -156,6 +173,22 @@ pub async fn bind_wallet_endpoint(
     .await
     .map_err(|e| err(StatusCode::INTERNAL_SERVER_ERROR, &format!("DB error: {}", e)))?;
 
+    let ens_name = lookup_ens_name(&wallet_address).await;
+
+    // A live session picks up the wallet without waiting for its next message
+    if let Some(session) = session_manager.get_session_if_exists(&session_key) {
+        let mut state = session.lock().await;
+        let chain_id = state.user_state().await.chain_id;
+        state
+            .sync_user_state(UserState {
+                address: Some(wallet_address.clone()),
+                chain_id,
+                is_connected: true,
+                ens_name: ens_name.clone(),
+            })
+            .await;
+    }
+
     if platform.eq_ignore_ascii_case("telegram") && wallet_changed {
         if let Ok(bot_token) = std::env::var("TELEGRAM_BOT_TOKEN") {
             if !bot_token.trim().is_empty() {
@@ -167,7 +200,10 @@ pub async fn bind_wallet_endpoint(
                     ))
                     .json(&json!({
                         "chat_id": platform_user_id,
-                        "text": format!("✅ Wallet connected: {}", wallet_address),
+                        "text": match &ens_name {
+                            Some(name) => format!("✅ Wallet connected: {} ({})", name, wallet_address),
+                            None => format!("✅ Wallet connected: {}", wallet_address),
+                        },
                     }))
                     .send()
                     .await;
@@ -194,6 +230,7 @@ pub async fn bind_wallet_endpoint(
         success: true,
         wallet_address,
         session_key,
+        ens_name,
     }))
 }
 
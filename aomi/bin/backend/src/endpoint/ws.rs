@@ -0,0 +1,462 @@
+//! WebSocket transport for a session.
+//!
+//! One socket replaces `/api/state` polling and the `/api/updates` SSE stream: the server
+//! pushes message deltas, system events and status changes, and the client sends user
+//! input, interrupts and wallet transaction responses on the same connection.
+//!
+//! System events carry their `SystemEventQueue` sequence number. Clients reconnect with
+//! `?last_seq=<n>` to replay every event after the last one they saw.
+//!
+//! Every `user_input` is checked against the API key quota and charged to the rate limit
+//! buckets of the handshake, like a `POST /api/chat`.
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    response::Response,
+    Extension,
+};
+use futures::{Sink, SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{collections::HashMap, sync::Arc, time::Duration, time::Instant};
+use tracing::{info, Instrument};
+
+use aomi_backend::{AuthorizedKey, ChatMessage, NamespaceAuth, SessionManager, UserState};
+use aomi_core::SystemEvent;
+
+use crate::auth::{unix_now, ApiAuth, SessionId};
+use crate::endpoint::history;
+use crate::rate_limit::{RateLimitIdentity, RateLimiter, RouteGroup};
+
+type SharedSessionManager = Arc<SessionManager>;
+
+/// How often session state is checked for changes to push.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Messages sent by the client.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    /// Same as `POST /api/chat`
+    UserInput {
+        message: String,
+        #[serde(default)]
+        user_state: Option<UserState>,
+    },
+    /// Same as `POST /api/interrupt`
+    Interrupt,
+    /// Wallet result for a `wallet_tx_request`, same as posting it to `/api/system`
+    WalletTxResponse {
+        #[serde(flatten)]
+        payload: Map<String, Value>,
+    },
+}
+
+/// Messages pushed by the server.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage {
+    /// First message on every connection
+    Connected { session_id: String, next_seq: usize },
+    /// Messages from index `start` onward; the client replaces its tail with them
+    Messages {
+        start: usize,
+        messages: Vec<ChatMessage>,
+    },
+    /// A system event with its queue sequence number
+    Event { seq: usize, event: SystemEvent },
+    /// Title or processing status changed
+    Status {
+        title: Option<String>,
+        is_processing: bool,
+    },
+    Error {
+        message: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        retry_after: Option<u64>,
+    },
+}
+
+impl ServerMessage {
+    fn error(message: impl Into<String>) -> Self {
+        Self::Error {
+            message: message.into(),
+            retry_after: None,
+        }
+    }
+}
+
+pub async fn ws_endpoint(
+    State(session_manager): State<SharedSessionManager>,
+    api_key: Option<Extension<AuthorizedKey>>,
+    api_auth: Option<Extension<Arc<ApiAuth>>>,
+    rate_limiter: Option<Extension<Arc<RateLimiter>>>,
+    rate_identity: Option<Extension<RateLimitIdentity>>,
+    Extension(SessionId(session_id)): Extension<SessionId>,
+    Query(params): Query<HashMap<String, String>>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    info!(session_id, "GET /api/ws");
+
+    let last_seq = params.get("last_seq").and_then(|s| s.parse::<usize>().ok());
+    let connection = WsConnection {
+        session_manager,
+        session_id,
+        api_key: api_key.map(|e| e.0),
+        api_auth: api_auth.map(|e| e.0),
+        rate_limit: rate_limiter
+            .zip(rate_identity)
+            .map(|(limiter, identity)| (limiter.0, identity.0)),
+        namespace: params.get("namespace").cloned(),
+        public_key: params.get("public_key").cloned(),
+        sent_messages: Vec::new(),
+        next_seq: None,
+        title: None,
+        is_processing: false,
+    };
+
+    ws.on_upgrade(move |socket| connection.run(socket, last_seq))
+}
+
+struct WsConnection {
+    session_manager: SharedSessionManager,
+    session_id: String,
+    api_key: Option<AuthorizedKey>,
+    api_auth: Option<Arc<ApiAuth>>,
+    /// Limiter and the buckets the handshake was charged to
+    rate_limit: Option<(Arc<RateLimiter>, RateLimitIdentity)>,
+    namespace: Option<String>,
+    public_key: Option<String>,
+    /// Messages as last pushed to the client
+    sent_messages: Vec<ChatMessage>,
+    /// Sequence number of the next event to push, `None` until the session exists
+    next_seq: Option<usize>,
+    title: Option<String>,
+    is_processing: bool,
+}
+
+impl WsConnection {
+    async fn run(mut self, socket: WebSocket, last_seq: Option<usize>) {
+        let (mut sender, mut receiver) = socket.split();
+
+        // Resume after the last event the client saw, otherwise only push new events
+        self.next_seq = match last_seq {
+            Some(seq) => Some(seq_after(seq)),
+            None => match self.session_manager.get_session_if_exists(&self.session_id) {
+                Some(session_state) => Some(session_state.lock().await.next_event_seq()),
+                None => None,
+            },
+        };
+        let connected = ServerMessage::Connected {
+            session_id: self.session_id.clone(),
+            next_seq: self.next_seq.unwrap_or(0),
+        };
+        if send(&mut sender, &connected).await.is_err() {
+            return;
+        }
+
+        let mut poll = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            tokio::select! {
+                incoming = receiver.next() => {
+                    let reply = match incoming {
+                        Some(Ok(Message::Text(text))) => self.handle_client_message(&text).await,
+                        Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                        // Ping/pong is answered by axum; binary frames are not part of the protocol
+                        Some(Ok(_)) => None,
+                    };
+                    if let Some(reply) = reply {
+                        if send(&mut sender, &reply).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+                _ = poll.tick() => {
+                    let mut closed = false;
+                    for update in self.poll_updates().await {
+                        if send(&mut sender, &update).await.is_err() {
+                            closed = true;
+                            break;
+                        }
+                    }
+                    if closed {
+                        break;
+                    }
+                }
+            }
+        }
+
+        info!(session_id = self.session_id, "WebSocket closed");
+    }
+
+    /// Collect everything that changed since the last poll.
+    async fn poll_updates(&mut self) -> Vec<ServerMessage> {
+        let Some(session_state) = self.session_manager.get_session_if_exists(&self.session_id)
+        else {
+            return Vec::new();
+        };
+
+        let (messages, is_processing, events) = {
+            let mut state = session_state.lock().await;
+            state.sync_state().await;
+            // The queue restarts when a session is replaced; replay it from the start
+            let next_seq = match self.next_seq {
+                Some(seq) if seq <= state.next_event_seq() => seq,
+                _ => 0,
+            };
+            (
+                state.messages.clone(),
+                state.is_processing,
+                state.events_since(next_seq),
+            )
+        };
+        let title = self.session_manager.get_session_title(&self.session_id);
+
+        let mut updates = Vec::new();
+        if let Some(start) = first_changed(&self.sent_messages, &messages) {
+            updates.push(ServerMessage::Messages {
+                start,
+                messages: messages[start..].to_vec(),
+            });
+            history::maybe_update_history(
+                &self.session_manager,
+                &self.session_id,
+                &messages,
+                is_processing,
+            )
+            .await;
+            self.sent_messages = messages;
+        }
+        for (seq, event) in events {
+            self.next_seq = Some(seq + 1);
+            updates.push(ServerMessage::Event { seq, event });
+        }
+        if self.next_seq.is_none() {
+            self.next_seq = Some(0);
+        }
+        if title != self.title || is_processing != self.is_processing {
+            self.title = title.clone();
+            self.is_processing = is_processing;
+            updates.push(ServerMessage::Status {
+                title,
+                is_processing,
+            });
+        }
+        updates
+    }
+
+    async fn handle_client_message(&mut self, text: &str) -> Option<ServerMessage> {
+        let message = match serde_json::from_str::<ClientMessage>(text) {
+            Ok(message) => message,
+            Err(e) => return Some(ServerMessage::error(format!("Invalid message: {e}"))),
+        };
+
+        match message {
+            ClientMessage::UserInput {
+                message,
+                user_state,
+            } => self.send_user_input(message, user_state).await,
+            ClientMessage::Interrupt => {
+                info!(session_id = self.session_id, "WS interrupt");
+                let session_state = self
+                    .session_manager
+                    .get_session_if_exists(&self.session_id)?;
+                let mut state = session_state.lock().await;
+                state
+                    .interrupt_processing()
+                    .await
+                    .err()
+                    .map(|e| ServerMessage::error(e.to_string()))
+            }
+            ClientMessage::WalletTxResponse { mut payload } => {
+                info!(session_id = self.session_id, "WS wallet_tx_response");
+                let Some(session_state) =
+                    self.session_manager.get_session_if_exists(&self.session_id)
+                else {
+                    return Some(ServerMessage::error("Session not found"));
+                };
+                payload.insert("type".into(), "wallet_tx_response".into());
+                let mut state = session_state.lock().await;
+                state
+                    .send_ui_event(Value::Object(payload).to_string())
+                    .await
+                    .err()
+                    .map(|e| ServerMessage::error(e.to_string()))
+            }
+        }
+    }
+
+    async fn send_user_input(
+        &mut self,
+        message: String,
+        user_state: Option<UserState>,
+    ) -> Option<ServerMessage> {
+        let namespace = self.namespace.as_deref();
+        info!(
+            session_id = self.session_id,
+            namespace = namespace.unwrap_or("default"),
+            "WS user_input"
+        );
+
+        // Each message costs as much as a chat request: the key's quota applies, and it
+        // draws from the chat buckets
+        if let (Some(api_auth), Some(key)) = (&self.api_auth, &self.api_key) {
+            if let Some(exceeded) = api_auth.check_quota(&key.key).await {
+                return Some(ServerMessage::Error {
+                    message: format!("API key {exceeded}"),
+                    retry_after: Some(exceeded.retry_after_secs(unix_now()) as u64),
+                });
+            }
+        }
+        if let Some((limiter, identity)) = &self.rate_limit {
+            let limited = limiter.check_all(
+                RouteGroup::Chat,
+                &identity.namespace,
+                &identity.clients,
+                Instant::now(),
+            );
+            if let Err(wait) = limited {
+                let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+                return Some(ServerMessage::Error {
+                    message: format!("Too many chat requests, retry in {retry_after}s"),
+                    retry_after: Some(retry_after),
+                });
+            }
+        }
+
+        let mut auth = NamespaceAuth::new(self.public_key.clone(), self.api_key.clone(), namespace);
+        let session_state = match self
+            .session_manager
+            .get_or_create_session(&self.session_id, &mut auth, None)
+            .await
+        {
+            Ok(state) => state,
+            Err(e) => {
+                tracing::warn!(session_id = self.session_id, error = %e, "Failed to get or create session");
+                return Some(ServerMessage::error(e.to_string()));
+            }
+        };
+
+        let mut state = session_state.lock().await;
+        if let Some(user_state) = user_state {
+            state.sync_user_state(user_state).await;
+        }
+
+        let span = tracing::info_span!(
+            "chat_request",
+            session_id = self.session_id.as_str(),
+            namespace = namespace.unwrap_or("default")
+        );
+        state
+            .send_user_input(message)
+            .instrument(span)
+            .await
+            .err()
+            .map(|e| ServerMessage::error(e.to_string()))
+    }
+}
+
+async fn send<S>(sender: &mut S, message: &ServerMessage) -> Result<(), axum::Error>
+where
+    S: Sink<Message, Error = axum::Error> + Unpin,
+{
+    let text = serde_json::to_string(message).map_err(axum::Error::new)?;
+    sender.send(Message::Text(text)).await
+}
+
+/// Sequence number to resume from after `last_seq`; an out-of-range value replays from 0.
+fn seq_after(last_seq: usize) -> usize {
+    last_seq.checked_add(1).unwrap_or(0)
+}
+
+/// Index of the first message that differs, `None` if nothing changed.
+fn first_changed(sent: &[ChatMessage], current: &[ChatMessage]) -> Option<usize> {
+    let common = sent.iter().zip(current).take_while(|(a, b)| a == b).count();
+    if common == sent.len() && common == current.len() {
+        None
+    } else {
+        Some(common)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aomi_backend::MessageSender;
+
+    fn message(content: &str, is_streaming: bool) -> ChatMessage {
+        ChatMessage {
+            sender: MessageSender::Assistant,
+            content: content.to_string(),
+            tool_result: None,
+            timestamp: "00:00:00 UTC".to_string(),
+            is_streaming,
+        }
+    }
+
+    #[test]
+    fn first_changed_finds_delta_start() {
+        let sent = vec![message("hi", false), message("Hel", true)];
+
+        assert_eq!(first_changed(&sent, &sent), None);
+        assert_eq!(first_changed(&[], &sent), Some(0));
+
+        let streamed = vec![message("hi", false), message("Hello", true)];
+        assert_eq!(first_changed(&sent, &streamed), Some(1));
+
+        let appended = vec![sent[0].clone(), sent[1].clone(), message("tool", false)];
+        assert_eq!(first_changed(&sent, &appended), Some(2));
+
+        // Truncated history: client drops everything from index 1
+        assert_eq!(first_changed(&sent, &sent[..1]), Some(1));
+    }
+
+    #[test]
+    fn resume_seq_does_not_overflow() {
+        assert_eq!(seq_after(4), 5);
+        assert_eq!(seq_after(usize::MAX), 0);
+    }
+
+    #[test]
+    fn parses_client_messages() {
+        let input: ClientMessage =
+            serde_json::from_str(r#"{"type":"user_input","message":"hi"}"#).unwrap();
+        assert!(
+            matches!(input, ClientMessage::UserInput { message, user_state: None } if message == "hi")
+        );
+
+        let interrupt: ClientMessage = serde_json::from_str(r#"{"type":"interrupt"}"#).unwrap();
+        assert!(matches!(interrupt, ClientMessage::Interrupt));
+
+        let response: ClientMessage = serde_json::from_str(
+            r#"{"type":"wallet_tx_response","status":"success","tx_hash":"0xabc"}"#,
+        )
+        .unwrap();
+        match response {
+            ClientMessage::WalletTxResponse { payload } => {
+                assert_eq!(payload["status"], "success");
+                assert_eq!(payload["tx_hash"], "0xabc");
+            }
+            other => panic!("unexpected message: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn serializes_server_messages() {
+        let event = ServerMessage::Event {
+            seq: 3,
+            event: SystemEvent::SystemNotice("Backend connected".into()),
+        };
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({
+                "type": "event",
+                "seq": 3,
+                "event": { "SystemNotice": "Backend connected" }
+            })
+        );
+    }
+}
@@ -1,8 +1,12 @@
 use anyhow::Result;
 use aomi_anvil::{provider_manager, set_providers_path};
 use aomi_backend::{PersistentHistoryBackend, SessionManager};
+use aomi_tools::db::{NameStore, SessionStore, UsageStore};
+use aomi_tools::names::{install_name_service, NameService};
+use aomi_tools::session_search::{install_session_search, SessionSearch};
 use clap::Parser;
 use sqlx::any::AnyPoolOptions;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::signal;
@@ -11,6 +15,8 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod auth;
 mod endpoint;
+mod rate_limit;
+mod telemetry;
 
 use endpoint::create_router;
 
@@ -26,6 +32,9 @@ static DATABASE_URL: std::sync::LazyLock<String> = std::sync::LazyLock::new(|| {
         .unwrap_or_else(|_| "postgresql://aomi@localhost:5432/chatbot".to_string())
 });
 
+const DEFAULT_TRACE_FILTER: &str =
+    "warn,backend=info,aomi_backend=info,aomi_core=info,aomi_tools=info";
+
 #[derive(Parser)]
 #[command(name = "backend")]
 #[command(about = "Web backend for AOMI EVM agent")]
@@ -45,11 +54,24 @@ struct Cli {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize tracing subscriber
+    // Initialize tracing subscriber, exporting spans when AOMI_TRACE_EXPORTER is set
+    let (trace_exporter, unknown_exporter) = match telemetry::TraceExporter::from_env() {
+        Ok(exporter) => (exporter, None),
+        Err(name) => (None, Some(name)),
+    };
+    let env_filter = match (&trace_exporter, std::env::var("RUST_LOG")) {
+        // Span export needs the aomi spans enabled even without RUST_LOG
+        (Some(_), Err(_)) => tracing_subscriber::EnvFilter::new(DEFAULT_TRACE_FILTER),
+        _ => tracing_subscriber::EnvFilter::from_default_env(),
+    };
     tracing_subscriber::registry()
-        .with(tracing_subscriber::EnvFilter::from_default_env())
+        .with(env_filter)
         .with(tracing_subscriber::fmt::layer())
+        .with(trace_exporter.map(telemetry::TraceLayer::spawn))
         .init();
+    if let Some(name) = unknown_exporter {
+        tracing::warn!(exporter = %name, "Unknown AOMI_TRACE_EXPORTER, span export disabled");
+    }
 
     let cli = Cli::parse();
 
@@ -80,6 +102,26 @@ async fn main() -> Result<()> {
     tracing::info!("Database migrations completed successfully");
 
     let api_auth = auth::ApiAuth::from_db(pool.clone()).await?;
+    let rate_limiter = rate_limit::RateLimiter::new(rate_limit::RateLimitConfig::load()?);
+
+    // Persist LLM token and tool usage for metering and quotas
+    aomi_tools::usage::install_usage_store(Arc::new(UsageStore::new(pool.clone())));
+
+    // Search over past sessions, shared by /api/sessions/search and the search_sessions tool
+    install_session_search(Arc::new(SessionSearch::new(Arc::new(SessionStore::new(
+        pool.clone(),
+    )))));
+
+    // ENS/Basenames lookups for the name tools and wallet binding, cached in name_records
+    let name_service = Arc::new(
+        NameService::new(NameService::default_resolvers())
+            .with_store(Arc::new(NameStore::new(pool.clone()))),
+    );
+    match name_service.purge_expired().await {
+        Ok(purged) => tracing::debug!(purged, "Purged expired name records"),
+        Err(e) => tracing::warn!("Failed to purge expired name records: {e}"),
+    }
+    install_name_service(name_service);
 
     // Create history backend (reuse existing pool)
     let history_backend = Arc::new(PersistentHistoryBackend::new(pool).await);
@@ -89,8 +131,15 @@ async fn main() -> Result<()> {
         Arc::new(SessionManager::initialize(cli.no_docs, cli.skip_mcp, history_backend).await?);
 
     // Build router
+    // Layers run bottom-up: CORS, API key auth, then rate limiting
     let app = create_router(session_manager)
         .layer(axum::Extension(db_pool))
+        .layer(axum::Extension(api_auth.clone()))
+        .layer(axum::Extension(rate_limiter.clone()))
+        .layer(axum::middleware::from_fn_with_state(
+            rate_limiter,
+            rate_limit::rate_limit_middleware,
+        ))
         .layer(axum::middleware::from_fn_with_state(
             api_auth,
             auth::api_key_middleware,
@@ -106,9 +155,13 @@ async fn main() -> Result<()> {
 
     // Start server with graceful shutdown
     let listener = tokio::net::TcpListener::bind(&bind_addr).await?;
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await?;
+    // Connection info gives the rate limiter the client IP
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await?;
 
     // Graceful shutdown: cleanup anvil processes
     tracing::info!("Received shutdown signal, cleaning up...");
@@ -0,0 +1,758 @@
+//! Token-bucket rate limiting for the HTTP API.
+//!
+//! Requests are grouped by route (chat, state polling, db admin) and always throttled
+//! per client IP. The authorized API key, else the `X-Session-Id` header, adds a
+//! narrower bucket on top, so rotating session ids cannot escape the IP limit. Limits
+//! are loaded from `rate_limits.toml` and can be overridden per namespace; a request
+//! only gets a namespace's limits when its API key is authorized for that namespace:
+//!
+//! ```toml
+//! trust_proxy_headers = true        # read the client IP from X-Forwarded-For
+//!
+//! [default]
+//! chat = { burst = 10, per_minute = 20 }
+//! state = { burst = 60, per_minute = 300 }
+//! admin = { burst = 20, per_minute = 60 }
+//!
+//! [namespaces.l2beat]
+//! chat = { burst = 5, per_minute = 10 }
+//! ```
+//!
+//! A namespace section replaces only the groups it sets. Throttled requests get
+//! HTTP 429 with a `Retry-After` header. Admitted requests carry their
+//! [`RateLimitIdentity`], so the WebSocket handler charges each chat message to the
+//! same buckets as its handshake.
+
+use anyhow::{bail, Context, Result};
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, State},
+    http::{header, HeaderMap, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use aomi_backend::{AuthorizedKey, DEFAULT_NAMESPACE};
+
+use crate::auth::SESSION_ID_HEADER;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Environment variable overriding the rate limit file location
+const RATE_LIMITS_TOML_ENV: &str = "RATE_LIMITS_TOML";
+
+/// Buckets idle this long are refilled anyway and can be dropped.
+const BUCKET_IDLE_TTL: Duration = Duration::from_secs(600);
+
+/// Bucket count above which idle buckets are pruned.
+const PRUNE_THRESHOLD: usize = 10_000;
+
+// ============================================================================
+// Config
+// ============================================================================
+
+/// Route groups with independent limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouteGroup {
+    /// Requests that start LLM and tool work
+    Chat,
+    /// Session state polling and event streams
+    State,
+    /// Database admin routes
+    Admin,
+}
+
+impl RouteGroup {
+    /// Route group for a request path, `None` for unthrottled routes.
+    pub fn for_path(path: &str) -> Option<Self> {
+        match path {
+            "/api/chat" | "/api/system" | "/api/ws" => Some(Self::Chat),
+            _ if path.starts_with("/api/sessions/") && path.ends_with("/edit") => Some(Self::Chat),
+            "/api/state" | "/api/updates" | "/api/events" => Some(Self::State),
+            _ if path.starts_with("/api/db/") => Some(Self::Admin),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Chat => "chat",
+            Self::State => "state",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+/// Bucket size and refill rate.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimit {
+    /// Requests allowed in a burst
+    pub burst: u32,
+    /// Sustained requests per minute
+    pub per_minute: u32,
+}
+
+impl RateLimit {
+    fn refill_per_sec(&self) -> f64 {
+        f64::from(self.per_minute) / 60.0
+    }
+}
+
+/// Per-group limits; unset groups fall back to the enclosing section.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RouteLimits {
+    pub chat: Option<RateLimit>,
+    pub state: Option<RateLimit>,
+    pub admin: Option<RateLimit>,
+}
+
+impl RouteLimits {
+    fn get(&self, group: RouteGroup) -> Option<RateLimit> {
+        match group {
+            RouteGroup::Chat => self.chat,
+            RouteGroup::State => self.state,
+            RouteGroup::Admin => self.admin,
+        }
+    }
+}
+
+/// Root of `rate_limits.toml`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitConfig {
+    /// Use X-Forwarded-For / X-Real-IP for the client IP (only behind a trusted proxy)
+    #[serde(default)]
+    pub trust_proxy_headers: bool,
+    /// Limits applied to every namespace
+    #[serde(default)]
+    pub default: RouteLimits,
+    /// Limits keyed by namespace name
+    #[serde(default)]
+    pub namespaces: HashMap<String, RouteLimits>,
+}
+
+impl RateLimitConfig {
+    pub fn from_toml_str(content: &str) -> Result<Self> {
+        let config: RateLimitConfig =
+            toml::from_str(content).context("Failed to parse rate limit config")?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::from_toml_str(&content)
+    }
+
+    /// Load `rate_limits.toml` from `RATE_LIMITS_TOML` or a walk up from the
+    /// current directory, falling back to the built-in defaults.
+    pub fn load() -> Result<Self> {
+        match resolve_rate_limits_path()? {
+            Some(path) => {
+                let config = Self::from_file(&path)?;
+                tracing::info!(
+                    path = %path.display(),
+                    namespaces = config.namespaces.len(),
+                    "Loaded rate limits"
+                );
+                Ok(config)
+            }
+            None => {
+                tracing::info!("No rate_limits.toml found, using default rate limits");
+                Ok(Self::default())
+            }
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        let sections = std::iter::once(("default", &self.default)).chain(
+            self.namespaces
+                .iter()
+                .map(|(name, limits)| (name.as_str(), limits)),
+        );
+        for (name, limits) in sections {
+            for limit in [limits.chat, limits.state, limits.admin].iter().flatten() {
+                if limit.burst == 0 || limit.per_minute == 0 {
+                    bail!("Rate limits in [{name}] must have non-zero burst and per_minute");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Limit for `group` in `namespace`: namespace section, then default
+    /// section, then the built-in default.
+    pub fn limit_for(&self, namespace: &str, group: RouteGroup) -> RateLimit {
+        self.namespaces
+            .get(&namespace.to_lowercase())
+            .and_then(|limits| limits.get(group))
+            .or_else(|| self.default.get(group))
+            .unwrap_or_else(|| builtin_limit(group))
+    }
+}
+
+fn builtin_limit(group: RouteGroup) -> RateLimit {
+    match group {
+        RouteGroup::Chat => RateLimit {
+            burst: 10,
+            per_minute: 20,
+        },
+        RouteGroup::State => RateLimit {
+            burst: 60,
+            per_minute: 300,
+        },
+        RouteGroup::Admin => RateLimit {
+            burst: 20,
+            per_minute: 60,
+        },
+    }
+}
+
+fn resolve_rate_limits_path() -> Result<Option<PathBuf>> {
+    if let Ok(path) = std::env::var(RATE_LIMITS_TOML_ENV) {
+        let path = PathBuf::from(path);
+        if path.exists() {
+            return Ok(Some(path));
+        }
+        bail!(
+            "{} was set but not found: {}",
+            RATE_LIMITS_TOML_ENV,
+            path.display()
+        );
+    }
+
+    let mut dir = std::env::current_dir()?;
+    loop {
+        let candidate = dir.join("rate_limits.toml");
+        if candidate.exists() {
+            return Ok(Some(candidate));
+        }
+        if !dir.pop() {
+            return Ok(None);
+        }
+    }
+}
+
+// ============================================================================
+// Limiter
+// ============================================================================
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn full(limit: RateLimit, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(limit.burst),
+            updated: now,
+        }
+    }
+
+    /// Add the tokens earned since the last update.
+    fn refill(&mut self, limit: RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.refill_per_sec()).min(f64::from(limit.burst));
+        self.updated = now;
+    }
+
+    /// How long until a token is available, `None` if one is available now.
+    fn wait(&self, limit: RateLimit) -> Option<Duration> {
+        (self.tokens < 1.0)
+            .then(|| Duration::from_secs_f64((1.0 - self.tokens) / limit.refill_per_sec()))
+    }
+}
+
+/// Who a bucket belongs to, most specific identity first.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ClientId {
+    ApiKey(String),
+    Session(String),
+    Ip(String),
+    Unknown,
+}
+
+/// Buckets and namespace a request was charged to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitIdentity {
+    pub namespace: String,
+    pub clients: Vec<ClientId>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct BucketKey {
+    group: RouteGroup,
+    namespace: String,
+    client: ClientId,
+}
+
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<BucketKey, TokenBucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Arc<Self> {
+        Arc::new(Self {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Charge one request to the client's bucket for `group`.
+    /// Returns the wait before the next request is allowed when throttled.
+    #[cfg(test)]
+    pub fn check(
+        &self,
+        group: RouteGroup,
+        namespace: &str,
+        client: ClientId,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        self.check_all(group, namespace, &[client], now)
+    }
+
+    /// Charge one request to every client bucket for `group`, only if each of them
+    /// has a token left. Returns the longest wait when any bucket is throttled.
+    pub fn check_all(
+        &self,
+        group: RouteGroup,
+        namespace: &str,
+        clients: &[ClientId],
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let limit = self.config.limit_for(namespace, group);
+        let namespace = namespace.to_lowercase();
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() >= PRUNE_THRESHOLD {
+            buckets.retain(|_, bucket| {
+                now.saturating_duration_since(bucket.updated) < BUCKET_IDLE_TTL
+            });
+        }
+
+        let keys: Vec<BucketKey> = clients
+            .iter()
+            .map(|client| BucketKey {
+                group,
+                namespace: namespace.clone(),
+                client: client.clone(),
+            })
+            .collect();
+        let mut wait = None;
+        for key in &keys {
+            let bucket = buckets
+                .entry(key.clone())
+                .or_insert_with(|| TokenBucket::full(limit, now));
+            bucket.refill(limit, now);
+            wait = wait.max(bucket.wait(limit));
+        }
+        if let Some(wait) = wait {
+            return Err(wait);
+        }
+        for key in &keys {
+            if let Some(bucket) = buckets.get_mut(key) {
+                bucket.tokens -= 1.0;
+            }
+        }
+        Ok(())
+    }
+
+    /// Buckets charged for a request: the client IP, plus the API key or session.
+    fn client_ids(&self, req: &Request<Body>) -> Vec<ClientId> {
+        let mut clients = vec![self.client_ip(req)];
+        if let Some(key) = req.extensions().get::<AuthorizedKey>() {
+            clients.push(ClientId::ApiKey(key.key.clone()));
+        } else if let Some(session_id) = header_value(req.headers(), SESSION_ID_HEADER) {
+            clients.push(ClientId::Session(session_id.to_string()));
+        }
+        clients
+    }
+
+    /// IP of the client behind a request.
+    fn client_ip(&self, req: &Request<Body>) -> ClientId {
+        if self.config.trust_proxy_headers {
+            let forwarded = header_value(req.headers(), "x-forwarded-for")
+                .and_then(|v| v.split(',').next())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .or_else(|| header_value(req.headers(), "x-real-ip"));
+            if let Some(ip) = forwarded {
+                return ClientId::Ip(ip.to_string());
+            }
+        }
+        req.extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| ClientId::Ip(addr.ip().to_string()))
+            .unwrap_or(ClientId::Unknown)
+    }
+}
+
+// ============================================================================
+// Middleware
+// ============================================================================
+
+/// Runs after `api_key_middleware` so authorized keys share one bucket per key and
+/// pick the namespace whose limits apply.
+pub async fn rate_limit_middleware(
+    State(limiter): State<Arc<RateLimiter>>,
+    mut req: Request<Body>,
+    next: Next,
+) -> Response {
+    if req.method() == Method::OPTIONS {
+        return next.run(req).await;
+    }
+    let Some(group) = RouteGroup::for_path(req.uri().path()) else {
+        return next.run(req).await;
+    };
+
+    let namespace = limited_namespace(&req);
+    let clients = limiter.client_ids(&req);
+    match limiter.check_all(group, &namespace, &clients, Instant::now()) {
+        Ok(()) => {
+            req.extensions_mut()
+                .insert(RateLimitIdentity { namespace, clients });
+            next.run(req).await
+        }
+        Err(wait) => {
+            tracing::debug!(group = group.as_str(), ?clients, "Rate limited request");
+            rate_limited_response(group, wait)
+        }
+    }
+}
+
+/// 429 response with the whole seconds until the next request is allowed.
+fn rate_limited_response(group: RouteGroup, wait: Duration) -> Response {
+    let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+    let body = json!({
+        "error": "rate_limited",
+        "message": format!("Too many {} requests, retry in {retry_after}s", group.as_str()),
+        "group": group.as_str(),
+        "retry_after": retry_after,
+    });
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(header::RETRY_AFTER, retry_after.to_string())],
+        Json(body),
+    )
+        .into_response()
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn header_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+/// Namespace whose limits apply: the requested one when the API key is authorized
+/// for it, else the key's first namespace. Requests without a key get the defaults.
+fn limited_namespace(req: &Request<Body>) -> String {
+    let Some(key) = req.extensions().get::<AuthorizedKey>() else {
+        return DEFAULT_NAMESPACE.to_string();
+    };
+    let requested = namespace_param(req.uri().query().unwrap_or(""));
+    if key.allows_namespace(&requested) {
+        return requested;
+    }
+    key.get_allowed_namespaces()
+        .first()
+        .cloned()
+        .unwrap_or_else(|| DEFAULT_NAMESPACE.to_string())
+}
+
+/// Namespace from the `namespace` or `chatbot` query parameter.
+fn namespace_param(query: &str) -> String {
+    let param = |key: &str| {
+        query.split('&').find_map(|pair| {
+            let mut parts = pair.splitn(2, '=');
+            if parts.next()?.trim() == key {
+                Some(parts.next().unwrap_or(""))
+            } else {
+                None
+            }
+        })
+    };
+    param("namespace")
+        .or_else(|| param("chatbot"))
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .unwrap_or(DEFAULT_NAMESPACE)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::post, Router};
+    use tower::util::ServiceExt;
+
+    fn limiter(toml: &str) -> Arc<RateLimiter> {
+        RateLimiter::new(RateLimitConfig::from_toml_str(toml).expect("valid config"))
+    }
+
+    #[test]
+    fn groups_routes() {
+        assert_eq!(RouteGroup::for_path("/api/chat"), Some(RouteGroup::Chat));
+        assert_eq!(RouteGroup::for_path("/api/system"), Some(RouteGroup::Chat));
+        assert_eq!(RouteGroup::for_path("/api/state"), Some(RouteGroup::State));
+        assert_eq!(
+            RouteGroup::for_path("/api/updates"),
+            Some(RouteGroup::State)
+        );
+        assert_eq!(
+            RouteGroup::for_path("/api/db/sessions/abc"),
+            Some(RouteGroup::Admin)
+        );
+        assert_eq!(
+            RouteGroup::for_path("/api/sessions/abc/edit"),
+            Some(RouteGroup::Chat)
+        );
+        assert_eq!(RouteGroup::for_path("/api/sessions/abc/fork"), None);
+        assert_eq!(RouteGroup::for_path("/api/interrupt"), None);
+        assert_eq!(RouteGroup::for_path("/health"), None);
+    }
+
+    #[test]
+    fn namespace_limits_override_default() {
+        let config = RateLimitConfig::from_toml_str(
+            r#"
+            [default]
+            chat = { burst = 4, per_minute = 8 }
+
+            [namespaces.l2beat]
+            chat = { burst = 2, per_minute = 2 }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.limit_for("default", RouteGroup::Chat).burst, 4);
+        assert_eq!(config.limit_for("L2BEAT", RouteGroup::Chat).burst, 2);
+        assert_eq!(
+            config.limit_for("l2beat", RouteGroup::State),
+            builtin_limit(RouteGroup::State)
+        );
+    }
+
+    #[test]
+    fn rejects_zero_limits() {
+        let err = RateLimitConfig::from_toml_str(
+            r#"
+            [namespaces.forge]
+            admin = { burst = 0, per_minute = 10 }
+            "#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("[forge]"));
+    }
+
+    #[test]
+    fn bucket_refills_over_time() {
+        let limiter = limiter("[default]\nchat = { burst = 2, per_minute = 60 }");
+        let client = ClientId::ApiKey("key-1".into());
+        let start = Instant::now();
+
+        assert!(limiter
+            .check(RouteGroup::Chat, "default", client.clone(), start)
+            .is_ok());
+        assert!(limiter
+            .check(RouteGroup::Chat, "default", client.clone(), start)
+            .is_ok());
+        let wait = limiter
+            .check(RouteGroup::Chat, "default", client.clone(), start)
+            .unwrap_err();
+        assert_eq!(wait.as_secs_f64().ceil() as u64, 1);
+
+        // Other clients and groups have their own buckets
+        assert!(limiter
+            .check(
+                RouteGroup::Chat,
+                "default",
+                ClientId::Ip("10.0.0.1".into()),
+                start
+            )
+            .is_ok());
+        assert!(limiter
+            .check(RouteGroup::State, "default", client.clone(), start)
+            .is_ok());
+
+        let later = start + Duration::from_secs(1);
+        assert!(limiter
+            .check(RouteGroup::Chat, "default", client, later)
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn middleware_returns_retry_after() {
+        let limiter =
+            limiter("trust_proxy_headers = true\n[default]\nchat = { burst = 1, per_minute = 6 }");
+        let app = Router::new()
+            .route("/api/chat", post(|| async { "ok" }))
+            .route("/api/interrupt", post(|| async { "ok" }))
+            .layer(axum::middleware::from_fn_with_state(
+                limiter,
+                rate_limit_middleware,
+            ));
+
+        let request = |path: &str, ip: &str, session: &str| {
+            Request::builder()
+                .method("POST")
+                .uri(path)
+                .header("x-forwarded-for", ip)
+                .header(SESSION_ID_HEADER, session)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let ok = app
+            .clone()
+            .oneshot(request("/api/chat", "10.0.0.1", "s1"))
+            .await
+            .unwrap();
+        assert_eq!(ok.status(), StatusCode::OK);
+
+        let limited = app
+            .clone()
+            .oneshot(request("/api/chat", "10.0.0.1", "s1"))
+            .await
+            .unwrap();
+        assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(limited.headers().get(header::RETRY_AFTER).unwrap(), "10");
+
+        // A fresh session id does not escape the IP bucket
+        let new_session = app
+            .clone()
+            .oneshot(request("/api/chat", "10.0.0.1", "s2"))
+            .await
+            .unwrap();
+        assert_eq!(new_session.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        let other_ip = app
+            .clone()
+            .oneshot(request("/api/chat", "10.0.0.2", "s3"))
+            .await
+            .unwrap();
+        assert_eq!(other_ip.status(), StatusCode::OK);
+
+        let unthrottled = app
+            .oneshot(request("/api/interrupt", "10.0.0.1", "s1"))
+            .await
+            .unwrap();
+        assert_eq!(unthrottled.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn middleware_passes_identity_to_handlers() {
+        let limiter = limiter("trust_proxy_headers = true");
+        let app = Router::new()
+            .route(
+                "/api/ws",
+                axum::routing::get(
+                    |axum::Extension(identity): axum::Extension<RateLimitIdentity>| async move {
+                        format!("{}:{:?}", identity.namespace, identity.clients)
+                    },
+                ),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                limiter,
+                rate_limit_middleware,
+            ));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/ws?namespace=l2beat")
+                    .header("x-forwarded-for", "10.0.0.1")
+                    .header(SESSION_ID_HEADER, "s1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        // Without an authorized key the requested namespace is not used
+        assert_eq!(
+            String::from_utf8(body.to_vec()).unwrap(),
+            "default:[Ip(\"10.0.0.1\"), Session(\"s1\")]"
+        );
+    }
+
+    #[test]
+    fn session_bucket_narrows_ip_bucket() {
+        let limiter = limiter("[default]\nchat = { burst = 2, per_minute = 60 }");
+        let ip = ClientId::Ip("10.0.0.1".into());
+        let s1 = [ip.clone(), ClientId::Session("s1".into())];
+        let s2 = [ip.clone(), ClientId::Session("s2".into())];
+        let now = Instant::now();
+
+        assert!(limiter
+            .check_all(RouteGroup::Chat, "default", &s1, now)
+            .is_ok());
+        assert!(limiter
+            .check_all(RouteGroup::Chat, "default", &s2, now)
+            .is_ok());
+        // The shared IP bucket is empty even though s2 has a token left
+        assert!(limiter
+            .check_all(RouteGroup::Chat, "default", &s2, now)
+            .is_err());
+        // A throttled request does not drain the buckets that still had tokens
+        assert!(limiter
+            .check(
+                RouteGroup::Chat,
+                "default",
+                ClientId::Session("s2".into()),
+                now
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn namespace_limits_need_an_authorized_key() {
+        let request = |uri: &str, key: Option<&[&str]>| {
+            let mut req = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            if let Some(namespaces) = key {
+                req.extensions_mut().insert(AuthorizedKey::new(
+                    "key-1".into(),
+                    None,
+                    true,
+                    namespaces.iter().map(|ns| ns.to_string()).collect(),
+                ));
+            }
+            req
+        };
+
+        assert_eq!(
+            limited_namespace(&request("/api/chat?namespace=l2beat", None)),
+            DEFAULT_NAMESPACE
+        );
+        assert_eq!(
+            limited_namespace(&request("/api/chat?namespace=l2beat", Some(&["l2beat"]))),
+            "l2beat"
+        );
+        assert_eq!(
+            limited_namespace(&request("/api/chat?namespace=forge", Some(&["l2beat"]))),
+            "l2beat"
+        );
+    }
+}
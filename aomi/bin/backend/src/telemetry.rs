@@ -0,0 +1,561 @@
+//! Span export for tracing a chat turn end to end.
+//!
+//! `TraceLayer` turns the `tracing` spans emitted by the aomi crates (`chat_request`,
+//! `send_user_input`, `process_message`, `llm_stream`, `tool_call`, `tool_completion`) into
+//! OpenTelemetry-style spans and hands them to an exporter chosen by `AOMI_TRACE_EXPORTER`:
+//!
+//! - `otlp`: batches are POSTed as OTLP/HTTP JSON to `OTEL_EXPORTER_OTLP_ENDPOINT`
+//!   (default `http://localhost:4318`) at `/v1/traces`.
+//! - `file`: one JSON span per line appended to `AOMI_TRACE_FILE` (default `traces.jsonl`),
+//!   for offline debugging.
+//!
+//! Async tool completions arrive on a separate task long after their `tool_call` span ended;
+//! they are stitched back into the originating trace through their `tool_call_id` field.
+//!
+//! Finished spans are queued for the exporter without blocking; when the queue is full they
+//! are dropped, and the count is logged on the next flush.
+
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::Subscriber;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+/// Span targets exported; everything else (HTTP clients, sqlx, ...) is ignored
+const EXPORTED_TARGETS: [&str; 2] = ["aomi", "backend"];
+
+/// Attributes copied from the parent (or linked) span when a span does not set them
+const INHERITED_FIELDS: [&str; 2] = ["session_id", "namespace"];
+
+/// Field that ties an async tool completion back to its `tool_call` span
+const LINK_FIELD: &str = "tool_call_id";
+
+/// Tool call spans remembered for linking late completions
+const MAX_LINKED_CALLS: usize = 10_000;
+
+const EXPORT_BATCH_SIZE: usize = 256;
+const EXPORT_INTERVAL: Duration = Duration::from_secs(2);
+/// Timeout for one OTLP export request, so a hung collector cannot stall the exporter
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Finished spans waiting for the exporter; spans beyond this are dropped and counted
+const SPAN_QUEUE_CAPACITY: usize = 8192;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceExporter {
+    Otlp { endpoint: String },
+    File { path: PathBuf },
+}
+
+impl TraceExporter {
+    /// Reads the exporter from `AOMI_TRACE_EXPORTER`; `Ok(None)` when tracing export is off.
+    /// An unknown exporter name is returned as the error so it can be reported once
+    /// the subscriber is up.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let Ok(exporter) = std::env::var("AOMI_TRACE_EXPORTER") else {
+            return Ok(None);
+        };
+        match exporter.trim() {
+            "otlp" => Ok(Some(Self::Otlp {
+                endpoint: std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+                    .unwrap_or_else(|_| "http://localhost:4318".to_string()),
+            })),
+            "file" => Ok(Some(Self::File {
+                path: std::env::var("AOMI_TRACE_FILE")
+                    .unwrap_or_else(|_| "traces.jsonl".to_string())
+                    .into(),
+            })),
+            "" | "none" | "off" => Ok(None),
+            other => Err(other.to_string()),
+        }
+    }
+}
+
+/// A finished span ready for export.
+#[derive(Debug, Clone)]
+pub struct SpanRecord {
+    pub trace_id: String,
+    pub span_id: String,
+    pub parent_span_id: Option<String>,
+    pub name: String,
+    pub start: SystemTime,
+    pub end: SystemTime,
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl SpanRecord {
+    /// Flat JSON form written by the file exporter
+    pub fn to_json(&self) -> Value {
+        json!({
+            "trace_id": self.trace_id,
+            "span_id": self.span_id,
+            "parent_span_id": self.parent_span_id,
+            "name": self.name,
+            "start_unix_nano": unix_nanos(self.start),
+            "end_unix_nano": unix_nanos(self.end),
+            "attributes": self.attributes,
+        })
+    }
+
+    /// Span in the OTLP/JSON encoding
+    fn to_otlp(&self) -> Value {
+        let attributes: Vec<Value> = self
+            .attributes
+            .iter()
+            .map(|(key, value)| json!({ "key": key, "value": { "stringValue": value } }))
+            .collect();
+        json!({
+            "traceId": self.trace_id,
+            "spanId": self.span_id,
+            "parentSpanId": self.parent_span_id.clone().unwrap_or_default(),
+            "name": self.name,
+            // SPAN_KIND_INTERNAL
+            "kind": 1,
+            "startTimeUnixNano": unix_nanos(self.start).to_string(),
+            "endTimeUnixNano": unix_nanos(self.end).to_string(),
+            "attributes": attributes,
+        })
+    }
+}
+
+/// OTLP/HTTP JSON request body for a batch of spans
+pub fn otlp_request(service_name: &str, spans: &[SpanRecord]) -> Value {
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [
+                    { "key": "service.name", "value": { "stringValue": service_name } }
+                ]
+            },
+            "scopeSpans": [{
+                "scope": { "name": "aomi" },
+                "spans": spans.iter().map(SpanRecord::to_otlp).collect::<Vec<_>>(),
+            }]
+        }]
+    })
+}
+
+fn unix_nanos(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0)
+}
+
+fn new_trace_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+fn new_span_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()[..16].to_string()
+}
+
+/// Per-span state kept in the registry's extensions while the span is open.
+struct SpanData {
+    trace_id: String,
+    span_id: String,
+    parent_span_id: Option<String>,
+    start: SystemTime,
+    attributes: BTreeMap<String, String>,
+}
+
+struct FieldVisitor<'a>(&'a mut BTreeMap<String, String>);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{value:?}"));
+    }
+}
+
+/// Where a linked tool call lives, plus the attributes late spans inherit from it
+#[derive(Clone)]
+struct LinkedCall {
+    trace_id: String,
+    span_id: String,
+    attributes: BTreeMap<String, String>,
+}
+
+#[derive(Default)]
+struct LinkIndex {
+    calls: HashMap<String, LinkedCall>,
+    order: VecDeque<String>,
+}
+
+impl LinkIndex {
+    fn insert(&mut self, key: String, call: LinkedCall) {
+        if self.calls.insert(key.clone(), call).is_none() {
+            self.order.push_back(key);
+        }
+        while self.order.len() > MAX_LINKED_CALLS {
+            if let Some(oldest) = self.order.pop_front() {
+                self.calls.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// `tracing` layer that records aomi spans and sends them to an exporter task.
+pub struct TraceLayer {
+    sender: mpsc::Sender<SpanRecord>,
+    links: Mutex<LinkIndex>,
+    /// Spans dropped because the exporter fell behind
+    dropped: Arc<AtomicU64>,
+}
+
+impl TraceLayer {
+    pub fn new(sender: mpsc::Sender<SpanRecord>) -> Self {
+        Self {
+            sender,
+            links: Mutex::new(LinkIndex::default()),
+            dropped: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Builds the layer and spawns its exporter on the current Tokio runtime.
+    pub fn spawn(exporter: TraceExporter) -> Self {
+        let (sender, receiver) = mpsc::channel(SPAN_QUEUE_CAPACITY);
+        let layer = Self::new(sender);
+        tokio::spawn(run_exporter(exporter, receiver, Arc::clone(&layer.dropped)));
+        layer
+    }
+}
+
+fn is_exported(target: &str) -> bool {
+    EXPORTED_TARGETS
+        .iter()
+        .any(|prefix| target.starts_with(prefix))
+}
+
+impl<S> Layer<S> for TraceLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        if !is_exported(span.metadata().target()) {
+            return;
+        }
+
+        let mut attributes = BTreeMap::new();
+        attrs.record(&mut FieldVisitor(&mut attributes));
+
+        let parent = span.parent().and_then(|parent| {
+            parent
+                .extensions()
+                .get::<SpanData>()
+                .map(|data| LinkedCall {
+                    trace_id: data.trace_id.clone(),
+                    span_id: data.span_id.clone(),
+                    attributes: data.attributes.clone(),
+                })
+        });
+        let link_key = attributes.get(LINK_FIELD).cloned();
+        // Root spans carrying a known tool call id continue that call's trace
+        let parent = parent.or_else(|| {
+            let key = link_key.as_ref()?;
+            self.links.lock().ok()?.calls.get(key).cloned()
+        });
+
+        if let Some(parent) = &parent {
+            for field in INHERITED_FIELDS {
+                if let Some(value) = parent.attributes.get(field) {
+                    attributes
+                        .entry(field.to_string())
+                        .or_insert_with(|| value.clone());
+                }
+            }
+        }
+
+        let data = SpanData {
+            trace_id: parent
+                .as_ref()
+                .map(|p| p.trace_id.clone())
+                .unwrap_or_else(new_trace_id),
+            span_id: new_span_id(),
+            parent_span_id: parent.map(|p| p.span_id),
+            start: SystemTime::now(),
+            attributes,
+        };
+
+        if let Some(key) = link_key {
+            if let Ok(mut links) = self.links.lock() {
+                if !links.calls.contains_key(&key) {
+                    links.insert(
+                        key,
+                        LinkedCall {
+                            trace_id: data.trace_id.clone(),
+                            span_id: data.span_id.clone(),
+                            attributes: data.attributes.clone(),
+                        },
+                    );
+                }
+            }
+        }
+
+        span.extensions_mut().insert(data);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(data) = span.extensions_mut().get_mut::<SpanData>() {
+                values.record(&mut FieldVisitor(&mut data.attributes));
+            }
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(data) = span.extensions_mut().remove::<SpanData>() else {
+            return;
+        };
+        let record = SpanRecord {
+            trace_id: data.trace_id,
+            span_id: data.span_id,
+            parent_span_id: data.parent_span_id,
+            name: span.name().to_string(),
+            start: data.start,
+            end: SystemTime::now(),
+            attributes: data.attributes,
+        };
+        // Never block the traced code on a slow exporter
+        if let Err(mpsc::error::TrySendError::Full(_)) = self.sender.try_send(record) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+async fn run_exporter(
+    exporter: TraceExporter,
+    mut receiver: mpsc::Receiver<SpanRecord>,
+    dropped: Arc<AtomicU64>,
+) {
+    let service_name =
+        std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| "aomi-backend".to_string());
+    let client = reqwest::Client::builder()
+        .timeout(EXPORT_TIMEOUT)
+        .build()
+        .expect("Failed to build trace export client");
+    let mut batch = Vec::new();
+
+    loop {
+        let flush = match tokio::time::timeout(EXPORT_INTERVAL, receiver.recv()).await {
+            Ok(Some(record)) => {
+                batch.push(record);
+                batch.len() >= EXPORT_BATCH_SIZE
+            }
+            Ok(None) => {
+                export_batch(&exporter, &client, &service_name, &batch).await;
+                return;
+            }
+            Err(_) => true,
+        };
+        if flush && !batch.is_empty() {
+            export_batch(&exporter, &client, &service_name, &batch).await;
+            batch.clear();
+        }
+        if flush {
+            let dropped = dropped.swap(0, Ordering::Relaxed);
+            if dropped > 0 {
+                tracing::warn!(dropped, "Dropped spans while the trace exporter was behind");
+            }
+        }
+    }
+}
+
+// Export failures are logged as events; the layer only exports spans, and the exporter task
+// runs outside any span, so they never feed back into an export batch
+async fn export_batch(
+    exporter: &TraceExporter,
+    client: &reqwest::Client,
+    service_name: &str,
+    batch: &[SpanRecord],
+) {
+    if batch.is_empty() {
+        return;
+    }
+    match exporter {
+        TraceExporter::Otlp { endpoint } => {
+            let url = format!("{}/v1/traces", endpoint.trim_end_matches('/'));
+            let result = client
+                .post(&url)
+                .json(&otlp_request(service_name, batch))
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            if let Err(e) = result {
+                tracing::warn!(spans = batch.len(), %url, error = %e, "Failed to export spans");
+            }
+        }
+        TraceExporter::File { path } => {
+            let result = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| {
+                    for record in batch {
+                        writeln!(file, "{}", record.to_json())?;
+                    }
+                    Ok(())
+                });
+            if let Err(e) = result {
+                tracing::warn!(
+                    spans = batch.len(),
+                    path = %path.display(),
+                    error = %e,
+                    "Failed to write spans"
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    fn collect_spans(f: impl FnOnce()) -> Vec<SpanRecord> {
+        let (sender, mut receiver) = mpsc::channel(SPAN_QUEUE_CAPACITY);
+        let subscriber = tracing_subscriber::registry().with(TraceLayer::new(sender));
+        tracing::subscriber::with_default(subscriber, f);
+        let mut spans = Vec::new();
+        while let Ok(record) = receiver.try_recv() {
+            spans.push(record);
+        }
+        spans
+    }
+
+    fn find<'a>(spans: &'a [SpanRecord], name: &str) -> &'a SpanRecord {
+        spans.iter().find(|s| s.name == name).expect(name)
+    }
+
+    #[test]
+    fn children_share_trace_and_inherit_session() {
+        let spans = collect_spans(|| {
+            let turn = tracing::info_span!(
+                target: "aomi_backend",
+                "send_user_input",
+                session_id = "s1",
+                namespace = "default"
+            );
+            let _turn = turn.enter();
+            let _tool = tracing::info_span!(
+                target: "aomi_core",
+                "tool_call",
+                tool = "brave_search",
+                tool_call_id = "call_1"
+            )
+            .entered();
+        });
+
+        let turn = find(&spans, "send_user_input");
+        let tool = find(&spans, "tool_call");
+        assert_eq!(tool.trace_id, turn.trace_id);
+        assert_eq!(tool.parent_span_id.as_deref(), Some(turn.span_id.as_str()));
+        assert_eq!(tool.attributes["session_id"], "s1");
+        assert_eq!(tool.attributes["tool"], "brave_search");
+        assert_eq!(turn.trace_id.len(), 32);
+        assert_eq!(turn.span_id.len(), 16);
+    }
+
+    #[test]
+    fn late_completion_joins_tool_call_trace() {
+        let spans = collect_spans(|| {
+            {
+                let _turn = tracing::info_span!(
+                    target: "aomi_backend",
+                    "send_user_input",
+                    session_id = "s1"
+                )
+                .entered();
+                let _tool = tracing::info_span!(
+                    target: "aomi_core",
+                    "tool_call",
+                    tool_call_id = "call_9"
+                )
+                .entered();
+            }
+            // Later, on another task, with no span in scope
+            let _completion = tracing::info_span!(
+                target: "aomi_core",
+                "tool_completion",
+                tool_call_id = "call_9"
+            )
+            .entered();
+        });
+
+        let tool = find(&spans, "tool_call");
+        let completion = find(&spans, "tool_completion");
+        assert_eq!(completion.trace_id, tool.trace_id);
+        assert_eq!(
+            completion.parent_span_id.as_deref(),
+            Some(tool.span_id.as_str())
+        );
+        assert_eq!(completion.attributes["session_id"], "s1");
+    }
+
+    #[test]
+    fn ignores_foreign_targets() {
+        let spans = collect_spans(|| {
+            let _span = tracing::info_span!(target: "hyper::client", "request").entered();
+        });
+        assert!(spans.is_empty());
+    }
+
+    #[test]
+    fn full_queue_drops_and_counts_spans() {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let layer = TraceLayer::new(sender);
+        let dropped = Arc::clone(&layer.dropped);
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            for _ in 0..3 {
+                let _span = tracing::info_span!(target: "aomi_core", "tool_call").entered();
+            }
+        });
+
+        assert!(receiver.try_recv().is_ok());
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(dropped.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn otlp_request_uses_hex_ids_and_string_attributes() {
+        let record = SpanRecord {
+            trace_id: "0".repeat(32),
+            span_id: "1".repeat(16),
+            parent_span_id: None,
+            name: "tool_call".to_string(),
+            start: UNIX_EPOCH,
+            end: UNIX_EPOCH + Duration::from_millis(5),
+            attributes: BTreeMap::from([("tool".to_string(), "brave_search".to_string())]),
+        };
+        let body = otlp_request("aomi-backend", &[record]);
+        let span = &body["resourceSpans"][0]["scopeSpans"][0]["spans"][0];
+        assert_eq!(span["traceId"], "0".repeat(32));
+        assert_eq!(span["parentSpanId"], "");
+        assert_eq!(span["endTimeUnixNano"], "5000000");
+        assert_eq!(
+            span["attributes"][0]["value"]["stringValue"],
+            "brave_search"
+        );
+    }
+}
@@ -11,7 +11,7 @@ use std::{
 };
 
 use aomi_backend::{BuildOpts, Namespace, build_backends};
-use aomi_core::{AomiModel, Selection, SystemEvent};
+use aomi_core::{AomiModel, Selection, SystemEvent, TOOL_APPROVAL_RESPONSE, approval_response};
 use clap::{Parser, ValueEnum};
 use colored::Colorize;
 use eyre::{Context, Result};
@@ -268,11 +268,32 @@ async fn handle_repl_line(
         println!("  /model small           Use BAML model selection (small)");
         println!("  /model list            Show available models");
         println!("  /model show            Show current model selection");
+        println!("  :approve <id>          Approve a pending tool call");
+        println!("  :reject <id> [reason]  Reject a pending tool call");
         println!("  :test-events           Emit mock SystemEvents locally");
         println!("  :exit                  Quit the CLI");
         return Ok(ReplState::ImmediatePrompt);
     }
 
+    if let Some((args, approved)) = trimmed
+        .strip_prefix(":approve")
+        .map(|args| (args, true))
+        .or_else(|| trimmed.strip_prefix(":reject").map(|args| (args, false)))
+    {
+        let mut parts = args.trim().splitn(2, ' ');
+        let Some(id) = parts.next().filter(|id| !id.is_empty()) else {
+            println!("Usage: :approve <id> | :reject <id> [reason]");
+            return Ok(ReplState::ImmediatePrompt);
+        };
+        cli_session.push_system_event(approval_response(json!({
+            "type": TOOL_APPROVAL_RESPONSE,
+            "id": id,
+            "approved": approved,
+            "reason": parts.next().map(str::trim),
+        })));
+        return Ok(ReplState::AwaitResponse);
+    }
+
     if trimmed == ":test-events" {
         cli_session.push_system_event(SystemEvent::InlineCall(json!({
             "type": "test_inline",
@@ -363,7 +384,7 @@ async fn handle_repl_line(
             }
             "list" => {
                 println!("Rig models:");
-                for model in AomiModel::rig_all() {
+                for model in AomiModel::rig_available() {
                     println!("  {} ({})", model.rig_label(), model.rig_slug());
                 }
                 println!("BAML clients:");
@@ -196,6 +196,18 @@ fn summarize_json(value: &Value) -> String {
             parts.push(format!("tool:{}", tool));
         }
 
+        // Approval requests carry the call to answer with :approve/:reject
+        if event_type == "tool_approval_request"
+            && let Some(payload) = obj.get("payload")
+        {
+            if let Some(tool) = payload.get("tool_name").and_then(|v| v.as_str()) {
+                parts.push(format!("tool:{}", tool));
+            }
+            if let Some(id) = payload.get("id").and_then(|v| v.as_str()) {
+                parts.push(format!("id:{}", id));
+            }
+        }
+
         // Add result summary for tool completions
         if let Some(result) = obj.get("result") {
             let result_str = if let Some(s) = result.as_str() {
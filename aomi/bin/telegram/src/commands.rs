@@ -116,7 +116,7 @@ fn make_namespace_keyboard(namespaces: &[String], current_namespace: &str) -> In
 }
 
 fn make_model_keyboard(current_model: AomiModel) -> InlineKeyboardMarkup {
-    let mut rows: Vec<Vec<InlineKeyboardButton>> = AomiModel::rig_all()
+    let mut rows: Vec<Vec<InlineKeyboardButton>> = AomiModel::rig_available()
         .chunks(2)
         .map(|chunk| {
             chunk
@@ -12,6 +12,7 @@ use aomi_backend::{DEFAULT_NAMESPACE, NamespaceAuth, SessionManager, types::User
 use aomi_bot_core::handler::extract_assistant_text;
 use aomi_bot_core::{DbWalletConnectService, WalletConnectService};
 use aomi_core::SystemEvent;
+use aomi_tools::names::name_service;
 
 use crate::{
     TelegramBot,
@@ -258,6 +259,12 @@ async fn process_and_respond(
         .send_chat_action(message.chat.id, ChatAction::Typing)
         .await?;
 
+    // Resolved before locking the session; cached after the first lookup
+    let ens_name = match bound_wallet {
+        Some(ref wallet_address) => wallet_ens_name(wallet_address).await,
+        None => None,
+    };
+
     let mut state = session.lock().await;
 
     // Check for bound wallet and inject into session
@@ -270,7 +277,7 @@ async fn process_and_respond(
             address: Some(wallet_address.clone()),
             chain_id: Some(1),
             is_connected: true,
-            ens_name: None,
+            ens_name,
         };
         state.sync_user_state(user_state).await;
     }
@@ -392,6 +399,18 @@ async fn process_and_respond(
     Ok(())
 }
 
+/// Primary ENS/Basenames name of a bound wallet, if it has one.
+async fn wallet_ens_name(wallet_address: &str) -> Option<String> {
+    let address = wallet_address.parse().ok()?;
+    match name_service().lookup_address(address).await {
+        Ok(resolved) => resolved.map(|resolved| resolved.name),
+        Err(e) => {
+            debug!("Name lookup for {} failed: {}", wallet_address, e);
+            None
+        }
+    }
+}
+
 /// Check if the bot is mentioned in a message.
 async fn is_bot_mentioned(bot: &teloxide::Bot, message: &Message) -> Result<bool> {
     let me = bot.get_me().await?;
@@ -1,6 +1,8 @@
 use anyhow::Result;
 use aomi_anvil::provider_manager;
 use aomi_backend::{PersistentHistoryBackend, SessionManager};
+use aomi_tools::db::NameStore;
+use aomi_tools::names::{NameService, install_name_service};
 use clap::Parser;
 use sqlx::any::AnyPoolOptions;
 use std::sync::Arc;
@@ -61,6 +63,12 @@ async fn main() -> Result<()> {
         .connect(&DATABASE_URL)
         .await?;
 
+    // Cache wallet name lookups in the shared name_records table
+    install_name_service(Arc::new(
+        NameService::new(NameService::default_resolvers())
+            .with_store(Arc::new(NameStore::new(pool.clone()))),
+    ));
+
     // Create history backend
     let history_backend = Arc::new(PersistentHistoryBackend::new(pool.clone()).await);
 
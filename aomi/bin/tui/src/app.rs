@@ -211,7 +211,7 @@ impl SessionContainer {
                 "list" => {
                     let mut output = String::new();
                     output.push_str("Rig models:\n");
-                    for model in AomiModel::rig_all() {
+                    for model in AomiModel::rig_available() {
                         output.push_str(&format!(
                             "- {} ({})\n",
                             model.rig_label(),
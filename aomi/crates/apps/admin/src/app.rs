@@ -8,13 +8,13 @@ use eyre::Result;
 
 use crate::tools::{
     AdminCreateApiKey, AdminDeleteContract, AdminDeleteSession, AdminDeleteUser, AdminListApiKeys,
-    AdminListContracts, AdminListSessions, AdminListUsers, AdminUpdateApiKey, AdminUpdateContract,
-    AdminUpdateSession, AdminUpdateUser,
+    AdminListContracts, AdminListSessions, AdminListUsers, AdminSetQuota, AdminUpdateApiKey,
+    AdminUpdateContract, AdminUpdateSession, AdminUpdateUser, AdminUsageReport,
 };
 
 pub type AdminCommand = CoreCommand;
 
-const ADMIN_ROLE: &str = "You are an AI assistant for admin database operations. Use the admin tools to list, create, update, and delete API keys, users, sessions, and contracts. Always confirm destructive actions (deletes or clearing fields) before executing.";
+const ADMIN_ROLE: &str = "You are an AI assistant for admin database operations. Use the admin tools to list, create, update, and delete API keys, users, sessions, and contracts, and to report usage and manage API key quotas. Always confirm destructive actions (deletes or clearing fields) before executing.";
 
 const ADMIN_TOOLS: &[&str] = &[
     "admin_create_api_key - Create an API key with namespaces",
@@ -29,6 +29,8 @@ const ADMIN_TOOLS: &[&str] = &[
     "admin_list_contracts - List contracts with filters",
     "admin_update_contract - Update contract metadata",
     "admin_delete_contract - Delete a contract",
+    "admin_usage_report - Report token and tool usage by API key, user, or session",
+    "admin_set_quota - Set daily/monthly token and tool call quotas for an API key",
 ];
 
 const ADMIN_WORKFLOW: &[&str] = &[
@@ -81,6 +83,8 @@ impl AdminApp {
             builder.add_tool(AdminListContracts)?;
             builder.add_tool(AdminUpdateContract)?;
             builder.add_tool(AdminDeleteContract)?;
+            builder.add_tool(AdminUsageReport)?;
+            builder.add_tool(AdminSetQuota)?;
         }
 
         if !opts.no_docs {
@@ -119,4 +123,12 @@ impl AomiApp for AdminApp {
     fn tool_namespaces(&self) -> std::sync::Arc<std::collections::HashMap<String, String>> {
         self.chat_app.tool_namespaces()
     }
+
+    fn context_budget(&self) -> Option<usize> {
+        Some(self.chat_app.context_budget())
+    }
+
+    fn summarizer(&self) -> Option<std::sync::Arc<dyn aomi_core::ConversationSummarizer>> {
+        self.chat_app.summarizer()
+    }
 }
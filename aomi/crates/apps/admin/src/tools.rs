@@ -4,8 +4,9 @@
 use std::future::Future;
 
 use aomi_tools::db::{
-    ApiKey, ApiKeyStore, ApiKeyStoreApi, ApiKeyUpdate, Contract, ContractSearchParams,
-    ContractStore, ContractStoreApi, ContractUpdate, Session, SessionStore, SessionStoreApi, User,
+    ApiKey, ApiKeyQuota, ApiKeyStore, ApiKeyStoreApi, ApiKeyUpdate, Contract, ContractSearchParams,
+    ContractStore, ContractStoreApi, ContractUpdate, QuotaPeriod, Session, SessionStore,
+    SessionStoreApi, UsageGroupBy, UsageReportParams, UsageStore, UsageStoreApi, UsageTotals, User,
 };
 use aomi_tools::{AomiTool, AomiToolArgs, ToolCallCtx, with_topic};
 use rand::{RngCore, rngs::OsRng};
@@ -95,6 +96,23 @@ fn contract_to_json(row: &Contract) -> Value {
     })
 }
 
+fn usage_totals_to_json(totals: &UsageTotals) -> Value {
+    json!({
+        "llm_requests": totals.llm_requests,
+        "input_tokens": totals.input_tokens,
+        "output_tokens": totals.output_tokens,
+        "total_tokens": totals.total_tokens(),
+        "tool_calls": totals.tool_calls,
+    })
+}
+
+fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
 fn generate_api_key() -> String {
     let mut bytes = [0u8; 32];
     OsRng.fill_bytes(&mut bytes);
@@ -493,6 +511,10 @@ impl AomiTool for AdminDeleteUser {
     type Output = Value;
     type Error = ToolError;
 
+    fn requires_approval(&self) -> bool {
+        true
+    }
+
     fn description(&self) -> &'static str {
         "Delete a user by public key."
     }
@@ -1005,3 +1027,185 @@ impl AomiTool for AdminDeleteContract {
         }
     }
 }
+
+// ============================================================================
+// AdminUsageReport
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageReportArgs {
+    pub group_by: Option<String>,
+    pub api_key: Option<String>,
+    pub public_key: Option<String>,
+    pub session_id: Option<String>,
+    pub days: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+impl AomiToolArgs for UsageReportArgs {
+    fn schema() -> Value {
+        with_topic(json!({
+            "type": "object",
+            "properties": {
+                "group_by": {
+                    "type": "string",
+                    "enum": ["api_key", "public_key", "session"],
+                    "default": "api_key"
+                },
+                "api_key": { "type": "string" },
+                "public_key": { "type": "string" },
+                "session_id": { "type": "string" },
+                "days": { "type": "integer", "description": "Only include the last N days of usage" },
+                "limit": { "type": "integer" }
+            }
+        }))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AdminUsageReport;
+
+impl AomiTool for AdminUsageReport {
+    const NAME: &'static str = "admin_usage_report";
+    const NAMESPACE: &'static str = ADMIN_NAMESPACE;
+
+    type Args = UsageReportArgs;
+    type Output = Value;
+    type Error = ToolError;
+
+    fn description(&self) -> &'static str {
+        "Report LLM token and tool usage grouped by API key, user public key, or session."
+    }
+
+    fn run_sync(
+        &self,
+        _ctx: ToolCallCtx,
+        args: Self::Args,
+    ) -> impl Future<Output = eyre::Result<Value>> + Send {
+        async move {
+            let group_by: UsageGroupBy = args
+                .group_by
+                .as_deref()
+                .unwrap_or("api_key")
+                .parse()
+                .map_err(|e: anyhow::Error| eyre::eyre!(e.to_string()))?;
+            let params = UsageReportParams {
+                api_key: args.api_key,
+                public_key: args.public_key,
+                session_id: args.session_id,
+                since: args.days.map(|days| unix_now() - days * 86_400),
+                until: None,
+                limit: args.limit,
+                offset: None,
+            };
+
+            let pool = admin_pool().await.map_err(|e| eyre::eyre!(e.to_string()))?;
+            let store = UsageStore::new(pool);
+            let rows = store
+                .usage_report(group_by, params)
+                .await
+                .map_err(|e| eyre::eyre!(e.to_string()))?;
+            Ok(json!(
+                rows.iter()
+                    .map(|row| {
+                        let mut value = usage_totals_to_json(&row.totals);
+                        value[group_by.column()] = json!(row.key);
+                        value["first_at"] = json!(row.first_at);
+                        value["last_at"] = json!(row.last_at);
+                        value
+                    })
+                    .collect::<Vec<_>>()
+            ))
+        }
+    }
+}
+
+// ============================================================================
+// AdminSetQuota
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetQuotaArgs {
+    pub api_key: String,
+    pub daily_token_limit: Option<i64>,
+    pub monthly_token_limit: Option<i64>,
+    pub daily_tool_call_limit: Option<i64>,
+    pub monthly_tool_call_limit: Option<i64>,
+}
+
+impl AomiToolArgs for SetQuotaArgs {
+    fn schema() -> Value {
+        with_topic(json!({
+            "type": "object",
+            "properties": {
+                "api_key": { "type": "string" },
+                "daily_token_limit": { "type": "integer", "description": "Max LLM tokens per UTC day (omit for unlimited)" },
+                "monthly_token_limit": { "type": "integer", "description": "Max LLM tokens per UTC month (omit for unlimited)" },
+                "daily_tool_call_limit": { "type": "integer", "description": "Max tool invocations per UTC day (omit for unlimited)" },
+                "monthly_tool_call_limit": { "type": "integer", "description": "Max tool invocations per UTC month (omit for unlimited)" }
+            },
+            "required": ["api_key"]
+        }))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AdminSetQuota;
+
+impl AomiTool for AdminSetQuota {
+    const NAME: &'static str = "admin_set_quota";
+    const NAMESPACE: &'static str = ADMIN_NAMESPACE;
+
+    type Args = SetQuotaArgs;
+    type Output = Value;
+    type Error = ToolError;
+
+    fn description(&self) -> &'static str {
+        "Set daily/monthly token and tool call quotas for an API key, replacing any existing quota."
+    }
+
+    fn run_sync(
+        &self,
+        _ctx: ToolCallCtx,
+        args: Self::Args,
+    ) -> impl Future<Output = eyre::Result<Value>> + Send {
+        async move {
+            let quota = ApiKeyQuota {
+                api_key: args.api_key,
+                daily_token_limit: args.daily_token_limit,
+                monthly_token_limit: args.monthly_token_limit,
+                daily_tool_call_limit: args.daily_tool_call_limit,
+                monthly_tool_call_limit: args.monthly_tool_call_limit,
+                updated_at: None,
+            };
+            let pool = admin_pool().await.map_err(|e| eyre::eyre!(e.to_string()))?;
+            let store = UsageStore::new(pool);
+            let quota = store
+                .set_quota(quota)
+                .await
+                .map_err(|e| eyre::eyre!(e.to_string()))?;
+
+            let now = unix_now();
+            let today = store
+                .usage_totals(&quota.api_key, QuotaPeriod::Daily.start(now))
+                .await
+                .map_err(|e| eyre::eyre!(e.to_string()))?;
+            let this_month = store
+                .usage_totals(&quota.api_key, QuotaPeriod::Monthly.start(now))
+                .await
+                .map_err(|e| eyre::eyre!(e.to_string()))?;
+            Ok(json!({
+                "api_key": quota.api_key,
+                "daily_token_limit": quota.daily_token_limit,
+                "monthly_token_limit": quota.monthly_token_limit,
+                "daily_tool_call_limit": quota.daily_tool_call_limit,
+                "monthly_tool_call_limit": quota.monthly_tool_call_limit,
+                "today": usage_totals_to_json(&today),
+                "this_month": usage_totals_to_json(&this_month),
+                "exceeded": quota
+                    .exceeded(&today, &this_month, now)
+                    .map(|e| e.to_string()),
+            }))
+        }
+    }
+}
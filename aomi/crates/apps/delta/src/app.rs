@@ -283,4 +283,12 @@ impl AomiApp for DeltaRfqApp {
     fn tool_namespaces(&self) -> std::sync::Arc<std::collections::HashMap<String, String>> {
         self.chat_app.tool_namespaces()
     }
+
+    fn context_budget(&self) -> Option<usize> {
+        Some(self.chat_app.context_budget())
+    }
+
+    fn summarizer(&self) -> Option<std::sync::Arc<dyn aomi_core::ConversationSummarizer>> {
+        self.chat_app.summarizer()
+    }
 }
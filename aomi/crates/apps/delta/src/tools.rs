@@ -372,6 +372,10 @@ impl AomiTool for FillQuote {
         false
     }
 
+    fn requires_approval(&self) -> bool {
+        true
+    }
+
     fn description(&self) -> &'static str {
         "Attempt to fill a quote with price feed evidence. The fill will only succeed if it satisfies the quote's Local Law constraints. Requires multiple price feed sources as evidence that the fill price is valid."
     }
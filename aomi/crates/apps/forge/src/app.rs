@@ -1,4 +1,4 @@
-use crate::tools::{NextGroups, SetExecutionPlan};
+use crate::tools::{NextGroups, SetExecutionPlan, TraceTransaction};
 use aomi_core::{
     AomiModel, BuildOpts, CoreApp, CoreAppBuilder, Selection,
     app::{AomiApp, CoreCommand, CoreCtx, CoreState},
@@ -15,6 +15,7 @@ const FORGE_ROLE: &str = "You are an AI assistant specialized in generating Ethe
 const FORGE_TOOLS: &[&str] = &[
     "set_execution_plan - Initialize an execution plan with operation groups and dependencies",
     "next_groups - Execute the next batch of ready groups and get transactions",
+    "trace_transaction - Replay a transaction hash or call on a fork and return its decoded call tree and revert reason",
 ];
 
 const FORGE_WORKFLOW: &[&str] = &[
@@ -61,6 +62,7 @@ const KEY_PRINCIPLES: &[&str] = &[
     "Transparency: Show generated Solidity code and explain what will happen",
     "Interface-Based: Operations reference standard interfaces (IERC20, IWETH, etc.) - contracts are fetched automatically",
     "Error Handling: If a group fails, stop and explain the error. Do not create another plan without user confirmation",
+    "Failure Analysis: When a user's transaction reverted, call trace_transaction with its hash and explain the failing call and revert reason",
 ];
 
 fn forge_preamble() -> String {
@@ -116,6 +118,7 @@ impl ForgeApp {
         if !opts.no_tools {
             builder.add_tool(SetExecutionPlan)?;
             builder.add_tool(NextGroups)?;
+            builder.add_tool(TraceTransaction)?;
         }
 
         // Build the final ForgeApp
@@ -151,4 +154,12 @@ impl AomiApp for ForgeApp {
     fn tool_namespaces(&self) -> std::sync::Arc<std::collections::HashMap<String, String>> {
         self.chat_app.tool_namespaces()
     }
+
+    fn context_budget(&self) -> Option<usize> {
+        Some(self.chat_app.context_budget())
+    }
+
+    fn summarizer(&self) -> Option<std::sync::Arc<dyn aomi_core::ConversationSummarizer>> {
+        self.chat_app.summarizer()
+    }
 }
@@ -2,9 +2,12 @@ use aomi_tools::{AomiTool, AomiToolArgs, ToolCallCtx, with_topic};
 use rig::tool::ToolError;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
+use std::str::FromStr;
 use std::sync::Arc;
 
+use alloy_primitives::{Address, Bytes, U256};
 use aomi_scripts::forge_executor::{ForgeManager, GroupResult, OperationGroup};
+use aomi_scripts::{TraceRequest, TraceTarget, trace_transaction};
 
 use tokio::sync::{OnceCell, mpsc};
 
@@ -220,6 +223,11 @@ impl AomiTool for NextGroups {
         true
     }
 
+    // Re-running picks up from the plan's group statuses, so no cursor is needed
+    fn is_resumable(&self) -> bool {
+        true
+    }
+
     fn description(&self) -> &'static str {
         "[Async Tool] Execute the next batch of ready operation groups for a plan id (groups whose dependencies are satisfied). Returns transaction data and generated Solidity code for each group, returns asynchronously after the groups are executed."
     }
@@ -263,9 +271,139 @@ impl AomiTool for NextGroups {
     }
 }
 
+/// Parameters for TraceTransaction tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceTransactionParameters {
+    /// Hash of a mined transaction to replay
+    pub tx_hash: Option<String>,
+    /// Sender of a call to simulate
+    pub from: Option<String>,
+    /// Target of a call to simulate
+    pub to: Option<String>,
+    /// Value in wei sent with the simulated call
+    pub value: Option<String>,
+    /// 0x-prefixed calldata of the simulated call
+    pub input: Option<String>,
+    /// Chain to replay on (defaults to the primary network)
+    pub chain_id: Option<u64>,
+}
+
+impl AomiToolArgs for TraceTransactionParameters {
+    fn schema() -> Value {
+        with_topic(json!({
+            "type": "object",
+            "properties": {
+                "tx_hash": {
+                    "type": "string",
+                    "description": "Hash of a mined transaction to replay. Omit to trace a call instead."
+                },
+                "from": {
+                    "type": "string",
+                    "description": "Sender address of the call to trace (required without tx_hash)"
+                },
+                "to": {
+                    "type": "string",
+                    "description": "Contract address of the call to trace (required without tx_hash)"
+                },
+                "value": {
+                    "type": "string",
+                    "description": "Wei sent with the call (default 0)"
+                },
+                "input": {
+                    "type": "string",
+                    "description": "0x-prefixed calldata of the call. Use encode_function_call output."
+                },
+                "chain_id": {
+                    "type": "number",
+                    "description": "Chain ID to replay on (default: primary network)"
+                }
+            }
+        }))
+    }
+}
+
+fn parse_arg<T: FromStr>(name: &str, value: &str) -> Result<T, ToolError>
+where
+    T::Err: std::fmt::Display,
+{
+    T::from_str(value.trim())
+        .map_err(|e| ToolError::ToolCallError(format!("Invalid {name} '{value}': {e}").into()))
+}
+
+fn trace_request(args: TraceTransactionParameters) -> Result<TraceRequest, ToolError> {
+    let target = match (args.tx_hash, args.from, args.to) {
+        (Some(hash), None, None) => TraceTarget::Mined(parse_arg("tx_hash", &hash)?),
+        (None, Some(from), Some(to)) => TraceTarget::Call {
+            from: parse_arg::<Address>("from", &from)?,
+            to: parse_arg::<Address>("to", &to)?,
+            value: match args.value {
+                Some(value) => parse_arg::<U256>("value", &value)?,
+                None => U256::ZERO,
+            },
+            input: match args.input {
+                Some(input) => parse_arg::<Bytes>("input", &input)?,
+                None => Bytes::new(),
+            },
+        },
+        _ => {
+            return Err(ToolError::ToolCallError(
+                "Provide either tx_hash, or from and to (with optional value and input)".into(),
+            ));
+        }
+    };
+
+    Ok(TraceRequest {
+        chain_id: args.chain_id,
+        target,
+    })
+}
+
+/// Tool for replaying a transaction and explaining its outcome
+#[derive(Debug, Clone)]
+pub struct TraceTransaction;
+
+async fn build_trace_transaction_result(
+    args: TraceTransactionParameters,
+) -> Result<Value, ToolError> {
+    let request = trace_request(args)?;
+    let report = trace_transaction(request).await.map_err(|e| {
+        ToolError::ToolCallError(format!("Failed to trace transaction: {}", e).into())
+    })?;
+    serde_json::to_value(report)
+        .map_err(|e| ToolError::ToolCallError(format!("Failed to serialize trace: {}", e).into()))
+}
+
+impl AomiTool for TraceTransaction {
+    const NAME: &'static str = "trace_transaction";
+
+    type Args = TraceTransactionParameters;
+    type Output = Value;
+    type Error = ToolError;
+
+    fn description(&self) -> &'static str {
+        "Replay a mined transaction (by hash) or a not-yet-sent call on a fork and return its decoded call tree: internal calls, emitted events, return values, storage writes and the revert reason. Use it to explain why a transaction failed or what it did."
+    }
+
+    fn run_sync(
+        &self,
+        _ctx: ToolCallCtx,
+        request: Self::Args,
+    ) -> impl std::future::Future<Output = eyre::Result<Value>> + Send {
+        async move {
+            build_trace_transaction_result(request)
+                .await
+                .map_err(|e| eyre::eyre!(e.to_string()))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{NextGroups, NextGroupsParameters, SetExecutionPlan, SetExecutionPlanParameters};
+    use super::{
+        NextGroups, NextGroupsParameters, SetExecutionPlan, SetExecutionPlanParameters,
+        TraceTransactionParameters, trace_request,
+    };
+    use aomi_scripts::TraceTarget;
     use aomi_scripts::forge_executor::OperationGroup;
     use aomi_tools::{AomiTool, CallMetadata, ToolCallCtx};
     use serde_json::Value;
@@ -285,6 +423,7 @@ mod tests {
             ),
             user_chain_id: None,
             user_address: None,
+            policy_scope: Default::default(),
         };
         tool.run_sync(ctx, args)
             .await
@@ -437,4 +576,41 @@ mod tests {
             assert!(parsed["remaining_groups"].is_number());
         }
     }
+
+    #[test]
+    fn test_trace_request_parsing() {
+        let params = |tx_hash: Option<&str>, from: Option<&str>, to: Option<&str>| {
+            TraceTransactionParameters {
+                tx_hash: tx_hash.map(str::to_string),
+                from: from.map(str::to_string),
+                to: to.map(str::to_string),
+                value: None,
+                input: None,
+                chain_id: Some(1),
+            }
+        };
+        let hash = "0x5c504ed432cb51138bcf09aa5e8a410dd4a1e204ef84bfed1be16dfba1b22060";
+        let from = "0x0000000000000000000000000000000000000001";
+        let to = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+
+        let mined = trace_request(params(Some(hash), None, None)).expect("mined tx");
+        assert_eq!(mined.chain_id, Some(1));
+        assert!(matches!(mined.target, TraceTarget::Mined(h) if h.to_string() == hash));
+
+        let mut call_params = params(None, Some(from), Some(to));
+        call_params.value = Some("1000".to_string());
+        call_params.input = Some("0xd0e30db0".to_string());
+        match trace_request(call_params).expect("call").target {
+            TraceTarget::Call { value, input, .. } => {
+                assert_eq!(value, alloy_primitives::U256::from(1000));
+                assert_eq!(input.to_vec(), vec![0xd0, 0xe3, 0x0d, 0xb0]);
+            }
+            other => panic!("expected a call, got {other:?}"),
+        }
+
+        assert!(trace_request(params(None, None, None)).is_err());
+        assert!(trace_request(params(Some(hash), Some(from), Some(to))).is_err());
+        assert!(trace_request(params(None, Some(from), None)).is_err());
+        assert!(trace_request(params(Some("0x1234"), None, None)).is_err());
+    }
 }
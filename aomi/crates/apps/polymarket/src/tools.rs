@@ -2,16 +2,26 @@
 #![allow(clippy::manual_async_fn)]
 
 use crate::client::{GetMarketsParams, GetTradesParams, PolymarketClient, SubmitOrderRequest};
-use aomi_tools::{AomiTool, AomiToolArgs, ToolCallCtx, WithTopic};
+use aomi_tools::{AomiTool, AomiToolArgs, CallPolicy, RetryPolicy, ToolCallCtx, WithTopic};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::sync::LazyLock;
+use std::time::Duration;
 use tokio::sync::Mutex;
 
 // Global client instance
 static POLYMARKET_CLIENT: LazyLock<Mutex<PolymarketClient>> =
     LazyLock::new(|| Mutex::new(PolymarketClient::new().expect("Failed to create client")));
 
+/// Read-only Gamma/Data API lookups, safe to retry
+fn read_call_policy() -> CallPolicy {
+    CallPolicy::default()
+        .with_timeout(Duration::from_secs(20))
+        .with_retry(RetryPolicy::exponential(2, Duration::from_secs(1)))
+        .idempotent()
+        .with_upstream("polymarket")
+}
+
 // ============================================================================
 // Tool 1: Get Markets
 // ============================================================================
@@ -87,6 +97,10 @@ impl AomiTool for GetMarkets {
         "Query Polymarket prediction markets with filtering options. Returns a list of markets with their current prices, volumes, liquidity, and other metadata."
     }
 
+    fn call_policy(&self) -> CallPolicy {
+        read_call_policy()
+    }
+
     fn run_sync(
         &self,
         _ctx: ToolCallCtx,
@@ -178,6 +192,10 @@ impl AomiTool for GetMarketDetails {
         "Get detailed information about a specific Polymarket prediction market by its ID or slug."
     }
 
+    fn call_policy(&self) -> CallPolicy {
+        read_call_policy()
+    }
+
     fn run_sync(
         &self,
         _ctx: ToolCallCtx,
@@ -281,6 +299,10 @@ impl AomiTool for GetTrades {
         "Retrieve historical trades from Polymarket. Returns trade history with timestamps, prices, sizes, and user information."
     }
 
+    fn call_policy(&self) -> CallPolicy {
+        read_call_policy()
+    }
+
     fn run_sync(
         &self,
         _ctx: ToolCallCtx,
@@ -404,10 +426,21 @@ impl AomiTool for PlacePolymarketOrder {
         false
     }
 
+    fn requires_approval(&self) -> bool {
+        true
+    }
+
     fn description(&self) -> &'static str {
         "Submit a signed Polymarket order to the CLOB API. Provide the wallet address that signed, the 0x signature string, and the order JSON."
     }
 
+    fn call_policy(&self) -> CallPolicy {
+        // Submitting an order twice could double-fill, so it is never retried
+        CallPolicy::default()
+            .with_timeout(Duration::from_secs(30))
+            .with_upstream("polymarket")
+    }
+
     fn run_sync(
         &self,
         _ctx: ToolCallCtx,
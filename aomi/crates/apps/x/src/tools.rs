@@ -1,10 +1,11 @@
 #![allow(clippy::manual_async_fn)]
 
 use crate::client::XClient;
-use aomi_tools::{AomiTool, AomiToolArgs, ToolCallCtx, WithTopic};
+use aomi_tools::{AomiTool, AomiToolArgs, CallPolicy, RetryPolicy, ToolCallCtx, WithTopic};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::sync::LazyLock;
+use std::time::Duration;
 use tokio::sync::Mutex;
 
 // Global client instance
@@ -21,6 +22,15 @@ async fn get_client() -> eyre::Result<XClient> {
     Ok(client)
 }
 
+/// All X tools are read-only lookups against the same API
+fn x_call_policy() -> CallPolicy {
+    CallPolicy::default()
+        .with_timeout(Duration::from_secs(20))
+        .with_retry(RetryPolicy::exponential(2, Duration::from_secs(1)))
+        .idempotent()
+        .with_upstream("x")
+}
+
 // ============================================================================
 // Tool 1: Get X User
 // ============================================================================
@@ -67,6 +77,10 @@ impl AomiTool for GetXUser {
         "Get an X (Twitter) user's profile information by username. Returns follower count, bio, verification status, and more."
     }
 
+    fn call_policy(&self) -> CallPolicy {
+        x_call_policy()
+    }
+
     fn run_sync(
         &self,
         _ctx: ToolCallCtx,
@@ -151,6 +165,10 @@ impl AomiTool for GetXUserPosts {
         "Get recent posts from an X (Twitter) user. Returns post text, engagement metrics, and metadata."
     }
 
+    fn call_policy(&self) -> CallPolicy {
+        x_call_policy()
+    }
+
     fn run_sync(
         &self,
         _ctx: ToolCallCtx,
@@ -235,6 +253,10 @@ impl AomiTool for SearchX {
         "Search for posts on X (Twitter) using advanced query operators. Supports filtering by user, hashtag, date range, and engagement metrics."
     }
 
+    fn call_policy(&self) -> CallPolicy {
+        x_call_policy()
+    }
+
     fn run_sync(
         &self,
         _ctx: ToolCallCtx,
@@ -300,6 +322,10 @@ impl AomiTool for GetXTrends {
         "Get current trending topics on X (Twitter). Returns trend names and post counts."
     }
 
+    fn call_policy(&self) -> CallPolicy {
+        x_call_policy()
+    }
+
     fn run_sync(
         &self,
         _ctx: ToolCallCtx,
@@ -376,6 +402,10 @@ impl AomiTool for GetXPost {
         "Get details of a specific X (Twitter) post by its ID. Returns full post content, engagement metrics, and author info."
     }
 
+    fn call_policy(&self) -> CallPolicy {
+        x_call_policy()
+    }
+
     fn run_sync(
         &self,
         _ctx: ToolCallCtx,
@@ -1,4 +1,5 @@
 use aomi_baml::baml_client::{async_client::B, types::ChatMessage as BamlChatMessage};
+use aomi_tools::metrics::metrics;
 use dashmap::DashMap;
 use serde_json::{json, Value};
 use std::{
@@ -31,6 +32,8 @@ pub struct BackgroundTasks {
 
 impl BackgroundTasks {
     const SYSTEM_UPDATE_BUFFER: usize = 64;
+    /// How long non-resumable tool calls get to finish when a session is evicted
+    const TOOL_PERSIST_TIMEOUT_SECS: u64 = 5;
 
     /// Create a new BackgroundTasks instance with cloned references to shared state
     pub fn new(
@@ -127,6 +130,24 @@ impl BackgroundTasks {
                 continue;
             };
 
+            // Persist the rolling context summary alongside the history
+            let state = self
+                .sessions
+                .get(session_id)
+                .map(|entry| entry.value().state.clone());
+            if let Some(state) = state {
+                let compaction = state.lock().await.compaction().await;
+                if let Some(compaction) = compaction {
+                    if let Err(e) = self
+                        .history_backend
+                        .save_context_summary(session_id, &compaction)
+                        .await
+                    {
+                        error!(session_id, error = %e, "Failed to save context summary");
+                    }
+                }
+            }
+
             // Try to flush - only mark successful if flush succeeds
             match self.history_backend.flush_history(&pk, session_id).await {
                 Ok(()) => {
@@ -141,6 +162,32 @@ impl BackgroundTasks {
 
         // Step 3: Only remove sessions that were successfully flushed
         for session_id in successfully_flushed {
+            // Stop the session's tool calls, keeping resumable ones for the next rehydrate
+            let session = self
+                .sessions
+                .get(&session_id)
+                .map(|entry| (entry.value().state.clone(), entry.metadata.memory_mode));
+            if let Some((state, memory_mode)) = session {
+                match state
+                    .lock()
+                    .await
+                    .close_tool_handler(Self::TOOL_PERSIST_TIMEOUT_SECS)
+                    .await
+                {
+                    Ok(Some(handler_state)) if !memory_mode => {
+                        if let Err(e) = self
+                            .history_backend
+                            .save_tool_state(&session_id, &handler_state)
+                            .await
+                        {
+                            error!(session_id, error = %e, "Failed to save tool state");
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => error!(session_id, error = %e, "Failed to persist tool handler"),
+                }
+            }
+
             self.sessions.remove(&session_id);
             debug!(session_id, "Cleaned up inactive session");
 
@@ -175,7 +222,11 @@ impl BackgroundTasks {
                     if let Some(obj) = value.as_object_mut() {
                         obj.insert("session_id".to_string(), json!(session_id.clone()));
                     }
-                    let _ = self.system_update_tx.send((session_id.clone(), value));
+                    let subscribers = self
+                        .system_update_tx
+                        .send((session_id.clone(), value))
+                        .unwrap_or(0);
+                    metrics().record_sse_broadcast(subscribers);
                 }
             }
         }
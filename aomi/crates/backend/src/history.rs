@@ -5,8 +5,9 @@ use aomi_baml::baml_client::{
     async_client::B,
     types::{ChatMessage as BamlChatMessage, ConversationSummary},
 };
-use aomi_core::{prompts::create_summary_content, Message};
-use aomi_tools::db::{Session, SessionStore, SessionStoreApi};
+use aomi_core::{prompts::create_summary_content, ConversationCompaction, Message};
+use aomi_tools::db::{Session, SessionParent, SessionStore, SessionStoreApi};
+use aomi_tools::scheduler::PersistedHandlerState;
 use dashmap::DashMap;
 use sqlx::{Any, Pool};
 
@@ -111,6 +112,68 @@ pub trait HistoryBackend: Send + Sync {
         Ok(())
     }
 
+    /// Persists the rolling context summary of a compacted session.
+    /// Default implementation is a no-op for non-persistent backends.
+    async fn save_context_summary(
+        &self,
+        session_id: &str,
+        compaction: &ConversationCompaction,
+    ) -> Result<()> {
+        let _ = (session_id, compaction);
+        Ok(())
+    }
+
+    /// Loads the rolling context summary saved for a session, if any.
+    /// Default implementation returns None (no-op for non-persistent backends).
+    async fn get_context_summary(
+        &self,
+        session_id: &str,
+    ) -> Result<Option<ConversationCompaction>> {
+        let _ = session_id;
+        Ok(None)
+    }
+
+    /// Persists the tool handler snapshot (unconsumed results, resumable calls) of a session.
+    /// Default implementation is a no-op for non-persistent backends.
+    async fn save_tool_state(&self, session_id: &str, state: &PersistedHandlerState) -> Result<()> {
+        let _ = (session_id, state);
+        Ok(())
+    }
+
+    /// Loads the tool handler snapshot saved for a session, if any.
+    /// Default implementation returns None (no-op for non-persistent backends).
+    async fn get_tool_state(&self, session_id: &str) -> Result<Option<PersistedHandlerState>> {
+        let _ = session_id;
+        Ok(None)
+    }
+
+    /// Persists a forked session with a link to the session it was forked from.
+    /// Default implementation is a no-op for non-persistent backends.
+    async fn save_session_fork(
+        &self,
+        session_id: &str,
+        public_key: Option<&str>,
+        title: &str,
+        parent: &SessionParent,
+    ) -> Result<()> {
+        let _ = (session_id, public_key, title, parent);
+        Ok(())
+    }
+
+    /// Loads the parent link of a forked session, if any.
+    /// Default implementation returns None (no-op for non-persistent backends).
+    async fn get_session_parent(&self, session_id: &str) -> Result<Option<SessionParent>> {
+        let _ = session_id;
+        Ok(None)
+    }
+
+    /// Drops persisted messages after the first `keep` (used when a message is edited).
+    /// Default implementation is a no-op for non-persistent backends.
+    async fn truncate_history(&self, session_id: &str, keep: usize) -> Result<()> {
+        let _ = (session_id, keep);
+        Ok(())
+    }
+
     /// Persists a session's title change to storage (if supported).
     async fn update_session_title(&self, session_id: &str, title: &str) -> Result<()>;
 
@@ -408,6 +471,116 @@ impl HistoryBackend for PersistentHistoryBackend {
             .await
     }
 
+    async fn save_context_summary(
+        &self,
+        session_id: &str,
+        compaction: &ConversationCompaction,
+    ) -> Result<()> {
+        if self.db.get_session(session_id).await?.is_none() {
+            tracing::debug!(
+                "Session {} does not exist in database, skipping context summary",
+                session_id
+            );
+            return Ok(());
+        }
+
+        self.db
+            .update_context_summary(session_id, Some(serde_json::to_value(compaction)?))
+            .await
+    }
+
+    async fn get_context_summary(
+        &self,
+        session_id: &str,
+    ) -> Result<Option<ConversationCompaction>> {
+        let Some(value) = self.db.get_context_summary(session_id).await? else {
+            return Ok(None);
+        };
+        match serde_json::from_value(value) {
+            Ok(compaction) => Ok(Some(compaction)),
+            Err(e) => {
+                tracing::warn!(
+                    "Ignoring malformed context summary for {}: {}",
+                    session_id,
+                    e
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    async fn save_tool_state(&self, session_id: &str, state: &PersistedHandlerState) -> Result<()> {
+        if self.db.get_session(session_id).await?.is_none() {
+            tracing::debug!(
+                "Session {} does not exist in database, skipping tool state",
+                session_id
+            );
+            return Ok(());
+        }
+
+        self.db
+            .update_tool_state(session_id, Some(serde_json::to_value(state)?))
+            .await
+    }
+
+    async fn get_tool_state(&self, session_id: &str) -> Result<Option<PersistedHandlerState>> {
+        let Some(value) = self.db.get_tool_state(session_id).await? else {
+            return Ok(None);
+        };
+        match serde_json::from_value(value) {
+            Ok(state) => Ok(Some(state)),
+            Err(e) => {
+                tracing::warn!("Ignoring malformed tool state for {}: {}", session_id, e);
+                Ok(None)
+            }
+        }
+    }
+
+    async fn save_session_fork(
+        &self,
+        session_id: &str,
+        public_key: Option<&str>,
+        title: &str,
+        parent: &SessionParent,
+    ) -> Result<()> {
+        if let Some(pk) = public_key {
+            let _ = self.db.get_or_create_user(pk).await?;
+        }
+
+        if self.db.get_session(session_id).await?.is_none() {
+            let now = chrono::Utc::now().timestamp();
+            self.db
+                .create_session(&Session {
+                    id: session_id.to_string(),
+                    public_key: public_key.map(String::from),
+                    started_at: now,
+                    last_active_at: now,
+                    title: Some(title.to_string()),
+                    pending_transaction: None,
+                })
+                .await?;
+        }
+
+        self.db
+            .update_session_parent(session_id, Some(parent))
+            .await
+    }
+
+    async fn get_session_parent(&self, session_id: &str) -> Result<Option<SessionParent>> {
+        self.db.get_session_parent(session_id).await
+    }
+
+    async fn truncate_history(&self, session_id: &str, keep: usize) -> Result<()> {
+        let keep = keep.min(i64::MAX as usize) as i64;
+        let deleted = self.db.truncate_messages(session_id, keep).await?;
+        tracing::debug!(
+            "Truncated {} persisted messages of session {}",
+            deleted,
+            session_id
+        );
+        Ok(())
+    }
+
     async fn update_session_title(&self, session_id: &str, title: &str) -> Result<()> {
         // Only update if session exists in database
         if self.db.get_session(session_id).await?.is_none() {
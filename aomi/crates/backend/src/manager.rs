@@ -1,5 +1,6 @@
 use anyhow::Result;
 use aomi_core::BuildOpts;
+use aomi_tools::{db::SessionParent, ethereum::PolicyScope, scheduler::PersistedHandlerState};
 use dashmap::DashMap;
 use serde_json::Value;
 use std::{collections::HashMap, sync::Arc, time::Instant};
@@ -13,7 +14,7 @@ use crate::{
     build_backends,
     history::{HistoryBackend, DEFAULT_TITLE},
     namespace::{Namespace, Selection},
-    types::{AomiBackend, ChatMessage, DefaultSessionState, SessionRecord},
+    types::{AomiBackend, ChatMessage, DefaultSessionState, MessageSender, SessionRecord},
 };
 
 const SESSION_LIST_LIMIT: usize = i32::MAX as usize;
@@ -147,6 +148,9 @@ impl SessionManager {
     ) -> Result<Self> {
         tracing::info!("Initializing backends...");
 
+        // Fail startup on a broken models.toml instead of running on the defaults
+        aomi_baml::load_model_registry()?;
+
         let selection = Selection::default();
         let backends = build_backends(vec![
             (
@@ -228,12 +232,41 @@ impl SessionManager {
             .map(|entry| Arc::clone(entry.value()))
             .expect("backend should exist after ensure_backend");
 
-        let current_messages = {
+        let (current_messages, compaction, scheduler_id, handler_state) = {
             let guard = state.lock().await;
-            guard.messages.clone()
+            // Release the old tool handler; its results and resumable calls move to the
+            // new backend's tool set
+            let handler_state = match guard.close_tool_handler(0).await {
+                Ok(handler_state) => handler_state,
+                Err(e) => {
+                    error!(error = %e, "Failed to release tool handler");
+                    None
+                }
+            };
+            (
+                guard.messages.clone(),
+                guard.compaction().await,
+                guard.scheduler_id().to_string(),
+                handler_state,
+            )
         };
 
-        let session_state = DefaultSessionState::new(backend, current_messages).await?;
+        let mut session_state = match handler_state {
+            Some(handler_state) => {
+                DefaultSessionState::switched(
+                    backend,
+                    current_messages,
+                    scheduler_id,
+                    handler_state,
+                )
+                .await?
+            }
+            None => {
+                DefaultSessionState::for_session(backend, current_messages, scheduler_id).await?
+            }
+        };
+        // Keep the rolling context summary across backend switches
+        session_state.restore_compaction(compaction).await;
 
         {
             let mut guard = state.lock().await;
@@ -350,6 +383,234 @@ impl SessionManager {
             .map(|entry| (entry.namespace, entry.selection))
     }
 
+    /// Whether `session_id` is live in memory or persisted in storage.
+    pub async fn session_exists(&self, session_id: &str) -> Result<bool> {
+        if self.sessions.contains_key(session_id) {
+            return Ok(true);
+        }
+        Ok(self
+            .history_backend
+            .get_session(session_id)
+            .await?
+            .is_some())
+    }
+
+    /// Fork `source_id` into a new session holding its messages up to and including
+    /// `message_index`.
+    ///
+    /// The fork keeps the source's namespace, model selection, wallet state, policy scope
+    /// and tool handler state, and is persisted with a link to its parent. In-flight tool
+    /// calls are not copied so their side effects only happen once. The rolling context
+    /// summary is not copied either, since it may cover messages after the fork point.
+    ///
+    /// A source that is only persisted (e.g. after a restart) is first loaded the way a
+    /// chat request would load it, under the namespace `auth` is authorized for.
+    pub async fn fork_session(
+        &self,
+        source_id: &str,
+        new_session_id: &str,
+        message_index: usize,
+        auth: &mut NamespaceAuth,
+    ) -> Result<Arc<Mutex<DefaultSessionState>>> {
+        if self.session_exists(new_session_id).await? {
+            return Err(anyhow::anyhow!(
+                "Session already exists: {}",
+                new_session_id
+            ));
+        }
+
+        if !self.sessions.contains_key(source_id) {
+            if !self.session_exists(source_id).await? {
+                return Err(anyhow::anyhow!("Session not found: {}", source_id));
+            }
+            self.get_or_create_session(source_id, auth, None).await?;
+        }
+
+        let (source_state, namespace, selection, title) = {
+            let source = self
+                .sessions
+                .get(source_id)
+                .ok_or_else(|| anyhow::anyhow!("Session not found: {}", source_id))?;
+            (
+                source.state.clone(),
+                source.namespace,
+                source.selection,
+                source.metadata.title.clone(),
+            )
+        };
+
+        let (messages, user_state, mut handler_state, policy_scope) = {
+            let source = source_state.lock().await;
+            if message_index >= source.messages.len() {
+                return Err(anyhow::anyhow!(
+                    "Message index {} out of range ({} messages)",
+                    message_index,
+                    source.messages.len()
+                ));
+            }
+            let mut messages = source.messages[..=message_index].to_vec();
+            for message in messages.iter_mut() {
+                message.is_streaming = false;
+            }
+            (
+                messages,
+                source.user_state().await,
+                source.handler_state().await,
+                source.policy_scope.read().await.clone(),
+            )
+        };
+        handler_state.pending_calls.clear();
+
+        self.ensure_backend(namespace, selection).await?;
+        let backend = self
+            .backends
+            .get(&(namespace, selection))
+            .map(|entry| Arc::clone(entry.value()))
+            .expect("backend should exist after ensure_backend");
+
+        let mut state = DefaultSessionState::restored(
+            backend,
+            messages.clone(),
+            format!("session_{}", new_session_id),
+            handler_state,
+        )
+        .await?;
+        state.sync_user_state(user_state).await;
+        state
+            .set_policy_scope(PolicyScope {
+                session_id: Some(new_session_id.to_string()),
+                ..policy_scope
+            })
+            .await;
+
+        let metadata = SessionMetadata {
+            title: title.clone(),
+            title_renewal_stamp: messages.len(),
+            ..SessionMetadata::default()
+        };
+        let state = Arc::new(Mutex::new(state));
+        self.sessions.insert(
+            new_session_id.to_string(),
+            SessionData {
+                state: state.clone(),
+                last_activity: Instant::now(),
+                namespace,
+                selection,
+                metadata,
+            },
+        );
+
+        let public_key = self.get_public_key(source_id);
+        if let Some(pk) = &public_key {
+            self.session_public_keys
+                .insert(new_session_id.to_string(), pk.clone());
+        }
+        let parent = SessionParent {
+            session_id: source_id.to_string(),
+            message_index: message_index as i64,
+        };
+        if let Err(e) = self
+            .history_backend
+            .save_session_fork(new_session_id, public_key.as_deref(), &title, &parent)
+            .await
+        {
+            error!(session_id = new_session_id, error = %e, "Failed to persist session fork");
+        }
+        self.update_user_history(new_session_id, &messages).await;
+
+        debug!(
+            source_id,
+            session_id = new_session_id,
+            message_index,
+            "Forked session"
+        );
+        Ok(state)
+    }
+
+    /// Replace the user message at `message_index` with `content`, drop every message
+    /// after it and regenerate the reply.
+    ///
+    /// The session is rebuilt over the truncated history so the agent forgets the
+    /// dropped turns; tool handler, wallet state and policy scope carry over.
+    pub async fn edit_and_resend(
+        &self,
+        session_id: &str,
+        message_index: usize,
+        content: String,
+    ) -> Result<Arc<Mutex<DefaultSessionState>>> {
+        let (state, namespace, selection) = self
+            .sessions
+            .get(session_id)
+            .map(|entry| (entry.state.clone(), entry.namespace, entry.selection))
+            .ok_or_else(|| anyhow::anyhow!("Session not found: {}", session_id))?;
+
+        self.ensure_backend(namespace, selection).await?;
+        let backend = self
+            .backends
+            .get(&(namespace, selection))
+            .map(|entry| Arc::clone(entry.value()))
+            .expect("backend should exist after ensure_backend");
+
+        let persisted_kept = {
+            let mut guard = state.lock().await;
+            match guard.messages.get(message_index) {
+                Some(message) if matches!(message.sender, MessageSender::User) => {}
+                Some(_) => {
+                    return Err(anyhow::anyhow!(
+                        "Message {} is not a user message",
+                        message_index
+                    ))
+                }
+                None => {
+                    return Err(anyhow::anyhow!(
+                        "Message index {} out of range ({} messages)",
+                        message_index,
+                        guard.messages.len()
+                    ))
+                }
+            }
+            guard.interrupt_processing().await?;
+
+            let history = guard.messages[..message_index].to_vec();
+            let rebuilt = guard.rebuild(backend, history.clone()).await?;
+            guard.shutdown();
+            *guard = rebuilt;
+            guard.send_user_input(content).await?;
+
+            // System messages are never persisted, so they don't count towards stored rows
+            history
+                .iter()
+                .filter(|m| !matches!(m.sender, MessageSender::System))
+                .count()
+        };
+
+        let hydrated = self
+            .sessions
+            .get(session_id)
+            .map(|entry| entry.metadata.db_hydrated_cnt)
+            .unwrap_or(0);
+        if message_index < hydrated {
+            if let Err(e) = self
+                .history_backend
+                .truncate_history(session_id, persisted_kept)
+                .await
+            {
+                error!(session_id, error = %e, "Failed to truncate persisted history");
+            }
+            if let Some(mut session_data) = self.sessions.get_mut(session_id) {
+                session_data.metadata.db_hydrated_cnt = message_index;
+            }
+        }
+
+        debug!(session_id, message_index, "Edited message and resent");
+        Ok(state)
+    }
+
+    /// Parent link of a forked session, if it has one.
+    pub async fn get_session_parent(&self, session_id: &str) -> Result<Option<SessionParent>> {
+        self.history_backend.get_session_parent(session_id).await
+    }
+
     /// Create session data and insert into the sessions map
     async fn create_session(
         &self,
@@ -358,6 +619,7 @@ impl SessionManager {
         selection: Selection,
         messages: Vec<ChatMessage>,
         metadata: SessionMetadata,
+        handler_state: Option<PersistedHandlerState>,
     ) -> anyhow::Result<Arc<Mutex<DefaultSessionState>>> {
         debug!(
             session_id,
@@ -375,7 +637,15 @@ impl SessionManager {
             .map(|entry| Arc::clone(entry.value()))
             .expect("backend should exist after ensure_backend");
 
-        let session_state = DefaultSessionState::new(backend, messages).await?;
+        // Each session owns its tool handler so its state can be persisted and restored
+        let scheduler_id = format!("session_{}", session_id);
+        let session_state = match handler_state {
+            Some(handler_state) => {
+                DefaultSessionState::restored(backend, messages, scheduler_id, handler_state)
+                    .await?
+            }
+            None => DefaultSessionState::for_session(backend, messages, scheduler_id).await?,
+        };
 
         let session_data = SessionData {
             state: Arc::new(Mutex::new(session_state)),
@@ -455,6 +725,7 @@ impl SessionManager {
                 session_data.last_activity = Instant::now();
             }
 
+            Self::apply_policy_scope(&state, session_id, new_namespace, auth).await;
             return Ok(state);
         }
 
@@ -462,29 +733,12 @@ impl SessionManager {
         let selection = selection.unwrap_or_default();
 
         // 2. Try to load from DB
-        if let Some(stored) = self.history_backend.get_session(session_id).await? {
-            // Restore public key mapping if available
-            if let Some(pk) = stored.public_key.clone() {
-                if self.session_public_keys.get(session_id).is_none() {
-                    self.session_public_keys.insert(session_id.to_string(), pk);
-                }
-            }
-
-            let namespace = requested_backend.unwrap_or(Namespace::Default);
-            // Always start with "New Chat" - title will be regenerated from messages
-            let metadata = SessionMetadata {
-                title: DEFAULT_TITLE.to_string(),
-                title_renewal_stamp: 0, // Force title regeneration
-                db_hydrated_cnt: stored.messages.len(),
-                is_archived: false,
-                memory_mode: false,
-            };
-
-            let state = self
-                .create_session(session_id, namespace, selection, stored.messages, metadata)
-                .await?;
-
-            debug!(session_id, "Rehydrated session from storage");
+        let namespace = requested_backend.unwrap_or(Namespace::Default);
+        if let Some(state) = self
+            .rehydrate_session(session_id, namespace, selection)
+            .await?
+        {
+            Self::apply_policy_scope(&state, session_id, namespace, auth).await;
             return Ok(state);
         }
 
@@ -502,17 +756,98 @@ impl SessionManager {
                 .await?;
         }
 
-        let namespace = requested_backend.unwrap_or(Namespace::Default);
         let metadata = SessionMetadata::default();
 
         let new_session = self
-            .create_session(session_id, namespace, selection, Vec::new(), metadata)
+            .create_session(session_id, namespace, selection, Vec::new(), metadata, None)
             .await?;
 
+        Self::apply_policy_scope(&new_session, session_id, namespace, auth).await;
         debug!(session_id, ?namespace, "Created new session");
         Ok(new_session)
     }
 
+    /// Load a persisted session into memory under `namespace` and `selection`.
+    /// Returns `None` when storage has no such session. Callers apply their own
+    /// policy scope afterwards.
+    async fn rehydrate_session(
+        &self,
+        session_id: &str,
+        namespace: Namespace,
+        selection: Selection,
+    ) -> Result<Option<Arc<Mutex<DefaultSessionState>>>> {
+        let Some(stored) = self.history_backend.get_session(session_id).await? else {
+            return Ok(None);
+        };
+
+        // Restore public key mapping if available
+        if let Some(pk) = stored.public_key.clone() {
+            if self.session_public_keys.get(session_id).is_none() {
+                self.session_public_keys.insert(session_id.to_string(), pk);
+            }
+        }
+
+        // Always start with "New Chat" - title will be regenerated from messages
+        let metadata = SessionMetadata {
+            title: DEFAULT_TITLE.to_string(),
+            title_renewal_stamp: 0, // Force title regeneration
+            db_hydrated_cnt: stored.messages.len(),
+            is_archived: false,
+            memory_mode: false,
+        };
+
+        // Unconsumed tool results and in-flight resumable calls from the last run
+        let handler_state = match self.history_backend.get_tool_state(session_id).await {
+            Ok(handler_state) => handler_state,
+            Err(e) => {
+                error!(session_id, error = %e, "Failed to load tool state");
+                None
+            }
+        };
+
+        let state = self
+            .create_session(
+                session_id,
+                namespace,
+                selection,
+                stored.messages,
+                metadata,
+                handler_state,
+            )
+            .await?;
+
+        match self.history_backend.get_context_summary(session_id).await {
+            Ok(Some(compaction)) => {
+                state
+                    .lock()
+                    .await
+                    .restore_compaction(Some(compaction))
+                    .await;
+            }
+            Ok(None) => {}
+            Err(e) => error!(session_id, error = %e, "Failed to load context summary"),
+        }
+
+        debug!(session_id, "Rehydrated session from storage");
+        Ok(Some(state))
+    }
+
+    /// Scope wallet transaction policies to the session's namespace and API key.
+    async fn apply_policy_scope(
+        state: &Arc<Mutex<DefaultSessionState>>,
+        session_id: &str,
+        namespace: Namespace,
+        auth: &NamespaceAuth,
+    ) {
+        let scope = PolicyScope::new(
+            session_id.to_string(),
+            namespace.as_str().to_string(),
+            auth.api_key.as_ref().map(|key| key.key.clone()),
+        )
+        .with_public_key(auth.pub_key.clone());
+        state.lock().await.set_policy_scope(scope).await;
+    }
+
     #[allow(dead_code)]
     pub fn active_session_count(&self) -> usize {
         self.sessions.len()
@@ -542,6 +877,23 @@ impl SessionManager {
             .history_backend
             .set_messages_persisted(session_id, false)
             .await;
+
+        // Snapshot the tool handler at the end of the turn so a restart can resume it
+        let session = self
+            .sessions
+            .get(session_id)
+            .filter(|session_data| !session_data.metadata.memory_mode)
+            .map(|session_data| session_data.state.clone());
+        if let Some(state) = session {
+            let handler_state = state.lock().await.handler_state().await;
+            if let Err(e) = self
+                .history_backend
+                .save_tool_state(session_id, &handler_state)
+                .await
+            {
+                error!(session_id, error = %e, "Failed to save tool state");
+            }
+        }
     }
 
     /// Sets memory-only mode for a session.
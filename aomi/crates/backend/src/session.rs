@@ -2,15 +2,19 @@ use crate::{history, UserState};
 use anyhow::Result;
 use aomi_core::{
     app::{CoreCtx, CoreState},
-    CoreCommand, SystemEvent, SystemEventQueue, ToolReturn,
+    approval_response, ConversationCompaction, ConversationSummarizer, CoreCommand, SystemEvent,
+    SystemEventQueue, ToolReturn, CONTEXT_COMPACTION_ENABLED, TOOL_APPROVAL_RESPONSE,
+};
+use aomi_tools::{
+    ethereum::{policy::policy_engine, PolicyScope},
+    scheduler::{PersistedHandlerState, SessionToolHandler, ToolScheduler},
 };
-use aomi_tools::scheduler::SessionToolHandler;
 use chrono::Local;
 use serde_json::json;
 use std::{sync::Arc, time::Duration};
 use tokio::sync::{mpsc, RwLock};
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, error};
+use tracing::{debug, error, Instrument};
 
 pub use crate::types::{
     AomiApp, AomiBackend, ChatMessage, DefaultSessionState, MessageSender, SessionRecord,
@@ -19,29 +23,95 @@ pub use crate::types::{
 
 impl SessionState {
     pub async fn new(chat_backend: Arc<AomiBackend>, history: Vec<ChatMessage>) -> Result<Self> {
+        // Use a unique session ID (for now, based on pointer address to ensure uniqueness)
+        let session_id = format!("session_{:p}", Arc::as_ptr(&chat_backend));
+        Self::for_session(chat_backend, history, session_id).await
+    }
+
+    /// Create a session whose tool handler is registered under `scheduler_id`,
+    /// so its tool state can be persisted and restored independently of other sessions.
+    pub async fn for_session(
+        chat_backend: Arc<AomiBackend>,
+        history: Vec<ChatMessage>,
+        scheduler_id: String,
+    ) -> Result<Self> {
+        let scheduler = tool_scheduler().await?;
+        let namespaces = backend_namespaces(&chat_backend);
+        let handler = scheduler.get_session_handler(scheduler_id.clone(), namespaces);
+
+        Self::with_handler(chat_backend, history, scheduler_id, handler).await
+    }
+
+    /// Create a session whose tool handler is restored from `handler_state` under
+    /// `scheduler_id`. Used for forks, so the copy owns its tool results, and when
+    /// rehydrating a session from storage.
+    pub async fn restored(
+        chat_backend: Arc<AomiBackend>,
+        history: Vec<ChatMessage>,
+        scheduler_id: String,
+        handler_state: PersistedHandlerState,
+    ) -> Result<Self> {
+        let scheduler = tool_scheduler().await?;
+        let handler = scheduler.restore_session(scheduler_id.clone(), handler_state);
+        Self::with_handler(chat_backend, history, scheduler_id, handler).await
+    }
+
+    /// Create a session for a new backend that carries over `handler_state` (unconsumed
+    /// results and resumable calls) with the new backend's tool set. Used when a session
+    /// switches namespace or model.
+    pub async fn switched(
+        chat_backend: Arc<AomiBackend>,
+        history: Vec<ChatMessage>,
+        scheduler_id: String,
+        handler_state: PersistedHandlerState,
+    ) -> Result<Self> {
+        let scheduler = tool_scheduler().await?;
+        let namespaces = backend_namespaces(&chat_backend);
+        let handler = scheduler.restore_session_with_namespaces(
+            scheduler_id.clone(),
+            namespaces,
+            handler_state,
+        );
+        Self::with_handler(chat_backend, history, scheduler_id, handler).await
+    }
+
+    /// Create a fresh session over `history` that keeps this session's tool handler,
+    /// wallet state and policy scope. Used to regenerate after an edited message.
+    pub async fn rebuild(
+        &self,
+        chat_backend: Arc<AomiBackend>,
+        history: Vec<ChatMessage>,
+    ) -> Result<Self> {
+        let mut state = Self::with_handler(
+            chat_backend,
+            history,
+            self.scheduler_id.clone(),
+            self.handler.clone(),
+        )
+        .await?;
+        state.sync_user_state(self.user_state().await).await;
+        state
+            .set_policy_scope(self.policy_scope.read().await.clone())
+            .await;
+        Ok(state)
+    }
+
+    async fn with_handler(
+        chat_backend: Arc<AomiBackend>,
+        history: Vec<ChatMessage>,
+        session_id: String,
+        handler: SessionToolHandler,
+    ) -> Result<Self> {
         let (input_sender, input_reciever) = mpsc::channel(100);
         let (command_sender, command_reciever) = mpsc::channel(1000);
         let (interrupt_sender, interrupt_receiver) = mpsc::channel(100);
         let system_event_queue = SystemEventQueue::new();
-        let scheduler = aomi_tools::scheduler::ToolScheduler::get_or_init()
-            .await
-            .map_err(|e| anyhow::anyhow!("Failed to get tool scheduler: {}", e))?;
-
-        // Derive namespaces from the backend's registered tools
-        let tool_namespaces = chat_backend.tool_namespaces();
-        let namespaces: Vec<String> = tool_namespaces
-            .values()
-            .cloned()
-            .collect::<std::collections::HashSet<_>>()
-            .into_iter()
-            .collect();
-
-        // Use a unique session ID (for now, based on pointer address to ensure uniqueness)
-        let session_id = format!("session_{:p}", Arc::as_ptr(&chat_backend));
-        let handler = scheduler.get_session_handler(session_id.clone(), namespaces.clone());
+        let namespaces = backend_namespaces(&chat_backend);
 
         // Create shared user state
         let user_state = Arc::new(RwLock::new(UserState::default()));
+        let policy_scope = Arc::new(RwLock::new(PolicyScope::default()));
+        let compaction = Arc::new(RwLock::new(None));
 
         // Create cancellation token to stop background tasks when session is replaced
         let cancellation_token = CancellationToken::new();
@@ -53,9 +123,11 @@ impl SessionState {
             command_sender.clone(),
             system_event_queue.clone(),
             history.clone(),
-            session_id,
+            session_id.clone(),
             namespaces,
             Arc::clone(&user_state),
+            Arc::clone(&policy_scope),
+            Arc::clone(&compaction),
             cancellation_token.clone(),
         );
 
@@ -74,6 +146,9 @@ impl SessionState {
             command_reciever,
             interrupt_sender,
             user_state,
+            policy_scope,
+            compaction,
+            scheduler_id: session_id,
             handler,
             cancellation_token,
         })
@@ -82,7 +157,7 @@ impl SessionState {
     #[allow(clippy::too_many_arguments)]
     fn start_processing(
         backend: Arc<AomiBackend>,
-        mut input_reciever: mpsc::Receiver<String>,
+        mut input_reciever: mpsc::Receiver<(String, tracing::Span)>,
         mut interrupt_receiver: mpsc::Receiver<()>,
         command_sender: mpsc::Sender<CoreCommand>,
         system_event_queue: SystemEventQueue,
@@ -90,12 +165,17 @@ impl SessionState {
         session_id: String,
         namespaces: Vec<String>,
         user_state: Arc<RwLock<crate::types::UserState>>,
+        policy_scope: Arc<RwLock<PolicyScope>>,
+        compaction: Arc<RwLock<Option<ConversationCompaction>>>,
         cancellation_token: CancellationToken,
     ) {
         tokio::spawn(async move {
             system_event_queue.push(SystemEvent::SystemNotice("Backend connected".into()));
             let agent_history_for_task =
                 Arc::new(RwLock::new(history::to_rig_messages(&initial_history)));
+            let summarizer: Option<Arc<dyn ConversationSummarizer>> = (*CONTEXT_COMPACTION_ENABLED)
+                .then(|| backend.summarizer())
+                .flatten();
 
             loop {
                 tokio::select! {
@@ -104,7 +184,7 @@ impl SessionState {
                         break;
                     }
                     input = input_reciever.recv() => {
-                        let Some(input) = input else {
+                        let Some((input, turn_span)) = input else {
                             break;
                         };
 
@@ -133,18 +213,36 @@ impl SessionState {
                             backend.tool_namespaces(),
                         );
                 // Enable sliding window context management
-                state.enable_context_window(None);
+                state.enable_context_window(backend.context_budget());
+                        if let Some(summarizer) = &summarizer {
+                            let restored = compaction.read().await.clone();
+                            state.enable_compaction(Arc::clone(summarizer), restored);
+                        }
+                        state.policy_scope = policy_scope.read().await.clone();
                         let ctx = CoreCtx {
                             command_sender: command_sender.clone(),
                             interrupt_receiver: Some(&mut interrupt_receiver),
                         };
-                        if let Err(err) = backend.process_message(input, &mut state, ctx).await {
+                        let span = tracing::info_span!(
+                            parent: &turn_span,
+                            "process_message",
+                            session_id = state.public_session_id(),
+                            namespace = state.policy_scope.namespace.as_deref().unwrap_or("default")
+                        );
+                        if let Err(err) = backend
+                            .process_message(input, &mut state, ctx)
+                            .instrument(span)
+                            .await
+                        {
                             let _ = command_sender
                                 .send(CoreCommand::Error(format!(
                                     "Failed to process message: {err}"
                                 )))
                                 .await;
                         } else {
+                            if summarizer.is_some() {
+                                *compaction.write().await = state.compaction();
+                            }
                             let mut history_guard = agent_history_for_task.write().await;
                             *history_guard = state.history;
                         }
@@ -157,7 +255,7 @@ impl SessionState {
     fn start_polling_tools(
         system_event_queue: SystemEventQueue,
         handler: SessionToolHandler,
-        _input_sender: mpsc::Sender<String>,
+        _input_sender: mpsc::Sender<(String, tracing::Span)>,
         cancellation_token: CancellationToken,
     ) {
         tokio::spawn(async move {
@@ -199,7 +297,15 @@ impl SessionState {
         self.add_user_message(message);
         self.is_processing = true;
 
-        if let Err(e) = self.input_sender.send(message.to_string()).await {
+        let span = {
+            let scope = self.policy_scope.read().await;
+            tracing::info_span!(
+                "send_user_input",
+                session_id = scope.session_id.as_deref(),
+                namespace = scope.namespace.as_deref()
+            )
+        };
+        if let Err(e) = self.input_sender.send((message.to_string(), span)).await {
             self.system_event_queue
                 .push(SystemEvent::SystemError(format!(
                     "Failed to send message: {e}. Agent may have disconnected."
@@ -214,7 +320,9 @@ impl SessionState {
 
     pub async fn send_system_prompt(&mut self, message: &str) -> Result<()> {
         let raw_message = format!("[[SYSTEM:{}]]", message);
-        self.input_sender.send(raw_message).await?;
+        self.input_sender
+            .send((raw_message, tracing::Span::current()))
+            .await?;
         Ok(())
     }
 
@@ -230,6 +338,15 @@ impl SessionState {
         self.messages.push(chat_message.clone());
 
         if let Ok(value) = serde_json::from_str::<serde_json::Value>(content) {
+            match value.get("type").and_then(|t| t.as_str()) {
+                Some("wallet_tx_response") => self.settle_wallet_spend(&value).await,
+                // Answers for the approval gate, kept out of the LLM context
+                Some(TOOL_APPROVAL_RESPONSE) => {
+                    self.system_event_queue.push(approval_response(value));
+                    return Ok(chat_message);
+                }
+                _ => {}
+            }
             self.system_event_queue
                 .push(SystemEvent::AsyncCallback(value)); // "wallet_tx_response"
         } else {
@@ -257,6 +374,37 @@ impl SessionState {
         Ok(())
     }
 
+    /// Settle the daily spend reserved for a wallet request. The response comes from the
+    /// client and cannot be verified, so the reservation always stays spent: a client that
+    /// broadcast a transaction could otherwise report a rejection and get its budget back.
+    async fn settle_wallet_spend(&self, response: &serde_json::Value) {
+        let value = response.get("value").and_then(|value| value.as_str());
+        match policy_engine() {
+            Ok(engine) => {
+                let scope = self.policy_scope.read().await;
+                engine.settle_spend(&scope, value, true);
+            }
+            Err(e) => error!("Failed to load transaction policies: {e}"),
+        }
+    }
+
+    /// Set the namespace and API key used to select transaction policies
+    pub async fn set_policy_scope(&mut self, scope: PolicyScope) {
+        let mut guard = self.policy_scope.write().await;
+        *guard = scope;
+    }
+
+    /// Current rolling summary of compacted context, if compaction has run
+    pub async fn compaction(&self) -> Option<ConversationCompaction> {
+        self.compaction.read().await.clone()
+    }
+
+    /// Restore a persisted rolling summary (applied on the next turn)
+    pub async fn restore_compaction(&mut self, compaction: Option<ConversationCompaction>) {
+        let mut guard = self.compaction.write().await;
+        *guard = compaction;
+    }
+
     /// Sync user wallet state from frontend
     pub async fn sync_user_state(&mut self, new_state: crate::types::UserState) {
         let mut guard = self.user_state.write().await;
@@ -412,16 +560,55 @@ impl SessionState {
         self.system_event_queue.get_sse_events(count)
     }
 
+    /// System events from sequence number `seq` onward with their sequence numbers.
+    /// Used by the WebSocket transport to resume after reconnects.
+    pub fn events_since(&self, seq: usize) -> Vec<(usize, SystemEvent)> {
+        self.system_event_queue.events_since(seq)
+    }
+
+    /// Sequence number the next system event will get.
+    pub fn next_event_seq(&self) -> usize {
+        self.system_event_queue.len()
+    }
+
     /// Advance SSE event counter and return new SSE events (SystemNotice, AsyncCallback).
     /// Used by broadcast_async_notifications.
     pub fn advance_http_events(&mut self) -> Vec<SystemEvent> {
         self.system_event_queue.advance_http_events()
     }
 
-    pub fn send_to_llm(&self) -> &mpsc::Sender<String> {
+    pub fn send_to_llm(&self) -> &mpsc::Sender<(String, tracing::Span)> {
         &self.input_sender
     }
 
+    /// Wallet state last synced from the frontend
+    pub async fn user_state(&self) -> UserState {
+        self.user_state.read().await.clone()
+    }
+
+    /// Id the session's tool handler is registered under in the scheduler
+    pub fn scheduler_id(&self) -> &str {
+        &self.scheduler_id
+    }
+
+    /// Snapshot of the tool handler (available tools, unconsumed results, resumable calls)
+    pub async fn handler_state(&self) -> PersistedHandlerState {
+        self.handler.lock().await.to_persisted()
+    }
+
+    /// Persist and release the tool handler: non-resumable calls get `timeout_secs` to
+    /// finish, resumable ones are stopped and saved for re-launch.
+    pub async fn close_tool_handler(
+        &self,
+        timeout_secs: u64,
+    ) -> Result<Option<PersistedHandlerState>> {
+        tool_scheduler()
+            .await?
+            .cleanup_session(&self.scheduler_id, timeout_secs)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to persist tool handler: {}", e))
+    }
+
     /// Check if there are any ongoing tool calls that haven't completed yet
     pub async fn has_ongoing_tool_calls(&self) -> bool {
         self.handler.lock().await.has_ongoing_calls()
@@ -435,6 +622,23 @@ impl SessionState {
     }
 }
 
+async fn tool_scheduler() -> Result<Arc<ToolScheduler>> {
+    ToolScheduler::get_or_init()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to get tool scheduler: {}", e))
+}
+
+/// Tool namespaces registered by a backend
+fn backend_namespaces(chat_backend: &AomiBackend) -> Vec<String> {
+    chat_backend
+        .tool_namespaces()
+        .values()
+        .cloned()
+        .collect::<std::collections::HashSet<_>>()
+        .into_iter()
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,5 +1,5 @@
-use aomi_core::{CoreCommand, Message, SystemEvent, SystemEventQueue};
-use aomi_tools::scheduler::SessionToolHandler;
+use aomi_core::{ConversationCompaction, CoreCommand, Message, SystemEvent, SystemEventQueue};
+use aomi_tools::{ethereum::PolicyScope, scheduler::SessionToolHandler};
 use chrono::Local;
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
@@ -106,7 +106,8 @@ use tokio::sync::RwLock;
 pub struct SessionState {
     pub is_processing: bool,
     // Channels
-    pub input_sender: mpsc::Sender<String>,
+    /// User input paired with the span of the request that sent it
+    pub input_sender: mpsc::Sender<(String, tracing::Span)>,
     pub command_reciever: mpsc::Receiver<CoreCommand>,
     pub interrupt_sender: mpsc::Sender<()>,
     // User-specific session state
@@ -114,7 +115,13 @@ pub struct SessionState {
     pub system_event_queue: SystemEventQueue,
     /// User wallet state synced from frontend (shared with processing task)
     pub user_state: Arc<RwLock<UserState>>,
+    /// Policy scope for wallet transactions (set by the session manager)
+    pub(crate) policy_scope: Arc<RwLock<PolicyScope>>,
+    /// Rolling summary of compacted context (shared with processing task)
+    pub(crate) compaction: Arc<RwLock<Option<ConversationCompaction>>>,
     // Tool utilities
+    /// Key of this session's tool handler in the scheduler
+    pub(crate) scheduler_id: String,
     pub(crate) handler: SessionToolHandler,
     /// Cancellation token to stop background tasks when session is replaced
     pub(crate) cancellation_token: CancellationToken,
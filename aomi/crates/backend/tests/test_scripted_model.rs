@@ -0,0 +1,352 @@
+//! End-to-end session tests driven by the scripted mock completion model.
+//!
+//! These run the real `CoreApp` completion loop (tool dispatch, scheduler,
+//! session state) without an API key.
+
+use aomi_backend::session::{AomiBackend, DefaultSessionState, MessageSender};
+use aomi_baml::{AomiModel, Selection};
+use aomi_core::{
+    BuildOpts, CoreAppBuilder, MockCompletionModel, MockScript, ScriptedChunk, ScriptedTurn,
+};
+use aomi_tools::{with_topic, AomiTool, AomiToolArgs, ToolCallCtx, ToolScheduler};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::sync::Barrier;
+
+#[derive(Debug, Clone, Deserialize)]
+struct EchoArgs {
+    input: String,
+}
+
+impl AomiToolArgs for EchoArgs {
+    fn schema() -> Value {
+        with_topic(json!({
+            "type": "object",
+            "properties": {
+                "input": { "type": "string", "description": "Text to echo back" }
+            },
+            "required": ["input"]
+        }))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct EchoTool;
+
+impl AomiTool for EchoTool {
+    const NAME: &'static str = "echo_tool";
+
+    type Args = EchoArgs;
+    type Output = Value;
+    type Error = std::io::Error;
+
+    fn description(&self) -> &'static str {
+        "Echo the input back"
+    }
+
+    fn run_sync(
+        &self,
+        _ctx: ToolCallCtx,
+        args: Self::Args,
+    ) -> impl std::future::Future<Output = eyre::Result<Value>> + Send {
+        async move { Ok(json!({ "echo": args.input })) }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RendezvousArgs {
+    input: String,
+}
+
+impl AomiToolArgs for RendezvousArgs {
+    fn schema() -> Value {
+        with_topic(json!({
+            "type": "object",
+            "properties": {
+                "input": { "type": "string" }
+            },
+            "required": ["input"]
+        }))
+    }
+}
+
+/// Read-only tool whose calls only finish once `barrier` is full, so a turn's calls
+/// complete only if they overlap.
+#[derive(Debug, Clone)]
+struct RendezvousTool {
+    barrier: Arc<Barrier>,
+}
+
+impl AomiTool for RendezvousTool {
+    const NAME: &'static str = "rendezvous_tool";
+
+    type Args = RendezvousArgs;
+    type Output = Value;
+    type Error = std::io::Error;
+
+    fn description(&self) -> &'static str {
+        "Echo the input back once every concurrent call has arrived"
+    }
+
+    fn read_only(&self) -> bool {
+        true
+    }
+
+    fn run_sync(
+        &self,
+        _ctx: ToolCallCtx,
+        args: Self::Args,
+    ) -> impl std::future::Future<Output = eyre::Result<Value>> + Send {
+        let barrier = self.barrier.clone();
+        async move {
+            tokio::time::timeout(Duration::from_secs(5), barrier.wait())
+                .await
+                .map_err(|_| eyre::eyre!("calls did not overlap"))?;
+            Ok(json!({ "echo": args.input }))
+        }
+    }
+}
+
+/// Tool with side effects that records how many of its calls were in flight at once.
+#[derive(Debug, Clone, Default)]
+struct SerialTool {
+    in_flight: Arc<AtomicUsize>,
+    max_in_flight: Arc<AtomicUsize>,
+}
+
+impl AomiTool for SerialTool {
+    const NAME: &'static str = "serial_tool";
+
+    type Args = RendezvousArgs;
+    type Output = Value;
+    type Error = std::io::Error;
+
+    fn description(&self) -> &'static str {
+        "Echo the input back, one call at a time"
+    }
+
+    fn run_sync(
+        &self,
+        _ctx: ToolCallCtx,
+        args: Self::Args,
+    ) -> impl std::future::Future<Output = eyre::Result<Value>> + Send {
+        let tool = self.clone();
+        async move {
+            let current = tool.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            tool.max_in_flight.fetch_max(current, Ordering::SeqCst);
+            tokio::task::yield_now().await;
+            tool.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(json!({ "echo": args.input }))
+        }
+    }
+}
+
+fn mock_opts() -> BuildOpts {
+    BuildOpts {
+        no_docs: true,
+        skip_mcp: true,
+        no_tools: true,
+        selection: Selection {
+            rig: AomiModel::parse_rig("mock").expect("mock model is always registered"),
+            baml: AomiModel::ClaudeOpus4,
+        },
+    }
+}
+
+async fn mock_session(model: MockCompletionModel) -> DefaultSessionState {
+    mock_session_with(model, |_| {}).await
+}
+
+/// Like `mock_session`, with extra tools registered by `register`.
+async fn mock_session_with(
+    model: MockCompletionModel,
+    register: impl FnOnce(&mut CoreAppBuilder),
+) -> DefaultSessionState {
+    let opts = mock_opts();
+    let scheduler = ToolScheduler::new_for_test().await.expect("scheduler");
+    let mut builder = CoreAppBuilder::new_mock("You are a test agent.", opts, model, scheduler)
+        .await
+        .expect("mock builder");
+    builder.add_tool(EchoTool).expect("register echo tool");
+    register(&mut builder);
+    let app = builder.build(opts, None).await.expect("build app");
+
+    let backend: Arc<AomiBackend> = Arc::new(app);
+    DefaultSessionState::new(backend, Vec::new())
+        .await
+        .expect("session init")
+}
+
+/// Pump the session until the completion loop finishes (real tasks, so poll with a deadline).
+async fn wait_until_idle(state: &mut DefaultSessionState) {
+    for _ in 0..200 {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        state.sync_state().await;
+        if !state.is_processing {
+            return;
+        }
+    }
+    panic!("session did not finish processing");
+}
+
+fn assistant_text(state: &DefaultSessionState) -> String {
+    state
+        .messages
+        .iter()
+        .filter(|msg| matches!(msg.sender, MessageSender::Assistant) && msg.tool_result.is_none())
+        .map(|msg| msg.content.as_str())
+        .collect()
+}
+
+#[tokio::test]
+async fn scripted_tool_round_trip_reaches_session_state() {
+    let script = MockScript::from_file(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/scripted_echo.yaml"),
+    )
+    .expect("fixture");
+    let model = MockCompletionModel::scripted("mock", script);
+    let mut state = mock_session(model.clone()).await;
+
+    state
+        .send_user_input("please echo gm".into())
+        .await
+        .expect("send user message");
+    wait_until_idle(&mut state).await;
+
+    assert_eq!(
+        model.turns_played(),
+        2,
+        "tool result should re-prompt the model once"
+    );
+
+    let text = assistant_text(&state);
+    assert!(
+        text.contains("Echoing your input."),
+        "missing first turn: {text}"
+    );
+    assert!(
+        text.contains("Echo returned gm."),
+        "missing streamed follow-up: {text}"
+    );
+
+    let (topic, content) = state
+        .messages
+        .iter()
+        .find_map(|msg| msg.tool_result.clone())
+        .expect("tool message present");
+    assert_eq!(topic, "echo gm");
+    assert!(content.contains("gm"), "unexpected tool content: {content}");
+}
+
+#[tokio::test]
+async fn unscripted_mock_echoes_user_input() {
+    let mut state = mock_session(MockCompletionModel::new("mock")).await;
+
+    state
+        .send_user_input("hello offline".into())
+        .await
+        .expect("send user message");
+    wait_until_idle(&mut state).await;
+
+    let text = assistant_text(&state);
+    assert!(
+        text.contains("[mock] hello offline"),
+        "unexpected reply: {text}"
+    );
+}
+
+/// Scripts one turn calling `tool` once per input, then a closing text turn.
+fn multi_call_script(tool: &str, inputs: &[&str]) -> MockScript {
+    let call = |input: &str| json!({ "input": input, "topic": format!("{tool} {input}") });
+    let mut turn = ScriptedTurn::tool_call(tool, call(inputs[0]));
+    for input in &inputs[1..] {
+        turn = turn.chunk(ScriptedChunk::ToolCall {
+            id: None,
+            name: tool.to_string(),
+            arguments: call(input),
+        });
+    }
+    MockScript::new(vec![turn, ScriptedTurn::text("All done.")])
+}
+
+fn tool_topics(state: &DefaultSessionState) -> Vec<(String, String)> {
+    state
+        .messages
+        .iter()
+        .filter_map(|msg| msg.tool_result.clone())
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn read_only_tool_calls_in_one_turn_run_concurrently_in_order() {
+    let model = MockCompletionModel::scripted(
+        "mock",
+        multi_call_script("rendezvous_tool", &["a", "b", "c"]),
+    );
+    let tool = RendezvousTool {
+        barrier: Arc::new(Barrier::new(3)),
+    };
+    let mut state = mock_session_with(model, |builder| {
+        builder.add_tool(tool).expect("register rendezvous tool");
+    })
+    .await;
+
+    state
+        .send_user_input("meet three times".into())
+        .await
+        .expect("send user message");
+    wait_until_idle(&mut state).await;
+
+    let results = tool_topics(&state);
+    let topics: Vec<&str> = results.iter().map(|(topic, _)| topic.as_str()).collect();
+    assert_eq!(
+        topics,
+        vec![
+            "rendezvous_tool a",
+            "rendezvous_tool b",
+            "rendezvous_tool c"
+        ]
+    );
+    for (topic, content) in &results {
+        assert!(
+            content.contains("echo") && !content.contains("did not overlap"),
+            "{topic} ran alone: {content}"
+        );
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn tool_calls_with_side_effects_run_sequentially_in_order() {
+    let model =
+        MockCompletionModel::scripted("mock", multi_call_script("serial_tool", &["a", "b", "c"]));
+    let tool = SerialTool::default();
+    let max_in_flight = tool.max_in_flight.clone();
+    let mut state = mock_session_with(model, |builder| {
+        builder.add_tool(tool).expect("register serial tool");
+    })
+    .await;
+
+    state
+        .send_user_input("run three times".into())
+        .await
+        .expect("send user message");
+    wait_until_idle(&mut state).await;
+
+    let topics: Vec<String> = tool_topics(&state)
+        .into_iter()
+        .map(|(topic, _)| topic)
+        .collect();
+    assert_eq!(
+        topics,
+        vec!["serial_tool a", "serial_tool b", "serial_tool c"]
+    );
+    assert_eq!(max_in_flight.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,303 @@
+mod utils;
+
+use aomi_backend::{
+    history::HistoryBackend, AomiBackend, AuthorizedKey, ChatMessage, MessageSender, Namespace,
+    NamespaceAuth, Selection, SessionManager, SessionRecord,
+};
+use aomi_tools::db::SessionParent;
+use dashmap::DashMap;
+use std::sync::Arc;
+use utils::{flush_state, MockBackend, MockInteraction};
+
+/// History backend that records fork links and truncations and serves preset stored sessions.
+#[derive(Default)]
+struct RecordingHistoryBackend {
+    parents: DashMap<String, SessionParent>,
+    truncations: DashMap<String, usize>,
+    stored: DashMap<String, SessionRecord>,
+}
+
+impl RecordingHistoryBackend {
+    fn store(&self, session_id: &str, messages: Vec<ChatMessage>) {
+        self.stored.insert(
+            session_id.to_string(),
+            SessionRecord {
+                session_id: session_id.to_string(),
+                title: "Stored".to_string(),
+                messages,
+                public_key: None,
+            },
+        );
+    }
+}
+
+#[async_trait::async_trait]
+impl HistoryBackend for RecordingHistoryBackend {
+    async fn get_or_create_history(
+        &self,
+        _pubkey: &str,
+        _session_id: &str,
+    ) -> anyhow::Result<Option<ChatMessage>> {
+        Ok(None)
+    }
+
+    fn update_history(&self, _session_id: &str, _messages: &[ChatMessage]) {}
+
+    async fn flush_history(&self, _pubkey: &str, _session_id: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn list_sessions(
+        &self,
+        _public_key: &str,
+        _limit: usize,
+    ) -> anyhow::Result<Vec<SessionRecord>> {
+        Ok(Vec::new())
+    }
+
+    async fn update_session_title(&self, _session_id: &str, _title: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn save_session_fork(
+        &self,
+        session_id: &str,
+        _public_key: Option<&str>,
+        _title: &str,
+        parent: &SessionParent,
+    ) -> anyhow::Result<()> {
+        self.parents.insert(session_id.to_string(), parent.clone());
+        Ok(())
+    }
+
+    async fn get_session(&self, session_id: &str) -> anyhow::Result<Option<SessionRecord>> {
+        Ok(self.stored.get(session_id).map(|r| r.value().clone()))
+    }
+
+    async fn get_session_parent(&self, session_id: &str) -> anyhow::Result<Option<SessionParent>> {
+        Ok(self.parents.get(session_id).map(|p| p.value().clone()))
+    }
+
+    async fn truncate_history(&self, session_id: &str, keep: usize) -> anyhow::Result<()> {
+        self.truncations.insert(session_id.to_string(), keep);
+        Ok(())
+    }
+}
+
+async fn send(manager: &SessionManager, session_id: &str, message: &str) {
+    let mut auth = NamespaceAuth::new(None, None, None);
+    let state = manager
+        .get_or_create_session(session_id, &mut auth, None)
+        .await
+        .expect("session");
+    let mut state = state.lock().await;
+    state
+        .send_user_input(message.to_string())
+        .await
+        .expect("send user message");
+    flush_state(&mut state).await;
+}
+
+async fn contents(manager: &SessionManager, session_id: &str) -> Vec<(MessageSender, String)> {
+    let state = manager
+        .get_session_if_exists(session_id)
+        .expect("session exists");
+    let state = state.lock().await;
+    state
+        .messages
+        .iter()
+        .filter(|m| !matches!(m.sender, MessageSender::System))
+        .map(|m| (m.sender.clone(), m.content.clone()))
+        .collect()
+}
+
+fn no_auth() -> NamespaceAuth {
+    NamespaceAuth::new(None, None, None)
+}
+
+fn index_of(messages: &[ChatMessage], content: &str) -> usize {
+    messages
+        .iter()
+        .position(|m| m.content == content)
+        .unwrap_or_else(|| panic!("message {content:?} not found"))
+}
+
+#[tokio::test]
+async fn fork_copies_history_up_to_index_and_records_parent() {
+    let backend = MockBackend::new(vec![
+        MockInteraction::streaming_only("first", "reply one"),
+        MockInteraction::streaming_only("second", "reply two"),
+        MockInteraction::streaming_only("branch", "reply branch"),
+    ]);
+    let history = Arc::new(RecordingHistoryBackend::default());
+    let chat_backend: Arc<AomiBackend> = Arc::new(backend);
+    let manager = SessionManager::with_backend(chat_backend, history.clone());
+
+    send(&manager, "source", "first").await;
+    send(&manager, "source", "second").await;
+
+    let fork_index = {
+        let state = manager.get_session_if_exists("source").unwrap();
+        let state = state.lock().await;
+        index_of(&state.messages, "reply one")
+    };
+    manager
+        .fork_session("source", "fork", fork_index, &mut no_auth())
+        .await
+        .expect("fork session");
+
+    let forked = contents(&manager, "fork").await;
+    assert_eq!(forked.last().map(|(_, c)| c.as_str()), Some("reply one"));
+    assert!(forked.iter().all(|(_, c)| c != "second"));
+
+    let parent = manager
+        .get_session_parent("fork")
+        .await
+        .unwrap()
+        .expect("fork has a parent");
+    assert_eq!(parent.session_id, "source");
+    assert_eq!(parent.message_index, fork_index as i64);
+
+    // The fork is an independent conversation
+    send(&manager, "fork", "branch").await;
+    let forked = contents(&manager, "fork").await;
+    assert_eq!(forked.last().map(|(_, c)| c.as_str()), Some("reply branch"));
+    let source = contents(&manager, "source").await;
+    assert_eq!(source.last().map(|(_, c)| c.as_str()), Some("reply two"));
+
+    assert!(manager
+        .fork_session("source", "fork", 0, &mut no_auth())
+        .await
+        .is_err());
+    assert!(manager
+        .fork_session("source", "fork-2", usize::MAX, &mut no_auth())
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn fork_rehydrates_a_source_that_is_only_in_storage() {
+    let history = Arc::new(RecordingHistoryBackend::default());
+    history.store(
+        "stored",
+        vec![
+            ChatMessage::new(MessageSender::User, "first".to_string(), None),
+            ChatMessage::new(MessageSender::Assistant, "reply one".to_string(), None),
+            ChatMessage::new(MessageSender::User, "second".to_string(), None),
+            ChatMessage::new(MessageSender::Assistant, "reply two".to_string(), None),
+        ],
+    );
+    let chat_backend: Arc<AomiBackend> = Arc::new(MockBackend::new(Vec::new()));
+    let manager = SessionManager::with_backend(chat_backend.clone(), history.clone());
+    manager.add_backend(Namespace::L2b, Selection::default(), chat_backend);
+
+    assert!(manager.get_session_if_exists("stored").is_none());
+    assert!(manager.session_exists("stored").await.unwrap());
+    assert!(!manager.session_exists("missing").await.unwrap());
+
+    let fork_index = {
+        let stored = history.stored.get("stored").unwrap();
+        index_of(&stored.messages, "reply one")
+    };
+    // The source is loaded under the caller's authorized namespace, not the default
+    let key = AuthorizedKey::new("key-1".into(), None, true, vec!["l2beat".into()]);
+    let mut auth = NamespaceAuth::new(None, Some(key), Some("l2beat"));
+    manager
+        .fork_session("stored", "fork", fork_index, &mut auth)
+        .await
+        .expect("fork persisted session");
+
+    let forked = contents(&manager, "fork").await;
+    assert_eq!(forked.last().map(|(_, c)| c.as_str()), Some("reply one"));
+    assert!(forked.iter().all(|(_, c)| c != "second"));
+    for session_id in ["stored", "fork"] {
+        let (namespace, _) = manager.get_session_config(session_id).unwrap();
+        assert_eq!(namespace, Namespace::L2b);
+    }
+
+    // A caller that is not authorized for the namespace cannot load the source
+    history.store("stored-2", Vec::new());
+    let mut unauthorized = NamespaceAuth::new(None, None, Some("l2beat"));
+    assert!(manager
+        .fork_session("stored-2", "fork-2", 0, &mut unauthorized)
+        .await
+        .is_err());
+    assert!(manager
+        .fork_session("missing", "fork-3", 0, &mut no_auth())
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn fork_refuses_an_id_that_is_only_in_storage() {
+    let history = Arc::new(RecordingHistoryBackend::default());
+    history.store("taken", Vec::new());
+    let backend = MockBackend::new(vec![MockInteraction::streaming_only("first", "reply one")]);
+    let chat_backend: Arc<AomiBackend> = Arc::new(backend);
+    let manager = SessionManager::with_backend(chat_backend, history.clone());
+
+    send(&manager, "source", "first").await;
+
+    let err = manager
+        .fork_session("source", "taken", 0, &mut no_auth())
+        .await
+        .expect_err("stored id is taken");
+    assert!(err.to_string().contains("already exists"));
+    assert!(manager.get_session_if_exists("taken").is_none());
+    assert!(history.parents.get("taken").is_none());
+}
+
+#[tokio::test]
+async fn edit_and_resend_drops_later_turns_and_regenerates() {
+    let backend = MockBackend::new(vec![
+        MockInteraction::streaming_only("first", "reply one"),
+        MockInteraction::streaming_only("second", "reply two"),
+        MockInteraction::streaming_only("second, edited", "reply edited"),
+    ]);
+    let history = Arc::new(RecordingHistoryBackend::default());
+    let chat_backend: Arc<AomiBackend> = Arc::new(backend.clone());
+    let manager = SessionManager::with_backend(chat_backend, history.clone());
+
+    send(&manager, "session", "first").await;
+    send(&manager, "session", "second").await;
+
+    let (edit_index, reply_index) = {
+        let state = manager.get_session_if_exists("session").unwrap();
+        let state = state.lock().await;
+        (
+            index_of(&state.messages, "second"),
+            index_of(&state.messages, "reply two"),
+        )
+    };
+
+    assert!(manager
+        .edit_and_resend("session", reply_index, "not a user message".into())
+        .await
+        .is_err());
+
+    let state = manager
+        .edit_and_resend("session", edit_index, "second, edited".into())
+        .await
+        .expect("edit and resend");
+    flush_state(&mut *state.lock().await).await;
+
+    let messages = contents(&manager, "session").await;
+    assert!(messages
+        .iter()
+        .all(|(_, c)| c != "second" && c != "reply two"));
+    assert_eq!(
+        &messages[messages.len() - 2..],
+        &[
+            (MessageSender::User, "second, edited".to_string()),
+            (MessageSender::Assistant, "reply edited".to_string()),
+        ]
+    );
+
+    // The agent saw the same history for the edited turn as for the original one
+    let lengths = backend.history_lengths().await;
+    assert_eq!(lengths.len(), 3);
+    assert_eq!(lengths[1], lengths[2]);
+
+    // Nothing was rehydrated from storage, so nothing persisted needed dropping
+    assert!(history.truncations.is_empty());
+}
@@ -147,7 +147,10 @@ async fn send_message(
     // Send message
     {
         let state = session.lock().await;
-        state.input_sender.send(message.to_string()).await?;
+        state
+            .input_sender
+            .send((message.to_string(), tracing::Span::current()))
+            .await?;
     }
 
     // Wait for processing to complete
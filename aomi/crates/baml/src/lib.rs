@@ -13,11 +13,16 @@ pub mod baml_client;
 
 pub mod client;
 pub mod model;
+pub mod registry;
 pub mod types;
 
 // Re-export main types for convenience
 pub use client::BamlClient;
 pub use model::{AomiModel, Selection};
+pub use registry::{
+    DEFAULT_CONTEXT_WINDOW, MOCK_MODEL_ID, MOCK_PROVIDER, ModelEntry, ModelRegistry,
+    ProviderConfig, ProviderKind, ResolvedModel, load_model_registry, model_registry,
+};
 pub use types::{
     CodeLine, ContractInfo, ContractSource, Event, ExtractedContractInfo, Function, Import,
     Interface, ScriptBlock, Storage,
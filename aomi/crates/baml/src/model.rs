@@ -1,3 +1,5 @@
+use crate::registry::{DEFAULT_CONTEXT_WINDOW, ModelEntry, model_registry};
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub enum AomiModel {
     ClaudeSonnet4,
@@ -8,6 +10,8 @@ pub enum AomiModel {
     Gpt5Chat,
     Fast,
     OpenaiFallback,
+    /// Model declared in the registry file, identified by its registry id.
+    Custom(&'static str),
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
@@ -26,25 +30,36 @@ impl Default for Selection {
 }
 
 impl AomiModel {
-    /// Check if model is currently supported (Anthropic models only for now)
+    /// Check if model is currently supported (Anthropic and registry models for now)
     pub const fn is_supported(self) -> bool {
         matches!(
             self,
-            AomiModel::ClaudeSonnet4 | AomiModel::ClaudeOpus4 | AomiModel::ClaudeHaiku35
+            AomiModel::ClaudeSonnet4
+                | AomiModel::ClaudeOpus4
+                | AomiModel::ClaudeHaiku35
+                | AomiModel::Custom(_)
         )
     }
 
-    pub const fn rig_provider(self) -> Option<&'static str> {
+    fn registry_entry(self) -> Option<&'static ModelEntry> {
+        match self {
+            AomiModel::Custom(id) => model_registry().find(id),
+            _ => None,
+        }
+    }
+
+    pub fn rig_provider(self) -> Option<&'static str> {
         match self {
             AomiModel::ClaudeSonnet4 | AomiModel::ClaudeOpus4 | AomiModel::ClaudeHaiku35 => {
                 Some("anthropic")
             }
             AomiModel::Gpt5 | AomiModel::Gpt5Mini | AomiModel::Gpt5Chat => Some("openai"),
             AomiModel::Fast | AomiModel::OpenaiFallback => None,
+            AomiModel::Custom(_) => self.registry_entry().map(|e| e.provider.as_str()),
         }
     }
 
-    pub const fn rig_id(self) -> &'static str {
+    pub fn rig_id(self) -> &'static str {
         match self {
             AomiModel::ClaudeSonnet4 => "claude-sonnet-4-20250514",
             AomiModel::ClaudeOpus4 => "claude-opus-4-1-20250805",
@@ -54,6 +69,7 @@ impl AomiModel {
             AomiModel::Gpt5Chat => "gpt-4o",
             AomiModel::Fast => "gpt-4o-mini",
             AomiModel::OpenaiFallback => "gpt-4o",
+            AomiModel::Custom(id) => self.registry_entry().map_or(id, |e| e.upstream_id()),
         }
     }
 
@@ -67,10 +83,11 @@ impl AomiModel {
             AomiModel::Gpt5Chat => "gpt-5-chat",
             AomiModel::Fast => "fast",
             AomiModel::OpenaiFallback => "openai-fallback",
+            AomiModel::Custom(id) => id,
         }
     }
 
-    pub const fn rig_label(self) -> &'static str {
+    pub fn rig_label(self) -> &'static str {
         match self {
             AomiModel::ClaudeSonnet4 => "Claude Sonnet 4",
             AomiModel::ClaudeOpus4 => "Claude Opus 4.1",
@@ -80,10 +97,30 @@ impl AomiModel {
             AomiModel::Gpt5Chat => "OpenAI GPT-4o (Chat)",
             AomiModel::Fast => "Fast Round Robin",
             AomiModel::OpenaiFallback => "OpenAI Fallback",
+            AomiModel::Custom(id) => self.registry_entry().map_or(id, |e| e.label()),
         }
     }
 
-    pub const fn baml_client_name(self) -> &'static str {
+    /// Maximum input tokens the upstream model accepts. Registry models can set
+    /// `context_window`; otherwise a conservative default is assumed.
+    pub fn context_window(self) -> usize {
+        match self {
+            AomiModel::ClaudeSonnet4 | AomiModel::ClaudeOpus4 | AomiModel::ClaudeHaiku35 => 200_000,
+            AomiModel::Gpt5
+            | AomiModel::Gpt5Mini
+            | AomiModel::Gpt5Chat
+            | AomiModel::Fast
+            | AomiModel::OpenaiFallback => 128_000,
+            AomiModel::Custom(_) => self
+                .registry_entry()
+                .and_then(|e| e.context_window)
+                .unwrap_or(DEFAULT_CONTEXT_WINDOW),
+        }
+    }
+
+    /// BAML client name. Registry models without an explicit `baml_client`
+    /// fall back to the default selection's client.
+    pub fn baml_client_name(self) -> &'static str {
         match self {
             AomiModel::ClaudeSonnet4 => "CustomSonnet4",
             AomiModel::ClaudeOpus4 => "CustomOpus4",
@@ -93,10 +130,14 @@ impl AomiModel {
             AomiModel::Gpt5Chat => "CustomGPT5Chat",
             AomiModel::Fast => "CustomFast",
             AomiModel::OpenaiFallback => "OpenaiFallback",
+            AomiModel::Custom(_) => self
+                .registry_entry()
+                .and_then(|e| e.baml_client.as_deref())
+                .unwrap_or_else(|| Selection::default().baml.baml_client_name()),
         }
     }
 
-    pub const fn baml_label(self) -> &'static str {
+    pub fn baml_label(self) -> &'static str {
         match self {
             AomiModel::ClaudeSonnet4 => "Claude Sonnet 4",
             AomiModel::ClaudeOpus4 => "Claude Opus 4.1",
@@ -106,10 +147,22 @@ impl AomiModel {
             AomiModel::Gpt5Chat => "OpenAI GPT-5 Chat",
             AomiModel::Fast => "Custom Fast (Round Robin)",
             AomiModel::OpenaiFallback => "OpenAI Fallback",
+            AomiModel::Custom(_) => self.rig_label(),
         }
     }
 
+    /// Parse a rig selection: built-in slugs/labels first, then registry ids.
     pub fn parse_rig(input: &str) -> Option<Self> {
+        Self::parse_builtin(input).or_else(|| Self::parse_registry(input))
+    }
+
+    fn parse_registry(input: &str) -> Option<Self> {
+        model_registry()
+            .find(input)
+            .map(|entry| AomiModel::Custom(entry.id.as_str()))
+    }
+
+    pub(crate) fn parse_builtin(input: &str) -> Option<Self> {
         let normalized = input.trim().to_lowercase();
         match normalized.as_str() {
             // Slugs
@@ -176,7 +229,7 @@ impl AomiModel {
             "openai gpt-5 (chat)" | "openai gpt-5 chat" => Some(AomiModel::Gpt5Chat),
             "fast round robin" | "custom fast (round robin)" => Some(AomiModel::Fast),
             "openai fallback" => Some(AomiModel::OpenaiFallback),
-            _ => None,
+            _ => Self::parse_registry(&normalized),
         }
     }
 
@@ -203,4 +256,65 @@ impl AomiModel {
             AomiModel::OpenaiFallback,
         ]
     }
+
+    /// Built-in rig models followed by every model declared in the registry.
+    pub fn rig_available() -> Vec<AomiModel> {
+        Self::rig_all()
+            .iter()
+            .copied()
+            .chain(
+                model_registry()
+                    .models()
+                    .iter()
+                    .map(|entry| AomiModel::Custom(entry.id.as_str())),
+            )
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::{MOCK_MODEL_ID, MOCK_PROVIDER};
+
+    #[test]
+    fn test_openai_variants_keep_gpt4o_ids() {
+        assert_eq!(AomiModel::Gpt5.rig_id(), "gpt-4o");
+        assert_eq!(AomiModel::Gpt5Mini.rig_id(), "gpt-4o-mini");
+        assert_eq!(AomiModel::Fast.rig_id(), "gpt-4o-mini");
+        assert_eq!(AomiModel::OpenaiFallback.rig_id(), "gpt-4o");
+        assert_eq!(AomiModel::parse_rig("gpt-4o"), Some(AomiModel::Gpt5));
+        assert_eq!(
+            AomiModel::parse_rig("gpt-4o-mini"),
+            Some(AomiModel::Gpt5Mini)
+        );
+        assert_eq!(
+            AomiModel::parse_rig("OpenAI GPT-5 (Responses)"),
+            Some(AomiModel::Gpt5)
+        );
+    }
+
+    #[test]
+    fn test_parse_mock_model_by_id() {
+        let model = AomiModel::parse_rig("mock").unwrap();
+        assert_eq!(model, AomiModel::Custom(MOCK_MODEL_ID));
+        assert_eq!(model.rig_slug(), MOCK_MODEL_ID);
+        assert_eq!(model.rig_provider(), Some(MOCK_PROVIDER));
+        assert_eq!(AomiModel::parse_baml("mock"), Some(model));
+        assert_eq!(
+            model.baml_client_name(),
+            AomiModel::ClaudeOpus4.baml_client_name()
+        );
+        assert!(AomiModel::rig_available().contains(&model));
+    }
+
+    #[test]
+    fn test_context_window_per_model() {
+        assert_eq!(AomiModel::ClaudeOpus4.context_window(), 200_000);
+        assert_eq!(AomiModel::Gpt5Mini.context_window(), 128_000);
+        assert_eq!(
+            AomiModel::Custom(MOCK_MODEL_ID).context_window(),
+            DEFAULT_CONTEXT_WINDOW
+        );
+    }
 }
@@ -0,0 +1,404 @@
+//! Model/provider registry loaded from `models.toml`.
+//!
+//! The built-in [`AomiModel`] variants always resolve against the `anthropic` and
+//! `openai` providers. Extra models are declared in the registry file and surface
+//! as [`AomiModel::Custom`], selectable by their string id:
+//!
+//! ```toml
+//! [providers.local]
+//! kind = "openai_compatible"
+//! base_url = "http://localhost:11434/v1"
+//!
+//! [[models]]
+//! id = "llama3"
+//! provider = "local"
+//! model = "llama3.1:8b"
+//! label = "Llama 3.1 8B (local)"
+//! context_window = 32768
+//! ```
+//!
+//! A `mock` provider and model are always available so the agent can run fully
+//! offline without any file. Declaring `[providers.mock] kind = "mock"` with a
+//! `script = "fixtures/chat.yaml"` replays a scripted conversation instead.
+
+use crate::model::AomiModel;
+use anyhow::{Result, anyhow, bail};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Environment variable overriding the registry file location.
+const MODELS_TOML_ENV: &str = "MODELS_TOML";
+
+/// Input token limit assumed for registry models that do not declare one.
+pub const DEFAULT_CONTEXT_WINDOW: usize = 128_000;
+
+/// Id of the always-available deterministic mock model.
+pub const MOCK_MODEL_ID: &str = "mock";
+
+/// Name of the built-in provider serving the mock model.
+pub const MOCK_PROVIDER: &str = "mock";
+
+static MODEL_REGISTRY: OnceLock<ModelRegistry> = OnceLock::new();
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderKind {
+    Anthropic,
+    #[serde(rename = "openai")]
+    OpenAI,
+    /// Any server speaking the OpenAI chat completions API (vLLM, Ollama, llama.cpp).
+    #[serde(rename = "openai_compatible")]
+    OpenAICompatible,
+    /// Deterministic in-process model, never hits the network.
+    Mock,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProviderConfig {
+    pub kind: ProviderKind,
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Name of the environment variable holding the API key.
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+    /// JSON/YAML fixture replayed by `mock` providers, one turn per model call.
+    #[serde(default)]
+    pub script: Option<String>,
+}
+
+impl ProviderConfig {
+    fn builtin(kind: ProviderKind) -> Self {
+        let api_key_env = match kind {
+            ProviderKind::Anthropic => Some("ANTHROPIC_API_KEY".to_string()),
+            ProviderKind::OpenAI => Some("OPENAI_API_KEY".to_string()),
+            ProviderKind::OpenAICompatible | ProviderKind::Mock => None,
+        };
+        Self {
+            kind,
+            base_url: None,
+            api_key_env,
+            script: None,
+        }
+    }
+
+    /// Read the API key from the configured environment variable, if any.
+    pub fn api_key(&self) -> Option<String> {
+        self.api_key_env
+            .as_deref()
+            .and_then(|name| std::env::var(name).ok())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelEntry {
+    /// Id used for selection (e.g. `/api/control/model?rig=<id>`).
+    pub id: String,
+    /// Key into the `[providers]` table.
+    pub provider: String,
+    /// Upstream model name sent to the provider, defaults to `id`.
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub label: Option<String>,
+    /// BAML client used when this model is selected for BAML calls.
+    #[serde(default)]
+    pub baml_client: Option<String>,
+    /// Maximum input tokens, used to size the context window budget.
+    #[serde(default)]
+    pub context_window: Option<usize>,
+}
+
+impl ModelEntry {
+    pub fn upstream_id(&self) -> &str {
+        self.model.as_deref().unwrap_or(&self.id)
+    }
+
+    pub fn label(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.id)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RegistryFile {
+    #[serde(default)]
+    providers: HashMap<String, ProviderConfig>,
+    #[serde(default)]
+    models: Vec<ModelEntry>,
+}
+
+/// Provider and upstream model id a selection resolves to.
+#[derive(Clone, Debug)]
+pub struct ResolvedModel {
+    pub provider: ProviderConfig,
+    pub model_id: String,
+}
+
+#[derive(Debug)]
+pub struct ModelRegistry {
+    providers: HashMap<String, ProviderConfig>,
+    models: Vec<ModelEntry>,
+}
+
+impl Default for ModelRegistry {
+    fn default() -> Self {
+        Self::with_builtins(RegistryFile::default())
+    }
+}
+
+impl ModelRegistry {
+    fn with_builtins(file: RegistryFile) -> Self {
+        let mut providers = HashMap::from([
+            (
+                "anthropic".to_string(),
+                ProviderConfig::builtin(ProviderKind::Anthropic),
+            ),
+            (
+                "openai".to_string(),
+                ProviderConfig::builtin(ProviderKind::OpenAI),
+            ),
+            (
+                MOCK_PROVIDER.to_string(),
+                ProviderConfig::builtin(ProviderKind::Mock),
+            ),
+        ]);
+        providers.extend(file.providers);
+
+        let mut models = file.models;
+        if !models
+            .iter()
+            .any(|m| m.id.eq_ignore_ascii_case(MOCK_MODEL_ID))
+        {
+            models.push(ModelEntry {
+                id: MOCK_MODEL_ID.to_string(),
+                provider: MOCK_PROVIDER.to_string(),
+                model: None,
+                label: Some("Mock (offline)".to_string()),
+                baml_client: None,
+                context_window: None,
+            });
+        }
+
+        Self { providers, models }
+    }
+
+    pub fn from_toml_str(content: &str) -> Result<Self> {
+        let file: RegistryFile =
+            toml::from_str(content).map_err(|e| anyhow!("Failed to parse model registry: {e}"))?;
+        let registry = Self::with_builtins(file);
+        registry.validate()?;
+        Ok(registry)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read {}: {e}", path.display()))?;
+        Self::from_toml_str(&content)
+    }
+
+    fn validate(&self) -> Result<()> {
+        for (name, provider) in &self.providers {
+            if provider.kind == ProviderKind::OpenAICompatible && provider.base_url.is_none() {
+                bail!("Provider '{name}' is openai_compatible but has no base_url");
+            }
+        }
+        for (idx, model) in self.models.iter().enumerate() {
+            if model.id.trim().is_empty() {
+                bail!("Model entry #{idx} has an empty id");
+            }
+            if model.context_window == Some(0) {
+                bail!("Model '{}' has a zero context_window", model.id);
+            }
+            if AomiModel::parse_builtin(&model.id).is_some() {
+                bail!("Model id '{}' shadows a built-in model", model.id);
+            }
+            if !self.providers.contains_key(&model.provider) {
+                bail!(
+                    "Model '{}' references unknown provider '{}'",
+                    model.id,
+                    model.provider
+                );
+            }
+            if self.models[..idx]
+                .iter()
+                .any(|m| m.id.eq_ignore_ascii_case(&model.id))
+            {
+                bail!("Duplicate model id '{}'", model.id);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn provider(&self, name: &str) -> Option<&ProviderConfig> {
+        self.providers.get(name)
+    }
+
+    pub fn models(&self) -> &[ModelEntry] {
+        &self.models
+    }
+
+    /// Case-insensitive lookup by model id.
+    pub fn find(&self, id: &str) -> Option<&ModelEntry> {
+        let id = id.trim();
+        self.models.iter().find(|m| m.id.eq_ignore_ascii_case(id))
+    }
+
+    /// Resolve a selection to its provider config and upstream model id.
+    pub fn resolve(&self, model: AomiModel) -> Result<ResolvedModel> {
+        let (provider_name, model_id) = match model {
+            AomiModel::Custom(id) => {
+                let entry = self
+                    .find(id)
+                    .ok_or_else(|| anyhow!("Model '{id}' is not in the registry"))?;
+                (entry.provider.as_str(), entry.upstream_id().to_string())
+            }
+            builtin => (
+                builtin.rig_provider().unwrap_or("anthropic"),
+                builtin.rig_id().to_string(),
+            ),
+        };
+        let provider = self
+            .provider(provider_name)
+            .cloned()
+            .ok_or_else(|| anyhow!("Unknown provider '{provider_name}'"))?;
+        Ok(ResolvedModel { provider, model_id })
+    }
+}
+
+/// Load the global registry from `models.toml`, once.
+///
+/// Path resolution: `MODELS_TOML` env var, then a walk up from the current
+/// directory. A missing file yields the built-in providers; an invalid file is
+/// an error so a broken registry is never silently replaced by the defaults.
+/// Call this at startup to fail fast on bad config.
+pub fn load_model_registry() -> Result<&'static ModelRegistry> {
+    if let Some(registry) = MODEL_REGISTRY.get() {
+        return Ok(registry);
+    }
+    let registry = match resolve_models_path() {
+        Some(path) => {
+            let registry = ModelRegistry::from_file(&path)
+                .map_err(|e| anyhow!("Invalid model registry {}: {e}", path.display()))?;
+            tracing::info!(
+                path = %path.display(),
+                models = registry.models().len(),
+                "Loaded model registry"
+            );
+            registry
+        }
+        None => ModelRegistry::default(),
+    };
+    Ok(MODEL_REGISTRY.get_or_init(|| registry))
+}
+
+/// Global registry, see [`load_model_registry`].
+///
+/// Panics if `models.toml` is invalid; binaries load the registry at startup so
+/// bad config is reported there first.
+pub fn model_registry() -> &'static ModelRegistry {
+    load_model_registry().unwrap_or_else(|e| panic!("{e}"))
+}
+
+fn resolve_models_path() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var(MODELS_TOML_ENV) {
+        let path = PathBuf::from(path);
+        if path.exists() {
+            return Some(path);
+        }
+        tracing::warn!(
+            "{} points to missing file {}",
+            MODELS_TOML_ENV,
+            path.display()
+        );
+    }
+
+    let mut dir = std::env::current_dir().ok()?;
+    loop {
+        let candidate = dir.join("models.toml");
+        if candidate.exists() {
+            return Some(candidate);
+        }
+        if !dir.pop() {
+            return None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"
+[providers.local]
+kind = "openai_compatible"
+base_url = "http://localhost:11434/v1"
+api_key_env = "LOCAL_LLM_KEY"
+
+[[models]]
+id = "llama3"
+provider = "local"
+model = "llama3.1:8b"
+label = "Llama 3.1 8B (local)"
+context_window = 32768
+"#;
+
+    #[test]
+    fn test_parse_registry_with_builtins() {
+        let registry = ModelRegistry::from_toml_str(SAMPLE).unwrap();
+        let entry = registry.find("LLAMA3").unwrap();
+        assert_eq!(entry.upstream_id(), "llama3.1:8b");
+        assert_eq!(entry.label(), "Llama 3.1 8B (local)");
+        assert_eq!(entry.context_window, Some(32768));
+        assert!(registry.find(MOCK_MODEL_ID).is_some());
+        assert_eq!(
+            registry.provider("anthropic").unwrap().kind,
+            ProviderKind::Anthropic
+        );
+    }
+
+    #[test]
+    fn test_resolve_builtin_and_custom() {
+        let registry = ModelRegistry::from_toml_str(SAMPLE).unwrap();
+
+        let gpt = registry.resolve(AomiModel::Gpt5).unwrap();
+        assert_eq!(gpt.provider.kind, ProviderKind::OpenAI);
+        assert_eq!(gpt.model_id, "gpt-4o");
+
+        let local = registry.resolve(AomiModel::Custom("llama3")).unwrap();
+        assert_eq!(local.provider.kind, ProviderKind::OpenAICompatible);
+        assert_eq!(
+            local.provider.base_url.as_deref(),
+            Some("http://localhost:11434/v1")
+        );
+        assert_eq!(local.model_id, "llama3.1:8b");
+
+        assert!(registry.resolve(AomiModel::Custom("missing")).is_err());
+    }
+
+    #[test]
+    fn test_invalid_registries_rejected() {
+        let missing_url = r#"
+[providers.local]
+kind = "openai_compatible"
+"#;
+        assert!(ModelRegistry::from_toml_str(missing_url).is_err());
+
+        let unknown_provider = r#"
+[[models]]
+id = "llama3"
+provider = "nowhere"
+"#;
+        assert!(ModelRegistry::from_toml_str(unknown_provider).is_err());
+
+        let shadowing = r#"
+[[models]]
+id = "opus"
+provider = "mock"
+"#;
+        assert!(ModelRegistry::from_toml_str(shadowing).is_err());
+    }
+}
@@ -1,12 +1,12 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
-use aomi_baml::{AomiModel, Selection};
+use aomi_baml::{AomiModel, ProviderKind, Selection, model_registry};
 use aomi_mcp::client::{self as mcp};
 use aomi_rag::DocumentStore;
 use aomi_tools::{
-    AomiTool, AomiToolWrapper, ToolScheduler, abi_encoder, account, brave_search, cast, context,
-    db_tools, etherscan, wallet,
+    AomiTool, AomiToolWrapper, ToolScheduler, abi_decoder, abi_encoder, account, brave_search,
+    cast, context, db_tools, etherscan, logs, names, portfolio, session_search, tokens, wallet,
 };
 use async_trait::async_trait;
 use eyre::Result;
@@ -15,6 +15,7 @@ use rig::{
     prelude::*,
     providers::{
         anthropic::completion::CompletionModel as AnthropicModel,
+        openai::completion::CompletionModel as OpenAICompatibleModel,
         openai::responses_api::ResponsesCompletionModel as OpenAIModel,
     },
 };
@@ -22,9 +23,12 @@ use rig::{
 use tokio::sync::Mutex;
 
 use crate::{
+    compaction::{BamlSummarizer, ConversationSummarizer},
     completion::stream_completion,
     connections::toolbox_with_retry,
+    context_window::budget_for_model,
     events::{SystemEvent, SystemEventQueue},
+    mock::MockCompletionModel,
     prompts::{PromptSection, generate_account_context, preamble_builder},
 };
 
@@ -40,12 +44,14 @@ pub static OPENAI_API_KEY: std::sync::LazyLock<Result<String, std::env::VarError
     std::sync::LazyLock::new(|| std::env::var("OPENAI_API_KEY"));
 
 /// Helper macro to reduce duplication when operating on AgentBuilderKind variants.
-/// Applies the same operation to both Anthropic and OpenAI builders.
+/// Applies the same operation to every provider's builder.
 macro_rules! with_builder {
     ($builder:expr, |$b:ident| $op:expr) => {
         match $builder {
             AgentBuilderKind::Anthropic($b) => AgentBuilderKind::Anthropic($op),
             AgentBuilderKind::OpenAI($b) => AgentBuilderKind::OpenAI($op),
+            AgentBuilderKind::OpenAICompatible($b) => AgentBuilderKind::OpenAICompatible($op),
+            AgentBuilderKind::Mock($b) => AgentBuilderKind::Mock($op),
         }
     };
 }
@@ -57,10 +63,24 @@ macro_rules! build_agent {
         match $builder {
             AgentBuilderKind::Anthropic($b) => AgentKind::Anthropic(Arc::new($op)),
             AgentBuilderKind::OpenAI($b) => AgentKind::OpenAI(Arc::new($op)),
+            AgentBuilderKind::OpenAICompatible($b) => AgentKind::OpenAICompatible(Arc::new($op)),
+            AgentBuilderKind::Mock($b) => AgentKind::Mock(Arc::new($op)),
         }
     };
 }
 
+/// OpenAI client, optionally pointed at a custom (OpenAI-compatible) base URL.
+fn openai_client(api_key: &str, base_url: Option<&str>) -> Result<rig::providers::openai::Client> {
+    let builder = rig::providers::openai::Client::builder(api_key);
+    let builder = match base_url {
+        Some(base_url) => builder.base_url(base_url),
+        None => builder,
+    };
+    builder
+        .build()
+        .map_err(|e| eyre::eyre!("Failed to build OpenAI client: {e}"))
+}
+
 async fn preamble() -> String {
     preamble_builder()
         .await
@@ -92,7 +112,9 @@ pub struct CoreAppBuilder {
     scheduler: Arc<ToolScheduler>,
     document_store: Option<Arc<Mutex<DocumentStore>>>,
     tool_namespaces: HashMap<String, String>,
-    model: AomiModel,
+    approval_tools: HashSet<String>,
+    read_only_tools: HashSet<String>,
+    selection: Selection,
 }
 
 impl CoreAppBuilder {
@@ -112,7 +134,9 @@ impl CoreAppBuilder {
             scheduler,
             document_store: None,
             tool_namespaces: HashMap::new(),
-            model: AomiModel::ClaudeOpus4,
+            approval_tools: HashSet::new(),
+            read_only_tools: HashSet::new(),
+            selection: Selection::default(),
         })
     }
 
@@ -125,29 +149,63 @@ impl CoreAppBuilder {
         opts: BuildOpts,
         system_events: Option<&SystemEventQueue>,
     ) -> Result<Self> {
-        // Determine provider from model selection
-        let provider = opts.selection.rig.rig_provider().unwrap_or("anthropic");
-        let model_id = opts.selection.rig.rig_id();
-
-        let agent_builder = match provider {
-            "openai" => {
-                let api_key = match OPENAI_API_KEY.as_ref() {
-                    Ok(key) => key.clone(),
-                    Err(_) => {
+        // Resolve provider and upstream model id through the model registry
+        let resolved = match model_registry().resolve(opts.selection.rig) {
+            Ok(resolved) => resolved,
+            Err(err) => {
+                if let Some(events) = system_events {
+                    events.push(SystemEvent::SystemError(err.to_string()));
+                }
+                return Err(eyre::eyre!(err.to_string()));
+            }
+        };
+        let model_id = resolved.model_id.as_str();
+
+        let agent_builder = match resolved.provider.kind {
+            ProviderKind::OpenAI => {
+                let api_key = match resolved
+                    .provider
+                    .api_key()
+                    .or_else(|| OPENAI_API_KEY.clone().ok())
+                {
+                    Some(key) => key,
+                    None => {
                         if let Some(events) = system_events {
                             events.push(SystemEvent::SystemError("OPENAI_API_KEY missing".into()));
                         }
                         return Err(eyre::eyre!("OPENAI_API_KEY not set"));
                     }
                 };
-                let client = rig::providers::openai::Client::new(&api_key);
+                let client = openai_client(&api_key, resolved.provider.base_url.as_deref())?;
                 AgentBuilderKind::OpenAI(client.agent(model_id).preamble(preamble))
             }
-            _ => {
-                // Default to Anthropic
-                let api_key = match ANTHROPIC_API_KEY.as_ref() {
-                    Ok(key) => key.clone(),
-                    Err(_) => {
+            ProviderKind::OpenAICompatible => {
+                // Local servers (vLLM, Ollama, llama.cpp) usually ignore the key
+                let api_key = resolved.provider.api_key().unwrap_or_default();
+                let base_url = resolved
+                    .provider
+                    .base_url
+                    .as_deref()
+                    .ok_or_else(|| eyre::eyre!("openai_compatible provider has no base_url"))?;
+                let client = openai_client(&api_key, Some(base_url))?;
+                let model = client.completion_model(model_id).completions_api();
+                AgentBuilderKind::OpenAICompatible(AgentBuilder::new(model).preamble(preamble))
+            }
+            ProviderKind::Mock => {
+                let model = MockCompletionModel::from_provider(
+                    model_id,
+                    resolved.provider.script.as_deref(),
+                )?;
+                AgentBuilderKind::Mock(AgentBuilder::new(model).preamble(preamble))
+            }
+            ProviderKind::Anthropic => {
+                let api_key = match resolved
+                    .provider
+                    .api_key()
+                    .or_else(|| ANTHROPIC_API_KEY.clone().ok())
+                {
+                    Some(key) => key,
+                    None => {
                         if let Some(events) = system_events {
                             events
                                 .push(SystemEvent::SystemError("ANTHROPIC_API_KEY missing".into()));
@@ -162,19 +220,42 @@ impl CoreAppBuilder {
 
         // Get or initialize the global scheduler and register core tools
         let scheduler = ToolScheduler::get_or_init().await?;
+        Self::with_agent_builder(agent_builder, scheduler, opts).await
+    }
 
+    /// Builder backed by a [`MockCompletionModel`], so the full completion loop
+    /// (tool calls, scheduler, session state) runs without an API key.
+    /// Tests typically pass an isolated `ToolScheduler::new_for_test()`.
+    pub async fn new_mock(
+        preamble: &str,
+        opts: BuildOpts,
+        model: MockCompletionModel,
+        scheduler: Arc<ToolScheduler>,
+    ) -> Result<Self> {
+        let agent_builder = AgentBuilderKind::Mock(AgentBuilder::new(model).preamble(preamble));
+        Self::with_agent_builder(agent_builder, scheduler, opts).await
+    }
+
+    async fn with_agent_builder(
+        agent_builder: AgentBuilderKind,
+        scheduler: Arc<ToolScheduler>,
+        opts: BuildOpts,
+    ) -> Result<Self> {
         if !opts.no_tools {
             let mut builder_state = Self {
                 agent_builder: Some(agent_builder),
                 scheduler,
                 document_store: None,
                 tool_namespaces: HashMap::new(),
-                model: opts.selection.rig,
+                approval_tools: HashSet::new(),
+                read_only_tools: HashSet::new(),
+                selection: opts.selection,
             };
 
             builder_state.add_tool(brave_search::BraveSearch)?;
             builder_state.add_tool(wallet::SendTransactionToWallet)?;
             builder_state.add_tool(abi_encoder::EncodeFunctionCall)?;
+            builder_state.add_tool(abi_decoder::DecodeCalldata)?;
             builder_state.add_tool(cast::CallViewFunction)?;
             builder_state.add_tool(cast::SimulateContractCall)?;
             builder_state.add_tool(context::GetTimeAndOnchainCtx)?;
@@ -183,6 +264,12 @@ impl CoreAppBuilder {
             builder_state.add_tool(etherscan::GetContractFromEtherscan)?;
             builder_state.add_tool(account::GetAccountInfo)?;
             builder_state.add_tool(account::GetAccountTransactionHistory)?;
+            builder_state.add_tool(logs::GetLogs)?;
+            builder_state.add_tool(portfolio::GetPortfolio)?;
+            builder_state.add_tool(tokens::ResolveToken)?;
+            builder_state.add_tool(names::ResolveName)?;
+            builder_state.add_tool(names::LookupAddress)?;
+            builder_state.add_tool(session_search::SearchSessions)?;
 
             // Add docs tool if not skipped
             if !opts.no_docs {
@@ -197,7 +284,9 @@ impl CoreAppBuilder {
             scheduler,
             document_store: None,
             tool_namespaces: HashMap::new(),
-            model: opts.selection.rig,
+            approval_tools: HashSet::new(),
+            read_only_tools: HashSet::new(),
+            selection: opts.selection,
         })
     }
 
@@ -208,6 +297,12 @@ impl CoreAppBuilder {
         self.scheduler.register_tool(&tool)?;
         self.tool_namespaces
             .insert(T::NAME.to_string(), T::NAMESPACE.to_string());
+        if tool.requires_approval() {
+            self.approval_tools.insert(T::NAME.to_string());
+        }
+        if tool.read_only() {
+            self.read_only_tools.insert(T::NAME.to_string());
+        }
 
         if let Some(builder) = self.agent_builder.take() {
             self.agent_builder =
@@ -225,6 +320,8 @@ impl CoreAppBuilder {
             SharedDocuments::NAME.to_string(),
             SharedDocuments::NAMESPACE.to_string(),
         );
+        self.read_only_tools
+            .insert(SharedDocuments::NAME.to_string());
 
         if let Some(builder) = self.agent_builder.take() {
             self.agent_builder =
@@ -283,7 +380,9 @@ impl CoreAppBuilder {
             agent,
             document_store: self.document_store,
             tool_namespaces: Arc::new(self.tool_namespaces),
-            model: self.model,
+            approval_tools: Arc::new(self.approval_tools),
+            read_only_tools: Arc::new(self.read_only_tools),
+            selection: self.selection,
         })
     }
 }
@@ -301,6 +400,18 @@ pub trait AomiApp: Send + Sync {
     fn tool_namespaces(&self) -> Arc<HashMap<String, String>> {
         Arc::new(HashMap::new())
     }
+
+    /// Token budget for the context window, sized to the selected model.
+    /// `None` falls back to `DEFAULT_CONTEXT_BUDGET`.
+    fn context_budget(&self) -> Option<usize> {
+        None
+    }
+
+    /// Summarizer used when context compaction is enabled.
+    /// `None` disables compaction for this app.
+    fn summarizer(&self) -> Option<Arc<dyn ConversationSummarizer>> {
+        None
+    }
 }
 
 /// Enum to hold agents for different providers.
@@ -312,19 +423,26 @@ pub trait AomiApp: Send + Sync {
 pub enum AgentKind {
     Anthropic(Arc<Agent<AnthropicModel>>),
     OpenAI(Arc<Agent<OpenAIModel>>),
+    /// OpenAI chat completions API against a custom base URL (vLLM, Ollama, llama.cpp).
+    OpenAICompatible(Arc<Agent<OpenAICompatibleModel>>),
+    Mock(Arc<Agent<MockCompletionModel>>),
 }
 
 /// Enum to hold agent builders for different providers
 pub enum AgentBuilderKind {
     Anthropic(AgentBuilder<AnthropicModel>),
     OpenAI(AgentBuilder<OpenAIModel>),
+    OpenAICompatible(AgentBuilder<OpenAICompatibleModel>),
+    Mock(AgentBuilder<MockCompletionModel>),
 }
 
 pub struct CoreApp {
     agent: AgentKind,
     document_store: Option<Arc<Mutex<DocumentStore>>>,
     tool_namespaces: Arc<HashMap<String, String>>,
-    model: AomiModel,
+    approval_tools: Arc<HashSet<String>>,
+    read_only_tools: Arc<HashSet<String>>,
+    selection: Selection,
 }
 
 impl CoreApp {
@@ -333,7 +451,7 @@ impl CoreApp {
     }
 
     pub fn model(&self) -> AomiModel {
-        self.model
+        self.selection.rig
     }
 
     pub async fn new(opts: BuildOpts) -> Result<Self> {
@@ -356,12 +474,43 @@ impl CoreApp {
         self.tool_namespaces.clone()
     }
 
+    /// Names of tools that wait for user approval before running
+    pub fn approval_tools(&self) -> Arc<HashSet<String>> {
+        self.approval_tools.clone()
+    }
+
+    /// Names of tools whose calls may run concurrently within a turn
+    pub fn read_only_tools(&self) -> Arc<HashSet<String>> {
+        self.read_only_tools.clone()
+    }
+
+    pub fn context_budget(&self) -> usize {
+        budget_for_model(self.selection.rig)
+    }
+
+    /// Summarizer for context compaction, running on the selection's BAML model.
+    /// Mock (offline) agents get none, so compaction never reaches a live provider.
+    pub fn summarizer(&self) -> Option<Arc<dyn ConversationSummarizer>> {
+        match self.agent {
+            AgentKind::Mock(_) => None,
+            _ => Some(Arc::new(BamlSummarizer::new(self.selection.baml))),
+        }
+    }
+
     pub async fn process_message(
         &self,
         input: String,
         state: &mut CoreState,
         mut ctx: CoreCtx<'_>,
     ) -> Result<()> {
+        // Fold newly evicted messages into the rolling summary before building context
+        if let Err(err) = state.compact_context().await {
+            tracing::warn!(
+                session_id = %state.session_id,
+                "Context compaction failed, evicted messages are dropped: {err}"
+            );
+        }
+
         // Clone the state for the stream, preserving context window settings
         let mut core_state = CoreState::new(
             state.user_state.clone(),
@@ -373,8 +522,11 @@ impl CoreApp {
         );
         // If source state has context window enabled, enable it on the clone too
         if state.context_stats().is_some() {
-            core_state.enable_context_window(None);
+            core_state.enable_context_window(Some(self.context_budget()));
         }
+        core_state.policy_scope = state.policy_scope.clone();
+        core_state.approval_tools = self.approval_tools.clone();
+        core_state.read_only_tools = self.read_only_tools.clone();
 
         let stream = match &self.agent {
             AgentKind::Anthropic(agent) => {
@@ -383,6 +535,12 @@ impl CoreApp {
             AgentKind::OpenAI(agent) => {
                 stream_completion(agent.clone(), input.clone(), core_state).await
             }
+            AgentKind::OpenAICompatible(agent) => {
+                stream_completion(agent.clone(), input.clone(), core_state).await
+            }
+            AgentKind::Mock(agent) => {
+                stream_completion(agent.clone(), input.clone(), core_state).await
+            }
         };
 
         let mut response = String::new();
@@ -415,4 +573,12 @@ impl AomiApp for CoreApp {
     fn tool_namespaces(&self) -> Arc<HashMap<String, String>> {
         self.tool_namespaces()
     }
+
+    fn context_budget(&self) -> Option<usize> {
+        Some(CoreApp::context_budget(self))
+    }
+
+    fn summarizer(&self) -> Option<Arc<dyn ConversationSummarizer>> {
+        CoreApp::summarizer(self)
+    }
 }
@@ -0,0 +1,184 @@
+//! Human-in-the-loop approval for tools flagged with `AomiTool::requires_approval`.
+//!
+//! Before such a tool runs, the completion loop emits a `tool_approval_request` InlineCall
+//! carrying the exact arguments and waits for the UI to answer through `/api/system` with:
+//!
+//! ```json
+//! { "type": "tool_approval_response", "id": "<tool call id>", "approved": true, "reason": "..." }
+//! ```
+//!
+//! The answer is queued as an InlineCall (see [`approval_response`]) and is kept out of the
+//! LLM context; the model only sees the rejection result when the call does not run.
+
+use std::time::Duration;
+
+use chrono::Utc;
+use serde_json::{Value, json};
+
+use crate::events::{SystemEvent, SystemEventQueue};
+
+pub const TOOL_APPROVAL_REQUEST: &str = "tool_approval_request";
+pub const TOOL_APPROVAL_RESPONSE: &str = "tool_approval_response";
+
+/// How long a flagged tool call waits for the user before it is treated as rejected.
+pub const APPROVAL_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// The user's answer to an approval request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApprovalDecision {
+    Approved,
+    Rejected(Option<String>),
+    TimedOut,
+}
+
+impl ApprovalDecision {
+    /// Tool result returned to the LLM in place of running the tool.
+    pub fn rejection_result(&self, tool_name: &str) -> Option<Value> {
+        match self {
+            ApprovalDecision::Approved => None,
+            ApprovalDecision::Rejected(reason) => Some(json!({
+                "status": "rejected",
+                "tool_name": tool_name,
+                "reason": reason.as_deref().unwrap_or("The user declined this action"),
+            })),
+            ApprovalDecision::TimedOut => Some(json!({
+                "status": "rejected",
+                "tool_name": tool_name,
+                "reason": "The user did not approve this action in time",
+            })),
+        }
+    }
+}
+
+/// Builds the InlineCall shown to the UI for a flagged tool call.
+pub fn approval_request(tool_call: &rig::message::ToolCall) -> SystemEvent {
+    SystemEvent::InlineCall(json!({
+        "type": TOOL_APPROVAL_REQUEST,
+        "payload": {
+            "id": tool_call.id,
+            "call_id": tool_call.call_id,
+            "tool_name": tool_call.function.name,
+            "args": tool_call.function.arguments,
+            "timestamp": Utc::now().to_rfc3339(),
+        },
+    }))
+}
+
+/// Wraps a UI answer to an approval request as the event the approval gate waits on.
+pub fn approval_response(value: Value) -> SystemEvent {
+    SystemEvent::InlineCall(value)
+}
+
+/// Parses a UI response for the tool call `id`, if `value` is one.
+pub fn parse_approval_response(value: &Value, id: &str) -> Option<ApprovalDecision> {
+    if value.get("type").and_then(Value::as_str) != Some(TOOL_APPROVAL_RESPONSE)
+        || value.get("id").and_then(Value::as_str) != Some(id)
+    {
+        return None;
+    }
+    if value.get("approved").and_then(Value::as_bool) == Some(true) {
+        Some(ApprovalDecision::Approved)
+    } else {
+        let reason = value
+            .get("reason")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        Some(ApprovalDecision::Rejected(reason))
+    }
+}
+
+/// Waits until the UI answers the approval request for `id` or `timeout` elapses.
+pub async fn wait_for_approval(
+    events: &SystemEventQueue,
+    id: &str,
+    timeout: Duration,
+) -> ApprovalDecision {
+    // Only responses that arrive after the request count
+    let mut seen = events.len();
+    let wait = async {
+        loop {
+            let pushed = events.pushed().notified();
+            let new_events = events.slice_from(seen);
+            seen += new_events.len();
+            if let Some(decision) = new_events.iter().find_map(|event| match event {
+                SystemEvent::InlineCall(value) => parse_approval_response(value, id),
+                _ => None,
+            }) {
+                return decision;
+            }
+            pushed.await;
+        }
+    };
+    tokio::time::timeout(timeout, wait)
+        .await
+        .unwrap_or(ApprovalDecision::TimedOut)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_approval_responses_for_matching_id() {
+        let approved = json!({ "type": TOOL_APPROVAL_RESPONSE, "id": "call_1", "approved": true });
+        assert_eq!(
+            parse_approval_response(&approved, "call_1"),
+            Some(ApprovalDecision::Approved)
+        );
+        assert_eq!(parse_approval_response(&approved, "call_2"), None);
+
+        let rejected = json!({
+            "type": TOOL_APPROVAL_RESPONSE,
+            "id": "call_1",
+            "approved": false,
+            "reason": "too expensive"
+        });
+        assert_eq!(
+            parse_approval_response(&rejected, "call_1"),
+            Some(ApprovalDecision::Rejected(Some(
+                "too expensive".to_string()
+            )))
+        );
+
+        let other = json!({ "type": "wallet_tx_response", "id": "call_1" });
+        assert_eq!(parse_approval_response(&other, "call_1"), None);
+    }
+
+    #[tokio::test]
+    async fn wait_for_approval_sees_later_response() {
+        let events = SystemEventQueue::new();
+        // Responses recorded before the request do not count
+        events.push(approval_response(
+            json!({ "type": TOOL_APPROVAL_RESPONSE, "id": "call_1", "approved": true }),
+        ));
+
+        let responder = events.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            responder.push(approval_response(
+                json!({ "type": TOOL_APPROVAL_RESPONSE, "id": "call_1", "approved": false }),
+            ));
+        });
+
+        let decision = wait_for_approval(&events, "call_1", Duration::from_secs(5)).await;
+        assert_eq!(decision, ApprovalDecision::Rejected(None));
+    }
+
+    #[test]
+    fn approval_responses_stay_out_of_llm_context() {
+        let events = SystemEventQueue::new();
+        let value = json!({ "type": TOOL_APPROVAL_RESPONSE, "id": "call_1", "approved": true });
+        events.push(approval_response(value.clone()));
+        // Even if a caller routes it as an async callback
+        events.push(SystemEvent::AsyncCallback(value));
+        assert!(events.advance_llm_events().is_empty());
+    }
+
+    #[tokio::test]
+    async fn wait_for_approval_times_out() {
+        let events = SystemEventQueue::new();
+        let decision = wait_for_approval(&events, "call_1", Duration::from_millis(10)).await;
+        assert_eq!(decision, ApprovalDecision::TimedOut);
+        assert!(decision.rejection_result("tool").is_some());
+    }
+}
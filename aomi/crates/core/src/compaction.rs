@@ -0,0 +1,174 @@
+//! LLM-driven compaction of messages evicted from the context window.
+//!
+//! With compaction enabled, messages that slide out of the window are folded into
+//! a rolling summary (via `GenerateConversationSummary`) that stays pinned at the
+//! head of the window, instead of being silently dropped.
+
+use std::sync::LazyLock;
+
+use aomi_baml::AomiModel;
+use aomi_baml::baml_client::{
+    async_client::B,
+    types::{ChatMessage as BamlChatMessage, ConversationSummary},
+};
+use async_trait::async_trait;
+use eyre::Result;
+use rig::message::{AssistantContent, Message, ToolResultContent, UserContent};
+
+/// Opt-in switch for compaction mode (`CONTEXT_COMPACTION=1`).
+pub static CONTEXT_COMPACTION_ENABLED: LazyLock<bool> = LazyLock::new(|| {
+    matches!(
+        std::env::var("CONTEXT_COMPACTION").as_deref(),
+        Ok("1" | "true")
+    )
+});
+
+/// Tool outputs can be huge (ABIs, traces); only the head is worth summarizing.
+const MAX_MESSAGE_CHARS: usize = 2_000;
+
+/// Produces the rolling summary for messages evicted from the context window.
+#[async_trait]
+pub trait ConversationSummarizer: Send + Sync {
+    /// Folds `evicted` into `previous` (the current summary, if any).
+    async fn summarize(&self, previous: Option<&str>, evicted: &[Message]) -> Result<String>;
+}
+
+/// Summarizer backed by the `GenerateConversationSummary` BAML function.
+#[derive(Debug, Clone, Copy)]
+pub struct BamlSummarizer {
+    model: AomiModel,
+}
+
+impl BamlSummarizer {
+    pub fn new(model: AomiModel) -> Self {
+        Self { model }
+    }
+}
+
+#[async_trait]
+impl ConversationSummarizer for BamlSummarizer {
+    async fn summarize(&self, previous: Option<&str>, evicted: &[Message]) -> Result<String> {
+        let messages = to_baml_messages(previous, evicted);
+        let summary = B
+            .GenerateConversationSummary
+            .with_client(self.model.baml_client_name())
+            .call(&messages)
+            .await
+            .map_err(|e| eyre::eyre!("Failed to summarize evicted context: {e}"))?;
+        Ok(render_summary(&summary))
+    }
+}
+
+fn render_summary(summary: &ConversationSummary) -> String {
+    format!(
+        "Topic: {}\nDetails: {}\nWhere we left off: {}",
+        summary.title,
+        summary.key_details.join("; "),
+        summary.current_state
+    )
+}
+
+/// Flattens rig messages (including tool traffic) into BAML chat messages.
+fn to_baml_messages(previous: Option<&str>, evicted: &[Message]) -> Vec<BamlChatMessage> {
+    let previous = previous.map(|summary| BamlChatMessage {
+        role: "assistant".to_string(),
+        content: format!("Summary of the conversation so far:\n{summary}"),
+    });
+    previous
+        .into_iter()
+        .chain(evicted.iter().filter_map(to_baml_message))
+        .collect()
+}
+
+fn to_baml_message(message: &Message) -> Option<BamlChatMessage> {
+    let (role, parts): (&str, Vec<String>) = match message {
+        Message::User { content } => (
+            "user",
+            content
+                .iter()
+                .filter_map(|content| match content {
+                    UserContent::Text(text) => Some(text.text.clone()),
+                    UserContent::ToolResult(result) => Some(format!(
+                        "Tool result ({}): {}",
+                        result.id,
+                        result
+                            .content
+                            .iter()
+                            .filter_map(|c| match c {
+                                ToolResultContent::Text(t) => Some(t.text.as_str()),
+                                ToolResultContent::Image(_) => None,
+                            })
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    )),
+                    _ => None,
+                })
+                .collect(),
+        ),
+        Message::Assistant { content, .. } => (
+            "assistant",
+            content
+                .iter()
+                .filter_map(|content| match content {
+                    AssistantContent::Text(text) => Some(text.text.clone()),
+                    AssistantContent::ToolCall(call) => Some(format!(
+                        "Called {}({})",
+                        call.function.name, call.function.arguments
+                    )),
+                    AssistantContent::Reasoning(_) => None,
+                })
+                .collect(),
+        ),
+    };
+
+    let content = parts.join("\n");
+    if content.trim().is_empty() {
+        return None;
+    }
+    Some(BamlChatMessage {
+        role: role.to_string(),
+        content: truncate(&content, MAX_MESSAGE_CHARS),
+    })
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((idx, _)) => format!("{}…", &text[..idx]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rig::OneOrMany;
+
+    #[test]
+    fn test_baml_messages_keep_tool_traffic() {
+        let evicted = vec![
+            Message::user("swap 1 ETH to USDC"),
+            Message::Assistant {
+                id: None,
+                content: OneOrMany::one(AssistantContent::tool_call(
+                    "call_1",
+                    "get_quote",
+                    serde_json::json!({ "amount": "1" }),
+                )),
+            },
+            Message::User {
+                content: OneOrMany::one(UserContent::tool_result(
+                    "call_1",
+                    OneOrMany::one(ToolResultContent::text("x".repeat(5_000))),
+                )),
+            },
+        ];
+
+        let messages = to_baml_messages(Some("User wants to trade"), &evicted);
+        assert_eq!(messages.len(), 4);
+        assert!(messages[0].content.contains("User wants to trade"));
+        assert_eq!(messages[2].role, "assistant");
+        assert!(messages[2].content.contains("get_quote"));
+        assert!(messages[3].content.starts_with("Tool result (call_1)"));
+        assert!(messages[3].content.chars().count() <= MAX_MESSAGE_CHARS + 1);
+    }
+}
@@ -1,13 +1,17 @@
 use crate::CoreCommand;
+use crate::approval::{APPROVAL_TIMEOUT, ApprovalDecision, approval_request, wait_for_approval};
 use crate::events::SystemEvent;
 use crate::state::CoreState;
+use aomi_tools::db::UsageEvent;
+use aomi_tools::metrics::metrics;
+use aomi_tools::usage::record_usage;
 use aomi_tools::{CallMetadata, ToolCallCtx, ToolReturn};
 use chrono::Utc;
-use futures::{Stream, StreamExt, stream::BoxStream};
+use futures::{Stream, StreamExt, future::join_all, stream::BoxStream};
 use rig::{
     OneOrMany,
     agent::Agent,
-    completion::CompletionModel,
+    completion::{CompletionModel, GetTokenUsage},
     message::{AssistantContent, Message},
     streaming::{StreamedAssistantContent, StreamingCompletion},
     tool::ToolSetError as RigToolError,
@@ -15,6 +19,7 @@ use rig::{
 use serde_json::{Value, json};
 use std::{pin::Pin, sync::Arc};
 use thiserror::Error;
+use tracing::Instrument;
 
 #[derive(Debug, Error)]
 pub enum StreamingError {
@@ -38,6 +43,8 @@ where
 {
     agent: Arc<Agent<M>>,
     state: CoreState,
+    /// Span covering the whole LLM stream; tool call spans are its children
+    span: tracing::Span,
 }
 
 struct StreamState<R> {
@@ -60,7 +67,30 @@ where
     <M as CompletionModel>::StreamingResponse: Send,
 {
     pub fn new(agent: Arc<Agent<M>>, state: CoreState) -> Self {
-        Self { agent, state }
+        let span = tracing::info_span!(
+            "llm_stream",
+            session_id = state.public_session_id(),
+            namespace = state.policy_scope.namespace.as_deref()
+        );
+        Self { agent, state, span }
+    }
+
+    fn tool_span(&self, tool_call: &rig::message::ToolCall) -> tracing::Span {
+        let name = &tool_call.function.name;
+        tracing::info_span!(
+            parent: &self.span,
+            "tool_call",
+            session_id = self.state.public_session_id(),
+            namespace = self
+                .state
+                .tool_namespaces
+                .get(name)
+                .map(String::as_str)
+                .unwrap_or("external"),
+            tool = name.as_str(),
+            tool_call_id = tool_call.id.as_str(),
+            call_id = tool_call.call_id.as_deref()
+        )
     }
 
     async fn init_stream_state(
@@ -105,31 +135,100 @@ where
                 vec![CoreCommand::StreamingText(reasoning.reasoning)],
             )),
             Some(Ok(StreamedAssistantContent::ToolCall(tool_call))) => {
-                let mut cmds = self
-                    .consume_system_events(&tool_call)
-                    .map(|c| vec![c])
-                    .unwrap_or_default();
-                let topic = match tool_call.function.arguments.get("topic") {
-                    Some(Value::String(topic)) => topic.clone(),
-                    _ => tool_call.function.name.clone(),
-                };
-                let tool_return = self.process_tool_call(tool_call.clone()).await?;
+                // Calls of one turn run together once the stream ends
                 state.cached_tool_calls.push(tool_call);
-                state.cached_tool_returns.push(tool_return.clone());
-
-                cmds.push(CoreCommand::ToolCall {
-                    topic,
-                    stream: tool_return,
-                });
-                Ok(ProcessStep::Emit(cmds))
+                Ok(ProcessStep::Continue)
+            }
+            Some(Ok(StreamedAssistantContent::Final(response))) => {
+                if let Some(usage) = response.token_usage() {
+                    metrics().record_llm_usage(
+                        self.state.policy_scope.namespace.as_deref(),
+                        self.state.policy_scope.api_key.as_deref(),
+                        usage.input_tokens,
+                        usage.output_tokens,
+                    );
+                    record_usage(UsageEvent::llm(
+                        self.state.public_session_id(),
+                        &self.state.policy_scope,
+                        usage.input_tokens,
+                        usage.output_tokens,
+                    ));
+                }
+                Ok(ProcessStep::Continue)
             }
-            Some(Ok(StreamedAssistantContent::Final(_))) => Ok(ProcessStep::Continue),
             Some(Err(e)) => Err(e.into()),
             None => {
                 state.llm_finished = true;
-                Ok(ProcessStep::Finished)
+                if state.cached_tool_calls.is_empty() {
+                    return Ok(ProcessStep::Finished);
+                }
+                let cmds = self.run_tool_calls(state).await?;
+                Ok(ProcessStep::Emit(cmds))
+            }
+        }
+    }
+
+    /// Runs the turn's tool calls and emits their results in call order.
+    /// Consecutive calls to read-only tools run concurrently; any other call waits for the
+    /// calls before it and runs alone, so side effects keep the order the LLM asked for.
+    /// Concurrency of Aomi sync tools is bounded per session by the `ToolScheduler`.
+    /// Dropping the returned future (on interrupt) cancels every outstanding call.
+    async fn run_tool_calls(
+        &mut self,
+        state: &mut StreamState<<M as CompletionModel>::StreamingResponse>,
+    ) -> Result<Vec<CoreCommand>, StreamingError> {
+        let runner = &*self;
+        let mut tool_returns = Vec::with_capacity(state.cached_tool_calls.len());
+        let mut read_only_batch = Vec::new();
+        for tool_call in &state.cached_tool_calls {
+            if runner
+                .state
+                .read_only_tools
+                .contains(&tool_call.function.name)
+            {
+                read_only_batch.push(runner.run_tool_call(tool_call));
+            } else {
+                tool_returns.extend(join_all(std::mem::take(&mut read_only_batch)).await);
+                tool_returns.push(runner.run_tool_call(tool_call).await);
             }
         }
+        tool_returns.extend(join_all(read_only_batch).await);
+
+        let mut cmds = Vec::new();
+        for (tool_call, tool_return) in state.cached_tool_calls.iter().zip(tool_returns) {
+            let tool_return = tool_return?;
+            cmds.extend(self.consume_system_events(tool_call, &tool_return));
+            state.cached_tool_returns.push(tool_return.clone());
+
+            let topic = match tool_call.function.arguments.get("topic") {
+                Some(Value::String(topic)) => topic.clone(),
+                _ => tool_call.function.name.clone(),
+            };
+            cmds.push(CoreCommand::ToolCall {
+                topic,
+                stream: tool_return,
+            });
+        }
+        Ok(cmds)
+    }
+
+    async fn run_tool_call(
+        &self,
+        tool_call: &rig::message::ToolCall,
+    ) -> Result<ToolReturn, StreamingError> {
+        match self.request_approval(tool_call).await {
+            ApprovalDecision::Approved => {
+                record_usage(UsageEvent::tool(
+                    self.state.public_session_id(),
+                    &self.state.policy_scope,
+                    &tool_call.function.name,
+                ));
+                self.process_tool_call(tool_call.clone())
+                    .instrument(self.tool_span(tool_call))
+                    .await
+            }
+            decision => Ok(self.rejected_tool_call(tool_call, &decision)),
+        }
     }
 
     pub async fn stream(self, prompt: Message) -> CoreCommandStream {
@@ -145,7 +244,12 @@ where
         let chat_command_stream = async_stream::stream! {
             // Outer loop: restart LLM when tools are called
             'outer: loop {
-                let mut streamer = match runner.init_stream_state(current_prompt.clone()).await {
+                let span = runner.span.clone();
+                let mut streamer = match runner
+                    .init_stream_state(current_prompt.clone())
+                    .instrument(span.clone())
+                    .await
+                {
                     Ok(state) => state,
                     Err(err) => {
                         yield Err(err);
@@ -155,7 +259,11 @@ where
 
                 // Inner loop: process LLM stream items
                 loop {
-                    match runner.consume_stream_item(&mut streamer).await {
+                    match runner
+                        .consume_stream_item(&mut streamer)
+                        .instrument(span.clone())
+                        .await
+                    {
                         Ok(ProcessStep::Emit(commands)) => {
                             for command in commands {
                                 yield Ok(command);
@@ -206,15 +314,94 @@ where
         chat_command_stream.boxed()
     }
 
-    fn consume_system_events(&mut self, tool_call: &rig::message::ToolCall) -> Option<CoreCommand> {
+    /// Pauses on tools flagged `requires_approval` until the UI answers the approval
+    /// request. Unflagged tools are approved immediately.
+    async fn request_approval(&self, tool_call: &rig::message::ToolCall) -> ApprovalDecision {
+        if !self.state.approval_tools.contains(&tool_call.function.name) {
+            return ApprovalDecision::Approved;
+        }
+        let Some(system_events) = self.state.system_events.as_ref() else {
+            return ApprovalDecision::Rejected(Some(
+                "This action requires user approval, which is unavailable in this session"
+                    .to_string(),
+            ));
+        };
+
+        system_events.push(approval_request(tool_call));
+        let decision = wait_for_approval(system_events, &tool_call.id, APPROVAL_TIMEOUT).await;
+        if decision != ApprovalDecision::Approved {
+            system_events.push(SystemEvent::SystemNotice(format!(
+                "{} was not approved",
+                tool_call.function.name
+            )));
+        }
+        decision
+    }
+
+    fn rejected_tool_call(
+        &self,
+        tool_call: &rig::message::ToolCall,
+        decision: &ApprovalDecision,
+    ) -> ToolReturn {
+        let name = &tool_call.function.name;
+        let namespace = self
+            .state
+            .tool_namespaces
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| "external".to_string());
+        ToolReturn {
+            metadata: CallMetadata::new(
+                name.clone(),
+                namespace,
+                tool_call.id.clone(),
+                tool_call.call_id.clone(),
+                false,
+            ),
+            inner: decision.rejection_result(name).unwrap_or(Value::Null),
+            is_sync_ack: true,
+        }
+    }
+
+    fn consume_system_events(
+        &mut self,
+        tool_call: &rig::message::ToolCall,
+        tool_return: &ToolReturn,
+    ) -> Option<CoreCommand> {
         self.state.system_events.as_ref()?;
         let system_events = self.state.system_events.as_ref()?;
         let called_event_name = tool_call.function.name.to_lowercase();
         if called_event_name == "send_transaction_to_wallet" {
+            // Policy-rejected transactions never reach the wallet; the agent explains via the tool result
+            if tool_return.inner.get("status").and_then(Value::as_str) == Some("rejected") {
+                system_events.push(SystemEvent::SystemNotice(
+                    "Transaction blocked by policy".to_string(),
+                ));
+                return None;
+            }
             match tool_call.function.arguments.clone() {
                 Value::Object(mut obj) => {
                     obj.entry("timestamp".to_string())
                         .or_insert_with(|| Value::String(Utc::now().to_rfc3339()));
+                    // Forward the fork simulation preview, decoded calldata and suggested
+                    // fees so the UI can show them for approval
+                    for key in [
+                        "simulation",
+                        "decoded",
+                        "maxFeePerGas",
+                        "maxPriorityFeePerGas",
+                        "fees",
+                    ] {
+                        if let Some(value) = tool_return.inner.get(key) {
+                            obj.insert(key.to_string(), value.clone());
+                        }
+                    }
+                    // Gas limit estimated by the tool when the agent didn't give one
+                    if obj.get("gas_limit").is_none_or(Value::is_null)
+                        && let Some(gas) = tool_return.inner.get("gas").filter(|gas| !gas.is_null())
+                    {
+                        obj.insert("gas_limit".to_string(), gas.clone());
+                    }
                     let payload = Value::Object(obj);
                     system_events.push(SystemEvent::InlineCall(json!({
                         "type": "wallet_tx_request",
@@ -235,7 +422,7 @@ where
     }
 
     async fn process_tool_call(
-        &mut self,
+        &self,
         tool_call: rig::message::ToolCall,
     ) -> Result<ToolReturn, StreamingError> {
         let rig::message::ToolFunction { name, arguments } = tool_call.function.clone();
@@ -254,6 +441,7 @@ where
                 metadata: metadata.clone(),
                 user_chain_id: self.state.user_state.chain_id,
                 user_address: self.state.user_state.address.clone(),
+                policy_scope: self.state.policy_scope.clone(),
             };
             let envelope = json!({
                 "ctx": ctx,
@@ -3,53 +3,69 @@
 //! Provides O(1) sliding window over conversation history to prevent context explosion
 //! while preserving full history for persistence.
 
+use std::sync::Arc;
+
+use aomi_baml::AomiModel;
 use rig::message::Message;
+use serde::{Deserialize, Serialize};
+
+use crate::tokenizer::{HeuristicTokenizer, Tokenizer, default_tokenizer};
 
-/// Default context budget (tokens). Claude 3.5 Sonnet supports 200k input.
-/// We leave headroom for system prompt and output tokens.
+/// Default context budget (tokens), used when no model is known.
+/// Claude supports 200k input; we leave headroom for output tokens.
 pub const DEFAULT_CONTEXT_BUDGET: usize = 180_000;
 
-/// Approximate tokens per character ratio for English text.
-/// Conservative estimate: ~4 chars per token on average.
-const CHARS_PER_TOKEN: usize = 4;
+/// Marker prefixed to the rolling summary pinned at the head of a compacted window.
+pub const CONVERSATION_SUMMARY_MARKER: &str = "[[CONVERSATION_SUMMARY]]";
+
+/// Context budget for a model: its input limit minus 10% headroom for output tokens.
+pub fn budget_for_model(model: AomiModel) -> usize {
+    model.context_window() / 10 * 9
+}
 
 /// Estimates token count for a string.
 /// Uses a simple heuristic: ~4 characters per token.
-/// This is faster than calling a tokenizer and accurate enough for budgeting.
+/// This is faster than calling a tokenizer and accurate enough for prose.
 #[inline]
 pub fn estimate_tokens(text: &str) -> usize {
-    // Add 1 to avoid zero for very short strings
-    (text.len() / CHARS_PER_TOKEN).max(1)
+    HeuristicTokenizer.count_tokens(text)
 }
 
-/// Estimates token count for a Message.
-pub fn estimate_message_tokens(message: &Message) -> usize {
+/// Counts tokens for a Message.
+pub fn count_message_tokens(message: &Message, tokenizer: &dyn Tokenizer) -> usize {
     match message {
-        Message::User { content, .. } => content.iter().map(estimate_user_content_tokens).sum(),
-        Message::Assistant { content, .. } => {
-            content.iter().map(estimate_assistant_content_tokens).sum()
-        }
+        Message::User { content, .. } => content
+            .iter()
+            .map(|c| count_user_content_tokens(c, tokenizer))
+            .sum(),
+        Message::Assistant { content, .. } => content
+            .iter()
+            .map(|c| count_assistant_content_tokens(c, tokenizer))
+            .sum(),
     }
 }
 
-/// Estimates tokens for user content variants.
-fn estimate_user_content_tokens(content: &rig::message::UserContent) -> usize {
+/// Counts tokens for user content variants.
+fn count_user_content_tokens(
+    content: &rig::message::UserContent,
+    tokenizer: &dyn Tokenizer,
+) -> usize {
     use rig::message::UserContent;
     match content {
-        UserContent::Text(text) => estimate_tokens(&text.text),
+        UserContent::Text(text) => tokenizer.count_tokens(&text.text),
         UserContent::Image(_) => 85,     // Default image token estimate
         UserContent::Audio(_) => 100,    // Audio transcription estimate
         UserContent::Document(_) => 500, // Document content estimate
         UserContent::ToolResult(result) => {
             // Tool results: id + content
-            let id_tokens = estimate_tokens(&result.id);
+            let id_tokens = tokenizer.count_tokens(&result.id);
             let content_tokens: usize = result
                 .content
                 .iter()
                 .map(|c| {
                     use rig::message::ToolResultContent;
                     match c {
-                        ToolResultContent::Text(t) => estimate_tokens(&t.text),
+                        ToolResultContent::Text(t) => tokenizer.count_tokens(&t.text),
                         ToolResultContent::Image(_) => 85,
                     }
                 })
@@ -59,23 +75,26 @@ fn estimate_user_content_tokens(content: &rig::message::UserContent) -> usize {
     }
 }
 
-/// Estimates tokens for assistant content variants.
-fn estimate_assistant_content_tokens(content: &rig::message::AssistantContent) -> usize {
+/// Counts tokens for assistant content variants.
+fn count_assistant_content_tokens(
+    content: &rig::message::AssistantContent,
+    tokenizer: &dyn Tokenizer,
+) -> usize {
     use rig::message::AssistantContent;
     match content {
-        AssistantContent::Text(text) => estimate_tokens(&text.text),
-        AssistantContent::Reasoning(r) => estimate_tokens(&r.reasoning),
+        AssistantContent::Text(text) => tokenizer.count_tokens(&text.text),
+        AssistantContent::Reasoning(r) => tokenizer.count_tokens(&r.reasoning),
         AssistantContent::ToolCall(call) => {
             // Tool calls: name + arguments (serialized)
-            let name_tokens = estimate_tokens(&call.function.name);
+            let name_tokens = tokenizer.count_tokens(&call.function.name);
             let args_str = serde_json::to_string(&call.function.arguments).unwrap_or_default();
-            let args_tokens = estimate_tokens(&args_str);
+            let args_tokens = tokenizer.count_tokens(&args_str);
             name_tokens + args_tokens + 10 // 10 tokens overhead for structure
         }
     }
 }
 
-/// Cached token count for a message.
+/// Message with its token count, computed once when it enters the window.
 #[derive(Debug, Clone)]
 pub struct TokenizedMessage {
     pub message: Message,
@@ -83,8 +102,8 @@ pub struct TokenizedMessage {
 }
 
 impl TokenizedMessage {
-    pub fn new(message: Message) -> Self {
-        let token_count = estimate_message_tokens(&message);
+    pub fn new(message: Message, tokenizer: &dyn Tokenizer) -> Self {
+        let token_count = count_message_tokens(&message, tokenizer);
         Self {
             message,
             token_count,
@@ -92,6 +111,27 @@ impl TokenizedMessage {
     }
 }
 
+/// Rolling summary of the messages that slid out of the context window.
+///
+/// Persisted with the session so a reload restores the same compacted state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConversationCompaction {
+    /// LLM-generated summary of everything before `covered`
+    pub summary: String,
+    /// Number of leading history messages folded into the summary
+    pub covered: usize,
+}
+
+impl ConversationCompaction {
+    /// Renders the summary as the message pinned at the head of the window.
+    pub fn to_message(&self) -> Message {
+        Message::user(format!(
+            "{} Summary of earlier conversation (older messages were compacted):\n{}",
+            CONVERSATION_SUMMARY_MARKER, self.summary
+        ))
+    }
+}
+
 /// Sliding window over conversation history.
 ///
 /// Maintains O(1) access to the context window by tracking:
@@ -120,6 +160,14 @@ pub struct ContextWindow {
     context_budget: usize,
     /// Reserved tokens for system prompt (not counted against messages)
     system_prompt_reserve: usize,
+    /// Whether evicted messages are kept as a rolling summary instead of dropped
+    compaction_enabled: bool,
+    /// Current rolling summary, pinned at the head of the window
+    compaction: Option<ConversationCompaction>,
+    /// Tokens used by the pinned summary message
+    summary_tokens: usize,
+    /// Tokenizer used to count message tokens
+    tokenizer: Arc<dyn Tokenizer>,
 }
 
 impl Default for ContextWindow {
@@ -137,6 +185,10 @@ impl ContextWindow {
             window_tokens: 0,
             context_budget,
             system_prompt_reserve: 10_000, // Reserve 10k for system prompt
+            compaction_enabled: false,
+            compaction: None,
+            summary_tokens: 0,
+            tokenizer: default_tokenizer(),
         }
     }
 
@@ -148,19 +200,42 @@ impl ContextWindow {
             window_tokens: 0,
             context_budget,
             system_prompt_reserve,
+            compaction_enabled: false,
+            compaction: None,
+            summary_tokens: 0,
+            tokenizer: default_tokenizer(),
+        }
+    }
+
+    /// Uses the given tokenizer instead of the process default.
+    /// Recounts any messages already in the window.
+    pub fn with_tokenizer(mut self, tokenizer: Arc<dyn Tokenizer>) -> Self {
+        self.tokenizer = tokenizer;
+        let messages: Vec<Message> = self.messages.drain(..).map(|tm| tm.message).collect();
+        let compaction = self.compaction.take();
+        self.window_start = 0;
+        self.window_tokens = 0;
+        self.summary_tokens = 0;
+        for message in messages {
+            self.push(message);
         }
+        if let Some(compaction) = compaction {
+            self.apply_compaction(compaction);
+        }
+        self
     }
 
-    /// Returns the effective budget for messages (total - system reserve).
+    /// Returns the effective budget for messages (total - system reserve - pinned summary).
     fn effective_budget(&self) -> usize {
         self.context_budget
             .saturating_sub(self.system_prompt_reserve)
+            .saturating_sub(self.summary_tokens)
     }
 
     /// Adds a message to the history and updates the sliding window.
     /// O(1) amortized - only slides window forward when over budget.
     pub fn push(&mut self, message: Message) {
-        let tokenized = TokenizedMessage::new(message);
+        let tokenized = TokenizedMessage::new(message, self.tokenizer.as_ref());
         self.window_tokens += tokenized.token_count;
         self.messages.push(tokenized);
 
@@ -179,13 +254,67 @@ impl ContextWindow {
 
     /// Returns messages within the current context window.
     /// O(1) - returns a slice reference.
+    /// When compaction is active, the rolling summary is prepended.
     pub fn get_context(&self) -> Vec<Message> {
-        self.messages[self.window_start..]
+        self.compaction
             .iter()
-            .map(|tm| tm.message.clone())
+            .map(ConversationCompaction::to_message)
+            .chain(
+                self.messages[self.window_start..]
+                    .iter()
+                    .map(|tm| tm.message.clone()),
+            )
             .collect()
     }
 
+    /// Turns on compaction mode, optionally restoring a persisted summary.
+    pub fn enable_compaction(&mut self, restored: Option<ConversationCompaction>) {
+        self.compaction_enabled = true;
+        if let Some(compaction) = restored {
+            self.apply_compaction(compaction);
+        }
+    }
+
+    /// Returns the current rolling summary, if any.
+    pub fn compaction(&self) -> Option<&ConversationCompaction> {
+        self.compaction.as_ref()
+    }
+
+    /// Returns messages that slid out of the window but are not yet summarized,
+    /// along with the history index the next summary will cover up to.
+    pub fn pending_compaction(&self) -> Option<(usize, Vec<Message>)> {
+        if !self.compaction_enabled {
+            return None;
+        }
+        let covered = self.compaction.as_ref().map_or(0, |c| c.covered);
+        (self.window_start > covered).then(|| {
+            let evicted = self.messages[covered..self.window_start]
+                .iter()
+                .map(|tm| tm.message.clone())
+                .collect();
+            (self.window_start, evicted)
+        })
+    }
+
+    /// Pins a new rolling summary covering the first `covered` messages.
+    /// `covered` is clamped to the history length, and the window never
+    /// re-includes summarized messages.
+    pub fn apply_compaction(&mut self, compaction: ConversationCompaction) {
+        let covered = compaction.covered.min(self.messages.len());
+        while self.window_start < covered {
+            self.window_tokens -= self.messages[self.window_start].token_count;
+            self.window_start += 1;
+        }
+        let compaction = ConversationCompaction {
+            summary: compaction.summary,
+            covered,
+        };
+        self.summary_tokens =
+            count_message_tokens(&compaction.to_message(), self.tokenizer.as_ref());
+        self.compaction = Some(compaction);
+        self.slide_window();
+    }
+
     /// Returns all messages (full history for persistence).
     pub fn get_all_messages(&self) -> Vec<Message> {
         self.messages.iter().map(|tm| tm.message.clone()).collect()
@@ -221,6 +350,8 @@ impl ContextWindow {
         self.messages.clear();
         self.window_start = 0;
         self.window_tokens = 0;
+        self.compaction = None;
+        self.summary_tokens = 0;
     }
 
     /// Initializes the window from existing messages.
@@ -238,7 +369,7 @@ impl ContextWindow {
     pub fn update_last(&mut self, new_content: Message) -> bool {
         if let Some(last) = self.messages.last_mut() {
             let old_tokens = last.token_count;
-            let new_tokenized = TokenizedMessage::new(new_content);
+            let new_tokenized = TokenizedMessage::new(new_content, self.tokenizer.as_ref());
             self.window_tokens =
                 self.window_tokens.saturating_sub(old_tokens) + new_tokenized.token_count;
             *last = new_tokenized;
@@ -260,6 +391,9 @@ impl ContextWindow {
             if self.window_start > self.messages.len() {
                 self.window_start = self.messages.len();
             }
+            if let Some(compaction) = self.compaction.as_mut() {
+                compaction.covered = compaction.covered.min(self.messages.len());
+            }
             Some(tokenized.message)
         } else {
             None
@@ -334,4 +468,114 @@ mod tests {
         assert!(popped.is_some());
         assert_eq!(window.total_len(), 1);
     }
+
+    fn first_text(message: &Message) -> String {
+        match message {
+            Message::User { content } => match content.first() {
+                rig::message::UserContent::Text(text) => text.text,
+                other => panic!("unexpected user content: {other:?}"),
+            },
+            Message::Assistant { .. } => panic!("expected a user message"),
+        }
+    }
+
+    #[test]
+    fn test_compaction_pins_summary() {
+        let mut window = ContextWindow::with_system_reserve(100, 0);
+        window.enable_compaction(None);
+        for i in 0..10 {
+            window.push(Message::user(format!(
+                "This is message number {} with some extra content to use up tokens",
+                i
+            )));
+        }
+
+        let (covered, evicted) = window.pending_compaction().expect("messages were evicted");
+        assert_eq!(covered, window.window_start);
+        assert_eq!(evicted.len(), covered);
+
+        window.apply_compaction(ConversationCompaction {
+            summary: "User counted messages".to_string(),
+            covered,
+        });
+
+        let context = window.get_context();
+        assert!(first_text(&context[0]).starts_with(CONVERSATION_SUMMARY_MARKER));
+        assert_eq!(context.len(), window.context_len() + 1);
+        assert!(window.window_tokens() + window.summary_tokens <= 100);
+        // Full history is still preserved
+        assert_eq!(window.total_len(), 10);
+
+        // Nothing new evicted, or the pinned summary slid more messages out
+        if let Some((next, evicted)) = window.pending_compaction() {
+            assert_eq!(evicted.len(), next - covered);
+        }
+    }
+
+    #[test]
+    fn test_compaction_restore_clamps_to_history() {
+        let messages = vec![
+            Message::user("First message"),
+            Message::assistant("Response"),
+            Message::user("Follow up"),
+        ];
+        let mut window = ContextWindow::from_messages(messages, 20000);
+        window.enable_compaction(Some(ConversationCompaction {
+            summary: "Earlier chat".to_string(),
+            covered: 2,
+        }));
+
+        let context = window.get_context();
+        assert_eq!(context.len(), 2);
+        assert_eq!(first_text(&context[1]), "Follow up");
+        assert!(window.pending_compaction().is_none());
+
+        // A summary covering more than the reloaded history is clamped
+        window.apply_compaction(ConversationCompaction {
+            summary: "Everything".to_string(),
+            covered: 50,
+        });
+        assert_eq!(window.compaction().unwrap().covered, 3);
+        assert_eq!(window.get_context().len(), 1);
+    }
+
+    #[test]
+    fn test_compaction_disabled_by_default() {
+        let mut window = ContextWindow::with_system_reserve(20, 0);
+        for i in 0..5 {
+            window.push(Message::user(format!("message {} with padding text", i)));
+        }
+        assert!(window.window_start > 0);
+        assert!(window.pending_compaction().is_none());
+    }
+
+    #[derive(Debug)]
+    struct CharTokenizer;
+
+    impl Tokenizer for CharTokenizer {
+        fn count_tokens(&self, text: &str) -> usize {
+            text.chars().count()
+        }
+    }
+
+    #[test]
+    fn test_context_window_custom_tokenizer() {
+        let mut window = ContextWindow::with_system_reserve(1000, 0);
+        window.push(Message::user("0xdeadbeef"));
+        assert_eq!(window.window_tokens(), 2);
+
+        // Existing messages are recounted with the new tokenizer
+        let window = window.with_tokenizer(Arc::new(CharTokenizer));
+        assert_eq!(window.window_tokens(), 10);
+        assert_eq!(window.total_len(), 1);
+    }
+
+    #[test]
+    fn test_budget_for_model() {
+        assert_eq!(
+            budget_for_model(AomiModel::ClaudeOpus4),
+            DEFAULT_CONTEXT_BUDGET
+        );
+        assert_eq!(budget_for_model(AomiModel::Gpt5), 115_200);
+    }
 }
@@ -1,12 +1,14 @@
 use serde::Serialize;
 use serde_json::Value;
 use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
 
 /// System-level events that travel outside the LLM chat stream.
 ///
 /// Event routing:
 /// - HTTP (sync, delivered with state polling):
-///   - `InlineCall`: LLM ↔ UI (sync json event like wallet_tx_request/response)
+///   - `InlineCall`: LLM ↔ UI (sync json event like wallet_tx_request/response,
+///     tool_approval_request/response)
 ///   - `SystemError`: System → UI & LLM (connection errors, etc.)
 /// - SSE (async, broadcast immediately):
 ///   - `SystemNotice`: System → UI only (title updates, etc.)
@@ -26,11 +28,13 @@ pub enum SystemEvent {
 
 impl SystemEvent {
     /// Returns true if this event should be delivered to the LLM.
+    /// Approval responses are consumed by the approval gate and never reach the LLM.
     pub fn is_llm_event(&self) -> bool {
-        matches!(
+        (matches!(
             self,
             SystemEvent::SystemError(_) | SystemEvent::AsyncCallback(_)
-        ) || self.is_wallet_tx_response()
+        ) || self.is_wallet_tx_response())
+            && !self.is_tool_approval_response()
     }
 
     /// Returns true if this event should be delivered via HTTP (sync, with state polling).
@@ -52,15 +56,20 @@ impl SystemEvent {
     }
 
     fn is_wallet_tx_response(&self) -> bool {
-        let value = match &self {
-            SystemEvent::AsyncCallback(value) => value,
-            SystemEvent::InlineCall(value) => value,
-            _ => &serde_json::Value::Null,
-        };
-        value
-            .get("type")
-            .and_then(Value::as_str)
-            .is_some_and(|t| t == "wallet_tx_response")
+        self.json_type() == Some("wallet_tx_response")
+    }
+
+    fn is_tool_approval_response(&self) -> bool {
+        self.json_type() == Some(crate::approval::TOOL_APPROVAL_RESPONSE)
+    }
+
+    fn json_type(&self) -> Option<&str> {
+        match &self {
+            SystemEvent::AsyncCallback(value) | SystemEvent::InlineCall(value) => {
+                value.get("type").and_then(Value::as_str)
+            }
+            _ => None,
+        }
     }
 }
 
@@ -79,24 +88,35 @@ struct SystemEventQueueInner {
 #[derive(Clone, Debug, Default)]
 pub struct SystemEventQueue {
     inner: Arc<Mutex<SystemEventQueueInner>>,
+    /// Wakes tasks waiting for a new event (e.g. the approval gate)
+    pushed: Arc<Notify>,
 }
 
 impl SystemEventQueue {
     pub fn new() -> Self {
         Self {
             inner: Arc::new(Mutex::new(SystemEventQueueInner::default())),
+            pushed: Arc::new(Notify::new()),
         }
     }
 
     /// Append an event to the queue. Returns the index of the new event.
     pub fn push(&self, event: SystemEvent) -> usize {
-        if let Ok(mut guard) = self.inner.lock() {
+        let idx = if let Ok(mut guard) = self.inner.lock() {
             let idx = guard.events.len();
             guard.events.push(event);
             idx
         } else {
             0
-        }
+        };
+        self.pushed.notify_waiters();
+        idx
+    }
+
+    /// Notified on every push. Create the `notified()` future before reading the
+    /// queue so an event pushed in between is not missed.
+    pub(crate) fn pushed(&self) -> &Notify {
+        &self.pushed
     }
 
     pub fn len(&self) -> usize {
@@ -115,6 +135,22 @@ impl SystemEventQueue {
         Vec::new()
     }
 
+    /// Events from sequence number `seq` onward, paired with their sequence numbers.
+    /// Sequence numbers are queue indices, so transports can resume from the last event
+    /// a client saw. Does not advance any consumer counter.
+    pub fn events_since(&self, seq: usize) -> Vec<(usize, SystemEvent)> {
+        if let Ok(guard) = self.inner.lock() {
+            return guard
+                .events
+                .iter()
+                .enumerate()
+                .skip(seq)
+                .map(|(idx, event)| (idx, event.clone()))
+                .collect();
+        }
+        Vec::new()
+    }
+
     /// Advance HTTP counter and return new HTTP events (InlineCall, SystemError) since last call.
     /// Used by get_session_response for sync event delivery with state polling.
     pub fn advance_http_events(&self) -> Vec<SystemEvent> {
@@ -199,6 +235,16 @@ impl SystemEventQueue {
     /// Push a tool completion event into the queue (async callbacks only).
     /// Convenience method for EventManager / scheduler poller.
     pub fn push_tool_update(&self, completion: aomi_tools::ToolCompletion) -> usize {
+        // Joins the originating tool call's trace through `tool_call_id`
+        let _span = tracing::info_span!(
+            "tool_completion",
+            tool = completion.metadata.name.as_str(),
+            namespace = completion.metadata.namespace.as_str(),
+            tool_call_id = completion.metadata.id.as_str(),
+            call_id = completion.metadata.call_id.as_deref(),
+            has_more = completion.has_more
+        )
+        .entered();
         let event_type = if completion.has_more {
             "tool_update"
         } else {
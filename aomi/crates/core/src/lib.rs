@@ -1,12 +1,22 @@
 use std::fmt;
 
 pub mod app;
+pub mod approval;
+pub mod compaction;
 pub mod completion;
 pub mod connections;
 pub mod context_window;
 pub mod events;
+pub mod mock;
 pub mod prompts;
 pub mod state;
+pub mod tokenizer;
+
+// Re-exports from approval module
+pub use approval::{
+    APPROVAL_TIMEOUT, ApprovalDecision, TOOL_APPROVAL_REQUEST, TOOL_APPROVAL_RESPONSE,
+    approval_response,
+};
 
 // Re-exports from events module
 pub use events::{SystemEvent, SystemEventQueue};
@@ -15,9 +25,19 @@ pub use events::{SystemEvent, SystemEventQueue};
 pub use state::{CoreCtx, CoreState, UserState};
 
 // Re-exports from context_window module
-pub use context_window::{ContextWindow, DEFAULT_CONTEXT_BUDGET, estimate_tokens};
+pub use context_window::{
+    CONVERSATION_SUMMARY_MARKER, ContextWindow, ConversationCompaction, DEFAULT_CONTEXT_BUDGET,
+    budget_for_model, estimate_tokens,
+};
+
+// Re-exports from tokenizer module
+pub use tokenizer::{BpeTokenizer, HeuristicTokenizer, Tokenizer, default_tokenizer};
+
+// Re-exports from compaction module
+pub use compaction::{BamlSummarizer, CONTEXT_COMPACTION_ENABLED, ConversationSummarizer};
 
 // Re-exports from aomi-tools - the canonical location for tool infrastructure
+pub use aomi_tools::metrics::{Metrics, PrometheusText, ToolOutcome, metrics};
 pub use aomi_tools::scheduler::{PersistedHandlerState, SessionToolHandler, ToolHandler};
 pub use aomi_tools::{
     AomiTool, AomiToolArgs, AomiToolWrapper, CallMetadata, RuntimeEnvelope, ToolCallCtx,
@@ -31,6 +51,9 @@ pub use prompts::generate_account_context;
 // Re-exports from app module
 pub use app::{BuildOpts, CoreApp, CoreAppBuilder};
 
+// Re-exports from mock module
+pub use mock::{MockCompletionModel, MockScript, ScriptedChunk, ScriptedTurn};
+
 // Re-exports from model module
 pub use aomi_baml::{AomiModel, Selection};
 
@@ -0,0 +1,408 @@
+//! Deterministic in-process completion model.
+//!
+//! Backs the `mock` provider from the model registry so the agent can run
+//! end-to-end without network access. Without a script every reply is derived
+//! from the last user message. With a [`MockScript`] each model call replays the
+//! next scripted turn (streamed text, reasoning and tool calls), which lets tests
+//! drive the full completion loop, including tool round-trips.
+//!
+//! Fixtures are JSON or YAML:
+//! ```yaml
+//! turns:
+//!   - chunks:
+//!       - { type: text, text: "Checking the time. " }
+//!       - { type: tool_call, name: get_time, arguments: { topic: "time" } }
+//!   - chunks:
+//!       - { type: text, text: "Done." }
+//! ```
+
+use futures::stream;
+use rig::{
+    OneOrMany,
+    completion::{
+        CompletionError, CompletionModel, CompletionRequest, CompletionResponse, GetTokenUsage,
+        Usage,
+    },
+    message::{AssistantContent, Message, UserContent},
+    streaming::{RawStreamingChoice, StreamingCompletionResponse},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use crate::context_window::estimate_tokens;
+
+// ============================================================================
+// Script fixtures
+// ============================================================================
+
+/// One streamed piece of a scripted assistant turn.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScriptedChunk {
+    Text {
+        text: String,
+    },
+    Reasoning {
+        text: String,
+    },
+    ToolCall {
+        /// Defaults to `mock_call_<turn>_<chunk>` when omitted.
+        #[serde(default)]
+        id: Option<String>,
+        name: String,
+        #[serde(default)]
+        arguments: Value,
+    },
+}
+
+/// Everything the model emits for a single completion call.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScriptedTurn {
+    #[serde(default)]
+    pub chunks: Vec<ScriptedChunk>,
+}
+
+impl ScriptedTurn {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            chunks: vec![ScriptedChunk::Text { text: text.into() }],
+        }
+    }
+
+    pub fn tool_call(name: impl Into<String>, arguments: Value) -> Self {
+        Self {
+            chunks: vec![ScriptedChunk::ToolCall {
+                id: None,
+                name: name.into(),
+                arguments,
+            }],
+        }
+    }
+
+    pub fn chunk(mut self, chunk: ScriptedChunk) -> Self {
+        self.chunks.push(chunk);
+        self
+    }
+}
+
+/// Ordered list of turns replayed by [`MockCompletionModel`].
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MockScript {
+    pub turns: Vec<ScriptedTurn>,
+}
+
+impl MockScript {
+    pub fn new(turns: Vec<ScriptedTurn>) -> Self {
+        Self { turns }
+    }
+
+    pub fn from_json_str(content: &str) -> eyre::Result<Self> {
+        serde_json::from_str(content).map_err(|e| eyre::eyre!("Invalid mock script: {e}"))
+    }
+
+    pub fn from_yaml_str(content: &str) -> eyre::Result<Self> {
+        serde_yaml::from_str(content).map_err(|e| eyre::eyre!("Invalid mock script: {e}"))
+    }
+
+    /// Load a fixture, picking the format from the file extension (`.yaml`/`.yml`, else JSON).
+    pub fn from_file(path: &Path) -> eyre::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| eyre::eyre!("Failed to read {}: {e}", path.display()))?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("yaml" | "yml") => Self::from_yaml_str(&content),
+            _ => Self::from_json_str(&content),
+        }
+    }
+}
+
+// ============================================================================
+// Model
+// ============================================================================
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MockResponse {
+    pub text: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+impl MockResponse {
+    fn usage(&self) -> Usage {
+        Usage {
+            input_tokens: self.input_tokens,
+            output_tokens: self.output_tokens,
+            total_tokens: self.input_tokens + self.output_tokens,
+        }
+    }
+}
+
+impl GetTokenUsage for MockResponse {
+    fn token_usage(&self) -> Option<Usage> {
+        Some(self.usage())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MockCompletionModel {
+    model_id: String,
+    script: Option<Arc<MockScript>>,
+    /// Shared across clones so the agent and its callers see one playback position.
+    cursor: Arc<AtomicUsize>,
+}
+
+impl MockCompletionModel {
+    /// Echo model: replies with the last user message.
+    pub fn new(model_id: impl Into<String>) -> Self {
+        Self {
+            model_id: model_id.into(),
+            script: None,
+            cursor: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Replay model: each call plays the next turn of `script`.
+    pub fn scripted(model_id: impl Into<String>, script: MockScript) -> Self {
+        Self {
+            script: Some(Arc::new(script)),
+            ..Self::new(model_id)
+        }
+    }
+
+    /// Build from a registry provider entry, loading its `script` fixture if set.
+    pub fn from_provider(model_id: &str, script: Option<&str>) -> eyre::Result<Self> {
+        match script {
+            Some(path) => Ok(Self::scripted(
+                model_id,
+                MockScript::from_file(Path::new(path))?,
+            )),
+            None => Ok(Self::new(model_id)),
+        }
+    }
+
+    /// Number of completion calls served so far.
+    pub fn turns_played(&self) -> usize {
+        self.cursor.load(Ordering::SeqCst)
+    }
+
+    fn next_turn(
+        &self,
+        request: &CompletionRequest,
+    ) -> Result<(usize, ScriptedTurn), CompletionError> {
+        let index = self.cursor.fetch_add(1, Ordering::SeqCst);
+        let Some(script) = &self.script else {
+            let prompt = last_user_text(request).unwrap_or_default();
+            return Ok((
+                index,
+                ScriptedTurn::text(format!("[{}] {}", self.model_id, prompt)),
+            ));
+        };
+        script
+            .turns
+            .get(index)
+            .cloned()
+            .map(|turn| (index, turn))
+            .ok_or_else(|| {
+                CompletionError::ProviderError(format!(
+                    "Mock script exhausted after {} turns",
+                    script.turns.len()
+                ))
+            })
+    }
+
+    fn response_for(request: &CompletionRequest, turn: &ScriptedTurn) -> MockResponse {
+        let prompt = last_user_text(request).unwrap_or_default();
+        let text = turn
+            .chunks
+            .iter()
+            .filter_map(|chunk| match chunk {
+                ScriptedChunk::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<String>();
+        MockResponse {
+            input_tokens: estimate_tokens(&prompt) as u64,
+            output_tokens: estimate_tokens(&text) as u64,
+            text,
+        }
+    }
+}
+
+fn tool_call_id(id: &Option<String>, turn: usize, chunk: usize) -> String {
+    id.clone()
+        .unwrap_or_else(|| format!("mock_call_{turn}_{chunk}"))
+}
+
+/// Text of the most recent user message, ignoring tool results.
+fn last_user_text(request: &CompletionRequest) -> Option<String> {
+    request.chat_history.iter().rev().find_map(|message| {
+        let Message::User { content } = message else {
+            return None;
+        };
+        let text = content
+            .iter()
+            .filter_map(|c| match c {
+                UserContent::Text(text) => Some(text.text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        (!text.is_empty()).then_some(text)
+    })
+}
+
+impl CompletionModel for MockCompletionModel {
+    type Response = MockResponse;
+    type StreamingResponse = MockResponse;
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<Self::Response>, CompletionError> {
+        let (index, turn) = self.next_turn(&request)?;
+        let response = Self::response_for(&request, &turn);
+
+        let content = turn
+            .chunks
+            .iter()
+            .enumerate()
+            .filter_map(|(i, chunk)| match chunk {
+                ScriptedChunk::Text { text } => Some(AssistantContent::text(text)),
+                ScriptedChunk::ToolCall {
+                    id,
+                    name,
+                    arguments,
+                } => Some(AssistantContent::tool_call(
+                    tool_call_id(id, index, i),
+                    name,
+                    arguments.clone(),
+                )),
+                ScriptedChunk::Reasoning { .. } => None,
+            })
+            .collect::<Vec<_>>();
+        let choice =
+            OneOrMany::many(content).unwrap_or_else(|_| OneOrMany::one(AssistantContent::text("")));
+
+        Ok(CompletionResponse {
+            choice,
+            usage: response.usage(),
+            raw_response: response,
+        })
+    }
+
+    async fn stream(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<StreamingCompletionResponse<Self::StreamingResponse>, CompletionError> {
+        let (index, turn) = self.next_turn(&request)?;
+        let response = Self::response_for(&request, &turn);
+
+        let mut chunks = turn
+            .chunks
+            .into_iter()
+            .enumerate()
+            .map(|(i, chunk)| {
+                Ok(match chunk {
+                    ScriptedChunk::Text { text } => RawStreamingChoice::Message(text),
+                    ScriptedChunk::Reasoning { text } => RawStreamingChoice::Reasoning {
+                        id: None,
+                        reasoning: text,
+                    },
+                    ScriptedChunk::ToolCall {
+                        id,
+                        name,
+                        arguments,
+                    } => RawStreamingChoice::ToolCall {
+                        id: tool_call_id(&id, index, i),
+                        call_id: None,
+                        name,
+                        arguments,
+                    },
+                })
+            })
+            .collect::<Vec<_>>();
+        chunks.push(Ok(RawStreamingChoice::FinalResponse(response)));
+
+        Ok(StreamingCompletionResponse::stream(Box::pin(stream::iter(
+            chunks,
+        ))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rig::completion::CompletionRequestBuilder;
+    use serde_json::json;
+
+    fn request(model: &MockCompletionModel, prompt: &str) -> CompletionRequest {
+        CompletionRequestBuilder::new(model.clone(), Message::user(prompt))
+            .preamble("system".to_string())
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_mock_completion_is_deterministic() {
+        let model = MockCompletionModel::new("mock");
+
+        let first = model.completion(request(&model, "ping")).await.unwrap();
+        let second = model.completion(request(&model, "ping")).await.unwrap();
+        assert_eq!(first.raw_response.text, "[mock] ping");
+        assert_eq!(first.raw_response.text, second.raw_response.text);
+    }
+
+    #[tokio::test]
+    async fn test_scripted_turns_replay_in_order() {
+        let script = MockScript::new(vec![
+            ScriptedTurn::text("Looking up. ").chunk(ScriptedChunk::ToolCall {
+                id: None,
+                name: "lookup".to_string(),
+                arguments: json!({ "q": "eth" }),
+            }),
+            ScriptedTurn::text("Done."),
+        ]);
+        let model = MockCompletionModel::scripted("mock", script);
+
+        let first = model.completion(request(&model, "go")).await.unwrap();
+        let tool_call = first
+            .choice
+            .iter()
+            .find_map(|c| match c {
+                AssistantContent::ToolCall(call) => Some(call.clone()),
+                _ => None,
+            })
+            .expect("scripted tool call");
+        assert_eq!(tool_call.id, "mock_call_0_1");
+        assert_eq!(tool_call.function.name, "lookup");
+
+        let second = model.completion(request(&model, "go")).await.unwrap();
+        assert_eq!(second.raw_response.text, "Done.");
+        assert_eq!(model.turns_played(), 2);
+
+        assert!(model.completion(request(&model, "go")).await.is_err());
+    }
+
+    #[test]
+    fn test_script_parses_json_and_yaml() {
+        let json_script = MockScript::from_json_str(
+            r#"{"turns":[{"chunks":[{"type":"tool_call","name":"lookup","arguments":{"q":"eth"}}]}]}"#,
+        )
+        .unwrap();
+        let yaml_script = MockScript::from_yaml_str(
+            "turns:\n  - chunks:\n      - { type: tool_call, name: lookup, arguments: { q: eth } }\n",
+        )
+        .unwrap();
+        assert_eq!(json_script, yaml_script);
+        assert_eq!(
+            json_script,
+            MockScript::new(vec![ScriptedTurn::tool_call(
+                "lookup",
+                json!({ "q": "eth" })
+            )])
+        );
+    }
+}
@@ -1,13 +1,15 @@
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
+use aomi_tools::ethereum::PolicyScope;
 use aomi_tools::{CallMetadata, ToolReturn};
 use rig::{
     OneOrMany,
     message::{AssistantContent, Message},
 };
 
-use crate::context_window::{ContextWindow, DEFAULT_CONTEXT_BUDGET};
+use crate::compaction::ConversationSummarizer;
+use crate::context_window::{ContextWindow, ConversationCompaction, DEFAULT_CONTEXT_BUDGET};
 use crate::events::{SystemEvent, SystemEventQueue};
 
 /// User wallet state synced from frontend
@@ -52,6 +54,8 @@ pub struct CoreState {
     pub history: Vec<Message>,
     /// Sliding window context manager for efficient LLM calls (lazily initialized)
     context_window: Option<ContextWindow>,
+    /// Summarizer for messages evicted from the window (compaction mode only)
+    summarizer: Option<Arc<dyn ConversationSummarizer>>,
     pub system_events: Option<SystemEventQueue>,
     /// Session identifier for session-aware tool execution
     pub session_id: String,
@@ -59,6 +63,12 @@ pub struct CoreState {
     pub namespaces: Vec<String>,
     /// Aomi tool name to namespace map for runtime envelope handling
     pub tool_namespaces: Arc<HashMap<String, String>>,
+    /// Tools whose calls wait for user approval before running
+    pub approval_tools: Arc<HashSet<String>>,
+    /// Tools whose calls may run concurrently within a turn
+    pub read_only_tools: Arc<HashSet<String>>,
+    /// Session namespace and API key used to select transaction policies
+    pub policy_scope: PolicyScope,
 }
 
 impl CoreState {
@@ -75,13 +85,25 @@ impl CoreState {
             user_state,
             history,
             context_window: None,
+            summarizer: None,
             system_events,
             session_id,
             namespaces,
             tool_namespaces,
+            approval_tools: Arc::new(HashSet::new()),
+            read_only_tools: Arc::new(HashSet::new()),
+            policy_scope: PolicyScope::default(),
         }
     }
 
+    /// Session id clients know the session by, falling back to the internal scheduler id.
+    pub fn public_session_id(&self) -> &str {
+        self.policy_scope
+            .session_id
+            .as_deref()
+            .unwrap_or(&self.session_id)
+    }
+
     /// Enables the sliding window context manager.
     /// Call this to optimize LLM context for long conversations.
     pub fn enable_context_window(&mut self, budget: Option<usize>) {
@@ -100,6 +122,44 @@ impl CoreState {
         }
     }
 
+    /// Enables compaction mode: evicted messages are folded into a rolling summary
+    /// pinned at the head of the window. No-op unless the context window is enabled.
+    pub fn enable_compaction(
+        &mut self,
+        summarizer: Arc<dyn ConversationSummarizer>,
+        restored: Option<ConversationCompaction>,
+    ) {
+        if let Some(ref mut window) = self.context_window {
+            window.enable_compaction(restored);
+            self.summarizer = Some(summarizer);
+        }
+    }
+
+    /// Returns the current rolling summary, for persistence.
+    pub fn compaction(&self) -> Option<ConversationCompaction> {
+        self.context_window
+            .as_ref()
+            .and_then(|w| w.compaction().cloned())
+    }
+
+    /// Summarizes messages that slid out of the window since the last compaction.
+    /// Returns true if the rolling summary was updated.
+    pub async fn compact_context(&mut self) -> eyre::Result<bool> {
+        let (Some(window), Some(summarizer)) =
+            (self.context_window.as_mut(), self.summarizer.clone())
+        else {
+            return Ok(false);
+        };
+        let Some((covered, evicted)) = window.pending_compaction() else {
+            return Ok(false);
+        };
+
+        let previous = window.compaction().map(|c| c.summary.clone());
+        let summary = summarizer.summarize(previous.as_deref(), &evicted).await?;
+        window.apply_compaction(ConversationCompaction { summary, covered });
+        Ok(true)
+    }
+
     /// Returns context window stats if enabled.
     pub fn context_stats(&self) -> Option<(usize, usize, usize)> {
         self.context_window
@@ -0,0 +1,286 @@
+//! Token counting for context window budgeting.
+//!
+//! The default [`HeuristicTokenizer`] assumes ~4 characters per token, which is far
+//! off for hex calldata and ABIs. Point `TOKENIZER_VOCAB` at a tiktoken-format vocab
+//! (`<base64 token> <rank>` per line, e.g. `cl100k_base.tiktoken`) to count with a
+//! real byte-level BPE instead.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use eyre::{Result, WrapErr, bail};
+
+/// Environment variable pointing at a local BPE vocab file.
+const TOKENIZER_VOCAB_ENV: &str = "TOKENIZER_VOCAB";
+
+/// Approximate tokens per character ratio for English text.
+/// Conservative estimate: ~4 chars per token on average.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Pieces are memoized; the cache is reset once it grows past this many entries.
+const MAX_CACHED_PIECES: usize = 100_000;
+
+static DEFAULT_TOKENIZER: OnceLock<Arc<dyn Tokenizer>> = OnceLock::new();
+
+/// Counts tokens for budgeting LLM context.
+pub trait Tokenizer: Send + Sync + fmt::Debug {
+    fn count_tokens(&self, text: &str) -> usize;
+}
+
+/// Character heuristic: fast, dependency-free, and accurate enough for prose.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HeuristicTokenizer;
+
+impl Tokenizer for HeuristicTokenizer {
+    #[inline]
+    fn count_tokens(&self, text: &str) -> usize {
+        // Add 1 to avoid zero for very short strings
+        (text.len() / CHARS_PER_TOKEN).max(1)
+    }
+}
+
+/// Byte-level BPE tokenizer driven by a merge-rank vocabulary.
+pub struct BpeTokenizer {
+    ranks: HashMap<Vec<u8>, u32>,
+    /// Token counts per pre-tokenized piece (words repeat heavily across turns)
+    cache: Mutex<HashMap<Vec<u8>, usize>>,
+}
+
+impl fmt::Debug for BpeTokenizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BpeTokenizer")
+            .field("vocab_size", &self.ranks.len())
+            .finish()
+    }
+}
+
+impl BpeTokenizer {
+    pub fn from_ranks(ranks: HashMap<Vec<u8>, u32>) -> Self {
+        Self {
+            ranks,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Parses a tiktoken-format vocab: one `<base64 token> <rank>` pair per line.
+    pub fn from_tiktoken_str(content: &str) -> Result<Self> {
+        let mut ranks = HashMap::new();
+        for (idx, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((token, rank)) = line.split_once(' ') else {
+                bail!("Malformed vocab line {}: {line}", idx + 1);
+            };
+            let token = STANDARD
+                .decode(token)
+                .wrap_err_with(|| format!("Invalid base64 token on vocab line {}", idx + 1))?;
+            let rank: u32 = rank
+                .trim()
+                .parse()
+                .wrap_err_with(|| format!("Invalid rank on vocab line {}", idx + 1))?;
+            ranks.insert(token, rank);
+        }
+        if ranks.is_empty() {
+            bail!("Vocab is empty");
+        }
+        Ok(Self::from_ranks(ranks))
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read vocab {}", path.display()))?;
+        Self::from_tiktoken_str(&content)
+    }
+
+    /// Number of BPE tokens for a single piece, merging lowest-rank pairs first.
+    fn piece_tokens(&self, piece: &[u8]) -> usize {
+        if piece.len() <= 1 || self.ranks.contains_key(piece) {
+            return piece.len().min(1);
+        }
+        if let Some(&count) = self.cache.lock().unwrap().get(piece) {
+            return count;
+        }
+
+        // Part boundaries; merging removes the boundary between two parts
+        let mut bounds: Vec<usize> = (0..=piece.len()).collect();
+        loop {
+            let best = (0..bounds.len().saturating_sub(2))
+                .filter_map(|i| {
+                    self.ranks
+                        .get(&piece[bounds[i]..bounds[i + 2]])
+                        .map(|&rank| (rank, i))
+                })
+                .min();
+            match best {
+                Some((_, i)) => {
+                    bounds.remove(i + 1);
+                }
+                None => break,
+            }
+        }
+        let count = bounds.len() - 1;
+
+        let mut cache = self.cache.lock().unwrap();
+        if cache.len() >= MAX_CACHED_PIECES {
+            cache.clear();
+        }
+        cache.insert(piece.to_vec(), count);
+        count
+    }
+}
+
+impl Tokenizer for BpeTokenizer {
+    fn count_tokens(&self, text: &str) -> usize {
+        split_pieces(text)
+            .map(|piece| self.piece_tokens(piece.as_bytes()))
+            .sum()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum CharClass {
+    Letter,
+    Digit,
+    Space,
+    Other,
+}
+
+fn class_of(c: char) -> CharClass {
+    if c.is_alphabetic() {
+        CharClass::Letter
+    } else if c.is_numeric() {
+        CharClass::Digit
+    } else if c.is_whitespace() {
+        CharClass::Space
+    } else {
+        CharClass::Other
+    }
+}
+
+/// Approximates the GPT-style pre-tokenizer: words and punctuation runs keep one
+/// leading space, digits are grouped in threes, other whitespace stands alone.
+fn split_pieces(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        let mut chars = rest.char_indices().peekable();
+        let (_, first) = chars.next()?;
+
+        let mut class = class_of(first);
+        let mut end = first.len_utf8();
+        // A single space attaches to the following word or punctuation run
+        if first == ' '
+            && let Some(&(_, next)) = chars.peek()
+            && !matches!(class_of(next), CharClass::Space | CharClass::Digit)
+        {
+            chars.next();
+            class = class_of(next);
+            end += next.len_utf8();
+        }
+
+        let mut digits = usize::from(class == CharClass::Digit);
+        for (idx, c) in chars {
+            if class_of(c) != class || (class == CharClass::Digit && digits == 3) {
+                break;
+            }
+            // Keep the last space of a run for the next word
+            if class == CharClass::Space
+                && c == ' '
+                && rest[idx + 1..]
+                    .chars()
+                    .next()
+                    .is_some_and(|n| class_of(n) != CharClass::Space)
+            {
+                break;
+            }
+            digits += usize::from(class == CharClass::Digit);
+            end = idx + c.len_utf8();
+        }
+
+        let (piece, tail) = rest.split_at(end);
+        rest = tail;
+        Some(piece)
+    })
+}
+
+/// Process-wide tokenizer: BPE from `TOKENIZER_VOCAB` if set and valid, else the heuristic.
+pub fn default_tokenizer() -> Arc<dyn Tokenizer> {
+    DEFAULT_TOKENIZER
+        .get_or_init(|| match std::env::var(TOKENIZER_VOCAB_ENV) {
+            Ok(path) => match BpeTokenizer::from_file(Path::new(&path)) {
+                Ok(tokenizer) => Arc::new(tokenizer),
+                Err(e) => {
+                    tracing::warn!("Falling back to heuristic token counts: {e:#}");
+                    Arc::new(HeuristicTokenizer)
+                }
+            },
+            Err(_) => Arc::new(HeuristicTokenizer),
+        })
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn toy_bpe() -> BpeTokenizer {
+        let tokens = [
+            "h", "e", "l", "o", " ", "0", "x", "he", "ll", "hell", "hello", " h",
+        ];
+        BpeTokenizer::from_ranks(
+            tokens
+                .iter()
+                .enumerate()
+                .map(|(rank, t)| (t.as_bytes().to_vec(), rank as u32))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_split_pieces() {
+        let pieces: Vec<&str> = split_pieces("hello world,  0x12345 ok").collect();
+        assert_eq!(
+            pieces,
+            vec![
+                "hello", " world", ",", " ", " ", "0", "x", "123", "45", " ok"
+            ]
+        );
+        assert_eq!(split_pieces("").count(), 0);
+        assert_eq!(
+            split_pieces("héllo wörld").collect::<String>(),
+            "héllo wörld"
+        );
+    }
+
+    #[test]
+    fn test_bpe_merges_by_rank() {
+        let bpe = toy_bpe();
+        assert_eq!(bpe.count_tokens("hello"), 1);
+        // "he" outranks " h", so the space is left on its own
+        assert_eq!(bpe.count_tokens(" hello"), 2);
+        assert_eq!(bpe.count_tokens("hello hello"), 3);
+        // Unknown bytes still count as one token each
+        assert_eq!(bpe.count_tokens("zz"), 2);
+        // Cached piece counts are stable
+        assert_eq!(bpe.count_tokens(" hello"), 2);
+    }
+
+    #[test]
+    fn test_parse_tiktoken_vocab() {
+        let vocab = format!(
+            "{} 0\n{} 1\n{} 2\n",
+            STANDARD.encode("a"),
+            STANDARD.encode("b"),
+            STANDARD.encode("ab")
+        );
+        let bpe = BpeTokenizer::from_tiktoken_str(&vocab).unwrap();
+        assert_eq!(bpe.count_tokens("abab"), 2);
+
+        assert!(BpeTokenizer::from_tiktoken_str("").is_err());
+        assert!(BpeTokenizer::from_tiktoken_str("YQ== notanumber").is_err());
+    }
+}
@@ -211,6 +211,7 @@ async fn test_list_available_tools() -> Result<()> {
     - brave_search
     - send_transaction_to_wallet
     - encode_function_call
+    - decode_calldata
     - call_view_function
     - simulate_contract_call
     - get_current_time
@@ -220,6 +221,8 @@ async fn test_list_available_tools() -> Result<()> {
     - get_erc20_balance
     - get_account_info
     - get_account_transaction_history
+    - get_portfolio
+    - resolve_token
 ";
 
     let case =
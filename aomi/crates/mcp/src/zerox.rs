@@ -29,12 +29,12 @@ pub struct SwapPriceParams {
     pub buy_token: String,
 
     #[schemars(
-        description = "The amount of sell_token to sell (in wei or smallest unit). Exactly one of sell_amount or buy_amount required."
+        description = "The amount of sell_token to sell (in wei or smallest unit; resolve_token converts human amounts). Exactly one of sell_amount or buy_amount required."
     )]
     pub sell_amount: Option<String>,
 
     #[schemars(
-        description = "The amount of buy_token to buy (in wei or smallest unit). Exactly one of sell_amount or buy_amount required."
+        description = "The amount of buy_token to buy (in wei or smallest unit; resolve_token converts human amounts). Exactly one of sell_amount or buy_amount required."
     )]
     pub buy_amount: Option<String>,
 
@@ -66,12 +66,12 @@ pub struct SwapQuoteParams {
     pub buy_token: String,
 
     #[schemars(
-        description = "The amount of sell_token to sell (in wei or smallest unit). Exactly one of sell_amount or buy_amount required."
+        description = "The amount of sell_token to sell (in wei or smallest unit; resolve_token converts human amounts). Exactly one of sell_amount or buy_amount required."
     )]
     pub sell_amount: Option<String>,
 
     #[schemars(
-        description = "The amount of buy_token to buy (in wei or smallest unit). Exactly one of sell_amount or buy_amount required."
+        description = "The amount of buy_token to buy (in wei or smallest unit; resolve_token converts human amounts). Exactly one of sell_amount or buy_amount required."
     )]
     pub buy_amount: Option<String>,
 
@@ -1,6 +1,7 @@
 pub mod compiler;
 pub mod runner;
 pub mod session;
+pub mod trace;
 
 #[cfg(test)]
 mod tests;
@@ -1,4 +1,4 @@
-use alloy_primitives::{Address, Bytes, Log, U256, map::AddressHashMap};
+use alloy_primitives::{Address, Bytes, Log, TxKind, U256, map::AddressHashMap};
 use cast::inspectors::CheatsConfig;
 use eyre::Result;
 use foundry_evm::{
@@ -7,6 +7,7 @@ use foundry_evm::{
     inspectors::cheatcodes::BroadcastableTransactions,
     traces::{TraceKind, Traces},
 };
+use serde::Serialize;
 // Note: These types are re-exported from foundry_evm to avoid direct revm dependency
 use foundry_evm::revm::{
     Database,
@@ -36,6 +37,17 @@ pub struct ExecutionResult {
     pub state: Option<(Vec<U256>, Vec<u8>)>,
     /// Transactions recorded by vm.startBroadcast/stopBroadcast
     pub broadcastable_transactions: BroadcastableTransactions,
+    /// Storage slots whose value changed, sorted by contract and slot
+    pub storage_writes: Vec<StorageWrite>,
+}
+
+/// A storage slot changed by an execution
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StorageWrite {
+    pub address: Address,
+    pub slot: U256,
+    pub previous: U256,
+    pub value: U256,
 }
 
 /// A contract runner that executes bytecode on an in-memory EVM instance
@@ -256,12 +268,30 @@ impl ContractRunner {
             labels,
             chisel_state,
             transactions,
+            state_changeset,
             ..
         } = res;
 
         // Extract broadcastable transactions from the RawCallResult
         let broadcastable_transactions = transactions.unwrap_or_default();
 
+        let mut storage_writes: Vec<StorageWrite> = state_changeset
+            .iter()
+            .flat_map(|(address, account)| {
+                account
+                    .storage
+                    .iter()
+                    .filter(|(_, slot)| slot.is_changed())
+                    .map(|(key, slot)| StorageWrite {
+                        address: *address,
+                        slot: *key,
+                        previous: slot.original_value(),
+                        value: slot.present_value(),
+                    })
+            })
+            .collect();
+        storage_writes.sort_by_key(|write| (write.address, write.slot));
+
         Ok(ExecutionResult {
             returned: result,
             success: !reverted,
@@ -274,9 +304,29 @@ impl ContractRunner {
             address: Some(to),
             state: chisel_state,
             broadcastable_transactions,
+            storage_writes,
         })
     }
 
+    /// Execute and commit a transaction from `from` without gas estimation or result
+    /// collection, e.g. to bring a fork to the state a later transaction saw. A revert is not
+    /// an error; it is committed like on chain.
+    pub fn replay(&mut self, from: Address, kind: TxKind, input: Bytes, value: U256) -> Result<()> {
+        match kind {
+            TxKind::Call(to) => {
+                self.executor
+                    .transact_raw(from, to, input, value)
+                    .map_err(|e| eyre::eyre!("Transaction failed: {}", e))?;
+            }
+            TxKind::Create => {
+                self.executor
+                    .deploy(from, input, value, None)
+                    .map_err(|e| eyre::eyre!("Deployment failed: {:?}", e))?;
+            }
+        }
+        Ok(())
+    }
+
     /// Deploy and call a contract in one operation
     pub fn deploy_and_call(
         &mut self,
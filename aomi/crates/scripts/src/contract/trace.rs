@@ -0,0 +1,421 @@
+//! Replays transactions on a fork and decodes what they did.
+//!
+//! A mined transaction is re-executed on top of its parent block after the transactions that
+//! preceded it in its own block (as `cast run` does), a call that was never sent on top of the
+//! latest block. Contract ABIs come from the contract store (falling back to
+//! Etherscan), so the call tree, events and revert reason are shown with function and
+//! parameter names wherever the contracts are known.
+
+use alloy::{
+    consensus::Transaction as _,
+    dyn_abi::JsonAbiExt,
+    json_abi::JsonAbi,
+    network::{ReceiptResponse as _, TransactionResponse as _},
+};
+use alloy_primitives::{Address, B256, Bytes, TxKind, U256, hex};
+use alloy_provider::Provider;
+use aomi_anvil::provider_manager;
+use aomi_tools::db_tools::get_or_fetch_contract;
+use eyre::Result;
+use foundry_common::fmt::format_token_raw;
+use foundry_evm::traces::{CallTraceDecoderBuilder, decode_trace_arena, render_trace_arena};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+
+use super::{
+    runner::{ContractRunner, StorageWrite},
+    session::ContractConfig,
+};
+use crate::forge_executor::executor::decode_revert_reason;
+
+/// Contracts looked up for decoding; deeper traces show the rest undecoded
+const MAX_DECODED_CONTRACTS: usize = 50;
+
+/// `Panic(uint256)`
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// What to replay
+#[derive(Debug, Clone)]
+pub enum TraceTarget {
+    /// A mined transaction, replayed on top of its parent block and its block predecessors
+    Mined(B256),
+    /// A call that has not been sent, executed on top of the latest block
+    Call {
+        from: Address,
+        to: Address,
+        value: U256,
+        input: Bytes,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct TraceRequest {
+    /// Chain to fork; the default provider when None
+    pub chain_id: Option<u64>,
+    pub target: TraceTarget,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceReport {
+    pub chain_id: u64,
+    /// Block the fork was taken at; None for the latest block
+    pub fork_block: Option<u64>,
+    /// Earlier transactions of the same block executed before the traced one
+    pub replayed_transactions: usize,
+    pub from: Address,
+    pub to: Address,
+    pub value: U256,
+    /// Whether the replay succeeded
+    pub success: bool,
+    /// Receipt status of a mined transaction
+    pub onchain_success: Option<bool>,
+    pub gas_used: u64,
+    pub revert_reason: Option<String>,
+    pub return_data: Bytes,
+    /// Decoded call tree with internal calls, emitted events and return values
+    pub call_tree: String,
+    pub storage_writes: Vec<StorageWrite>,
+    /// Names of the contracts the trace touched, where known
+    pub contracts: HashMap<Address, String>,
+    /// Reasons the replay may not match what happened on chain
+    pub warnings: Vec<String>,
+}
+
+/// A transaction mined before the traced one in the same block
+struct PrecedingTx {
+    hash: B256,
+    from: Address,
+    kind: TxKind,
+    value: U256,
+    input: Bytes,
+}
+
+struct ResolvedCall {
+    from: Address,
+    to: Address,
+    value: U256,
+    input: Bytes,
+    fork_block: Option<u64>,
+    onchain_success: Option<bool>,
+    preceding: Vec<PrecedingTx>,
+}
+
+/// Replays `request` on a fork and returns the decoded trace.
+pub async fn trace_transaction(request: TraceRequest) -> Result<TraceReport> {
+    let manager = provider_manager().await.map_err(|e| eyre::eyre!(e))?;
+    let provider = manager
+        .get_provider(request.chain_id, None)
+        .await
+        .map_err(|e| eyre::eyre!(e))?;
+    let fork_url = match request.chain_id {
+        Some(chain_id) => manager.endpoint_for_chain(chain_id),
+        None => manager.default_endpoint(),
+    }
+    .ok_or_else(|| eyre::eyre!("No provider configured for chain {:?}", request.chain_id))?;
+    let chain_id = provider.get_chain_id().await?;
+
+    let call = match request.target {
+        TraceTarget::Mined(hash) => {
+            let tx = provider
+                .get_transaction_by_hash(hash)
+                .await?
+                .ok_or_else(|| eyre::eyre!("Transaction {hash} not found on chain {chain_id}"))?;
+            let TxKind::Call(to) = tx.kind() else {
+                eyre::bail!("Contract creation transactions cannot be traced");
+            };
+            let receipt = provider.get_transaction_receipt(hash).await?;
+            let preceding = match (tx.block_number(), tx.transaction_index()) {
+                (Some(block), Some(index)) => {
+                    let block = provider
+                        .get_block_by_number(block.into())
+                        .full()
+                        .await?
+                        .ok_or_else(|| {
+                            eyre::eyre!("Block {block} not found on chain {chain_id}")
+                        })?;
+                    block
+                        .transactions
+                        .txns()
+                        .take(index as usize)
+                        .map(|tx| PrecedingTx {
+                            hash: tx.tx_hash(),
+                            from: tx.from(),
+                            kind: tx.kind(),
+                            value: tx.value(),
+                            input: tx.input().clone(),
+                        })
+                        .collect()
+                }
+                _ => Vec::new(),
+            };
+            ResolvedCall {
+                from: tx.from(),
+                to,
+                value: tx.value(),
+                input: tx.input().clone(),
+                // Pending transactions run against the latest state
+                fork_block: tx.block_number().map(|block| block.saturating_sub(1)),
+                onchain_success: receipt.map(|receipt| receipt.status()),
+                preceding,
+            }
+        }
+        TraceTarget::Call {
+            from,
+            to,
+            value,
+            input,
+        } => ResolvedCall {
+            from,
+            to,
+            value,
+            input,
+            fork_block: None,
+            onchain_success: None,
+            preceding: Vec::new(),
+        },
+    };
+
+    let mut config = ContractConfig::default();
+    config.evm_opts.fork_url = Some(fork_url);
+    config.evm_opts.fork_block_number = call.fork_block;
+    config.evm_opts.no_storage_caching = true;
+
+    tracing::info!(
+        chain_id,
+        fork_block = ?call.fork_block,
+        preceding = call.preceding.len(),
+        from = %call.from,
+        to = %call.to,
+        "replaying transaction for trace"
+    );
+    let mut warnings = Vec::new();
+    let mut result = {
+        let mut runner = ContractRunner::new(&config).await?;
+        for tx in &call.preceding {
+            if let Err(e) = runner.replay(tx.from, tx.kind, tx.input.clone(), tx.value) {
+                warnings.push(format!(
+                    "Preceding transaction {} could not be replayed ({e}); state may differ from chain",
+                    tx.hash
+                ));
+            }
+        }
+        runner.set_sender(call.from);
+        runner.call_static(call.to, call.input.clone(), call.value)?
+    };
+    if let Some(onchain) = call
+        .onchain_success
+        .filter(|onchain| *onchain != result.success)
+    {
+        warnings.push(format!(
+            "Replay {} but the transaction {} on chain; the trace may not reflect the mined execution",
+            if result.success { "succeeded" } else { "reverted" },
+            if onchain { "succeeded" } else { "reverted" },
+        ));
+    }
+
+    let addresses: BTreeSet<Address> = result
+        .traces
+        .iter()
+        .flat_map(|(_, arena)| arena.nodes().iter().map(|node| node.trace.address))
+        .collect();
+    let (contracts, abis) = load_abis(chain_id, addresses).await;
+
+    let mut builder = CallTraceDecoderBuilder::new().with_labels(contracts.clone());
+    for abi in &abis {
+        builder = builder.with_abi(abi);
+    }
+    let decoder = builder.build();
+
+    let mut call_tree = String::new();
+    for (_, arena) in &mut result.traces {
+        decode_trace_arena(arena, &decoder).await;
+        call_tree.push_str(&strip_ansi(&render_trace_arena(arena)));
+    }
+
+    let revert_reason = if result.success {
+        None
+    } else {
+        explain_revert(&result.returned, &abis)
+    };
+
+    Ok(TraceReport {
+        chain_id,
+        fork_block: call.fork_block,
+        replayed_transactions: call.preceding.len(),
+        from: call.from,
+        to: call.to,
+        value: call.value,
+        success: result.success,
+        onchain_success: call.onchain_success,
+        gas_used: result.gas_used,
+        revert_reason,
+        return_data: result.returned,
+        call_tree,
+        storage_writes: result.storage_writes,
+        contracts,
+        warnings,
+    })
+}
+
+/// Names and ABIs of the traced contracts known to the contract store or Etherscan
+async fn load_abis(
+    chain_id: u64,
+    addresses: BTreeSet<Address>,
+) -> (HashMap<Address, String>, Vec<JsonAbi>) {
+    let mut names = HashMap::new();
+    let mut abis = Vec::new();
+
+    for address in addresses.into_iter().take(MAX_DECODED_CONTRACTS) {
+        let contract = match get_or_fetch_contract(chain_id as u32, format!("{address:#x}")).await {
+            Ok(contract) => contract,
+            Err(e) => {
+                tracing::debug!(%address, error = %e, "no ABI for traced contract");
+                continue;
+            }
+        };
+        if let Some(name) = contract.name {
+            names.insert(address, name);
+        }
+        match serde_json::from_value::<JsonAbi>(contract.abi) {
+            Ok(abi) => abis.push(abi),
+            Err(e) => tracing::debug!(%address, error = %e, "stored ABI is not valid JSON ABI"),
+        }
+    }
+
+    (names, abis)
+}
+
+/// Human readable revert reason: `Error(string)`, `Panic(uint256)` or a custom error from
+/// one of `abis`, else the raw revert data.
+fn explain_revert(data: &[u8], abis: &[JsonAbi]) -> Option<String> {
+    if data.is_empty() {
+        return None;
+    }
+    if let Some(reason) = decode_revert_reason(data) {
+        return Some(reason);
+    }
+    if data.len() == 36 && data[..4] == PANIC_SELECTOR {
+        let code = U256::from_be_slice(&data[4..]);
+        return Some(format!("panic: {} ({code:#x})", panic_description(code)));
+    }
+    if data.len() >= 4 {
+        let custom = abis
+            .iter()
+            .flat_map(|abi| abi.errors())
+            .filter(|error| error.selector()[..] == data[..4])
+            .find_map(|error| {
+                let values = error.abi_decode_input(&data[4..]).ok()?;
+                let args: Vec<String> = values.iter().map(format_token_raw).collect();
+                Some(format!("{}({})", error.name, args.join(", ")))
+            });
+        if custom.is_some() {
+            return custom;
+        }
+    }
+    Some(format!("0x{}", hex::encode(data)))
+}
+
+fn panic_description(code: U256) -> &'static str {
+    match code.saturating_to::<u64>() {
+        0x01 => "assertion failed",
+        0x11 => "arithmetic overflow or underflow",
+        0x12 => "division or modulo by zero",
+        0x21 => "invalid enum value",
+        0x22 => "corrupted storage byte array",
+        0x31 => "pop on empty array",
+        0x32 => "array index out of bounds",
+        0x41 => "out of memory",
+        0x51 => "call to uninitialized function",
+        _ => "unknown panic code",
+    }
+}
+
+/// Removes the terminal colors trace rendering adds
+fn strip_ansi(rendered: &str) -> String {
+    let mut out = String::with_capacity(rendered.len());
+    let mut chars = rendered.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // CSI sequences end with a letter
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::keccak256;
+
+    fn error_string(reason: &str) -> Vec<u8> {
+        let mut data = keccak256("Error(string)")[..4].to_vec();
+        data.extend(U256::from(32).to_be_bytes::<32>());
+        data.extend(U256::from(reason.len()).to_be_bytes::<32>());
+        let mut padded = reason.as_bytes().to_vec();
+        padded.resize(reason.len().div_ceil(32) * 32, 0);
+        data.extend(padded);
+        data
+    }
+
+    #[test]
+    fn explains_error_string() {
+        let data = error_string("ERC20: transfer amount exceeds balance");
+        assert_eq!(
+            explain_revert(&data, &[]).as_deref(),
+            Some("ERC20: transfer amount exceeds balance")
+        );
+    }
+
+    #[test]
+    fn explains_panic() {
+        assert_eq!(PANIC_SELECTOR, keccak256("Panic(uint256)")[..4]);
+        let mut data = PANIC_SELECTOR.to_vec();
+        data.extend(U256::from(0x11).to_be_bytes::<32>());
+        assert_eq!(
+            explain_revert(&data, &[]).as_deref(),
+            Some("panic: arithmetic overflow or underflow (0x11)")
+        );
+    }
+
+    #[test]
+    fn explains_custom_error_from_abi() {
+        let abi: JsonAbi = serde_json::from_value(serde_json::json!([{
+            "type": "error",
+            "name": "InsufficientBalance",
+            "inputs": [
+                {"name": "available", "type": "uint256"},
+                {"name": "required", "type": "uint256"}
+            ]
+        }]))
+        .unwrap();
+        let mut data = keccak256("InsufficientBalance(uint256,uint256)")[..4].to_vec();
+        data.extend(U256::from(5).to_be_bytes::<32>());
+        data.extend(U256::from(7).to_be_bytes::<32>());
+
+        assert_eq!(
+            explain_revert(&data, &[abi]).as_deref(),
+            Some("InsufficientBalance(5, 7)")
+        );
+        // Unknown errors fall back to the raw data
+        assert_eq!(
+            explain_revert(&data, &[]),
+            Some(format!("0x{}", hex::encode(&data)))
+        );
+        assert_eq!(explain_revert(&[], &[]), None);
+    }
+
+    #[test]
+    fn strips_terminal_colors() {
+        assert_eq!(
+            strip_ansi("\u{1b}[32m[21000]\u{1b}[0m Token::transfer()"),
+            "[21000] Token::transfer()"
+        );
+    }
+}
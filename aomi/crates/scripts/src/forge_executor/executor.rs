@@ -679,7 +679,7 @@ impl ForgeExecutor {
 }
 
 /// Attempt to decode a standard Error(string) revert reason.
-fn decode_revert_reason(data: &[u8]) -> Option<String> {
+pub(crate) fn decode_revert_reason(data: &[u8]) -> Option<String> {
     const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
     if data.len() < 4 || data[..4] != ERROR_SELECTOR {
         return None;
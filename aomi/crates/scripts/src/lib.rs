@@ -2,6 +2,7 @@ pub mod contract;
 pub mod forge_executor;
 
 pub use contract::session::{ContractConfig, ContractSession};
+pub use contract::trace::{TraceReport, TraceRequest, TraceTarget, trace_transaction};
 pub use forge_executor::assembler::{AssemblyConfig, FundingRequirement, ScriptAssembler};
 pub use forge_executor::executor::ForgeExecutor;
 pub use forge_executor::plan::{ExecutionPlan, GroupStatus, OperationGroup};
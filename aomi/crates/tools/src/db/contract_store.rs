@@ -1,5 +1,6 @@
-use super::traits::ContractStoreApi;
-use super::{Contract, ContractSearchParams, ContractUpdate};
+use super::token_store::TokenStore;
+use super::traits::{ContractStoreApi, TokenStoreApi};
+use super::{Contract, ContractProxyRecord, ContractSearchParams, ContractUpdate, Token};
 use anyhow::Result;
 use async_trait::async_trait;
 use chrono::Utc;
@@ -14,6 +15,22 @@ impl ContractStore {
     pub fn new(pool: Pool<Any>) -> Self {
         Self { pool }
     }
+
+    /// Best-effort registration of ERC20 contracts in the token registry.
+    async fn register_token(&self, contract: &Contract) {
+        let Some(token) = Token::from_erc20_contract(contract) else {
+            return;
+        };
+        let store = TokenStore::new(self.pool.clone());
+        if let Err(err) = store.insert_token_if_missing(token).await {
+            warn!(
+                address = %contract.address,
+                chain_id = contract.chain_id,
+                error = %err,
+                "failed to register ERC20 in token registry"
+            );
+        }
+    }
 }
 
 #[async_trait]
@@ -91,6 +108,8 @@ impl ContractStoreApi for ContractStore {
             .execute(&self.pool)
             .await?;
 
+        self.register_token(&contract).await;
+
         Ok(())
     }
 
@@ -428,8 +447,46 @@ impl ContractStoreApi for ContractStore {
         .fetch_one(&self.pool)
         .await?;
 
+        self.register_token(&row).await;
+
         Ok(row)
     }
+
+    async fn get_proxy_record(
+        &self,
+        chain_id: u32,
+        address: &str,
+    ) -> Result<Option<ContractProxyRecord>> {
+        let query = "SELECT chain_id, address, layout, checked_at
+                     FROM contract_proxies
+                     WHERE chain_id = $1 AND address = $2";
+
+        let record = sqlx::query_as::<Any, ContractProxyRecord>(query)
+            .bind(chain_id as i32)
+            .bind(address)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(record)
+    }
+
+    async fn upsert_proxy_record(&self, record: &ContractProxyRecord) -> Result<()> {
+        let query = "INSERT INTO contract_proxies (chain_id, address, layout, checked_at)
+                     VALUES ($1, $2, $3, $4)
+                     ON CONFLICT (chain_id, address) DO UPDATE SET
+                         layout = EXCLUDED.layout,
+                         checked_at = EXCLUDED.checked_at";
+
+        sqlx::query::<Any>(query)
+            .bind(record.chain_id as i32)
+            .bind(&record.address)
+            .bind(&record.layout)
+            .bind(record.checked_at)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
 }
 
 fn normalize_name(name: &str) -> String {
@@ -481,6 +538,20 @@ mod tests {
         .execute(&pool)
         .await?;
 
+        sqlx::query(
+            r#"
+            CREATE TABLE contract_proxies (
+                chain_id INTEGER NOT NULL,
+                address TEXT NOT NULL,
+                layout TEXT,
+                checked_at INTEGER NOT NULL,
+                PRIMARY KEY (chain_id, address)
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
         Ok(ContractStore::new(pool))
     }
 
@@ -767,4 +838,41 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_proxy_record_upsert_replaces_layout() -> Result<()> {
+        let store = setup_test_store().await?;
+
+        assert_eq!(
+            store.get_proxy_record(ETHEREUM_MAINNET, "0xabc").await?,
+            None
+        );
+
+        let not_proxy = ContractProxyRecord {
+            chain_id: ETHEREUM_MAINNET,
+            address: "0xabc".to_string(),
+            layout: None,
+            checked_at: 100,
+        };
+        store.upsert_proxy_record(&not_proxy).await?;
+        assert_eq!(
+            store.get_proxy_record(ETHEREUM_MAINNET, "0xabc").await?,
+            Some(not_proxy)
+        );
+
+        let upgraded = ContractProxyRecord {
+            chain_id: ETHEREUM_MAINNET,
+            address: "0xabc".to_string(),
+            layout: Some(r#"{"kind":"eip1967"}"#.to_string()),
+            checked_at: 200,
+        };
+        store.upsert_proxy_record(&upgraded).await?;
+        assert_eq!(
+            store.get_proxy_record(ETHEREUM_MAINNET, "0xabc").await?,
+            Some(upgraded)
+        );
+        assert_eq!(store.get_proxy_record(POLYGON, "0xabc").await?, None);
+
+        Ok(())
+    }
 }
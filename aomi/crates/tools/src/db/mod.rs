@@ -1,14 +1,23 @@
 mod api_key_store;
 mod contract_store;
+mod name_store;
 mod session_store;
+mod token_store;
 mod traits;
 mod transaction_store;
+mod usage_store;
 
 pub use api_key_store::ApiKeyStore;
 pub use contract_store::ContractStore;
+pub use name_store::NameStore;
 pub use session_store::SessionStore;
-pub use traits::{ApiKeyStoreApi, ContractStoreApi, SessionStoreApi, TransactionStoreApi};
+pub use token_store::{TokenList, TokenListEntry, TokenStore};
+pub use traits::{
+    ApiKeyStoreApi, ContractStoreApi, NameStoreApi, SessionStoreApi, TokenStoreApi,
+    TransactionStoreApi, UsageStoreApi,
+};
 pub use transaction_store::TransactionStore;
+pub use usage_store::{QuotaExceeded, QuotaPeriod, UsageStore};
 
 /// Default set of namespaces for new users
 pub const DEFAULT_NAMESPACE_SET: &[&str] = &["default", "polymarket"];
@@ -48,6 +57,167 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for ApiKey {
     }
 }
 
+// Usage metering domain models
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageKind {
+    /// One LLM completion; carries token counts
+    Llm,
+    /// One tool invocation
+    Tool,
+}
+
+impl UsageKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UsageKind::Llm => "llm",
+            UsageKind::Tool => "tool",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UsageEvent {
+    pub api_key: Option<String>,
+    pub public_key: Option<String>,
+    pub session_id: String,
+    pub namespace: Option<String>,
+    pub kind: UsageKind,
+    /// Set for `UsageKind::Tool`
+    pub tool_name: Option<String>,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub created_at: i64,
+}
+
+/// Aggregated usage over some set of events
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UsageTotals {
+    pub llm_requests: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub tool_calls: i64,
+}
+
+impl UsageTotals {
+    pub fn total_tokens(&self) -> i64 {
+        self.input_tokens + self.output_tokens
+    }
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for UsageTotals {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+        // SUM() over no rows is NULL
+        let sum = |column: &str| -> Result<i64, sqlx::Error> {
+            Ok(row.try_get::<Option<i64>, _>(column)?.unwrap_or(0))
+        };
+        Ok(UsageTotals {
+            llm_requests: sum("llm_requests")?,
+            input_tokens: sum("input_tokens")?,
+            output_tokens: sum("output_tokens")?,
+            tool_calls: sum("tool_calls")?,
+        })
+    }
+}
+
+/// Dimension a usage report is grouped by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageGroupBy {
+    ApiKey,
+    PublicKey,
+    Session,
+}
+
+impl UsageGroupBy {
+    pub fn column(&self) -> &'static str {
+        match self {
+            UsageGroupBy::ApiKey => "api_key",
+            UsageGroupBy::PublicKey => "public_key",
+            UsageGroupBy::Session => "session_id",
+        }
+    }
+}
+
+impl std::str::FromStr for UsageGroupBy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "api_key" | "api-key" | "key" => Ok(UsageGroupBy::ApiKey),
+            "public_key" | "public-key" | "user" => Ok(UsageGroupBy::PublicKey),
+            "session" | "session_id" => Ok(UsageGroupBy::Session),
+            other => anyhow::bail!("unknown usage grouping '{other}'"),
+        }
+    }
+}
+
+// Usage report filters; `since`/`until` are unix seconds
+#[derive(Debug, Clone, Default)]
+pub struct UsageReportParams {
+    pub api_key: Option<String>,
+    pub public_key: Option<String>,
+    pub session_id: Option<String>,
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UsageReportRow {
+    /// Value of the grouped column (`None` for events without e.g. an API key)
+    pub key: Option<String>,
+    pub totals: UsageTotals,
+    pub first_at: i64,
+    pub last_at: i64,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for UsageReportRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+        Ok(UsageReportRow {
+            key: row.try_get("group_key")?,
+            totals: <UsageTotals as sqlx::FromRow<'r, sqlx::any::AnyRow>>::from_row(row)?,
+            first_at: row.try_get("first_at")?,
+            last_at: row.try_get("last_at")?,
+        })
+    }
+}
+
+/// Daily/monthly limits for one API key; `None` means unlimited
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApiKeyQuota {
+    pub api_key: String,
+    pub daily_token_limit: Option<i64>,
+    pub monthly_token_limit: Option<i64>,
+    pub daily_tool_call_limit: Option<i64>,
+    pub monthly_tool_call_limit: Option<i64>,
+    pub updated_at: Option<i64>,
+}
+
+impl ApiKeyQuota {
+    pub fn is_unlimited(&self) -> bool {
+        self.daily_token_limit.is_none()
+            && self.monthly_token_limit.is_none()
+            && self.daily_tool_call_limit.is_none()
+            && self.monthly_tool_call_limit.is_none()
+    }
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for ApiKeyQuota {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+        Ok(ApiKeyQuota {
+            api_key: row.try_get("api_key")?,
+            daily_token_limit: row.try_get("daily_token_limit")?,
+            monthly_token_limit: row.try_get("monthly_token_limit")?,
+            daily_tool_call_limit: row.try_get("daily_tool_call_limit")?,
+            monthly_tool_call_limit: row.try_get("monthly_tool_call_limit")?,
+            updated_at: row.try_get("updated_at").ok(),
+        })
+    }
+}
+
 // Domain model
 #[derive(Debug, Clone)]
 pub struct Contract {
@@ -133,6 +303,90 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for Contract {
     }
 }
 
+/// Proxy layout last read on-chain for a contract. `layout` is the serialized `ProxyInfo`, or
+/// None when the contract was not a proxy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractProxyRecord {
+    pub chain_id: u32,
+    /// Lowercased contract address
+    pub address: String,
+    pub layout: Option<String>,
+    /// Unix timestamp of the on-chain check
+    pub checked_at: i64,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for ContractProxyRecord {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+        Ok(ContractProxyRecord {
+            chain_id: row.try_get::<i32, _>("chain_id")? as u32,
+            address: row.try_get("address")?,
+            layout: row.try_get("layout")?,
+            checked_at: row.try_get("checked_at")?,
+        })
+    }
+}
+
+// Token registry domain model
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub chain_id: u32,
+    /// Lowercased contract address
+    pub address: String,
+    pub symbol: String,
+    pub name: Option<String>,
+    /// `None` until known (e.g. auto-registered from a stored ERC20 contract)
+    pub decimals: Option<u8>,
+    /// Where the entry came from: a token list name, or "contract" for auto-registration
+    pub source: String,
+    pub created_at: Option<i64>,
+    pub updated_at: Option<i64>,
+}
+
+impl Token {
+    /// Registry entry for an ERC20 contract, if it carries enough metadata.
+    pub fn from_erc20_contract(contract: &Contract) -> Option<Self> {
+        let is_erc20 = contract
+            .contract_type
+            .as_deref()
+            .is_some_and(|t| t.eq_ignore_ascii_case("erc20"));
+        let symbol = contract.symbol.as_deref()?.trim();
+        if !is_erc20 || symbol.is_empty() {
+            return None;
+        }
+        Some(Token {
+            chain_id: contract.chain_id,
+            address: contract.address.to_lowercase(),
+            symbol: symbol.to_string(),
+            name: contract.name.clone().filter(|n| n != "Unknown"),
+            decimals: None,
+            source: "contract".to_string(),
+            created_at: None,
+            updated_at: None,
+        })
+    }
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for Token {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+        Ok(Token {
+            chain_id: row.try_get::<i32, _>("chain_id")? as u32,
+            address: row.try_get("address")?,
+            symbol: row.try_get("symbol")?,
+            name: row.try_get("name").ok(),
+            decimals: row
+                .try_get::<Option<i32>, _>("decimals")
+                .ok()
+                .flatten()
+                .and_then(|d| u8::try_from(d).ok()),
+            source: row.try_get("source")?,
+            created_at: row.try_get("created_at").ok(),
+            updated_at: row.try_get("updated_at").ok(),
+        })
+    }
+}
+
 // Transaction history domain models
 #[derive(Debug, Clone)]
 pub struct TransactionRecord {
@@ -307,6 +561,51 @@ impl Session {
     }
 }
 
+/// Link from a forked session to the session and message it was forked from
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SessionParent {
+    pub session_id: String,
+    /// Index of the last parent message copied into the fork
+    pub message_index: i64,
+}
+
+/// A cached name service lookup. `value` is None when the lookup found nothing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameRecord {
+    /// Name service that answered, e.g. "ens"
+    pub service: String,
+    /// What was looked up, e.g. "name:vitalik.eth" or "text:vitalik.eth:avatar"
+    pub lookup: String,
+    pub value: Option<String>,
+    /// Unix timestamp after which the record must be refreshed
+    pub expires_at: i64,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for NameRecord {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+        Ok(NameRecord {
+            service: row.try_get("service")?,
+            lookup: row.try_get("lookup")?,
+            value: row.try_get("value")?,
+            expires_at: row.try_get("expires_at")?,
+        })
+    }
+}
+
+/// A persisted chat message matching a session search
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct SessionSearchHit {
+    pub session_id: String,
+    pub title: Option<String>,
+    pub message_id: i64,
+    pub sender: String,
+    pub text: String,
+    pub timestamp: i64,
+    /// Relevance, higher is better (only comparable within one result set)
+    pub score: f64,
+}
+
 #[derive(Debug, Clone)]
 pub struct Message {
     pub id: i64,
@@ -0,0 +1,163 @@
+use super::NameRecord;
+use super::traits::NameStoreApi;
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::{Pool, any::Any};
+
+#[derive(Clone, Debug)]
+pub struct NameStore {
+    pool: Pool<Any>,
+}
+
+impl NameStore {
+    pub fn new(pool: Pool<Any>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl NameStoreApi for NameStore {
+    async fn get_name_record(&self, service: &str, lookup: &str) -> Result<Option<NameRecord>> {
+        let query = "SELECT service, lookup, value, expires_at
+                     FROM name_records
+                     WHERE service = $1 AND lookup = $2";
+
+        let record = sqlx::query_as::<Any, NameRecord>(query)
+            .bind(service)
+            .bind(lookup)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(record)
+    }
+
+    async fn upsert_name_record(&self, record: &NameRecord) -> Result<()> {
+        let query = "INSERT INTO name_records (service, lookup, value, expires_at)
+                     VALUES ($1, $2, $3, $4)
+                     ON CONFLICT (service, lookup) DO UPDATE SET
+                         value = EXCLUDED.value,
+                         expires_at = EXCLUDED.expires_at";
+
+        sqlx::query::<Any>(query)
+            .bind(&record.service)
+            .bind(&record.lookup)
+            .bind(&record.value)
+            .bind(record.expires_at)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_expired_name_records(&self, now: i64) -> Result<u64> {
+        let result = sqlx::query::<Any>("DELETE FROM name_records WHERE expires_at < $1")
+            .bind(now)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::any::AnyPoolOptions;
+
+    async fn setup_test_store() -> Result<NameStore> {
+        sqlx::any::install_default_drivers();
+
+        let pool = AnyPoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE name_records (
+                service TEXT NOT NULL,
+                lookup TEXT NOT NULL,
+                value TEXT,
+                expires_at BIGINT NOT NULL,
+                PRIMARY KEY (service, lookup)
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        Ok(NameStore::new(pool))
+    }
+
+    fn record(lookup: &str, value: Option<&str>, expires_at: i64) -> NameRecord {
+        NameRecord {
+            service: "ens".to_string(),
+            lookup: lookup.to_string(),
+            value: value.map(str::to_string),
+            expires_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upsert_and_get_name_record() -> Result<()> {
+        let store = setup_test_store().await?;
+
+        let name = record(
+            "name:vitalik.eth",
+            Some("0xd8da6bf26964af9d7eed9e03e53415d37aa96045"),
+            100,
+        );
+        store.upsert_name_record(&name).await?;
+        assert_eq!(
+            store.get_name_record("ens", "name:vitalik.eth").await?,
+            Some(name)
+        );
+        assert_eq!(
+            store
+                .get_name_record("basenames", "name:vitalik.eth")
+                .await?,
+            None
+        );
+
+        // Misses are cached too, and a later lookup replaces the record
+        store
+            .upsert_name_record(&record("name:vitalik.eth", None, 200))
+            .await?;
+        let updated = store
+            .get_name_record("ens", "name:vitalik.eth")
+            .await?
+            .expect("record exists");
+        assert_eq!(updated.value, None);
+        assert_eq!(updated.expires_at, 200);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delete_expired_name_records() -> Result<()> {
+        let store = setup_test_store().await?;
+
+        store
+            .upsert_name_record(&record("name:old.eth", None, 100))
+            .await?;
+        store
+            .upsert_name_record(&record("name:fresh.eth", None, 300))
+            .await?;
+
+        assert_eq!(store.delete_expired_name_records(200).await?, 1);
+        assert!(
+            store
+                .get_name_record("ens", "name:old.eth")
+                .await?
+                .is_none()
+        );
+        assert!(
+            store
+                .get_name_record("ens", "name:fresh.eth")
+                .await?
+                .is_some()
+        );
+
+        Ok(())
+    }
+}
@@ -1,8 +1,11 @@
 use super::traits::SessionStoreApi;
-use super::{Message, PendingTransaction, Session, User};
+use super::{Message, PendingTransaction, Session, SessionParent, SessionSearchHit, User};
 use anyhow::Result;
 use async_trait::async_trait;
-use sqlx::{Pool, QueryBuilder, Row, any::Any};
+use sqlx::{
+    Pool, QueryBuilder, Row,
+    any::{Any, AnyRow},
+};
 
 /// Parse namespaces from database - handles both PostgreSQL array format and JSON
 fn parse_namespaces(raw: Option<String>) -> Vec<String> {
@@ -28,6 +31,51 @@ fn parse_namespaces(raw: Option<String>) -> Vec<String> {
     }
 }
 
+/// Quotes every word of a search query so user input can't hit FTS5 query syntax
+fn fts5_query(query: &str) -> String {
+    query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{word}\""))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Reads a search hit from a row with `id, session_id, title, sender, text, timestamp, score`
+fn search_hit(row: &AnyRow) -> Result<SessionSearchHit> {
+    Ok(SessionSearchHit {
+        session_id: row.try_get("session_id")?,
+        title: row.try_get("title")?,
+        message_id: row.try_get("id")?,
+        sender: row.try_get("sender")?,
+        text: row
+            .try_get::<Option<String>, _>("text")?
+            .unwrap_or_default(),
+        timestamp: row.try_get("timestamp")?,
+        score: row.try_get("score")?,
+    })
+}
+
+/// Reads an unscored search hit from a row carrying the raw JSON `content`
+fn content_hit(row: &AnyRow) -> Result<SessionSearchHit> {
+    let content_str: String = row.try_get("content")?;
+    let content: serde_json::Value = serde_json::from_str(&content_str)?;
+    let text = match content.get("text").and_then(|t| t.as_str()) {
+        Some(text) => text.to_string(),
+        None => content_str,
+    };
+
+    Ok(SessionSearchHit {
+        session_id: row.try_get("session_id")?,
+        title: row.try_get("title")?,
+        message_id: row.try_get("id")?,
+        sender: row.try_get("sender")?,
+        text,
+        timestamp: row.try_get("timestamp")?,
+        score: 0.0,
+    })
+}
+
 #[derive(Clone, Debug)]
 pub struct SessionStore {
     pool: Pool<Any>,
@@ -321,6 +369,107 @@ impl SessionStoreApi for SessionStore {
         Ok(row.map(|r| r.try_get("messages_persisted")).transpose()?)
     }
 
+    async fn update_context_summary(
+        &self,
+        session_id: &str,
+        summary: Option<serde_json::Value>,
+    ) -> Result<()> {
+        let summary_json = summary.map(|v| serde_json::to_string(&v)).transpose()?;
+        let query = "UPDATE sessions SET context_summary = $1 WHERE id = $2";
+
+        sqlx::query::<Any>(query)
+            .bind(summary_json)
+            .bind(session_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_context_summary(&self, session_id: &str) -> Result<Option<serde_json::Value>> {
+        let query = "SELECT context_summary FROM sessions WHERE id = $1";
+
+        let row = sqlx::query(query)
+            .bind(session_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        let summary_str: Option<String> = match row {
+            Some(r) => r.try_get("context_summary")?,
+            None => None,
+        };
+        Ok(summary_str.map(|s| serde_json::from_str(&s)).transpose()?)
+    }
+
+    async fn update_tool_state(
+        &self,
+        session_id: &str,
+        state: Option<serde_json::Value>,
+    ) -> Result<()> {
+        let state_json = state.map(|v| serde_json::to_string(&v)).transpose()?;
+        let query = "UPDATE sessions SET tool_state = $1 WHERE id = $2";
+
+        sqlx::query::<Any>(query)
+            .bind(state_json)
+            .bind(session_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_tool_state(&self, session_id: &str) -> Result<Option<serde_json::Value>> {
+        let query = "SELECT tool_state FROM sessions WHERE id = $1";
+
+        let row = sqlx::query(query)
+            .bind(session_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        let state_str: Option<String> = match row {
+            Some(r) => r.try_get("tool_state")?,
+            None => None,
+        };
+        Ok(state_str.map(|s| serde_json::from_str(&s)).transpose()?)
+    }
+
+    async fn update_session_parent(
+        &self,
+        session_id: &str,
+        parent: Option<&SessionParent>,
+    ) -> Result<()> {
+        let query =
+            "UPDATE sessions SET parent_session_id = $1, fork_message_index = $2 WHERE id = $3";
+
+        sqlx::query::<Any>(query)
+            .bind(parent.map(|p| p.session_id.clone()))
+            .bind(parent.map(|p| p.message_index))
+            .bind(session_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_session_parent(&self, session_id: &str) -> Result<Option<SessionParent>> {
+        let query = "SELECT parent_session_id, fork_message_index FROM sessions WHERE id = $1";
+
+        let row = sqlx::query(query)
+            .bind(session_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let parent_session_id: Option<String> = row.try_get("parent_session_id")?;
+        let message_index: Option<i64> = row.try_get("fork_message_index")?;
+        Ok(parent_session_id.map(|session_id| SessionParent {
+            session_id,
+            message_index: message_index.unwrap_or(0),
+        }))
+    }
+
     async fn get_user_sessions(&self, public_key: &str, limit: i32) -> Result<Vec<Session>> {
         let query = "SELECT id, public_key, started_at, last_active_at, title, \
                      CAST(pending_transaction AS TEXT) AS pending_transaction
@@ -588,6 +737,145 @@ impl SessionStoreApi for SessionStore {
 
         Ok(messages)
     }
+
+    async fn truncate_messages(&self, session_id: &str, keep: i64) -> Result<u64> {
+        let query = "DELETE FROM messages
+                     WHERE session_id = $1
+                       AND id NOT IN (
+                           SELECT id FROM messages WHERE session_id = $1 ORDER BY id LIMIT $2
+                       )";
+
+        let result = sqlx::query::<Any>(query)
+            .bind(session_id)
+            .bind(keep)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn search_messages(
+        &self,
+        public_key: &str,
+        query: &str,
+        limit: i64,
+    ) -> Result<Vec<SessionSearchHit>> {
+        if query.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Try PostgreSQL full-text search first (backed by idx_messages_fts)
+        let pg_query = "SELECT m.id, m.session_id, s.title, m.sender, m.content->>'text' AS text, m.timestamp,
+                               CAST(ts_rank(to_tsvector('english', m.content->>'text'),
+                                            plainto_tsquery('english', $2)) AS DOUBLE PRECISION) AS score
+                        FROM messages m
+                        JOIN sessions s ON m.session_id = s.id
+                        WHERE s.public_key = $1 AND m.message_type = 'chat'
+                          AND to_tsvector('english', m.content->>'text') @@ plainto_tsquery('english', $2)
+                        ORDER BY score DESC, m.timestamp DESC
+                        LIMIT $3";
+        let result = sqlx::query::<Any>(pg_query)
+            .bind(public_key)
+            .bind(query)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await;
+
+        let rows = match result {
+            Ok(rows) => rows,
+            Err(_) => {
+                // Fallback for SQLite - the messages_fts FTS5 table (bm25 is lower-is-better)
+                let fts_query = fts5_query(query);
+                if fts_query.is_empty() {
+                    return Ok(Vec::new());
+                }
+                let sqlite_query = "SELECT m.id, m.session_id, s.title, m.sender,
+                                           json_extract(m.content, '$.text') AS text, m.timestamp,
+                                           -bm25(messages_fts) AS score
+                                    FROM messages_fts
+                                    JOIN messages m ON m.id = messages_fts.rowid
+                                    JOIN sessions s ON m.session_id = s.id
+                                    WHERE messages_fts MATCH $2 AND s.public_key = $1
+                                      AND m.message_type = 'chat'
+                                    ORDER BY score DESC, m.timestamp DESC
+                                    LIMIT $3";
+                sqlx::query::<Any>(sqlite_query)
+                    .bind(public_key)
+                    .bind(fts_query)
+                    .bind(limit)
+                    .fetch_all(&self.pool)
+                    .await?
+            }
+        };
+
+        rows.iter().map(search_hit).collect()
+    }
+
+    async fn get_unembedded_messages(
+        &self,
+        public_key: &str,
+        limit: i64,
+    ) -> Result<Vec<SessionSearchHit>> {
+        let query = "SELECT m.id, m.session_id, s.title, m.sender, CAST(m.content AS TEXT) AS content, m.timestamp
+                     FROM messages m
+                     JOIN sessions s ON m.session_id = s.id
+                     LEFT JOIN message_embeddings e ON e.message_id = m.id
+                     WHERE s.public_key = $1 AND m.message_type = 'chat' AND e.message_id IS NULL
+                     ORDER BY m.id DESC
+                     LIMIT $2";
+
+        let rows = sqlx::query::<Any>(query)
+            .bind(public_key)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter().map(content_hit).collect()
+    }
+
+    async fn save_message_embedding(&self, message_id: i64, embedding: &[f32]) -> Result<()> {
+        let query = "INSERT INTO message_embeddings (message_id, embedding, created_at)
+                     VALUES ($1, $2, $3)
+                     ON CONFLICT (message_id) DO UPDATE SET embedding = excluded.embedding";
+
+        sqlx::query::<Any>(query)
+            .bind(message_id)
+            .bind(serde_json::to_string(embedding)?)
+            .bind(chrono::Utc::now().timestamp())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_message_embeddings(
+        &self,
+        public_key: &str,
+        limit: i64,
+    ) -> Result<Vec<(SessionSearchHit, Vec<f32>)>> {
+        let query =
+            "SELECT m.id, m.session_id, s.title, m.sender, CAST(m.content AS TEXT) AS content,
+                            m.timestamp, e.embedding
+                     FROM message_embeddings e
+                     JOIN messages m ON m.id = e.message_id
+                     JOIN sessions s ON m.session_id = s.id
+                     WHERE s.public_key = $1 AND m.message_type = 'chat'
+                     ORDER BY m.id DESC
+                     LIMIT $2";
+
+        let rows = sqlx::query::<Any>(query)
+            .bind(public_key)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter()
+            .map(|row| {
+                let embedding: String = row.try_get("embedding")?;
+                Ok((content_hit(row)?, serde_json::from_str(&embedding)?))
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -629,7 +917,11 @@ mod tests {
                 last_active_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
                 title TEXT,
                 pending_transaction TEXT,
-                messages_persisted INTEGER NOT NULL DEFAULT 0
+                messages_persisted INTEGER NOT NULL DEFAULT 0,
+                context_summary TEXT,
+                tool_state TEXT,
+                parent_session_id TEXT,
+                fork_message_index INTEGER
             )
             "#,
         )
@@ -651,6 +943,47 @@ mod tests {
         .execute(&pool)
         .await?;
 
+        // SQLite keyword search index, kept in sync with chat messages by triggers
+        sqlx::query("CREATE VIRTUAL TABLE messages_fts USING fts5(text)")
+            .execute(&pool)
+            .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TRIGGER messages_fts_insert AFTER INSERT ON messages
+            WHEN new.message_type = 'chat'
+            BEGIN
+                INSERT INTO messages_fts (rowid, text)
+                VALUES (new.id, json_extract(new.content, '$.text'));
+            END
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TRIGGER messages_fts_delete AFTER DELETE ON messages
+            BEGIN
+                DELETE FROM messages_fts WHERE rowid = old.id;
+            END
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE message_embeddings (
+                message_id INTEGER PRIMARY KEY REFERENCES messages(id) ON DELETE CASCADE,
+                embedding TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
         Ok(SessionStore::new(pool))
     }
 
@@ -1092,4 +1425,291 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_update_context_summary() -> Result<()> {
+        let store = setup_test_store().await?;
+
+        let session = Session {
+            id: "session_summary".to_string(),
+            public_key: None,
+            started_at: 1699564800,
+            last_active_at: 1699564800,
+            title: None,
+            pending_transaction: None,
+        };
+
+        store.create_session(&session).await?;
+        assert!(
+            store
+                .get_context_summary("session_summary")
+                .await?
+                .is_none()
+        );
+
+        let summary = json!({
+            "summary": "User is bridging USDC to Base",
+            "covered": 42
+        });
+        store
+            .update_context_summary("session_summary", Some(summary.clone()))
+            .await?;
+        assert_eq!(
+            store.get_context_summary("session_summary").await?,
+            Some(summary)
+        );
+
+        store
+            .update_context_summary("session_summary", None)
+            .await?;
+        assert!(
+            store
+                .get_context_summary("session_summary")
+                .await?
+                .is_none()
+        );
+
+        // Unknown sessions have no summary
+        assert!(store.get_context_summary("missing").await?.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_update_tool_state() -> Result<()> {
+        let store = setup_test_store().await?;
+
+        let session = Session {
+            id: "session_tools".to_string(),
+            public_key: None,
+            started_at: 1699564800,
+            last_active_at: 1699564800,
+            title: None,
+            pending_transaction: None,
+        };
+
+        store.create_session(&session).await?;
+        assert!(store.get_tool_state("session_tools").await?.is_none());
+
+        let state = json!({
+            "namespaces": ["default"],
+            "available_tools": {},
+            "completed_calls": [],
+            "pending_calls": [{ "args": { "from_block": 100 }, "cursor": { "block": 150 } }]
+        });
+        store
+            .update_tool_state("session_tools", Some(state.clone()))
+            .await?;
+        assert_eq!(store.get_tool_state("session_tools").await?, Some(state));
+
+        store.update_tool_state("session_tools", None).await?;
+        assert!(store.get_tool_state("session_tools").await?.is_none());
+        assert!(store.get_tool_state("missing").await?.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_session_parent_link() -> Result<()> {
+        let store = setup_test_store().await?;
+
+        for id in ["parent", "fork"] {
+            store
+                .create_session(&Session {
+                    id: id.to_string(),
+                    public_key: None,
+                    started_at: 1699564800,
+                    last_active_at: 1699564800,
+                    title: None,
+                    pending_transaction: None,
+                })
+                .await?;
+        }
+        assert!(store.get_session_parent("fork").await?.is_none());
+
+        let parent = SessionParent {
+            session_id: "parent".to_string(),
+            message_index: 3,
+        };
+        store.update_session_parent("fork", Some(&parent)).await?;
+        assert_eq!(store.get_session_parent("fork").await?, Some(parent));
+        assert!(store.get_session_parent("parent").await?.is_none());
+
+        store.update_session_parent("fork", None).await?;
+        assert!(store.get_session_parent("fork").await?.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_truncate_messages() -> Result<()> {
+        let store = setup_test_store().await?;
+
+        store
+            .create_session(&Session {
+                id: "session_edit".to_string(),
+                public_key: None,
+                started_at: 1699564800,
+                last_active_at: 1699564800,
+                title: None,
+                pending_transaction: None,
+            })
+            .await?;
+        for i in 0..5 {
+            store
+                .save_message(&Message {
+                    id: 0,
+                    session_id: "session_edit".to_string(),
+                    message_type: "chat".to_string(),
+                    sender: "user".to_string(),
+                    content: json!({"text": format!("Message {i}")}),
+                    timestamp: 1699564800 + i,
+                })
+                .await?;
+        }
+
+        assert_eq!(store.truncate_messages("session_edit", 2).await?, 3);
+
+        let mut messages = store.get_messages("session_edit", None, None).await?;
+        messages.sort_by_key(|m| m.id);
+        let texts: Vec<_> = messages
+            .iter()
+            .map(|m| m.content["text"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(texts, vec!["Message 0", "Message 1"]);
+
+        Ok(())
+    }
+
+    async fn seed_search_messages(store: &SessionStore) -> Result<()> {
+        for (public_key, session_id, title, texts) in [
+            (
+                "searcher",
+                "session_aave",
+                "Aave position",
+                [
+                    "How healthy is my Aave position?",
+                    "Your health factor is 1.8",
+                ],
+            ),
+            (
+                "searcher",
+                "session_swap",
+                "Swap ETH",
+                ["Swap 1 ETH to USDC", "Here is the swap transaction"],
+            ),
+            (
+                "someone_else",
+                "session_other",
+                "Other Aave",
+                ["Close my Aave position", "Done"],
+            ),
+        ] {
+            store.get_or_create_user(public_key).await?;
+            store
+                .create_session(&Session {
+                    id: session_id.to_string(),
+                    public_key: Some(public_key.to_string()),
+                    started_at: 1699564800,
+                    last_active_at: 1699564800,
+                    title: Some(title.to_string()),
+                    pending_transaction: None,
+                })
+                .await?;
+            for (i, text) in texts.iter().enumerate() {
+                store
+                    .save_message(&Message {
+                        id: 0,
+                        session_id: session_id.to_string(),
+                        message_type: "chat".to_string(),
+                        sender: if i == 0 { "user" } else { "agent" }.to_string(),
+                        content: json!({ "text": text }),
+                        timestamp: 1699564800 + i as i64,
+                    })
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_search_messages() -> Result<()> {
+        let store = setup_test_store().await?;
+        seed_search_messages(&store).await?;
+
+        let hits = store
+            .search_messages("searcher", "aave position", 10)
+            .await?;
+        assert_eq!(hits.len(), 1, "only the searcher's own sessions match");
+        assert_eq!(hits[0].session_id, "session_aave");
+        assert_eq!(hits[0].title.as_deref(), Some("Aave position"));
+        assert_eq!(hits[0].text, "How healthy is my Aave position?");
+        assert_eq!(hits[0].sender, "user");
+
+        // Query syntax characters are treated as plain words
+        let hits = store
+            .search_messages("searcher", "swap: \"ETH\"-", 10)
+            .await?;
+        assert!(hits.iter().all(|h| h.session_id == "session_swap"));
+        assert!(!hits.is_empty());
+
+        assert!(
+            store
+                .search_messages("searcher", "  ", 10)
+                .await?
+                .is_empty()
+        );
+        assert!(
+            store
+                .search_messages("searcher", "polymarket", 10)
+                .await?
+                .is_empty()
+        );
+
+        // Truncated messages drop out of the index
+        store.truncate_messages("session_aave", 0).await?;
+        assert!(
+            store
+                .search_messages("searcher", "aave", 10)
+                .await?
+                .is_empty()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_message_embeddings() -> Result<()> {
+        let store = setup_test_store().await?;
+        seed_search_messages(&store).await?;
+
+        let pending = store.get_unembedded_messages("searcher", 10).await?;
+        assert_eq!(pending.len(), 4);
+        assert_eq!(pending[0].text, "Here is the swap transaction");
+
+        store
+            .save_message_embedding(pending[0].message_id, &[0.5, -1.0])
+            .await?;
+        // Saving again replaces the stored vector
+        store
+            .save_message_embedding(pending[0].message_id, &[1.0, 0.0])
+            .await?;
+
+        assert_eq!(
+            store.get_unembedded_messages("searcher", 10).await?.len(),
+            3
+        );
+        let embedded = store.get_message_embeddings("searcher", 10).await?;
+        assert_eq!(embedded.len(), 1);
+        assert_eq!(embedded[0].0.session_id, "session_swap");
+        assert_eq!(embedded[0].1, vec![1.0, 0.0]);
+        assert!(
+            store
+                .get_message_embeddings("someone_else", 10)
+                .await?
+                .is_empty()
+        );
+
+        Ok(())
+    }
 }
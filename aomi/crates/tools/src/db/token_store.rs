@@ -0,0 +1,408 @@
+use super::Token;
+use super::traits::TokenStoreApi;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, QueryBuilder, any::Any};
+use std::path::Path;
+use tracing::warn;
+
+const TOKEN_COLUMNS: &str =
+    "chain_id, address, symbol, name, decimals, source, created_at, updated_at";
+
+/// A token-list document (https://tokenlists.org schema; extra fields are ignored).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenList {
+    pub name: String,
+    pub tokens: Vec<TokenListEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenListEntry {
+    pub chain_id: u64,
+    pub address: String,
+    pub symbol: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub decimals: u32,
+}
+
+impl TokenList {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read token list {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid token list {}", path.display()))
+    }
+}
+
+impl TokenListEntry {
+    /// Converts to a registry entry, rejecting malformed addresses and out-of-range values.
+    fn to_token(&self, source: &str) -> Option<Token> {
+        let address = self.address.trim();
+        let valid_address = address.len() == 42
+            && address.starts_with("0x")
+            && address[2..].chars().all(|c| c.is_ascii_hexdigit());
+        let symbol = self.symbol.trim();
+        if !valid_address || symbol.is_empty() {
+            return None;
+        }
+        Some(Token {
+            chain_id: u32::try_from(self.chain_id).ok()?,
+            address: address.to_lowercase(),
+            symbol: symbol.to_string(),
+            name: self.name.clone(),
+            decimals: Some(u8::try_from(self.decimals).ok()?),
+            source: source.to_string(),
+            created_at: None,
+            updated_at: None,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TokenStore {
+    pool: Pool<Any>,
+}
+
+impl TokenStore {
+    pub fn new(pool: Pool<Any>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl TokenStoreApi for TokenStore {
+    async fn get_token(&self, chain_id: u32, address: &str) -> Result<Option<Token>> {
+        let query =
+            format!("SELECT {TOKEN_COLUMNS} FROM tokens WHERE chain_id = $1 AND address = $2");
+
+        let token = sqlx::query_as::<Any, Token>(&query)
+            .bind(chain_id as i32)
+            .bind(address.to_lowercase())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(token)
+    }
+
+    async fn resolve_symbol(&self, symbol: &str, chain_id: Option<u32>) -> Result<Vec<Token>> {
+        let mut query = QueryBuilder::<Any>::new(format!(
+            "SELECT {TOKEN_COLUMNS} FROM tokens WHERE LOWER(symbol) = LOWER("
+        ));
+        query.push_bind(symbol.trim().to_string()).push(")");
+
+        if let Some(chain_id) = chain_id {
+            query.push(" AND chain_id = ").push_bind(chain_id as i32);
+        }
+
+        // Curated list entries first, then auto-registered contracts
+        query.push(" ORDER BY CASE WHEN source = 'contract' THEN 1 ELSE 0 END, chain_id, address");
+
+        let tokens = query
+            .build_query_as::<Token>()
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(tokens)
+    }
+
+    async fn upsert_token(&self, token: Token) -> Result<()> {
+        let query = "INSERT INTO tokens (chain_id, address, symbol, name, decimals, source, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+             ON CONFLICT (chain_id, address) DO UPDATE SET
+                symbol = EXCLUDED.symbol,
+                name = COALESCE(EXCLUDED.name, tokens.name),
+                decimals = COALESCE(EXCLUDED.decimals, tokens.decimals),
+                source = EXCLUDED.source,
+                updated_at = EXCLUDED.updated_at";
+
+        let now = Utc::now().timestamp();
+        sqlx::query::<Any>(query)
+            .bind(token.chain_id as i32)
+            .bind(token.address.to_lowercase())
+            .bind(&token.symbol)
+            .bind(&token.name)
+            .bind(token.decimals.map(i32::from))
+            .bind(&token.source)
+            .bind(token.created_at.unwrap_or(now))
+            .bind(token.updated_at.unwrap_or(now))
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn insert_token_if_missing(&self, token: Token) -> Result<bool> {
+        let query = "INSERT INTO tokens (chain_id, address, symbol, name, decimals, source, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+             ON CONFLICT (chain_id, address) DO NOTHING";
+
+        let now = Utc::now().timestamp();
+        let result = sqlx::query::<Any>(query)
+            .bind(token.chain_id as i32)
+            .bind(token.address.to_lowercase())
+            .bind(&token.symbol)
+            .bind(&token.name)
+            .bind(token.decimals.map(i32::from))
+            .bind(&token.source)
+            .bind(token.created_at.unwrap_or(now))
+            .bind(token.updated_at.unwrap_or(now))
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn set_token_decimals(&self, chain_id: u32, address: &str, decimals: u8) -> Result<()> {
+        let query =
+            "UPDATE tokens SET decimals = $1, updated_at = $2 WHERE chain_id = $3 AND address = $4";
+
+        sqlx::query::<Any>(query)
+            .bind(i32::from(decimals))
+            .bind(Utc::now().timestamp())
+            .bind(chain_id as i32)
+            .bind(address.to_lowercase())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn import_token_list(&self, list: &TokenList) -> Result<usize> {
+        let mut imported = 0;
+        for entry in &list.tokens {
+            let Some(token) = entry.to_token(&list.name) else {
+                warn!(
+                    chain_id = entry.chain_id,
+                    address = %entry.address,
+                    symbol = %entry.symbol,
+                    "invalid token list entry; skipping"
+                );
+                continue;
+            };
+            self.upsert_token(token).await?;
+            imported += 1;
+        }
+        Ok(imported)
+    }
+
+    async fn list_tokens(
+        &self,
+        chain_id: Option<u32>,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> Result<Vec<Token>> {
+        let mut query =
+            QueryBuilder::<Any>::new(format!("SELECT {TOKEN_COLUMNS} FROM tokens WHERE 1=1"));
+
+        if let Some(chain_id) = chain_id {
+            query.push(" AND chain_id = ").push_bind(chain_id as i32);
+        }
+
+        query.push(" ORDER BY chain_id, symbol");
+
+        if let Some(limit) = limit {
+            query.push(" LIMIT ").push_bind(limit);
+        }
+
+        if let Some(offset) = offset {
+            query.push(" OFFSET ").push_bind(offset);
+        }
+
+        let tokens = query
+            .build_query_as::<Token>()
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(tokens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{Contract, ContractStore, ContractStoreApi};
+    use serde_json::json;
+    use sqlx::any::AnyPoolOptions;
+
+    const USDC_MAINNET: &str = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
+    const USDC_BASE: &str = "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913";
+
+    async fn setup_test_pool() -> Result<Pool<Any>> {
+        // Install SQLite driver for sqlx::Any
+        sqlx::any::install_default_drivers();
+
+        let pool = AnyPoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE tokens (
+                chain_id INTEGER NOT NULL,
+                address TEXT NOT NULL,
+                symbol TEXT NOT NULL,
+                name TEXT,
+                decimals INTEGER,
+                source TEXT NOT NULL,
+                created_at INTEGER,
+                updated_at INTEGER,
+                PRIMARY KEY (chain_id, address)
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        Ok(pool)
+    }
+
+    fn sample_list() -> TokenList {
+        serde_json::from_value(json!({
+            "name": "Test List",
+            "timestamp": "2024-01-01T00:00:00Z",
+            "tokens": [
+                {
+                    "chainId": 1,
+                    "address": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+                    "symbol": "USDC",
+                    "name": "USD Coin",
+                    "decimals": 6,
+                    "logoURI": "https://example.com/usdc.png"
+                },
+                {
+                    "chainId": 8453,
+                    "address": "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
+                    "symbol": "USDC",
+                    "name": "USD Coin",
+                    "decimals": 6
+                },
+                { "chainId": 1, "address": "not-an-address", "symbol": "BAD", "decimals": 18 }
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_import_and_resolve_symbol() -> Result<()> {
+        let store = TokenStore::new(setup_test_pool().await?);
+
+        let imported = store.import_token_list(&sample_list()).await?;
+        assert_eq!(imported, 2);
+
+        let all_chains = store.resolve_symbol("usdc", None).await?;
+        assert_eq!(all_chains.len(), 2);
+
+        let base = store.resolve_symbol("USDC", Some(8453)).await?;
+        assert_eq!(base.len(), 1);
+        assert_eq!(base[0].address, USDC_BASE);
+        assert_eq!(base[0].decimals, Some(6));
+        assert_eq!(base[0].source, "Test List");
+
+        let by_address = store
+            .get_token(1, "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48")
+            .await?;
+        assert_eq!(by_address.map(|t| t.symbol), Some("USDC".to_string()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_insert_if_missing_keeps_list_entry() -> Result<()> {
+        let store = TokenStore::new(setup_test_pool().await?);
+        store.import_token_list(&sample_list()).await?;
+
+        let inserted = store
+            .insert_token_if_missing(Token {
+                chain_id: 1,
+                address: USDC_MAINNET.to_string(),
+                symbol: "USDC.fake".to_string(),
+                name: None,
+                decimals: None,
+                source: "contract".to_string(),
+                created_at: None,
+                updated_at: None,
+            })
+            .await?;
+        assert!(!inserted);
+
+        let token = store.get_token(1, USDC_MAINNET).await?.unwrap();
+        assert_eq!(token.symbol, "USDC");
+        assert_eq!(token.decimals, Some(6));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_erc20_contract_auto_registers() -> Result<()> {
+        let pool = setup_test_pool().await?;
+        sqlx::query(
+            r#"
+            CREATE TABLE contracts (
+                address TEXT NOT NULL,
+                chain TEXT NOT NULL,
+                chain_id INTEGER NOT NULL,
+                source_code TEXT NOT NULL,
+                abi TEXT NOT NULL,
+                description TEXT,
+                name TEXT,
+                symbol TEXT,
+                protocol TEXT,
+                contract_type TEXT,
+                version TEXT,
+                is_proxy INTEGER,
+                implementation_address TEXT,
+                created_at INTEGER,
+                updated_at INTEGER,
+                PRIMARY KEY (chain_id, address)
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        let contracts = ContractStore::new(pool.clone());
+        let tokens = TokenStore::new(pool);
+
+        contracts
+            .store_contract(Contract {
+                address: "0xABCDEF0000000000000000000000000000000001".to_string(),
+                chain: "ethereum".to_string(),
+                chain_id: 1,
+                source_code: "contract Token {}".to_string(),
+                abi: json!([]),
+                description: None,
+                name: Some("Test Token".to_string()),
+                symbol: Some("TST".to_string()),
+                protocol: None,
+                contract_type: Some("ERC20".to_string()),
+                version: None,
+                is_proxy: None,
+                implementation_address: None,
+                created_at: None,
+                updated_at: None,
+            })
+            .await?;
+
+        let resolved = tokens.resolve_symbol("tst", Some(1)).await?;
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(
+            resolved[0].address,
+            "0xabcdef0000000000000000000000000000000001"
+        );
+        assert_eq!(resolved[0].decimals, None);
+        assert_eq!(resolved[0].source, "contract");
+
+        tokens
+            .set_token_decimals(1, &resolved[0].address, 18)
+            .await?;
+        let token = tokens.get_token(1, &resolved[0].address).await?.unwrap();
+        assert_eq!(token.decimals, Some(18));
+
+        Ok(())
+    }
+}
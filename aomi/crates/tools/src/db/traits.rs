@@ -1,6 +1,8 @@
 use super::{
-    ApiKey, ApiKeyUpdate, Contract, ContractSearchParams, Message, PendingTransaction, Session,
-    Transaction, TransactionRecord, User,
+    ApiKey, ApiKeyQuota, ApiKeyUpdate, Contract, ContractProxyRecord, ContractSearchParams,
+    Message, NameRecord, PendingTransaction, QuotaExceeded, Session, SessionParent,
+    SessionSearchHit, Token, TokenList, Transaction, TransactionRecord, UsageEvent, UsageGroupBy,
+    UsageReportParams, UsageReportRow, UsageTotals, User,
 };
 use anyhow::Result;
 use async_trait::async_trait;
@@ -21,6 +23,37 @@ pub trait ContractStoreApi: Send + Sync {
         offset: Option<i64>,
     ) -> Result<Vec<Contract>>;
     async fn update_contract(&self, update: super::ContractUpdate) -> Result<Contract>;
+
+    /// Last on-chain proxy check for the contract, however old
+    async fn get_proxy_record(
+        &self,
+        chain_id: u32,
+        address: &str,
+    ) -> Result<Option<ContractProxyRecord>>;
+
+    /// Insert or replace the proxy check for `record.chain_id` and `record.address`
+    async fn upsert_proxy_record(&self, record: &ContractProxyRecord) -> Result<()>;
+}
+
+// Top-level interface for the token registry
+#[async_trait]
+pub trait TokenStoreApi: Send + Sync {
+    async fn get_token(&self, chain_id: u32, address: &str) -> Result<Option<Token>>;
+    /// Case-insensitive symbol lookup, optionally restricted to one chain
+    async fn resolve_symbol(&self, symbol: &str, chain_id: Option<u32>) -> Result<Vec<Token>>;
+    /// Insert or overwrite; known name/decimals are never replaced by NULL
+    async fn upsert_token(&self, token: Token) -> Result<()>;
+    /// Insert only if the (chain_id, address) pair is not registered yet
+    async fn insert_token_if_missing(&self, token: Token) -> Result<bool>;
+    async fn set_token_decimals(&self, chain_id: u32, address: &str, decimals: u8) -> Result<()>;
+    /// Seed from a token-list document, returning the number of entries imported
+    async fn import_token_list(&self, list: &TokenList) -> Result<usize>;
+    async fn list_tokens(
+        &self,
+        chain_id: Option<u32>,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> Result<Vec<Token>>;
 }
 
 // Top-level interface for transaction storage
@@ -78,6 +111,24 @@ pub trait SessionStoreApi: Send + Sync {
     async fn set_session_title(&self, session_id: &str, title: Option<String>) -> Result<()>;
     async fn update_messages_persisted(&self, session_id: &str, persisted: bool) -> Result<()>;
     async fn get_messages_persisted(&self, session_id: &str) -> Result<Option<bool>>;
+    async fn update_context_summary(
+        &self,
+        session_id: &str,
+        summary: Option<serde_json::Value>,
+    ) -> Result<()>;
+    async fn get_context_summary(&self, session_id: &str) -> Result<Option<serde_json::Value>>;
+    async fn update_tool_state(
+        &self,
+        session_id: &str,
+        state: Option<serde_json::Value>,
+    ) -> Result<()>;
+    async fn get_tool_state(&self, session_id: &str) -> Result<Option<serde_json::Value>>;
+    async fn update_session_parent(
+        &self,
+        session_id: &str,
+        parent: Option<&SessionParent>,
+    ) -> Result<()>;
+    async fn get_session_parent(&self, session_id: &str) -> Result<Option<SessionParent>>;
     async fn get_user_sessions(&self, public_key: &str, limit: i32) -> Result<Vec<Session>>;
     async fn list_sessions(
         &self,
@@ -104,6 +155,30 @@ pub trait SessionStoreApi: Send + Sync {
         limit: Option<i32>,
     ) -> Result<Vec<Message>>;
     async fn get_user_message_history(&self, public_key: &str, limit: i32) -> Result<Vec<Message>>;
+    /// Deletes all but the first `keep` messages of a session (in insertion order).
+    async fn truncate_messages(&self, session_id: &str, keep: i64) -> Result<u64>;
+
+    // Search operations
+    /// Full-text search over a user's chat messages, best matches first.
+    async fn search_messages(
+        &self,
+        public_key: &str,
+        query: &str,
+        limit: i64,
+    ) -> Result<Vec<SessionSearchHit>>;
+    /// A user's most recent chat messages that have no stored embedding yet.
+    async fn get_unembedded_messages(
+        &self,
+        public_key: &str,
+        limit: i64,
+    ) -> Result<Vec<SessionSearchHit>>;
+    async fn save_message_embedding(&self, message_id: i64, embedding: &[f32]) -> Result<()>;
+    /// Embedded chat messages of a user, most recent first.
+    async fn get_message_embeddings(
+        &self,
+        public_key: &str,
+        limit: i64,
+    ) -> Result<Vec<(SessionSearchHit, Vec<f32>)>>;
 }
 
 // Top-level interface for api key storage
@@ -153,3 +228,47 @@ pub trait ApiKeyStoreApi: Send + Sync {
     /// Delete all entries for an API key
     async fn delete_api_key_all(&self, api_key: &str) -> Result<u64>;
 }
+
+// Top-level interface for usage metering and per-key quotas
+#[async_trait]
+pub trait UsageStoreApi: Send + Sync {
+    async fn record_usage(&self, event: &UsageEvent) -> Result<()>;
+
+    /// Usage of an API key since the given unix timestamp
+    async fn usage_totals(&self, api_key: &str, since: i64) -> Result<UsageTotals>;
+
+    async fn usage_report(
+        &self,
+        group_by: UsageGroupBy,
+        params: UsageReportParams,
+    ) -> Result<Vec<UsageReportRow>>;
+
+    async fn get_quota(&self, api_key: &str) -> Result<Option<ApiKeyQuota>>;
+
+    /// Insert or replace the quota for `quota.api_key`
+    async fn set_quota(&self, quota: ApiKeyQuota) -> Result<ApiKeyQuota>;
+
+    async fn delete_quota(&self, api_key: &str) -> Result<bool>;
+
+    async fn list_quotas(
+        &self,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> Result<Vec<ApiKeyQuota>>;
+
+    /// First limit the key has reached at `now` (unix seconds), if any
+    async fn check_quota(&self, api_key: &str, now: i64) -> Result<Option<QuotaExceeded>>;
+}
+
+// Top-level interface for the name service lookup cache
+#[async_trait]
+pub trait NameStoreApi: Send + Sync {
+    /// Cached record, expired or not
+    async fn get_name_record(&self, service: &str, lookup: &str) -> Result<Option<NameRecord>>;
+
+    /// Insert or replace the record for `record.service` and `record.lookup`
+    async fn upsert_name_record(&self, record: &NameRecord) -> Result<()>;
+
+    /// Drop records that expired before `now` (unix seconds)
+    async fn delete_expired_name_records(&self, now: i64) -> Result<u64>;
+}
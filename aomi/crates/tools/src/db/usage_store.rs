@@ -0,0 +1,537 @@
+use super::traits::UsageStoreApi;
+use super::{
+    ApiKeyQuota, UsageEvent, UsageGroupBy, UsageReportParams, UsageReportRow, UsageTotals,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc};
+use serde::Serialize;
+use sqlx::{Pool, QueryBuilder, any::Any};
+use std::fmt;
+
+const QUOTA_COLUMNS: &str = "api_key, daily_token_limit, monthly_token_limit, \
+                             daily_tool_call_limit, monthly_tool_call_limit, updated_at";
+
+// COALESCE + CAST keeps the sums BIGINT on Postgres (SUM(BIGINT) is NUMERIC) and non-NULL
+const TOTALS_COLUMNS: &str = "\
+    CAST(COALESCE(SUM(CASE WHEN kind = 'llm' THEN 1 ELSE 0 END), 0) AS BIGINT) AS llm_requests, \
+    CAST(COALESCE(SUM(input_tokens), 0) AS BIGINT) AS input_tokens, \
+    CAST(COALESCE(SUM(output_tokens), 0) AS BIGINT) AS output_tokens, \
+    CAST(COALESCE(SUM(CASE WHEN kind = 'tool' THEN 1 ELSE 0 END), 0) AS BIGINT) AS tool_calls";
+
+/// Window a quota limit applies to. Periods follow UTC calendar days and months.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaPeriod {
+    Daily,
+    Monthly,
+}
+
+impl QuotaPeriod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuotaPeriod::Daily => "daily",
+            QuotaPeriod::Monthly => "monthly",
+        }
+    }
+
+    /// Unix timestamp at which the period containing `now` started
+    pub fn start(&self, now: i64) -> i64 {
+        let date = utc_date(now);
+        let start = match self {
+            QuotaPeriod::Daily => date,
+            QuotaPeriod::Monthly => date.with_day(1).unwrap_or(date),
+        };
+        midnight(start)
+    }
+
+    /// Unix timestamp at which the period containing `now` ends
+    pub fn resets_at(&self, now: i64) -> i64 {
+        let date = utc_date(now);
+        let next = match self {
+            QuotaPeriod::Daily => date.succ_opt(),
+            QuotaPeriod::Monthly => date
+                .with_day(1)
+                .and_then(|first| first.checked_add_months(Months::new(1))),
+        };
+        next.map(midnight).unwrap_or(i64::MAX)
+    }
+}
+
+fn utc_date(now: i64) -> NaiveDate {
+    DateTime::<Utc>::from_timestamp(now, 0)
+        .unwrap_or_default()
+        .date_naive()
+}
+
+fn midnight(date: NaiveDate) -> i64 {
+    date.and_hms_opt(0, 0, 0)
+        .map(|dt| dt.and_utc().timestamp())
+        .unwrap_or_default()
+}
+
+/// A quota limit an API key has reached.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QuotaExceeded {
+    pub period: QuotaPeriod,
+    /// "tokens" or "tool_calls"
+    pub metric: &'static str,
+    pub limit: i64,
+    pub used: i64,
+    /// Unix timestamp at which the period rolls over
+    pub resets_at: i64,
+}
+
+impl QuotaExceeded {
+    pub fn retry_after_secs(&self, now: i64) -> i64 {
+        (self.resets_at - now).max(0)
+    }
+}
+
+impl fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} quota exceeded: used {} of {}",
+            self.period.as_str(),
+            self.metric.replace('_', " "),
+            self.used,
+            self.limit
+        )
+    }
+}
+
+impl ApiKeyQuota {
+    /// First limit reached given the usage of the current day and month, checked
+    /// daily before monthly and tokens before tool calls.
+    pub fn exceeded(
+        &self,
+        daily: &UsageTotals,
+        monthly: &UsageTotals,
+        now: i64,
+    ) -> Option<QuotaExceeded> {
+        let checks = [
+            (
+                QuotaPeriod::Daily,
+                "tokens",
+                self.daily_token_limit,
+                daily.total_tokens(),
+            ),
+            (
+                QuotaPeriod::Daily,
+                "tool_calls",
+                self.daily_tool_call_limit,
+                daily.tool_calls,
+            ),
+            (
+                QuotaPeriod::Monthly,
+                "tokens",
+                self.monthly_token_limit,
+                monthly.total_tokens(),
+            ),
+            (
+                QuotaPeriod::Monthly,
+                "tool_calls",
+                self.monthly_tool_call_limit,
+                monthly.tool_calls,
+            ),
+        ];
+        checks
+            .into_iter()
+            .find_map(|(period, metric, limit, used)| {
+                let limit = limit?;
+                (used >= limit).then(|| QuotaExceeded {
+                    period,
+                    metric,
+                    limit,
+                    used,
+                    resets_at: period.resets_at(now),
+                })
+            })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct UsageStore {
+    pool: Pool<Any>,
+}
+
+impl UsageStore {
+    pub fn new(pool: Pool<Any>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl UsageStoreApi for UsageStore {
+    async fn record_usage(&self, event: &UsageEvent) -> Result<()> {
+        let query = "INSERT INTO usage_events (api_key, public_key, session_id, namespace, kind, tool_name, input_tokens, output_tokens, created_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)";
+
+        sqlx::query::<Any>(query)
+            .bind(&event.api_key)
+            .bind(&event.public_key)
+            .bind(&event.session_id)
+            .bind(&event.namespace)
+            .bind(event.kind.as_str())
+            .bind(&event.tool_name)
+            .bind(event.input_tokens)
+            .bind(event.output_tokens)
+            .bind(event.created_at)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn usage_totals(&self, api_key: &str, since: i64) -> Result<UsageTotals> {
+        let query = format!(
+            "SELECT {TOTALS_COLUMNS} FROM usage_events WHERE api_key = $1 AND created_at >= $2"
+        );
+
+        let totals = sqlx::query_as::<Any, UsageTotals>(&query)
+            .bind(api_key)
+            .bind(since)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(totals)
+    }
+
+    async fn usage_report(
+        &self,
+        group_by: UsageGroupBy,
+        params: UsageReportParams,
+    ) -> Result<Vec<UsageReportRow>> {
+        let column = group_by.column();
+        let mut query = QueryBuilder::<Any>::new(format!(
+            "SELECT {column} AS group_key, {TOTALS_COLUMNS}, \
+             MIN(created_at) AS first_at, MAX(created_at) AS last_at \
+             FROM usage_events WHERE 1 = 1"
+        ));
+
+        if let Some(api_key) = params.api_key {
+            query.push(" AND api_key = ").push_bind(api_key);
+        }
+        if let Some(public_key) = params.public_key {
+            query.push(" AND public_key = ").push_bind(public_key);
+        }
+        if let Some(session_id) = params.session_id {
+            query.push(" AND session_id = ").push_bind(session_id);
+        }
+        if let Some(since) = params.since {
+            query.push(" AND created_at >= ").push_bind(since);
+        }
+        if let Some(until) = params.until {
+            query.push(" AND created_at < ").push_bind(until);
+        }
+
+        query.push(format!(
+            " GROUP BY {column} ORDER BY SUM(input_tokens + output_tokens) DESC, {column}"
+        ));
+
+        if let Some(limit) = params.limit {
+            query.push(" LIMIT ").push_bind(limit);
+        }
+        if let Some(offset) = params.offset {
+            query.push(" OFFSET ").push_bind(offset);
+        }
+
+        let rows = query
+            .build_query_as::<UsageReportRow>()
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows)
+    }
+
+    async fn get_quota(&self, api_key: &str) -> Result<Option<ApiKeyQuota>> {
+        let query = format!("SELECT {QUOTA_COLUMNS} FROM api_key_quotas WHERE api_key = $1");
+
+        let quota = sqlx::query_as::<Any, ApiKeyQuota>(&query)
+            .bind(api_key)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(quota)
+    }
+
+    async fn set_quota(&self, quota: ApiKeyQuota) -> Result<ApiKeyQuota> {
+        let query = format!(
+            "INSERT INTO api_key_quotas ({QUOTA_COLUMNS})
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (api_key) DO UPDATE SET
+                daily_token_limit = EXCLUDED.daily_token_limit,
+                monthly_token_limit = EXCLUDED.monthly_token_limit,
+                daily_tool_call_limit = EXCLUDED.daily_tool_call_limit,
+                monthly_tool_call_limit = EXCLUDED.monthly_tool_call_limit,
+                updated_at = EXCLUDED.updated_at
+             RETURNING {QUOTA_COLUMNS}"
+        );
+
+        let row = sqlx::query_as::<Any, ApiKeyQuota>(&query)
+            .bind(quota.api_key)
+            .bind(quota.daily_token_limit)
+            .bind(quota.monthly_token_limit)
+            .bind(quota.daily_tool_call_limit)
+            .bind(quota.monthly_tool_call_limit)
+            .bind(Utc::now().timestamp())
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(row)
+    }
+
+    async fn delete_quota(&self, api_key: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM api_key_quotas WHERE api_key = $1")
+            .bind(api_key)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list_quotas(
+        &self,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> Result<Vec<ApiKeyQuota>> {
+        let mut query = QueryBuilder::<Any>::new(format!(
+            "SELECT {QUOTA_COLUMNS} FROM api_key_quotas ORDER BY api_key"
+        ));
+
+        if let Some(limit) = limit {
+            query.push(" LIMIT ").push_bind(limit);
+        }
+        if let Some(offset) = offset {
+            query.push(" OFFSET ").push_bind(offset);
+        }
+
+        let rows = query
+            .build_query_as::<ApiKeyQuota>()
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows)
+    }
+
+    async fn check_quota(&self, api_key: &str, now: i64) -> Result<Option<QuotaExceeded>> {
+        let Some(quota) = self.get_quota(api_key).await? else {
+            return Ok(None);
+        };
+        if quota.is_unlimited() {
+            return Ok(None);
+        }
+
+        let daily = self
+            .usage_totals(api_key, QuotaPeriod::Daily.start(now))
+            .await?;
+        let monthly =
+            if quota.monthly_token_limit.is_some() || quota.monthly_tool_call_limit.is_some() {
+                self.usage_totals(api_key, QuotaPeriod::Monthly.start(now))
+                    .await?
+            } else {
+                UsageTotals::default()
+            };
+
+        Ok(quota.exceeded(&daily, &monthly, now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::UsageKind;
+    use sqlx::any::AnyPoolOptions;
+
+    // 2026-03-15T12:00:00Z
+    const NOW: i64 = 1_773_576_000;
+    const DAY_START: i64 = 1_773_532_800;
+    const MONTH_START: i64 = 1_772_323_200;
+
+    async fn setup_test_pool() -> Result<Pool<Any>> {
+        sqlx::any::install_default_drivers();
+
+        let pool = AnyPoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE usage_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                api_key TEXT,
+                public_key TEXT,
+                session_id TEXT NOT NULL,
+                namespace TEXT,
+                kind TEXT NOT NULL,
+                tool_name TEXT,
+                input_tokens INTEGER NOT NULL DEFAULT 0,
+                output_tokens INTEGER NOT NULL DEFAULT 0,
+                created_at INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE api_key_quotas (
+                api_key TEXT PRIMARY KEY,
+                daily_token_limit INTEGER,
+                monthly_token_limit INTEGER,
+                daily_tool_call_limit INTEGER,
+                monthly_tool_call_limit INTEGER,
+                updated_at INTEGER
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        Ok(pool)
+    }
+
+    fn llm(api_key: &str, session_id: &str, tokens: i64, created_at: i64) -> UsageEvent {
+        UsageEvent {
+            api_key: Some(api_key.to_string()),
+            public_key: Some("0xabc".to_string()),
+            session_id: session_id.to_string(),
+            namespace: Some("default".to_string()),
+            kind: UsageKind::Llm,
+            tool_name: None,
+            input_tokens: tokens,
+            output_tokens: tokens / 10,
+            created_at,
+        }
+    }
+
+    fn tool(api_key: &str, session_id: &str, created_at: i64) -> UsageEvent {
+        UsageEvent {
+            kind: UsageKind::Tool,
+            tool_name: Some("brave_search".to_string()),
+            input_tokens: 0,
+            output_tokens: 0,
+            ..llm(api_key, session_id, 0, created_at)
+        }
+    }
+
+    #[test]
+    fn test_quota_periods_follow_utc_calendar() {
+        assert_eq!(QuotaPeriod::Daily.start(NOW), DAY_START);
+        assert_eq!(QuotaPeriod::Daily.resets_at(NOW), DAY_START + 86_400);
+        assert_eq!(QuotaPeriod::Monthly.start(NOW), MONTH_START);
+        // April 1st
+        assert_eq!(QuotaPeriod::Monthly.resets_at(NOW), 1_775_001_600);
+    }
+
+    #[tokio::test]
+    async fn test_totals_and_report() -> Result<()> {
+        let store = UsageStore::new(setup_test_pool().await?);
+        store.record_usage(&llm("key-a", "s1", 1000, NOW)).await?;
+        store.record_usage(&llm("key-a", "s2", 500, NOW)).await?;
+        store.record_usage(&tool("key-a", "s1", NOW)).await?;
+        store.record_usage(&llm("key-b", "s3", 200, NOW)).await?;
+        // Yesterday
+        store
+            .record_usage(&llm("key-a", "s1", 4000, DAY_START - 60))
+            .await?;
+
+        let today = store.usage_totals("key-a", DAY_START).await?;
+        assert_eq!(
+            today,
+            UsageTotals {
+                llm_requests: 2,
+                input_tokens: 1500,
+                output_tokens: 150,
+                tool_calls: 1,
+            }
+        );
+        assert_eq!(
+            store.usage_totals("missing", DAY_START).await?,
+            UsageTotals::default()
+        );
+
+        let by_session = store
+            .usage_report(
+                UsageGroupBy::Session,
+                UsageReportParams {
+                    api_key: Some("key-a".to_string()),
+                    since: Some(DAY_START),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        assert_eq!(by_session.len(), 2);
+        assert_eq!(by_session[0].key.as_deref(), Some("s1"));
+        assert_eq!(by_session[0].totals.tool_calls, 1);
+        assert_eq!(by_session[0].totals.input_tokens, 1000);
+
+        let by_key = store
+            .usage_report(UsageGroupBy::ApiKey, UsageReportParams::default())
+            .await?;
+        assert_eq!(by_key[0].key.as_deref(), Some("key-a"));
+        assert_eq!(by_key[0].totals.input_tokens, 5500);
+        assert_eq!(by_key[0].first_at, DAY_START - 60);
+        assert_eq!(by_key[1].key.as_deref(), Some("key-b"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_check_quota() -> Result<()> {
+        let store = UsageStore::new(setup_test_pool().await?);
+        assert!(store.check_quota("key-a", NOW).await?.is_none());
+
+        let quota = store
+            .set_quota(ApiKeyQuota {
+                api_key: "key-a".to_string(),
+                daily_token_limit: Some(1000),
+                monthly_tool_call_limit: Some(2),
+                ..Default::default()
+            })
+            .await?;
+        assert_eq!(quota.daily_token_limit, Some(1000));
+        assert!(quota.updated_at.is_some());
+
+        store.record_usage(&llm("key-a", "s1", 500, NOW)).await?;
+        assert!(store.check_quota("key-a", NOW).await?.is_none());
+
+        // 1000 input + 100 output today
+        store.record_usage(&llm("key-a", "s1", 500, NOW)).await?;
+        let exceeded = store.check_quota("key-a", NOW).await?.expect("over quota");
+        assert_eq!(exceeded.period, QuotaPeriod::Daily);
+        assert_eq!(exceeded.metric, "tokens");
+        assert_eq!(exceeded.used, 1100);
+        assert_eq!(exceeded.retry_after_secs(NOW), DAY_START + 86_400 - NOW);
+
+        // Tomorrow the daily budget is fresh, but the month's tool calls are used up
+        let tomorrow = NOW + 86_400;
+        assert!(store.check_quota("key-a", tomorrow).await?.is_none());
+        store.record_usage(&tool("key-a", "s1", NOW)).await?;
+        store.record_usage(&tool("key-a", "s2", NOW)).await?;
+        let exceeded = store
+            .check_quota("key-a", tomorrow)
+            .await?
+            .expect("over quota");
+        assert_eq!(exceeded.period, QuotaPeriod::Monthly);
+        assert_eq!(exceeded.metric, "tool_calls");
+        assert_eq!(
+            exceeded.to_string(),
+            "monthly tool calls quota exceeded: used 2 of 2"
+        );
+
+        // Raising the limit through an upsert lifts the block
+        store
+            .set_quota(ApiKeyQuota {
+                monthly_tool_call_limit: Some(100),
+                ..quota
+            })
+            .await?;
+        assert!(store.check_quota("key-a", tomorrow).await?.is_none());
+        assert!(store.delete_quota("key-a").await?);
+        assert!(store.list_quotas(None, None).await?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,1183 @@
+//! Calldata decoding, the inverse of `abi_encoder`.
+//!
+//! Function signatures come from the target's ABI (contract DB, then Etherscan) and
+//! fall back to a local 4-byte selector table. Calls embedded in the arguments
+//! (multicall, Multicall3, Safe `execTransaction`/`multiSend`, Universal Router
+//! `execute`) are decoded recursively into a tree.
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+
+use alloy::{
+    dyn_abi::{DynSolType, DynSolValue},
+    hex,
+    primitives::{Address, U256, keccak256},
+};
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use rig::tool::ToolError;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sqlx::any::AnyPoolOptions;
+use tracing::{debug, warn};
+
+use crate::db::{ContractStore, ContractStoreApi};
+use crate::db_tools::run_sync;
+use crate::etherscan::{fetch_and_store_contract, fetch_contract_from_etherscan};
+use crate::{AomiTool, AomiToolArgs, ToolCallCtx, with_topic};
+
+/// Nested calls deeper than this are left as raw bytes.
+const MAX_DEPTH: usize = 4;
+
+/// Rendered argument values are cut to this many characters in explanations.
+const MAX_RENDERED_VALUE: usize = 80;
+
+/// Well-known function signatures used when the target's ABI is unavailable.
+const KNOWN_SIGNATURES: &[&str] = &[
+    // ERC20 / ERC721 / ERC1155
+    "transfer(address,uint256)",
+    "transferFrom(address,address,uint256)",
+    "approve(address,uint256)",
+    "increaseAllowance(address,uint256)",
+    "decreaseAllowance(address,uint256)",
+    "permit(address,address,uint256,uint256,uint8,bytes32,bytes32)",
+    "setApprovalForAll(address,bool)",
+    "safeTransferFrom(address,address,uint256)",
+    "safeTransferFrom(address,address,uint256,bytes)",
+    "safeTransferFrom(address,address,uint256,uint256,bytes)",
+    // WETH
+    "deposit()",
+    "withdraw(uint256)",
+    // Multicall variants
+    "multicall(bytes[])",
+    "multicall(uint256,bytes[])",
+    "multicall(bytes32,bytes[])",
+    "aggregate((address,bytes)[])",
+    "tryAggregate(bool,(address,bytes)[])",
+    "blockAndAggregate((address,bytes)[])",
+    "aggregate3((address,bool,bytes)[])",
+    "aggregate3Value((address,bool,uint256,bytes)[])",
+    // Safe
+    "execTransaction(address,uint256,bytes,uint8,uint256,uint256,uint256,address,address,bytes)",
+    "execTransactionFromModule(address,uint256,bytes,uint8)",
+    "multiSend(bytes)",
+    // Smart accounts (ERC-4337)
+    "execute(address,uint256,bytes)",
+    "executeBatch(address[],bytes[])",
+    "executeBatch(address[],uint256[],bytes[])",
+    // Uniswap Universal Router
+    "execute(bytes,bytes[],uint256)",
+    "execute(bytes,bytes[])",
+    // Uniswap V2 router
+    "swapExactTokensForTokens(uint256,uint256,address[],address,uint256)",
+    "swapTokensForExactTokens(uint256,uint256,address[],address,uint256)",
+    "swapExactETHForTokens(uint256,address[],address,uint256)",
+    "swapETHForExactTokens(uint256,address[],address,uint256)",
+    "swapExactTokensForETH(uint256,uint256,address[],address,uint256)",
+    "swapTokensForExactETH(uint256,uint256,address[],address,uint256)",
+    "addLiquidity(address,address,uint256,uint256,uint256,uint256,address,uint256)",
+    "addLiquidityETH(address,uint256,uint256,uint256,address,uint256)",
+    "removeLiquidity(address,address,uint256,uint256,uint256,address,uint256)",
+    // Uniswap V3 SwapRouter / SwapRouter02
+    "exactInputSingle((address,address,uint24,address,uint256,uint256,uint256,uint160))",
+    "exactInput((bytes,address,uint256,uint256,uint256))",
+    "exactOutputSingle((address,address,uint24,address,uint256,uint256,uint256,uint160))",
+    "exactOutput((bytes,address,uint256,uint256,uint256))",
+    "exactInputSingle((address,address,uint24,address,uint256,uint256,uint160))",
+    "exactInput((bytes,address,uint256,uint256))",
+    "refundETH()",
+    "unwrapWETH9(uint256,address)",
+    "sweepToken(address,uint256,address)",
+    // Permit2
+    "approve(address,address,uint160,uint48)",
+    // Aave V3 pool
+    "supply(address,uint256,address,uint16)",
+    "withdraw(address,uint256,address)",
+    "borrow(address,uint256,uint256,uint16,address)",
+    "repay(address,uint256,uint256,address)",
+    // Admin
+    "transferOwnership(address)",
+    "upgradeTo(address)",
+    "upgradeToAndCall(address,bytes)",
+];
+
+static SELECTOR_DB: LazyLock<HashMap<[u8; 4], Vec<&'static str>>> = LazyLock::new(|| {
+    let mut db: HashMap<[u8; 4], Vec<&'static str>> = HashMap::new();
+    for signature in KNOWN_SIGNATURES {
+        db.entry(selector_of(signature))
+            .or_default()
+            .push(signature);
+    }
+    db
+});
+
+/// Universal Router commands: (command type, name, parameter types, parameter names).
+const UNIVERSAL_ROUTER_COMMANDS: &[(u8, &str, &str, &[&str])] = &[
+    (
+        0x00,
+        "V3_SWAP_EXACT_IN",
+        "(address,uint256,uint256,bytes,bool)",
+        &[
+            "recipient",
+            "amountIn",
+            "amountOutMin",
+            "path",
+            "payerIsUser",
+        ],
+    ),
+    (
+        0x01,
+        "V3_SWAP_EXACT_OUT",
+        "(address,uint256,uint256,bytes,bool)",
+        &[
+            "recipient",
+            "amountOut",
+            "amountInMax",
+            "path",
+            "payerIsUser",
+        ],
+    ),
+    (
+        0x02,
+        "PERMIT2_TRANSFER_FROM",
+        "(address,address,uint256)",
+        &["token", "recipient", "amount"],
+    ),
+    (
+        0x04,
+        "SWEEP",
+        "(address,address,uint256)",
+        &["token", "recipient", "amountMin"],
+    ),
+    (
+        0x05,
+        "TRANSFER",
+        "(address,address,uint256)",
+        &["token", "recipient", "value"],
+    ),
+    (
+        0x06,
+        "PAY_PORTION",
+        "(address,address,uint256)",
+        &["token", "recipient", "bips"],
+    ),
+    (
+        0x08,
+        "V2_SWAP_EXACT_IN",
+        "(address,uint256,uint256,address[],bool)",
+        &[
+            "recipient",
+            "amountIn",
+            "amountOutMin",
+            "path",
+            "payerIsUser",
+        ],
+    ),
+    (
+        0x09,
+        "V2_SWAP_EXACT_OUT",
+        "(address,uint256,uint256,address[],bool)",
+        &[
+            "recipient",
+            "amountOut",
+            "amountInMax",
+            "path",
+            "payerIsUser",
+        ],
+    ),
+    (
+        0x0a,
+        "PERMIT2_PERMIT",
+        "(((address,uint160,uint48,uint48),address,uint256),bytes)",
+        &["permitSingle", "signature"],
+    ),
+    (
+        0x0b,
+        "WRAP_ETH",
+        "(address,uint256)",
+        &["recipient", "amountMin"],
+    ),
+    (
+        0x0c,
+        "UNWRAP_WETH",
+        "(address,uint256)",
+        &["recipient", "amountMin"],
+    ),
+    (
+        0x0e,
+        "BALANCE_CHECK_ERC20",
+        "(address,address,uint256)",
+        &["owner", "token", "minBalance"],
+    ),
+];
+
+const UNIVERSAL_ROUTER_COMMAND_MASK: u8 = 0x3f;
+const UNIVERSAL_ROUTER_ALLOW_REVERT: u8 = 0x80;
+
+// ============================================================================
+// Decoded Types
+// ============================================================================
+
+/// Where a call's signature came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DecodeSource {
+    /// Verified ABI of the target contract
+    Abi,
+    /// Local 4-byte selector table
+    SelectorDatabase,
+    /// Universal Router command byte
+    RouterCommand,
+    /// Empty calldata (plain value transfer)
+    NativeTransfer,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecodedArg {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub value: Value,
+}
+
+/// One node of the decoded call tree.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecodedCall {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// Native value forwarded with the call (wei), when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// Canonical signature, e.g. `transfer(address,uint256)`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+    pub source: DecodeSource,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<DecodedArg>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<DecodedCall>,
+    /// Calldata that could not be decoded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<String>,
+}
+
+impl DecodedCall {
+    fn unknown(target: Option<String>, data: &[u8]) -> Self {
+        Self {
+            target,
+            value: None,
+            selector: (data.len() >= 4).then(|| hex::encode_prefixed(&data[..4])),
+            function: None,
+            source: DecodeSource::Unknown,
+            args: Vec::new(),
+            calls: Vec::new(),
+            raw: Some(hex::encode_prefixed(data)),
+        }
+    }
+
+    /// Function name without the parameter list.
+    pub fn name(&self) -> Option<&str> {
+        self.function
+            .as_deref()
+            .map(|sig| sig.split('(').next().unwrap_or(sig))
+    }
+
+    /// Indented, one-line-per-call rendering of the tree.
+    pub fn explain(&self) -> String {
+        let mut out = String::new();
+        self.explain_into(0, &mut out);
+        out.trim_end().to_string()
+    }
+
+    fn explain_into(&self, depth: usize, out: &mut String) {
+        let indent = "  ".repeat(depth);
+        let headline = match (self.name(), self.source) {
+            (_, DecodeSource::NativeTransfer) => "native transfer".to_string(),
+            (Some(name), _) => {
+                let args = self
+                    .args
+                    .iter()
+                    .map(|arg| {
+                        let value = render_value(&arg.value);
+                        match &arg.name {
+                            Some(name) => format!("{name}={value}"),
+                            None => value,
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{name}({args})")
+            }
+            (None, _) => format!(
+                "unknown call {}",
+                self.selector.as_deref().unwrap_or("(no selector)")
+            ),
+        };
+        out.push_str(&indent);
+        out.push_str(&headline);
+        if let Some(target) = &self.target {
+            out.push_str(&format!(" on {target}"));
+        }
+        if let Some(value) = self.value.as_deref().filter(|v| *v != "0") {
+            out.push_str(&format!(" with {value} wei"));
+        }
+        out.push('\n');
+        for call in &self.calls {
+            call.explain_into(depth + 1, out);
+        }
+    }
+}
+
+fn render_value(value: &Value) -> String {
+    let rendered = match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    match rendered.char_indices().nth(MAX_RENDERED_VALUE) {
+        Some((idx, _)) => format!("{}…", &rendered[..idx]),
+        None => rendered,
+    }
+}
+
+// ============================================================================
+// ABI Resolution
+// ============================================================================
+
+/// Supplies contract ABIs (Etherscan JSON format) for decoding.
+#[async_trait]
+pub trait AbiResolver: Send + Sync {
+    async fn resolve_abi(&self, chain_id: u32, address: &str) -> Option<Value>;
+}
+
+/// Resolver that never finds an ABI, so only the selector table is used.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SelectorOnly;
+
+#[async_trait]
+impl AbiResolver for SelectorOnly {
+    async fn resolve_abi(&self, _chain_id: u32, _address: &str) -> Option<Value> {
+        None
+    }
+}
+
+/// Contract DB first (`ContractStoreApi::get_abi`), then Etherscan.
+pub struct ContractAbiResolver {
+    store: Option<ContractStore>,
+}
+
+impl ContractAbiResolver {
+    pub fn new(store: Option<ContractStore>) -> Self {
+        Self { store }
+    }
+
+    /// Connects to `DATABASE_URL`; falls back to Etherscan-only if unavailable.
+    pub async fn from_env() -> Self {
+        sqlx::any::install_default_drivers();
+        let database_url = std::env::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://aomi@localhost:5432/chatbot".to_string());
+
+        let store = match AnyPoolOptions::new()
+            .max_connections(5)
+            .acquire_timeout(Duration::from_secs(3))
+            .connect(&database_url)
+            .await
+        {
+            Ok(pool) => Some(ContractStore::new(pool)),
+            Err(e) => {
+                warn!(
+                    "Database connection error: {}. Decoding without contract DB.",
+                    e
+                );
+                None
+            }
+        };
+        Self::new(store)
+    }
+}
+
+#[async_trait]
+impl AbiResolver for ContractAbiResolver {
+    async fn resolve_abi(&self, chain_id: u32, address: &str) -> Option<Value> {
+        let address = address.to_lowercase();
+        if let Some(store) = &self.store {
+            match store.get_abi(chain_id, address.clone()).await {
+                Ok(Some(abi)) => return Some(abi),
+                Ok(None) => {}
+                Err(e) => warn!("Failed to read ABI for {} from DB: {}", address, e),
+            }
+        }
+
+        let fetched = match &self.store {
+            Some(store) => fetch_and_store_contract(chain_id, address.clone(), store).await,
+            None => fetch_contract_from_etherscan(chain_id, address.clone()).await,
+        };
+        match fetched {
+            Ok(contract) => Some(contract.abi),
+            Err(e) => {
+                debug!("No ABI for {} on chain {}: {}", address, chain_id, e);
+                None
+            }
+        }
+    }
+}
+
+/// Canonical type string for an ABI JSON parameter (tuples expanded).
+fn canonical_type(param: &Value) -> Option<String> {
+    let ty = param.get("type")?.as_str()?;
+    match ty.strip_prefix("tuple") {
+        Some(suffix) => {
+            let components = param
+                .get("components")?
+                .as_array()?
+                .iter()
+                .map(canonical_type)
+                .collect::<Option<Vec<_>>>()?;
+            Some(format!("({}){}", components.join(","), suffix))
+        }
+        None => Some(ty.to_string()),
+    }
+}
+
+/// Finds the function matching `selector` in an ABI, returning its signature and input names.
+fn find_in_abi(abi: &Value, selector: [u8; 4]) -> Option<(String, Vec<Option<String>>)> {
+    // Some sources store the ABI as a JSON-encoded string
+    let parsed;
+    let abi = match abi {
+        Value::String(s) => {
+            parsed = serde_json::from_str::<Value>(s).ok()?;
+            &parsed
+        }
+        other => other,
+    };
+
+    abi.as_array()?
+        .iter()
+        .filter(|item| item.get("type").and_then(Value::as_str) == Some("function"))
+        .find_map(|item| {
+            let name = item.get("name")?.as_str()?;
+            let inputs = item
+                .get("inputs")
+                .and_then(Value::as_array)
+                .cloned()
+                .unwrap_or_default();
+            let types = inputs
+                .iter()
+                .map(canonical_type)
+                .collect::<Option<Vec<_>>>()?;
+            let signature = format!("{name}({})", types.join(","));
+            if selector_of(&signature) != selector {
+                return None;
+            }
+            let names = inputs
+                .iter()
+                .map(|input| {
+                    input
+                        .get("name")
+                        .and_then(Value::as_str)
+                        .filter(|n| !n.is_empty())
+                        .map(str::to_string)
+                })
+                .collect();
+            Some((signature, names))
+        })
+}
+
+// ============================================================================
+// Decoding
+// ============================================================================
+
+fn selector_of(signature: &str) -> [u8; 4] {
+    let hash = keccak256(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// Signature for `calldata` from the local selector table (first match only).
+pub fn known_signature(calldata: &str) -> Option<&'static str> {
+    let bytes = hex::decode(calldata.trim().trim_start_matches("0x")).ok()?;
+    let selector: [u8; 4] = bytes.get(..4)?.try_into().ok()?;
+    SELECTOR_DB.get(&selector)?.first().copied()
+}
+
+/// Decodes the parameters of `signature` from `data` (selector excluded).
+fn decode_params(signature: &str, data: &[u8]) -> Option<(Vec<DynSolType>, Vec<DynSolValue>)> {
+    let params = &signature[signature.find('(')?..];
+    let DynSolType::Tuple(types) = DynSolType::parse(params).ok()? else {
+        return None;
+    };
+    if types.is_empty() {
+        return data.is_empty().then_some((Vec::new(), Vec::new()));
+    }
+    let values = match DynSolType::Tuple(types.clone())
+        .abi_decode_params(data)
+        .ok()?
+    {
+        DynSolValue::Tuple(values) => values,
+        other => vec![other],
+    };
+    (values.len() == types.len()).then_some((types, values))
+}
+
+pub(crate) fn value_to_json(value: &DynSolValue) -> Value {
+    if let Some(address) = value.as_address() {
+        json!(address.to_checksum(None))
+    } else if let Some(b) = value.as_bool() {
+        json!(b)
+    } else if let Some((n, _)) = value.as_uint() {
+        json!(n.to_string())
+    } else if let Some((n, _)) = value.as_int() {
+        json!(n.to_string())
+    } else if let Some(bytes) = value.as_bytes() {
+        json!(hex::encode_prefixed(bytes))
+    } else if let Some((bytes, _)) = value.as_fixed_bytes() {
+        json!(hex::encode_prefixed(bytes))
+    } else if let Some(s) = value.as_str() {
+        json!(s)
+    } else if let Some(items) = value
+        .as_tuple()
+        .or_else(|| value.as_array())
+        .or_else(|| value.as_fixed_array())
+    {
+        Value::Array(items.iter().map(value_to_json).collect())
+    } else {
+        json!(format!("{value:?}"))
+    }
+}
+
+fn to_args(
+    types: &[DynSolType],
+    values: &[DynSolValue],
+    names: &[Option<String>],
+) -> Vec<DecodedArg> {
+    types
+        .iter()
+        .zip(values)
+        .enumerate()
+        .map(|(i, (ty, value))| DecodedArg {
+            name: names.get(i).cloned().flatten(),
+            ty: ty.sol_type_name().into_owned(),
+            value: value_to_json(value),
+        })
+        .collect()
+}
+
+/// Renders a packed Uniswap V3 path (token, fee, token, ...) as a JSON list.
+fn decode_v3_path(path: &[u8]) -> Option<Value> {
+    if path.len() < 43 || (path.len() - 20) % 23 != 0 {
+        return None;
+    }
+    let mut hops = vec![json!(Address::from_slice(&path[..20]).to_checksum(None))];
+    for hop in path[20..].chunks(23) {
+        let fee = u32::from_be_bytes([0, hop[0], hop[1], hop[2]]);
+        hops.push(json!(fee));
+        hops.push(json!(Address::from_slice(&hop[3..]).to_checksum(None)));
+    }
+    Some(Value::Array(hops))
+}
+
+fn decode_router_command(target: Option<String>, command: u8, input: &[u8]) -> DecodedCall {
+    let kind = command & UNIVERSAL_ROUTER_COMMAND_MASK;
+    let Some(&(_, name, params, names)) = UNIVERSAL_ROUTER_COMMANDS
+        .iter()
+        .find(|(id, ..)| *id == kind)
+    else {
+        let mut call = DecodedCall::unknown(target, input);
+        call.selector = None;
+        call.function = Some(format!("COMMAND_{kind:#04x}"));
+        return call;
+    };
+
+    let signature = format!("{name}{params}");
+    let Some((types, values)) = decode_params(&signature, input) else {
+        let mut call = DecodedCall::unknown(target, input);
+        call.selector = None;
+        call.function = Some(signature);
+        return call;
+    };
+
+    let names: Vec<Option<String>> = names.iter().map(|n| Some(n.to_string())).collect();
+    let mut args = to_args(&types, &values, &names);
+    for arg in args.iter_mut() {
+        if arg.name.as_deref() == Some("path")
+            && let Some(bytes) = arg
+                .value
+                .as_str()
+                .and_then(|s| hex::decode(s.trim_start_matches("0x")).ok())
+            && let Some(hops) = decode_v3_path(&bytes)
+        {
+            arg.value = hops;
+        }
+    }
+    if command & UNIVERSAL_ROUTER_ALLOW_REVERT != 0 {
+        args.push(DecodedArg {
+            name: Some("allowRevert".to_string()),
+            ty: "bool".to_string(),
+            value: json!(true),
+        });
+    }
+
+    DecodedCall {
+        target,
+        value: None,
+        selector: None,
+        function: Some(signature),
+        source: DecodeSource::RouterCommand,
+        args,
+        calls: Vec::new(),
+        raw: None,
+    }
+}
+
+/// Parses Safe `multiSend` packed transactions: operation(1) to(20) value(32) len(32) data.
+fn parse_multisend(mut packed: &[u8]) -> Option<Vec<(Address, U256, Vec<u8>)>> {
+    let mut txs = Vec::new();
+    while !packed.is_empty() {
+        if packed.len() < 85 {
+            return None;
+        }
+        let to = Address::from_slice(&packed[1..21]);
+        let value = U256::from_be_slice(&packed[21..53]);
+        let len = usize::try_from(U256::from_be_slice(&packed[53..85])).ok()?;
+        let data = packed.get(85..85usize.checked_add(len)?)?;
+        txs.push((to, value, data.to_vec()));
+        packed = &packed[85 + len..];
+    }
+    Some(txs)
+}
+
+/// A call embedded in another call's arguments.
+struct Embedded {
+    target: Option<String>,
+    value: Option<String>,
+    data: Vec<u8>,
+    /// Paired with an explicit target, so worth showing even if undecodable
+    explicit: bool,
+}
+
+/// Collects `bytes` arguments that look like calldata. Tuples with exactly one
+/// address (Multicall3 `Call`) target that address; other bytes target `default`.
+fn collect_embedded(value: &DynSolValue, default: Option<&str>, out: &mut Vec<Embedded>) {
+    if let Some(items) = value.as_tuple() {
+        let addresses: Vec<Address> = items.iter().filter_map(|v| v.as_address()).collect();
+        let has_calldata = items
+            .iter()
+            .any(|v| v.as_bytes().is_some_and(|b| b.len() >= 4));
+        if let [address] = addresses[..]
+            && has_calldata
+        {
+            for bytes in items.iter().filter_map(|v| v.as_bytes()) {
+                out.push(Embedded {
+                    target: Some(address.to_checksum(None)),
+                    value: None,
+                    data: bytes.to_vec(),
+                    explicit: true,
+                });
+            }
+        } else {
+            for item in items {
+                collect_embedded(item, default, out);
+            }
+        }
+    } else if let Some(items) = value.as_array().or_else(|| value.as_fixed_array()) {
+        for item in items {
+            collect_embedded(item, default, out);
+        }
+    } else if let Some(bytes) = value.as_bytes()
+        && bytes.len() >= 4
+    {
+        out.push(Embedded {
+            target: default.map(str::to_string),
+            value: None,
+            data: bytes.to_vec(),
+            explicit: false,
+        });
+    }
+}
+
+/// Decodes calldata into a call tree, caching ABIs per target.
+pub struct CalldataDecoder<'a> {
+    resolver: &'a dyn AbiResolver,
+    chain_id: u32,
+    abis: Mutex<HashMap<String, Option<Arc<Value>>>>,
+}
+
+impl<'a> CalldataDecoder<'a> {
+    pub fn new(resolver: &'a dyn AbiResolver, chain_id: u32) -> Self {
+        Self {
+            resolver,
+            chain_id,
+            abis: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn decode(&self, target: Option<&str>, calldata: &[u8]) -> DecodedCall {
+        self.decode_at(target.map(str::to_string), calldata.to_vec(), 0)
+            .await
+    }
+
+    async fn abi_for(&self, target: &str) -> Option<Arc<Value>> {
+        let key = target.to_lowercase();
+        if let Some(cached) = self.abis.lock().unwrap().get(&key) {
+            return cached.clone();
+        }
+        let abi = self
+            .resolver
+            .resolve_abi(self.chain_id, &key)
+            .await
+            .map(Arc::new);
+        self.abis.lock().unwrap().insert(key, abi.clone());
+        abi
+    }
+
+    fn decode_at(
+        &self,
+        target: Option<String>,
+        data: Vec<u8>,
+        depth: usize,
+    ) -> BoxFuture<'_, DecodedCall> {
+        Box::pin(async move {
+            if data.is_empty() {
+                return DecodedCall {
+                    target,
+                    value: None,
+                    selector: None,
+                    function: None,
+                    source: DecodeSource::NativeTransfer,
+                    args: Vec::new(),
+                    calls: Vec::new(),
+                    raw: None,
+                };
+            }
+            let Some(selector) = data.get(..4).and_then(|s| <[u8; 4]>::try_from(s).ok()) else {
+                return DecodedCall::unknown(target, &data);
+            };
+
+            let mut candidates: Vec<(String, Vec<Option<String>>, DecodeSource)> = Vec::new();
+            if let Some(address) = &target
+                && let Some(abi) = self.abi_for(address).await
+                && let Some((signature, names)) = find_in_abi(&abi, selector)
+            {
+                candidates.push((signature, names, DecodeSource::Abi));
+            }
+            for signature in SELECTOR_DB.get(&selector).into_iter().flatten() {
+                candidates.push((
+                    signature.to_string(),
+                    Vec::new(),
+                    DecodeSource::SelectorDatabase,
+                ));
+            }
+
+            for (signature, names, source) in candidates {
+                let Some((types, values)) = decode_params(&signature, &data[4..]) else {
+                    continue;
+                };
+                let calls = if depth < MAX_DEPTH {
+                    self.nested_calls(&signature, &types, &values, target.as_deref(), depth)
+                        .await
+                } else {
+                    Vec::new()
+                };
+                return DecodedCall {
+                    target,
+                    value: None,
+                    selector: Some(hex::encode_prefixed(selector)),
+                    args: to_args(&types, &values, &names),
+                    function: Some(signature),
+                    source,
+                    calls,
+                    raw: None,
+                };
+            }
+
+            DecodedCall::unknown(target, &data)
+        })
+    }
+
+    async fn nested_calls(
+        &self,
+        signature: &str,
+        types: &[DynSolType],
+        values: &[DynSolValue],
+        target: Option<&str>,
+        depth: usize,
+    ) -> Vec<DecodedCall> {
+        // Universal Router: one command byte per ABI-encoded input
+        if signature.starts_with("execute(bytes,bytes[]")
+            && let Some(commands) = values[0].as_bytes()
+            && let Some(inputs) = values[1].as_array()
+            && commands.len() == inputs.len()
+        {
+            return commands
+                .iter()
+                .zip(inputs)
+                .map(|(command, input)| {
+                    decode_router_command(
+                        target.map(str::to_string),
+                        *command,
+                        input.as_bytes().unwrap_or_default(),
+                    )
+                })
+                .collect();
+        }
+
+        let mut embedded = Vec::new();
+        if signature == "multiSend(bytes)"
+            && let Some(txs) = values[0].as_bytes().and_then(parse_multisend)
+        {
+            // Safe MultiSend: packed (operation, to, value, data) entries
+            embedded.extend(txs.into_iter().map(|(to, value, data)| Embedded {
+                target: Some(to.to_checksum(None)),
+                value: Some(value.to_string()),
+                data,
+                explicit: true,
+            }));
+        } else if let [
+            DynSolType::Address,
+            DynSolType::Uint(256),
+            DynSolType::Bytes,
+            ..,
+        ] = types
+            && let (Some(to), Some((value, _)), Some(data)) = (
+                values[0].as_address(),
+                values[1].as_uint(),
+                values[2].as_bytes(),
+            )
+        {
+            // (to, value, data, ...) executors: Safe execTransaction, smart accounts
+            embedded.push(Embedded {
+                target: Some(to.to_checksum(None)),
+                value: Some(value.to_string()),
+                data: data.to_vec(),
+                explicit: true,
+            });
+        } else {
+            for value in values {
+                collect_embedded(value, target, &mut embedded);
+            }
+        }
+
+        let mut calls = Vec::with_capacity(embedded.len());
+        for item in embedded {
+            let mut call = self.decode_at(item.target, item.data, depth + 1).await;
+            if call.source == DecodeSource::Unknown && !item.explicit {
+                continue;
+            }
+            call.value = item.value;
+            calls.push(call);
+        }
+        calls
+    }
+}
+
+/// Decodes `calldata` sent to `target` on `chain_id` into a call tree.
+pub async fn decode_calldata(
+    resolver: &dyn AbiResolver,
+    chain_id: u32,
+    target: Option<&str>,
+    calldata: &[u8],
+) -> DecodedCall {
+    CalldataDecoder::new(resolver, chain_id)
+        .decode(target, calldata)
+        .await
+}
+
+// ============================================================================
+// Tool
+// ============================================================================
+
+/// Tool for decoding calldata into a human-readable call tree
+#[derive(Debug, Clone)]
+pub struct DecodeCalldata;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecodeCalldataArgs {
+    /// Hex calldata (0x-prefixed)
+    pub calldata: String,
+    /// Contract the calldata is sent to
+    pub to: String,
+    pub chain_id: u32,
+}
+
+impl AomiToolArgs for DecodeCalldataArgs {
+    fn schema() -> serde_json::Value {
+        with_topic(json!({
+            "type": "object",
+            "properties": {
+                "calldata": {
+                    "type": "string",
+                    "description": "Hex-encoded calldata (0x-prefixed), e.g. a transaction's input field"
+                },
+                "to": {
+                    "type": "string",
+                    "description": "Address of the contract the calldata is sent to (used to look up its ABI)"
+                },
+                "chain_id": {
+                    "type": "number",
+                    "description": "Chain ID of the target contract (e.g. 1 for Ethereum)"
+                }
+            },
+            "required": ["calldata", "to", "chain_id"]
+        }))
+    }
+}
+
+pub async fn execute_decode_calldata(
+    args: DecodeCalldataArgs,
+) -> Result<serde_json::Value, ToolError> {
+    debug!("decode_calldata tool called with args: {:?}", args);
+
+    let calldata = hex::decode(args.calldata.trim().trim_start_matches("0x")).map_err(|e| {
+        ToolError::ToolCallError(format!("Invalid calldata: must be hex ({e})").into())
+    })?;
+
+    run_sync(async move {
+        let resolver = ContractAbiResolver::from_env().await;
+        let decoded = decode_calldata(&resolver, args.chain_id, Some(&args.to), &calldata).await;
+        Ok(json!({
+            "explanation": decoded.explain(),
+            "decoded": decoded,
+        }))
+    })
+}
+
+impl AomiTool for DecodeCalldata {
+    const NAME: &'static str = "decode_calldata";
+
+    type Args = DecodeCalldataArgs;
+    type Output = serde_json::Value;
+    type Error = ToolError;
+
+    fn description(&self) -> &'static str {
+        "Decode transaction calldata into the called function and its arguments, including nested calls (multicall, Safe, Universal Router). Inverse of encode_function_call."
+    }
+
+    fn read_only(&self) -> bool {
+        true
+    }
+
+    fn run_sync(
+        &self,
+        _ctx: ToolCallCtx,
+        args: Self::Args,
+    ) -> impl std::future::Future<Output = eyre::Result<serde_json::Value>> + Send {
+        async move {
+            execute_decode_calldata(args)
+                .await
+                .map_err(|e| eyre::eyre!(e.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECIPIENT: &str = "0x742d35Cc6634C0532925a3b844Bc9e7595f33749";
+    const USDC: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+
+    fn encode(signature: &str, values: Vec<DynSolValue>) -> Vec<u8> {
+        let mut data = selector_of(signature).to_vec();
+        if !values.is_empty() {
+            data.extend(DynSolValue::Tuple(values).abi_encode_params());
+        }
+        data
+    }
+
+    fn transfer(amount: u64) -> Vec<u8> {
+        encode(
+            "transfer(address,uint256)",
+            vec![
+                DynSolValue::Address(RECIPIENT.parse().unwrap()),
+                DynSolValue::Uint(U256::from(amount), 256),
+            ],
+        )
+    }
+
+    struct StaticAbi(Value);
+
+    #[async_trait]
+    impl AbiResolver for StaticAbi {
+        async fn resolve_abi(&self, _chain_id: u32, _address: &str) -> Option<Value> {
+            Some(self.0.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_decode_with_selector_db() {
+        let decoded = decode_calldata(&SelectorOnly, 1, Some(USDC), &transfer(1_000)).await;
+
+        assert_eq!(decoded.source, DecodeSource::SelectorDatabase);
+        assert_eq!(decoded.selector.as_deref(), Some("0xa9059cbb"));
+        assert_eq!(
+            decoded.function.as_deref(),
+            Some("transfer(address,uint256)")
+        );
+        let recipient: Address = RECIPIENT.parse().unwrap();
+        assert_eq!(decoded.args[0].value, json!(recipient.to_checksum(None)));
+        assert_eq!(decoded.args[1].value, json!("1000"));
+        assert!(decoded.calls.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_decode_with_abi_names() {
+        let abi = json!([{
+            "type": "function",
+            "name": "transfer",
+            "inputs": [
+                { "name": "to", "type": "address" },
+                { "name": "amount", "type": "uint256" }
+            ]
+        }]);
+        let decoded = decode_calldata(&StaticAbi(abi), 1, Some(USDC), &transfer(5)).await;
+
+        assert_eq!(decoded.source, DecodeSource::Abi);
+        assert_eq!(decoded.args[0].name.as_deref(), Some("to"));
+        assert_eq!(decoded.args[1].name.as_deref(), Some("amount"));
+        assert!(decoded.explain().starts_with("transfer(to="));
+    }
+
+    #[tokio::test]
+    async fn test_decode_nested_multicall() {
+        let data = encode(
+            "multicall(bytes[])",
+            vec![DynSolValue::Array(vec![
+                DynSolValue::Bytes(transfer(1)),
+                DynSolValue::Bytes(transfer(2)),
+            ])],
+        );
+        let decoded = decode_calldata(&SelectorOnly, 1, Some(USDC), &data).await;
+
+        assert_eq!(decoded.name(), Some("multicall"));
+        assert_eq!(decoded.calls.len(), 2);
+        assert_eq!(decoded.calls[1].name(), Some("transfer"));
+        assert_eq!(decoded.calls[1].target.as_deref(), Some(USDC));
+        assert_eq!(decoded.calls[1].args[1].value, json!("2"));
+    }
+
+    #[tokio::test]
+    async fn test_decode_safe_multisend() {
+        let mut packed = Vec::new();
+        for (to, value, data) in [(USDC, 0u64, transfer(7)), (RECIPIENT, 42, Vec::new())] {
+            packed.push(0u8);
+            packed.extend(to.parse::<Address>().unwrap().as_slice());
+            packed.extend(U256::from(value).to_be_bytes::<32>());
+            packed.extend(U256::from(data.len()).to_be_bytes::<32>());
+            packed.extend(data);
+        }
+        let multisend = encode("multiSend(bytes)", vec![DynSolValue::Bytes(packed)]);
+        let multisend_addr: Address = "0x40A2aCCbd92BCA938b02010E17A5b8929b49130D"
+            .parse()
+            .unwrap();
+        let exec = encode(
+            "execTransaction(address,uint256,bytes,uint8,uint256,uint256,uint256,address,address,bytes)",
+            vec![
+                DynSolValue::Address(multisend_addr),
+                DynSolValue::Uint(U256::ZERO, 256),
+                DynSolValue::Bytes(multisend),
+                DynSolValue::Uint(U256::from(1), 8),
+                DynSolValue::Uint(U256::ZERO, 256),
+                DynSolValue::Uint(U256::ZERO, 256),
+                DynSolValue::Uint(U256::ZERO, 256),
+                DynSolValue::Address(Address::ZERO),
+                DynSolValue::Address(Address::ZERO),
+                DynSolValue::Bytes(vec![0u8; 65]),
+            ],
+        );
+
+        let decoded = decode_calldata(&SelectorOnly, 1, Some(RECIPIENT), &exec).await;
+
+        assert_eq!(decoded.name(), Some("execTransaction"));
+        let multisend = &decoded.calls[0];
+        assert_eq!(multisend.name(), Some("multiSend"));
+        assert_eq!(multisend.target, Some(multisend_addr.to_checksum(None)));
+        assert_eq!(multisend.calls.len(), 2);
+        assert_eq!(multisend.calls[0].name(), Some("transfer"));
+        assert_eq!(multisend.calls[1].source, DecodeSource::NativeTransfer);
+        assert_eq!(multisend.calls[1].value.as_deref(), Some("42"));
+    }
+
+    #[tokio::test]
+    async fn test_decode_universal_router() {
+        let weth: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+            .parse()
+            .unwrap();
+        let usdc: Address = USDC.parse().unwrap();
+        let recipient: Address = RECIPIENT.parse().unwrap();
+
+        let wrap = DynSolValue::Tuple(vec![
+            DynSolValue::Address(recipient),
+            DynSolValue::Uint(U256::from(100), 256),
+        ])
+        .abi_encode_params();
+        let mut path = weth.to_vec();
+        path.extend([0x00, 0x01, 0xf4]);
+        path.extend(usdc.as_slice());
+        let swap = DynSolValue::Tuple(vec![
+            DynSolValue::Address(recipient),
+            DynSolValue::Uint(U256::from(100), 256),
+            DynSolValue::Uint(U256::from(1), 256),
+            DynSolValue::Bytes(path),
+            DynSolValue::Bool(false),
+        ])
+        .abi_encode_params();
+
+        let data = encode(
+            "execute(bytes,bytes[],uint256)",
+            vec![
+                DynSolValue::Bytes(vec![0x0b, 0x00]),
+                DynSolValue::Array(vec![DynSolValue::Bytes(wrap), DynSolValue::Bytes(swap)]),
+                DynSolValue::Uint(U256::from(1_716_302_400u64), 256),
+            ],
+        );
+        let decoded = decode_calldata(&SelectorOnly, 1, None, &data).await;
+
+        assert_eq!(decoded.calls.len(), 2);
+        assert_eq!(decoded.calls[0].name(), Some("WRAP_ETH"));
+        assert_eq!(decoded.calls[1].source, DecodeSource::RouterCommand);
+        assert_eq!(decoded.calls[1].name(), Some("V3_SWAP_EXACT_IN"));
+        assert_eq!(
+            decoded.calls[1].args[3].value,
+            json!([weth.to_checksum(None), 500, usdc.to_checksum(None)])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_decode_unknown_and_empty() {
+        let decoded = decode_calldata(&SelectorOnly, 1, None, &[0xde, 0xad, 0xbe, 0xef, 1]).await;
+        assert_eq!(decoded.source, DecodeSource::Unknown);
+        assert_eq!(decoded.selector.as_deref(), Some("0xdeadbeef"));
+        assert_eq!(decoded.raw.as_deref(), Some("0xdeadbeef01"));
+
+        let empty = decode_calldata(&SelectorOnly, 1, Some(RECIPIENT), &[]).await;
+        assert_eq!(empty.source, DecodeSource::NativeTransfer);
+    }
+
+    #[test]
+    fn test_known_signature() {
+        assert_eq!(
+            known_signature("0xa9059cbb0000"),
+            Some("transfer(address,uint256)")
+        );
+        assert_eq!(known_signature("0x"), None);
+        assert_eq!(known_signature("0xdeadbeef"), None);
+    }
+
+    #[test]
+    fn test_canonical_type_expands_tuples() {
+        let param = json!({
+            "type": "tuple[]",
+            "components": [
+                { "type": "address" },
+                { "type": "tuple", "components": [{ "type": "uint256" }, { "type": "bytes" }] }
+            ]
+        });
+        assert_eq!(
+            canonical_type(&param).as_deref(),
+            Some("(address,(uint256,bytes))[]")
+        );
+    }
+}
@@ -298,6 +298,10 @@ impl AomiTool for EncodeFunctionCall {
         "Encodes a function call into hex calldata for any contract function."
     }
 
+    fn read_only(&self) -> bool {
+        true
+    }
+
     fn run_sync(
         &self,
         _ctx: ToolCallCtx,
@@ -11,6 +11,7 @@ use std::future::Future;
 use tokio::task;
 use tracing::debug;
 
+use super::abi_decoder::known_signature;
 use super::gateway::get_gateway;
 
 // ============================================================================
@@ -235,6 +236,7 @@ pub async fn execute_get_account_transaction_history(
                     "gas_used": tx.gas_used,
                     "is_error": tx.is_error,
                     "input": tx.input,
+                    "method": known_signature(&tx.input),
                     "contract_address": tx.contract_address,
                 })
             })
@@ -262,6 +264,10 @@ impl AomiTool for GetAccountInfo {
         "Fetch account information (balance and nonce)."
     }
 
+    fn read_only(&self) -> bool {
+        true
+    }
+
     fn run_sync(
         &self,
         _ctx: ToolCallCtx,
@@ -286,6 +292,10 @@ impl AomiTool for GetAccountTransactionHistory {
         "Fetch transaction history with smart database caching."
     }
 
+    fn read_only(&self) -> bool {
+        true
+    }
+
     fn run_sync(
         &self,
         _ctx: ToolCallCtx,
@@ -102,6 +102,12 @@ pub trait ERC20 {
 
     /// Fetch the token name by calling name() on the contract
     async fn get_name(&self, address: &str) -> Option<String>;
+
+    /// Fetch the token decimals by calling decimals() on the contract
+    async fn get_decimals(&self, address: &str) -> Option<u8>;
+
+    /// Fetch a holder's raw balance by calling balanceOf(holder) on the contract
+    async fn get_balance_of(&self, address: &str, holder: &str) -> Option<U256>;
 }
 
 #[async_trait]
@@ -179,6 +185,54 @@ impl ERC20 for CastClient {
             _ => None,
         }
     }
+
+    async fn get_decimals(&self, address: &str) -> Option<u8> {
+        use alloy::dyn_abi::{DynSolType, DynSolValue};
+
+        let bytes = self.erc20_call(address, "decimals()(uint8)", &[]).await?;
+        match DynSolType::Uint(8).abi_decode(&bytes) {
+            Ok(DynSolValue::Uint(value, _)) => u8::try_from(value).ok(),
+            _ => None,
+        }
+    }
+
+    async fn get_balance_of(&self, address: &str, holder: &str) -> Option<U256> {
+        use alloy::dyn_abi::{DynSolType, DynSolValue};
+
+        let bytes = self
+            .erc20_call(address, "balanceOf(address)(uint256)", &[holder])
+            .await?;
+        match DynSolType::Uint(256).abi_decode(&bytes) {
+            Ok(DynSolValue::Uint(value, _)) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl CastClient {
+    /// Execute a read-only ERC20 call and return the raw return data.
+    async fn erc20_call(&self, address: &str, signature: &str, args: &[&str]) -> Option<Vec<u8>> {
+        use cast::SimpleCast;
+
+        let calldata = SimpleCast::calldata_encode(signature, args).ok()?;
+        let calldata_bytes = calldata.parse::<Bytes>().ok()?;
+        let contract_addr = address.parse::<Address>().ok()?;
+
+        let tx = TransactionRequest::default()
+            .to(contract_addr)
+            .input(TransactionInput::new(calldata_bytes))
+            .with_input_and_data();
+
+        let result = self
+            .cast
+            .call(&tx.into(), None, None, None, None)
+            .await
+            .ok()?;
+
+        result
+            .strip_prefix("0x")
+            .and_then(|hex_str| hex::decode(hex_str).ok())
+    }
 }
 
 impl CastClient {
@@ -993,6 +1047,10 @@ impl AomiTool for GetAccountBalance {
         "Get account balance using Cast."
     }
 
+    fn read_only(&self) -> bool {
+        true
+    }
+
     fn run_sync(
         &self,
         _ctx: ToolCallCtx,
@@ -1018,6 +1076,10 @@ impl AomiTool for CallViewFunction {
         "Call a view function (read-only) using Cast. This performs an eth_call to read contract state without sending a transaction. Use this to validate calldata format and test if calls would succeed. The input must be 0x-prefixed hex calldata (use encode_function_call first)."
     }
 
+    fn read_only(&self) -> bool {
+        true
+    }
+
     fn run_sync(
         &self,
         _ctx: ToolCallCtx,
@@ -1043,6 +1105,10 @@ impl AomiTool for SimulateContractCall {
         "Simulate a contract call using Cast to test if a transaction would succeed before sending it. IMPORTANT: Always simulate state-changing transactions with this tool before using send_transaction_to_wallet. This validates calldata format, checks for reverts, and estimates gas. The input must be 0x-prefixed hex calldata (use encode_function_call first)."
     }
 
+    fn read_only(&self) -> bool {
+        true
+    }
+
     fn run_sync(
         &self,
         _ctx: ToolCallCtx,
@@ -1093,6 +1159,10 @@ impl AomiTool for GetContractCode {
         "Get contract bytecode using Cast."
     }
 
+    fn read_only(&self) -> bool {
+        true
+    }
+
     fn run_sync(
         &self,
         _ctx: ToolCallCtx,
@@ -1118,6 +1188,10 @@ impl AomiTool for GetContractCodeSize {
         "Get contract code size using Cast."
     }
 
+    fn read_only(&self) -> bool {
+        true
+    }
+
     fn run_sync(
         &self,
         _ctx: ToolCallCtx,
@@ -1143,6 +1217,10 @@ impl AomiTool for GetTransactionDetails {
         "Get transaction details using Cast."
     }
 
+    fn read_only(&self) -> bool {
+        true
+    }
+
     fn run_sync(
         &self,
         _ctx: ToolCallCtx,
@@ -1168,6 +1246,10 @@ impl AomiTool for GetBlockDetails {
         "Get block details using Cast."
     }
 
+    fn read_only(&self) -> bool {
+        true
+    }
+
     fn run_sync(
         &self,
         _ctx: ToolCallCtx,
@@ -23,9 +23,10 @@ use serde_json::json;
 use sqlx::any::AnyPoolOptions;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 use tracing::{debug, info, warn};
 
-use crate::{AomiTool, AomiToolArgs, ToolCallCtx, with_topic};
+use crate::{AomiTool, AomiToolArgs, CallPolicy, RetryPolicy, ToolCallCtx, with_topic};
 
 // Chain ID constants
 pub const ETHEREUM_MAINNET: u32 = 1;
@@ -513,6 +514,15 @@ pub async fn fetch_and_store_contract(
                 if should_fetch_name && let Some(name) = cast_client.get_name(&address).await {
                     contract.name = Some(name);
                 }
+
+                // symbol() + decimals() is enough to treat it as a token; storing it
+                // as ERC20 also registers it in the token registry
+                if contract.symbol.is_some()
+                    && contract.contract_type.is_none()
+                    && cast_client.get_decimals(&address).await.is_some()
+                {
+                    contract.contract_type = Some("ERC20".to_string());
+                }
             }
             Err(e) => {
                 warn!(
@@ -921,6 +931,10 @@ impl AomiTool for GetErc20Balance {
         "Fetch ERC20 token balance via Etherscan."
     }
 
+    fn read_only(&self) -> bool {
+        true
+    }
+
     fn run_sync(
         &self,
         _ctx: ToolCallCtx,
@@ -948,6 +962,15 @@ impl AomiTool for GetContractFromEtherscan {
         "Fetch and store a contract from Etherscan."
     }
 
+    fn call_policy(&self) -> CallPolicy {
+        // Storing the fetched contract is an upsert, so retrying is safe
+        CallPolicy::default()
+            .with_timeout(Duration::from_secs(30))
+            .with_retry(RetryPolicy::exponential(3, Duration::from_secs(1)))
+            .idempotent()
+            .with_upstream("etherscan")
+    }
+
     fn run_sync(
         &self,
         _ctx: ToolCallCtx,
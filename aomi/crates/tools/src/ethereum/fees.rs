@@ -0,0 +1,376 @@
+//! EIP-1559 fee oracle.
+//!
+//! Fee tiers come from `eth_feeHistory`: the next block's base fee plus the median of the
+//! recent blocks' 10th/50th/90th percentile priority fees for slow/normal/fast. Gas limits
+//! come from `eth_estimateGas`. When a [`PriceSource`] is available, costs are also reported
+//! in USD; the built-in one uses CoinGecko when `COINGECKO_API_KEY` is set, and the backend
+//! can install another with [`install_price_source`].
+
+use alloy::{
+    eips::BlockNumberOrTag,
+    primitives::{Address, Bytes, U256},
+    rpc::types::{FeeHistory, TransactionInput, TransactionRequest},
+};
+use alloy_provider::Provider;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+use crate::clients::build_http_client;
+
+static PRICE_SOURCE: OnceLock<Option<Arc<dyn PriceSource>>> = OnceLock::new();
+
+/// Recent blocks sampled for priority fees
+const FEE_HISTORY_BLOCKS: u64 = 20;
+/// Priority fee percentiles for slow, normal and fast
+const REWARD_PERCENTILES: [f64; 3] = [10.0, 50.0, 90.0];
+/// Headroom added to `eth_estimateGas`, in percent
+const GAS_LIMIT_MARGIN_PERCENT: u64 = 20;
+
+const COINGECKO_URL: &str = "https://api.coingecko.com/api/v3/simple/price";
+/// How long fetched prices are reused
+const PRICE_TTL: Duration = Duration::from_secs(60);
+
+/// Fee parameters for one speed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeeTier {
+    /// In wei; twice the base fee plus the tip, so it survives a few full blocks
+    pub max_fee_per_gas: String,
+    pub max_priority_fee_per_gas: String,
+    /// Expected cost in wei: gas limit × (base fee + tip)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_cost_wei: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_cost_usd: Option<f64>,
+}
+
+/// Slow/normal/fast fee options for a chain, and for a transaction if a gas limit is known
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeeRecommendation {
+    pub chain_id: u64,
+    /// Base fee of the next block, in wei
+    pub base_fee_per_gas: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gas_limit: Option<String>,
+    pub slow: FeeTier,
+    pub normal: FeeTier,
+    pub fast: FeeTier,
+    /// USD price of the chain's native currency used for the cost estimates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub native_usd_price: Option<f64>,
+}
+
+/// Next block's base fee and the median priority fee at each of [`REWARD_PERCENTILES`].
+/// None if the chain reports no base fee (pre-EIP-1559).
+fn fee_levels(history: &FeeHistory) -> Option<(u128, [u128; 3])> {
+    let base_fee = history.next_block_base_fee().filter(|fee| *fee > 0)?;
+
+    let rewards = history.reward.as_deref().unwrap_or_default();
+    let mut priority = [0u128; 3];
+    for (i, level) in priority.iter_mut().enumerate() {
+        // Empty blocks report zero tips and would drag the median down
+        let mut samples: Vec<u128> = rewards
+            .iter()
+            .zip(&history.gas_used_ratio)
+            .filter(|(_, ratio)| **ratio > 0.0)
+            .filter_map(|(block, _)| block.get(i).copied())
+            .collect();
+        if samples.is_empty() {
+            continue;
+        }
+        samples.sort_unstable();
+        *level = samples[samples.len() / 2];
+    }
+    Some((base_fee, priority))
+}
+
+fn fee_tier(
+    base_fee: u128,
+    priority_fee: u128,
+    gas_limit: Option<u64>,
+    usd_price: Option<f64>,
+) -> FeeTier {
+    let cost = gas_limit.map(|gas| u128::from(gas).saturating_mul(base_fee + priority_fee));
+    FeeTier {
+        max_fee_per_gas: (base_fee.saturating_mul(2) + priority_fee).to_string(),
+        max_priority_fee_per_gas: priority_fee.to_string(),
+        estimated_cost_wei: cost.map(|cost| cost.to_string()),
+        estimated_cost_usd: cost
+            .zip(usd_price)
+            .map(|(cost, price)| cost as f64 / 1e18 * price),
+    }
+}
+
+fn recommendation(
+    chain_id: u64,
+    base_fee: u128,
+    priority: [u128; 3],
+    gas_limit: Option<u64>,
+    usd_price: Option<f64>,
+) -> FeeRecommendation {
+    let [slow, normal, fast] = priority.map(|tip| fee_tier(base_fee, tip, gas_limit, usd_price));
+    FeeRecommendation {
+        chain_id,
+        base_fee_per_gas: base_fee.to_string(),
+        gas_limit: gas_limit.map(|gas| gas.to_string()),
+        slow,
+        normal,
+        fast,
+        native_usd_price: usd_price.filter(|_| gas_limit.is_some()),
+    }
+}
+
+async fn provider_for(
+    chain_id: u64,
+) -> eyre::Result<Arc<alloy_provider::RootProvider<alloy::network::AnyNetwork>>> {
+    let manager = aomi_anvil::provider_manager()
+        .await
+        .map_err(|e| eyre::eyre!("Failed to get provider manager: {}", e))?;
+    manager
+        .get_provider(Some(chain_id), None)
+        .await
+        .map_err(|e| eyre::eyre!("No provider configured for chain {}: {}", chain_id, e))
+}
+
+/// Slow/normal/fast EIP-1559 fees on `chain_id`, with costs for `gas_limit` if given.
+pub async fn recommend_fees(
+    chain_id: u64,
+    gas_limit: Option<u64>,
+) -> eyre::Result<FeeRecommendation> {
+    let provider = provider_for(chain_id).await?;
+    let history = provider
+        .get_fee_history(
+            FEE_HISTORY_BLOCKS,
+            BlockNumberOrTag::Latest,
+            &REWARD_PERCENTILES,
+        )
+        .await?;
+    let (base_fee, priority) = fee_levels(&history)
+        .ok_or_else(|| eyre::eyre!("Chain {} does not report EIP-1559 base fees", chain_id))?;
+
+    let usd_price = match (gas_limit, price_source()) {
+        (Some(_), Some(source)) => source.native_usd_price(chain_id).await,
+        _ => None,
+    };
+    Ok(recommendation(
+        chain_id, base_fee, priority, gas_limit, usd_price,
+    ))
+}
+
+/// `eth_estimateGas` for the call plus a safety margin.
+pub async fn estimate_gas(
+    chain_id: u64,
+    from: &str,
+    to: &str,
+    value: &str,
+    data: &str,
+) -> eyre::Result<u64> {
+    let tx = TransactionRequest::default()
+        .from(Address::from_str(from)?)
+        .to(Address::from_str(to)?)
+        .value(U256::from_str(value)?)
+        .input(TransactionInput::new(Bytes::from_str(
+            if data.is_empty() { "0x" } else { data },
+        )?));
+
+    let provider = provider_for(chain_id).await?;
+    let estimate = provider.estimate_gas(tx.into()).await?;
+    Ok(estimate + estimate * GAS_LIMIT_MARGIN_PERCENT / 100)
+}
+
+/// Fee recommendation for a wallet transaction. `gas_limit` is estimated when not given;
+/// if estimation fails (e.g. the call reverts) the fees are returned without costs.
+pub async fn quote_transaction(
+    chain_id: u64,
+    from: &str,
+    to: &str,
+    value: &str,
+    data: &str,
+    gas_limit: Option<&str>,
+) -> eyre::Result<FeeRecommendation> {
+    let gas_limit = match gas_limit {
+        Some(gas) => Some(gas.parse::<u64>()?),
+        None => match estimate_gas(chain_id, from, to, value, data).await {
+            Ok(gas) => Some(gas),
+            Err(e) => {
+                warn!(chain_id, error = %e, "Gas estimation failed");
+                None
+            }
+        },
+    };
+    recommend_fees(chain_id, gas_limit).await
+}
+
+// ============================================================================
+// Price sources
+// ============================================================================
+
+/// USD prices of native currencies
+#[async_trait]
+pub trait PriceSource: Send + Sync {
+    /// Price of one unit of `chain_id`'s native currency; None if unknown
+    async fn native_usd_price(&self, chain_id: u64) -> Option<f64>;
+}
+
+/// Uses `source` for USD cost estimates. Returns false if a source was already installed
+/// or the default one was already used.
+pub fn install_price_source(source: Arc<dyn PriceSource>) -> bool {
+    PRICE_SOURCE.set(Some(source)).is_ok()
+}
+
+/// The installed price source, else CoinGecko if `COINGECKO_API_KEY` is set to a non-blank key.
+pub fn price_source() -> Option<Arc<dyn PriceSource>> {
+    PRICE_SOURCE
+        .get_or_init(|| {
+            let api_key = coingecko_api_key(std::env::var("COINGECKO_API_KEY").ok())?;
+            Some(Arc::new(CoinGeckoPrices::new(api_key)) as Arc<dyn PriceSource>)
+        })
+        .clone()
+}
+
+/// `.env.template` ships an empty `COINGECKO_API_KEY=`, which must not count as configured
+fn coingecko_api_key(value: Option<String>) -> Option<String> {
+    value
+        .map(|key| key.trim().to_string())
+        .filter(|key| !key.is_empty())
+}
+
+/// CoinGecko ids of the native currencies of supported mainnets
+fn coingecko_id(chain_id: u64) -> Option<&'static str> {
+    match chain_id {
+        1 | 10 | 8453 | 42161 => Some("ethereum"),
+        137 => Some("polygon-ecosystem-token"),
+        _ => None,
+    }
+}
+
+/// CoinGecko simple price API, with prices reused for [`PRICE_TTL`]
+pub struct CoinGeckoPrices {
+    client: reqwest::Client,
+    api_key: String,
+    cache: Mutex<HashMap<&'static str, (Instant, f64)>>,
+}
+
+impl CoinGeckoPrices {
+    pub fn new(api_key: String) -> Self {
+        Self {
+            client: build_http_client(),
+            api_key,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    async fn fetch(&self, id: &str) -> eyre::Result<f64> {
+        let response: serde_json::Value = self
+            .client
+            .get(COINGECKO_URL)
+            .query(&[("ids", id), ("vs_currencies", "usd")])
+            .header("x-cg-demo-api-key", &self.api_key)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        response[id]["usd"]
+            .as_f64()
+            .ok_or_else(|| eyre::eyre!("No USD price for {} in CoinGecko response", id))
+    }
+}
+
+#[async_trait]
+impl PriceSource for CoinGeckoPrices {
+    async fn native_usd_price(&self, chain_id: u64) -> Option<f64> {
+        let id = coingecko_id(chain_id)?;
+        if let Some((fetched_at, price)) = self.cache.lock().unwrap().get(id)
+            && fetched_at.elapsed() < PRICE_TTL
+        {
+            return Some(*price);
+        }
+
+        match self.fetch(id).await {
+            Ok(price) => {
+                self.cache
+                    .lock()
+                    .unwrap()
+                    .insert(id, (Instant::now(), price));
+                Some(price)
+            }
+            Err(e) => {
+                debug!(chain_id, error = %e, "Failed to fetch native price");
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(base_fees: Vec<u128>, ratios: Vec<f64>, rewards: Vec<[u128; 3]>) -> FeeHistory {
+        FeeHistory {
+            base_fee_per_gas: base_fees,
+            gas_used_ratio: ratios,
+            reward: Some(rewards.into_iter().map(Vec::from).collect()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_fee_levels_use_next_base_fee_and_median_tips() {
+        let history = history(
+            vec![10, 11, 12, 13],
+            vec![0.5, 0.0, 0.9],
+            vec![[1, 2, 3], [0, 0, 0], [3, 4, 9]],
+        );
+        // The empty block is ignored; the median of two samples takes the upper one
+        assert_eq!(fee_levels(&history), Some((13, [3, 4, 9])));
+
+        let legacy = history(vec![0, 0], vec![0.5], vec![[1, 1, 1]]);
+        assert_eq!(fee_levels(&legacy), None);
+    }
+
+    #[test]
+    fn test_recommendation_costs() {
+        let gwei = 1_000_000_000u128;
+        let rec = recommendation(
+            1,
+            20 * gwei,
+            [gwei, 2 * gwei, 5 * gwei],
+            Some(21_000),
+            Some(2_000.0),
+        );
+
+        assert_eq!(rec.normal.max_fee_per_gas, (42 * gwei).to_string());
+        assert_eq!(rec.normal.max_priority_fee_per_gas, (2 * gwei).to_string());
+        // 21,000 gas at 22 gwei
+        assert_eq!(
+            rec.normal.estimated_cost_wei.as_deref(),
+            Some("462000000000000")
+        );
+        let usd = rec.normal.estimated_cost_usd.unwrap();
+        assert!((usd - 0.924).abs() < 1e-9, "{usd}");
+        assert!(rec.slow.max_fee_per_gas < rec.fast.max_fee_per_gas);
+
+        let without_gas = recommendation(1, 20 * gwei, [gwei; 3], None, Some(2_000.0));
+        assert_eq!(without_gas.normal.estimated_cost_wei, None);
+        assert_eq!(without_gas.native_usd_price, None);
+        let json = serde_json::to_value(&without_gas).unwrap();
+        assert!(json.get("gas_limit").is_none());
+    }
+
+    #[test]
+    fn test_blank_coingecko_key_is_unset() {
+        assert_eq!(coingecko_api_key(None), None);
+        assert_eq!(coingecko_api_key(Some(String::new())), None);
+        assert_eq!(coingecko_api_key(Some("  \t".to_string())), None);
+        assert_eq!(
+            coingecko_api_key(Some(" CG-key ".to_string())).as_deref(),
+            Some("CG-key")
+        );
+    }
+}
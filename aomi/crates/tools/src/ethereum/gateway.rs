@@ -21,6 +21,10 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::OnceCell;
 
+use super::abi_decoder::DecodedCall;
+use super::fees::FeeRecommendation;
+use super::policy::{PolicyScope, PolicyTransaction, PolicyViolation, policy_engine};
+use super::simulation::TransactionSimulation;
 use crate::db::{Contract, Transaction};
 
 // ============================================================================
@@ -57,6 +61,35 @@ pub enum WalletTransactionResult {
         gas: Option<String>,
         description: String,
         timestamp: String,
+        /// Preview of the transaction executed on a fork of the target chain
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        simulation: Option<TransactionSimulation>,
+        /// Decoded call tree of `data`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        decoded: Option<DecodedCall>,
+        /// EIP-1559 fee caps from the normal tier of `fees`
+        #[serde(
+            rename = "maxFeePerGas",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        max_fee_per_gas: Option<String>,
+        #[serde(
+            rename = "maxPriorityFeePerGas",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        max_priority_fee_per_gas: Option<String>,
+        /// Slow/normal/fast fee options with cost estimates
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        fees: Option<FeeRecommendation>,
+    },
+    /// Transaction blocked by the session's policy and never sent to the wallet
+    #[serde(rename = "rejected")]
+    Rejected {
+        to: String,
+        value: String,
+        violations: Vec<PolicyViolation>,
     },
 }
 
@@ -136,10 +169,66 @@ pub trait EvmGateway: Send + Sync {
 
     /// Send a transaction to the user's wallet for signing.
     ///
+    /// The transaction is first checked against the policy for `scope`; any
+    /// violation returns `Rejected` without reaching the wallet. Otherwise this
+    /// delegates to [`EvmGateway::submit_transaction_to_wallet`].
+    #[allow(clippy::too_many_arguments)]
+    async fn send_transaction_to_wallet(
+        &self,
+        scope: &PolicyScope,
+        chain_id: Option<u64>,
+        from: &str,
+        to: &str,
+        value: &str,
+        data: &str,
+        gas_limit: Option<&str>,
+        description: &str,
+    ) -> eyre::Result<WalletTransactionResult> {
+        let engine = policy_engine()?;
+        let tx = PolicyTransaction {
+            chain_id,
+            to,
+            value,
+            data,
+        };
+
+        let mut violations = engine.evaluate(scope, &tx);
+        if violations.is_empty()
+            && let Err(violation) = engine.reserve_spend(scope, value)
+        {
+            violations.push(violation);
+        }
+        if !violations.is_empty() {
+            return Ok(WalletTransactionResult::Rejected {
+                to: to.to_string(),
+                value: value.to_string(),
+                violations,
+            });
+        }
+
+        let result = self
+            .submit_transaction_to_wallet(from, to, value, data, gas_limit, description)
+            .await;
+        match &result {
+            // Pending requests keep their reservation until the wallet answers
+            Ok(WalletTransactionResult::PendingApproval { .. }) => {}
+            Ok(WalletTransactionResult::Confirmed { .. }) => {
+                engine.settle_spend(scope, Some(value), true)
+            }
+            Ok(WalletTransactionResult::Rejected { .. }) | Err(_) => {
+                engine.release_spend(scope, value)
+            }
+        }
+        result
+    }
+
+    /// Hand a policy-approved transaction to the user's wallet.
+    ///
     /// In production mode, this returns `PendingApproval` for the frontend to handle.
     /// In eval-test mode with an autosign wallet, this executes the transaction
-    /// directly and returns `Confirmed`.
-    async fn send_transaction_to_wallet(
+    /// directly and returns `Confirmed`. Callers should go through
+    /// [`EvmGateway::send_transaction_to_wallet`] so policies are enforced.
+    async fn submit_transaction_to_wallet(
         &self,
         from: &str,
         to: &str,
@@ -243,10 +332,32 @@ mod tests {
             gas: Some("21000".to_string()),
             description: "Test transaction".to_string(),
             timestamp: "2024-01-01T00:00:00Z".to_string(),
+            simulation: None,
+            decoded: None,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            fees: None,
         };
 
         let json = serde_json::to_string(&pending).unwrap();
         assert!(json.contains("\"status\":\"pending_approval\""));
         assert!(json.contains("\"description\":\"Test transaction\""));
+        assert!(!json.contains("simulation"));
+        assert!(!json.contains("decoded"));
+        assert!(!json.contains("maxFeePerGas"));
+
+        let rejected = WalletTransactionResult::Rejected {
+            to: "0xdef".to_string(),
+            value: "5".to_string(),
+            violations: vec![PolicyViolation::MaxValueExceeded {
+                value: "5".to_string(),
+                max: "1".to_string(),
+                message: "too much".to_string(),
+            }],
+        };
+
+        let json = serde_json::to_string(&rejected).unwrap();
+        assert!(json.contains("\"status\":\"rejected\""));
+        assert!(json.contains("\"rule\":\"max_value_exceeded\""));
     }
 }
@@ -261,7 +261,7 @@ impl EvmGateway for LocalGateway {
     // Wallet Transactions
     // =========================================================================
 
-    async fn send_transaction_to_wallet(
+    async fn submit_transaction_to_wallet(
         &self,
         from: &str,
         to: &str,
@@ -328,6 +328,11 @@ impl EvmGateway for LocalGateway {
             gas: gas_limit.map(String::from),
             description: description.to_string(),
             timestamp: chrono::Utc::now().to_rfc3339(),
+            simulation: None,
+            decoded: None,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            fees: None,
         })
     }
 
@@ -0,0 +1,636 @@
+//! Decoded event log queries.
+//!
+//! The event is given by name or signature and resolved against the contract's ABI (contract
+//! store, then Etherscan; implementation ABIs are merged in for proxies), which supplies
+//! parameter names and which parameters are indexed. Indexed-argument filters become topic
+//! filters, and the block range is fetched in fixed-size batches since many RPC endpoints
+//! cap the range of a single `eth_getLogs`.
+
+use alloy::{
+    dyn_abi::{DynSolType, DynSolValue, EventExt, Specifier},
+    json_abi::{Event, JsonAbi},
+    primitives::{Address, B256, keccak256},
+    rpc::types::{Filter, Log},
+};
+use alloy_provider::Provider;
+use rig::tool::ToolError;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::future::Future;
+use tokio::sync::mpsc;
+use tracing::{debug, info};
+
+use super::abi_decoder::value_to_json;
+use crate::cast::tool_error;
+use crate::db_tools::get_contract_abi;
+use crate::{AomiTool, AomiToolArgs, ToolCallCtx, with_topic};
+
+/// Blocks per `eth_getLogs` request; some endpoints reject wider ranges
+const BATCH_SIZE: u64 = 1_000;
+/// Range searched when no start block is given
+const DEFAULT_BLOCK_RANGE: u64 = 10_000;
+/// Widest range one call may search
+const MAX_BLOCK_RANGE: u64 = 100_000;
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 200;
+
+/// Position of a log in the chain, `(block_number, log_index)`
+type LogPosition = (u64, u64);
+
+fn position(log: &Log) -> LogPosition {
+    (
+        log.block_number.unwrap_or_default(),
+        log.log_index.unwrap_or_default(),
+    )
+}
+
+/// Cursors are the position of the first log not yet returned, as `block:log_index`
+fn parse_cursor(cursor: &str) -> Result<LogPosition, ToolError> {
+    cursor
+        .split_once(':')
+        .and_then(|(block, index)| Some((block.parse().ok()?, index.parse().ok()?)))
+        .ok_or_else(|| tool_error(format!("Invalid cursor '{cursor}'")))
+}
+
+fn format_cursor((block, index): LogPosition) -> String {
+    format!("{block}:{index}")
+}
+
+/// Picks the event `spec` refers to: a name looked up in `abi`, or a signature such as
+/// `Transfer(address indexed from, address indexed to, uint256 value)`. A signature that
+/// matches an ABI event takes the ABI's parameter names and indexed flags.
+fn resolve_event(spec: &str, abi: Option<&JsonAbi>) -> Result<Event, ToolError> {
+    let spec = spec.trim();
+    let events = || abi.into_iter().flat_map(|abi| abi.events());
+
+    if spec.contains('(') {
+        let parsed = Event::parse(spec)
+            .map_err(|e| tool_error(format!("Invalid event signature '{spec}': {e}")))?;
+        let selector = parsed.selector();
+        return Ok(events()
+            .find(|event| !event.anonymous && event.selector() == selector)
+            .cloned()
+            .unwrap_or(parsed));
+    }
+
+    let Some(abi) = abi else {
+        return Err(tool_error(format!(
+            "No ABI found for the contract, so event '{spec}' cannot be looked up by name. Pass the full signature with indexed parameters, e.g. \"Transfer(address indexed from, address indexed to, uint256 value)\""
+        )));
+    };
+    match abi.events.get(spec).map(Vec::as_slice) {
+        Some([event]) => Ok(event.clone()),
+        Some(overloads) => Err(tool_error(format!(
+            "Event '{spec}' is overloaded; pass one of these signatures: {}",
+            overloads
+                .iter()
+                .map(Event::full_signature)
+                .collect::<Vec<_>>()
+                .join("; ")
+        ))),
+        None => {
+            let names: Vec<&str> = abi.events.keys().map(String::as_str).collect();
+            Err(tool_error(format!(
+                "Event '{spec}' is not in the contract ABI. Available events: {}",
+                if names.is_empty() {
+                    "none".to_string()
+                } else {
+                    names.join(", ")
+                }
+            )))
+        }
+    }
+}
+
+/// Topic a log carries for `value` in an indexed parameter of type `ty`. Dynamic types are
+/// stored as the hash of their encoding.
+fn topic_for(ty: &DynSolType, value: &Value) -> Result<B256, ToolError> {
+    let raw = match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let value = ty
+        .coerce_str(&raw)
+        .map_err(|e| tool_error(format!("Invalid {ty} filter value '{raw}': {e}")))?;
+    Ok(match value.as_word() {
+        Some(word) => word,
+        None => keccak256(value.abi_encode_packed()),
+    })
+}
+
+/// Topic filters for positions 1-3 built from `filters`, keyed by indexed parameter name
+/// (or position among the event's parameters). A list of values matches any of them.
+fn topic_filters(
+    event: &Event,
+    filters: &Map<String, Value>,
+) -> Result<[Option<Vec<B256>>; 3], ToolError> {
+    let mut topics: [Option<Vec<B256>>; 3] = Default::default();
+
+    for (key, values) in filters {
+        let (position, param) = event
+            .inputs
+            .iter()
+            .enumerate()
+            .find(|(i, param)| param.name == *key || i.to_string() == *key)
+            .ok_or_else(|| {
+                tool_error(format!(
+                    "Event {} has no parameter '{key}'",
+                    event.signature()
+                ))
+            })?;
+        if !param.indexed {
+            return Err(tool_error(format!(
+                "Parameter '{key}' of {} is not indexed and cannot be filtered on",
+                event.signature()
+            )));
+        }
+        let slot = event.inputs[..position]
+            .iter()
+            .filter(|param| param.indexed)
+            .count();
+        let ty = param
+            .resolve()
+            .map_err(|e| tool_error(format!("Unsupported parameter type {}: {e}", param.ty)))?;
+
+        let values = match values {
+            Value::Array(values) => values.as_slice(),
+            value => std::slice::from_ref(value),
+        };
+        let encoded = values
+            .iter()
+            .map(|value| topic_for(&ty, value))
+            .collect::<Result<Vec<_>, _>>()?;
+        topics[slot] = Some(encoded);
+    }
+    Ok(topics)
+}
+
+/// The log's arguments by parameter name (`arg<i>` for unnamed ones)
+fn decode_log(event: &Event, log: &Log) -> Result<Map<String, Value>, String> {
+    let decoded = event
+        .decode_log_parts(log.topics().iter().copied(), &log.data().data)
+        .map_err(|e| e.to_string())?;
+    let mut indexed = decoded.indexed.iter();
+    let mut body = decoded.body.iter();
+
+    let mut args = Map::new();
+    for (i, param) in event.inputs.iter().enumerate() {
+        let value: Option<&DynSolValue> = if param.indexed {
+            indexed.next()
+        } else {
+            body.next()
+        };
+        let name = if param.name.is_empty() {
+            format!("arg{i}")
+        } else {
+            param.name.clone()
+        };
+        args.insert(name, value.map(value_to_json).unwrap_or(Value::Null));
+    }
+    Ok(args)
+}
+
+fn log_row(event: &Event, log: &Log) -> Value {
+    let mut row = json!({
+        "block_number": log.block_number,
+        "transaction_hash": log.transaction_hash.map(|hash| hash.to_string()),
+        "log_index": log.log_index,
+        "address": log.address().to_string(),
+    });
+    match decode_log(event, log) {
+        Ok(args) => row["args"] = Value::Object(args),
+        // Same topic0, different indexing (e.g. ERC721 vs ERC20 Transfer)
+        Err(e) => {
+            row["decode_error"] = json!(e);
+            row["topics"] = json!(log.topics());
+            row["data"] = json!(log.data().data.to_string());
+        }
+    }
+    row
+}
+
+struct LogPage {
+    logs: Vec<Log>,
+    next: Option<LogPosition>,
+}
+
+/// Fetches `from..=to` in batches of `batch_size` blocks until more than `limit` logs at or
+/// after `after` are found; `next` is where the following page starts.
+async fn collect_page<F, Fut>(
+    from: u64,
+    to: u64,
+    batch_size: u64,
+    after: Option<LogPosition>,
+    limit: usize,
+    mut fetch: F,
+) -> Result<LogPage, ToolError>
+where
+    F: FnMut(u64, u64) -> Fut,
+    Fut: Future<Output = Result<Vec<Log>, ToolError>>,
+{
+    let mut logs = Vec::new();
+    let mut current_from = from;
+
+    while current_from <= to {
+        let current_to = current_from.saturating_add(batch_size - 1).min(to);
+        let batch = fetch(current_from, current_to).await?;
+        logs.extend(
+            batch
+                .into_iter()
+                .filter(|log| after.is_none_or(|after| position(log) >= after)),
+        );
+        if logs.len() > limit {
+            break;
+        }
+        current_from = current_to + 1;
+    }
+
+    logs.sort_by_key(position);
+    let next = logs.get(limit).map(position);
+    logs.truncate(limit);
+    Ok(LogPage { logs, next })
+}
+
+/// Fetches and decodes event logs of a contract
+#[derive(Debug, Clone)]
+pub struct GetLogs;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetLogsArgs {
+    pub chain_id: u64,
+    pub address: String,
+    pub event: String,
+    #[serde(default)]
+    pub filters: Option<Map<String, Value>>,
+    #[serde(default)]
+    pub from_block: Option<u64>,
+    #[serde(default)]
+    pub to_block: Option<u64>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+impl AomiToolArgs for GetLogsArgs {
+    fn schema() -> serde_json::Value {
+        with_topic(json!({
+            "type": "object",
+            "properties": {
+                "chain_id": {
+                    "type": "number",
+                    "description": "Chain ID (e.g., 1 for Ethereum, 8453 for Base, 42161 for Arbitrum)"
+                },
+                "address": {
+                    "type": "string",
+                    "description": "Contract that emitted the events"
+                },
+                "event": {
+                    "type": "string",
+                    "description": "Event name from the contract ABI (e.g. \"Transfer\") or full signature with indexed parameters (e.g. \"Transfer(address indexed from, address indexed to, uint256 value)\")"
+                },
+                "filters": {
+                    "type": "object",
+                    "description": "Filters on indexed parameters, keyed by parameter name. A list matches any of its values (e.g. {\"to\": \"0x...\"} or {\"from\": [\"0x...\", \"0x...\"]})"
+                },
+                "from_block": {
+                    "type": "number",
+                    "description": "First block to search (default: 10,000 blocks before to_block)"
+                },
+                "to_block": {
+                    "type": "number",
+                    "description": "Last block to search (default: latest)"
+                },
+                "limit": {
+                    "type": "number",
+                    "description": "Maximum number of logs to return (default 50, max 200)"
+                },
+                "cursor": {
+                    "type": "string",
+                    "description": "next_cursor from a previous call with the same arguments, to fetch the next page"
+                }
+            },
+            "required": ["chain_id", "address", "event"]
+        }))
+    }
+}
+
+async fn execute_get_logs(args: GetLogsArgs) -> Result<serde_json::Value, ToolError> {
+    let address: Address = args
+        .address
+        .trim()
+        .parse()
+        .map_err(|e| tool_error(format!("Invalid address '{}': {e}", args.address)))?;
+
+    let manager = aomi_anvil::provider_manager()
+        .await
+        .map_err(|e| tool_error(format!("Provider manager unavailable: {e}")))?;
+    let provider = manager
+        .get_provider(Some(args.chain_id), None)
+        .await
+        .map_err(|_| {
+            let mut supported = manager.supported_chain_ids();
+            supported.sort_unstable();
+            tool_error(format!(
+                "No RPC provider for chain {}. Supported chains: {supported:?}",
+                args.chain_id
+            ))
+        })?;
+
+    let abi = match get_contract_abi(args.chain_id as u32, &format!("{address:#x}")).await {
+        Ok(abi) => serde_json::from_value::<JsonAbi>(abi).ok(),
+        Err(e) => {
+            debug!(%address, error = %e, "no ABI for get_logs");
+            None
+        }
+    };
+    let event = resolve_event(&args.event, abi.as_ref())?;
+    if event.anonymous {
+        return Err(tool_error(format!(
+            "Event {} is anonymous and cannot be filtered by signature",
+            event.signature()
+        )));
+    }
+    let topics = topic_filters(&event, &args.filters.unwrap_or_default())?;
+
+    let latest = provider
+        .get_block_number()
+        .await
+        .map_err(|e| tool_error(format!("Failed to fetch latest block: {e}")))?;
+    let to_block = args.to_block.unwrap_or(latest).min(latest);
+    let from_block = args
+        .from_block
+        .unwrap_or_else(|| to_block.saturating_sub(DEFAULT_BLOCK_RANGE - 1));
+    if from_block > to_block {
+        return Err(tool_error(format!(
+            "from_block {from_block} is after to_block {to_block}"
+        )));
+    }
+    if to_block - from_block >= MAX_BLOCK_RANGE {
+        return Err(tool_error(format!(
+            "Block range {from_block}-{to_block} is too wide; search at most {MAX_BLOCK_RANGE} blocks per call"
+        )));
+    }
+
+    let after = args.cursor.as_deref().map(parse_cursor).transpose()?;
+    let start = after.map_or(from_block, |(block, _)| block.max(from_block));
+    let limit = args.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let mut filter = Filter::new()
+        .address(address)
+        .event_signature(event.selector());
+    for (slot, topic) in topics.into_iter().enumerate() {
+        if let Some(topic) = topic {
+            filter.topics[slot + 1] = topic.into();
+        }
+    }
+
+    info!(
+        chain_id = args.chain_id,
+        %address,
+        event = %event.signature(),
+        from_block = start,
+        to_block,
+        "fetching logs"
+    );
+    let page = collect_page(start, to_block, BATCH_SIZE, after, limit, |from, to| {
+        let filter = filter.clone().from_block(from).to_block(to);
+        let provider = provider.clone();
+        async move {
+            provider.get_logs(&filter).await.map_err(|e| {
+                tool_error(format!("Failed to fetch logs for blocks {from}-{to}: {e}"))
+            })
+        }
+    })
+    .await?;
+
+    let rows: Vec<Value> = page.logs.iter().map(|log| log_row(&event, log)).collect();
+    Ok(json!({
+        "chain_id": args.chain_id,
+        "address": address.to_string(),
+        "event": event.full_signature(),
+        "topic0": event.selector().to_string(),
+        "from_block": from_block,
+        "to_block": to_block,
+        "count": rows.len(),
+        "logs": rows,
+        "next_cursor": page.next.map(format_cursor),
+    }))
+}
+
+impl AomiTool for GetLogs {
+    const NAME: &'static str = "get_logs";
+
+    type Args = GetLogsArgs;
+    type Output = serde_json::Value;
+    type Error = ToolError;
+
+    fn support_async(&self) -> bool {
+        true
+    }
+
+    /// Log scans are read-only, so a restart can safely run them again
+    fn is_resumable(&self) -> bool {
+        true
+    }
+
+    fn description(&self) -> &'static str {
+        "[Async Tool] Fetch and decode event logs emitted by a contract, e.g. Transfer events of a token to a given address. Takes the event name (resolved from the contract ABI) or signature, filters on indexed parameters and a block range (default: the last 10,000 blocks). Results are in chain order; pass next_cursor back to get the next page."
+    }
+
+    fn run_sync(
+        &self,
+        _ctx: ToolCallCtx,
+        args: Self::Args,
+    ) -> impl std::future::Future<Output = eyre::Result<serde_json::Value>> + Send {
+        async move {
+            execute_get_logs(args)
+                .await
+                .map_err(|e| eyre::eyre!(e.to_string()))
+        }
+    }
+
+    fn run_async(
+        &self,
+        sender: mpsc::Sender<(eyre::Result<serde_json::Value>, bool)>,
+        _ctx: ToolCallCtx,
+        args: Self::Args,
+    ) -> impl std::future::Future<Output = ()> + Send {
+        async move {
+            let result = execute_get_logs(args)
+                .await
+                .map_err(|e| eyre::eyre!(e.to_string()));
+            let _ = sender.send((result, false)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{LogData, U256, address};
+
+    const TOKEN: Address = address!("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+    const ALICE: Address = address!("742d35Cc6634C0532925a3b844Bc9e7595f33749");
+    const BOB: Address = address!("d8dA6BF26964aF9D7eEd9e03E53415D37aA96045");
+
+    fn erc20_abi() -> JsonAbi {
+        serde_json::from_value(json!([
+            {
+                "type": "event",
+                "name": "Transfer",
+                "anonymous": false,
+                "inputs": [
+                    {"name": "from", "type": "address", "indexed": true},
+                    {"name": "to", "type": "address", "indexed": true},
+                    {"name": "value", "type": "uint256", "indexed": false}
+                ]
+            },
+            {
+                "type": "event",
+                "name": "Approval",
+                "anonymous": false,
+                "inputs": [
+                    {"name": "owner", "type": "address", "indexed": true},
+                    {"name": "spender", "type": "address", "indexed": true},
+                    {"name": "value", "type": "uint256", "indexed": false}
+                ]
+            }
+        ]))
+        .unwrap()
+    }
+
+    fn transfer_log(from: Address, to: Address, value: u64, block: u64, index: u64) -> Log {
+        let topics = vec![
+            keccak256("Transfer(address,address,uint256)"),
+            from.into_word(),
+            to.into_word(),
+        ];
+        let data = U256::from(value).to_be_bytes::<32>().to_vec();
+        Log {
+            inner: alloy::primitives::Log {
+                address: TOKEN,
+                data: LogData::new_unchecked(topics, data.into()),
+            },
+            block_number: Some(block),
+            log_index: Some(index),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_resolve_event_by_name_and_signature() {
+        let abi = erc20_abi();
+
+        let event = resolve_event("Transfer", Some(&abi)).unwrap();
+        assert_eq!(event.signature(), "Transfer(address,address,uint256)");
+
+        // A bare signature picks up names and indexed flags from the ABI
+        let event = resolve_event("Transfer(address,address,uint256)", Some(&abi)).unwrap();
+        assert_eq!(event.inputs[1].name, "to");
+        assert!(event.inputs[1].indexed);
+
+        let event = resolve_event("Deposit(address indexed dst, uint256 wad)", Some(&abi)).unwrap();
+        assert!(event.inputs[0].indexed && !event.inputs[1].indexed);
+
+        let err = resolve_event("Deposit", Some(&abi))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Approval, Transfer"), "{err}");
+        assert!(resolve_event("Transfer", None).is_err());
+    }
+
+    #[test]
+    fn test_topic_filters() {
+        let event = resolve_event("Transfer", Some(&erc20_abi())).unwrap();
+        let filters = json!({"to": BOB.to_string(), "from": [ALICE.to_string(), BOB.to_string()]});
+
+        let topics = topic_filters(&event, filters.as_object().unwrap()).unwrap();
+        assert_eq!(topics[0], Some(vec![ALICE.into_word(), BOB.into_word()]));
+        assert_eq!(topics[1], Some(vec![BOB.into_word()]));
+        assert_eq!(topics[2], None);
+
+        let not_indexed = json!({"value": "1"});
+        assert!(topic_filters(&event, not_indexed.as_object().unwrap()).is_err());
+        let unknown = json!({"amount": "1"});
+        assert!(topic_filters(&event, unknown.as_object().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_topic_for_dynamic_types_hashes_value() {
+        assert_eq!(
+            topic_for(&DynSolType::String, &json!("hello")).unwrap(),
+            keccak256("hello")
+        );
+        assert_eq!(
+            topic_for(&DynSolType::Uint(256), &json!(5)).unwrap(),
+            B256::from(U256::from(5))
+        );
+    }
+
+    #[test]
+    fn test_decode_log_rows() {
+        let event = resolve_event("Transfer", Some(&erc20_abi())).unwrap();
+        let row = log_row(&event, &transfer_log(ALICE, BOB, 42, 100, 3));
+
+        assert_eq!(row["block_number"], json!(100));
+        assert_eq!(row["args"]["from"], json!(ALICE.to_checksum(None)));
+        assert_eq!(row["args"]["to"], json!(BOB.to_checksum(None)));
+        assert_eq!(row["args"]["value"], json!("42"));
+
+        // ERC721 Transfer shares topic0 but indexes the token id
+        let erc721 = resolve_event(
+            "Transfer(address indexed from, address indexed to, uint256 indexed tokenId)",
+            None,
+        )
+        .unwrap();
+        let row = log_row(&erc721, &transfer_log(ALICE, BOB, 42, 100, 3));
+        assert!(row.get("decode_error").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_collect_page_batches_and_paginates() {
+        let logs: Vec<Log> = (0..5)
+            .map(|i| transfer_log(ALICE, BOB, i, 10 + i * 1_000, 0))
+            .collect();
+        let fetch = |requests: &std::sync::Mutex<Vec<(u64, u64)>>, from: u64, to: u64| {
+            requests.lock().unwrap().push((from, to));
+            let batch: Vec<Log> = logs
+                .iter()
+                .filter(|log| (from..=to).contains(&log.block_number.unwrap()))
+                .cloned()
+                .collect();
+            async move { Ok::<_, ToolError>(batch) }
+        };
+
+        let requests = std::sync::Mutex::new(Vec::new());
+        let page = collect_page(0, 4_999, 1_000, None, 2, |from, to| {
+            fetch(&requests, from, to)
+        })
+        .await
+        .unwrap();
+        assert_eq!(page.logs.len(), 2);
+        assert_eq!(page.next, Some((2_010, 0)));
+        // Stops once the page is full
+        assert_eq!(
+            *requests.lock().unwrap(),
+            vec![(0, 999), (1_000, 1_999), (2_000, 2_999)]
+        );
+
+        let requests = std::sync::Mutex::new(Vec::new());
+        let page = collect_page(2_010, 4_999, 1_000, page.next, 10, |from, to| {
+            fetch(&requests, from, to)
+        })
+        .await
+        .unwrap();
+        let blocks: Vec<_> = page.logs.iter().map(|l| l.block_number.unwrap()).collect();
+        assert_eq!(blocks, vec![2_010, 3_010, 4_010]);
+        assert_eq!(page.next, None);
+
+        assert_eq!(
+            parse_cursor(&format_cursor((2_010, 7))).unwrap(),
+            (2_010, 7)
+        );
+        assert!(parse_cursor("2010").is_err());
+    }
+}
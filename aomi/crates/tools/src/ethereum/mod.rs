@@ -1,8 +1,16 @@
+pub mod abi_decoder;
 pub mod abi_encoder;
 pub mod account;
 pub mod cast;
 pub mod etherscan;
+pub mod fees;
 pub mod gateway;
+pub mod logs;
+pub mod names;
+pub mod policy;
+pub mod portfolio;
+pub mod proxy;
+pub mod simulation;
 pub mod wallet;
 
 // Gateway implementations (conditionally compiled)
@@ -16,3 +24,5 @@ pub mod local;
 pub use gateway::{
     AccountInfo, Erc20BalanceResult, EvmGateway, WalletTransactionResult, get_gateway,
 };
+pub use policy::{PolicyScope, PolicyViolation, api_key_id};
+pub use proxy::{ProxyInfo, ProxyKind, detect_proxy};
@@ -0,0 +1,824 @@
+//! Forward and reverse name resolution (ENS, Basenames) with a TTL cache.
+//!
+//! Each name service is a [`NameResolver`]. ENS-compatible registries such as Basenames on
+//! Base only differ in registry address, network and reverse suffix, so they share
+//! [`EnsCompatibleResolver`]; other L2 name services plug in by implementing the trait.
+//! [`NameService`] routes names to resolvers by suffix and caches answers, misses included,
+//! in a [`NameStoreApi`]. The backend installs one backed by the database with
+//! [`install_name_service`]; without it lookups go uncached.
+
+use alloy::{
+    dyn_abi::{DynSolType, DynSolValue},
+    primitives::{Address, B256, Bytes, address},
+    rpc::types::{TransactionInput, TransactionRequest},
+};
+use alloy_ens::namehash;
+use alloy_provider::Provider;
+use async_trait::async_trait;
+use rig::tool::ToolError;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::cast::tool_error;
+use crate::clients::{CastClient, external_clients};
+use crate::db::{NameRecord, NameStoreApi};
+use crate::{AomiTool, AomiToolArgs, ToolCallCtx, with_topic};
+
+static NAME_SERVICE: OnceLock<Arc<NameService>> = OnceLock::new();
+
+/// How long lookups are cached unless configured otherwise
+pub const DEFAULT_NAME_TTL: Duration = Duration::from_secs(60 * 60);
+/// Text record returned with every resolved name
+pub const AVATAR_KEY: &str = "avatar";
+/// Upper bound on text records fetched per tool call
+const MAX_TEXT_RECORDS: usize = 10;
+
+/// ENS registry, deployed at the same address on mainnet and testnets
+pub const ENS_REGISTRY: Address = address!("00000000000C2E074eC69A0dFb2997BA6C7d2e1e");
+/// Basenames registry on Base
+pub const BASENAMES_REGISTRY: Address = address!("b94704422c2a1e396835a571837aa5ae53285a95");
+
+/// `resolver(bytes32)` on the registry
+const RESOLVER_SELECTOR: [u8; 4] = [0x01, 0x78, 0xb8, 0xbf];
+/// `addr(bytes32)`
+const ADDR_SELECTOR: [u8; 4] = [0x3b, 0x3b, 0x57, 0xde];
+/// `name(bytes32)`, answered for reverse nodes
+const NAME_SELECTOR: [u8; 4] = [0x69, 0x1f, 0x34, 0x31];
+/// `text(bytes32,string)`
+const TEXT_SELECTOR: [u8; 4] = [0x59, 0xd1, 0xd4, 0x3c];
+
+/// Makes `service` the one used by the name tools and wallet binding. Returns false if a
+/// service was already installed or used.
+pub fn install_name_service(service: Arc<NameService>) -> bool {
+    NAME_SERVICE.set(service).is_ok()
+}
+
+/// The installed name service, or an uncached ENS + Basenames one.
+pub fn name_service() -> Arc<NameService> {
+    NAME_SERVICE
+        .get_or_init(|| Arc::new(NameService::new(NameService::default_resolvers())))
+        .clone()
+}
+
+/// A name service that maps names to addresses and back.
+///
+/// `Ok(None)` means the service has no answer and is cached as a miss; errors (RPC failures,
+/// missing clients) are not cached.
+#[async_trait]
+pub trait NameResolver: Send + Sync {
+    /// Identifier used in cache keys and tool output, e.g. "ens"
+    fn service(&self) -> &str;
+
+    /// Parent name this service is authoritative for (e.g. "base.eth"); None accepts any
+    /// name no other resolver claims
+    fn suffix(&self) -> Option<&str>;
+
+    async fn resolve(&self, name: &str) -> Result<Option<Address>, ToolError>;
+
+    /// Primary name of `address`, verified to resolve back to it
+    async fn lookup(&self, address: Address) -> Result<Option<String>, ToolError>;
+
+    async fn text(&self, name: &str, key: &str) -> Result<Option<String>, ToolError>;
+}
+
+/// A name service built on the ENS registry and resolver interfaces
+#[derive(Debug, Clone)]
+pub struct EnsCompatibleResolver {
+    service: String,
+    /// Cast client network the registry lives on
+    network: String,
+    registry: Address,
+    suffix: Option<String>,
+    /// Parent of reverse records, e.g. "addr.reverse"
+    reverse_suffix: String,
+}
+
+impl EnsCompatibleResolver {
+    pub fn new(
+        service: impl Into<String>,
+        network: impl Into<String>,
+        registry: Address,
+        suffix: Option<String>,
+        reverse_suffix: impl Into<String>,
+    ) -> Self {
+        Self {
+            service: service.into(),
+            network: network.into(),
+            registry,
+            suffix,
+            reverse_suffix: reverse_suffix.into(),
+        }
+    }
+
+    /// ENS on Ethereum mainnet
+    pub fn ens() -> Self {
+        Self::new("ens", "ethereum", ENS_REGISTRY, None, "addr.reverse")
+    }
+
+    /// Basenames (`*.base.eth`) on Base; reverse records live under Base's ENSIP-11 coin type
+    pub fn basenames() -> Self {
+        Self::new(
+            "basenames",
+            "base",
+            BASENAMES_REGISTRY,
+            Some("base.eth".to_string()),
+            "80002105.reverse",
+        )
+    }
+
+    async fn client(&self) -> Result<Arc<CastClient>, ToolError> {
+        external_clients()
+            .await
+            .get_cast_client(&self.network)
+            .await
+    }
+
+    /// Resolver contract set for `node`, if any
+    async fn resolver_of(
+        &self,
+        client: &CastClient,
+        node: B256,
+    ) -> Result<Option<Address>, ToolError> {
+        let raw = eth_call(client, self.registry, with_node(RESOLVER_SELECTOR, node)).await?;
+        Ok(raw.and_then(|raw| decode_address(&raw)))
+    }
+
+    /// Calls `selector(node, ..)` on the resolver of `node`
+    async fn call_resolver(
+        &self,
+        node: B256,
+        calldata: Vec<u8>,
+        output: DynSolType,
+    ) -> Result<Option<DynSolValue>, ToolError> {
+        let client = self.client().await?;
+        let Some(resolver) = self.resolver_of(&client, node).await? else {
+            return Ok(None);
+        };
+        let raw = eth_call(&client, resolver, calldata).await?;
+        Ok(raw.and_then(|raw| output.abi_decode(&raw).ok()))
+    }
+}
+
+#[async_trait]
+impl NameResolver for EnsCompatibleResolver {
+    fn service(&self) -> &str {
+        &self.service
+    }
+
+    fn suffix(&self) -> Option<&str> {
+        self.suffix.as_deref()
+    }
+
+    async fn resolve(&self, name: &str) -> Result<Option<Address>, ToolError> {
+        let node = namehash(name);
+        let value = self
+            .call_resolver(node, with_node(ADDR_SELECTOR, node), DynSolType::Address)
+            .await?;
+        Ok(match value {
+            Some(DynSolValue::Address(address)) if !address.is_zero() => Some(address),
+            _ => None,
+        })
+    }
+
+    async fn lookup(&self, address: Address) -> Result<Option<String>, ToolError> {
+        let reverse = format!("{}.{}", hex::encode(address), self.reverse_suffix);
+        let node = namehash(&reverse);
+        let value = self
+            .call_resolver(node, with_node(NAME_SELECTOR, node), DynSolType::String)
+            .await?;
+        let Some(DynSolValue::String(name)) = value else {
+            return Ok(None);
+        };
+        let name = normalize_name(&name);
+        if name.is_empty() {
+            return Ok(None);
+        }
+
+        // Anyone can set any reverse record, so only trust names that point back
+        if self.resolve(&name).await? != Some(address) {
+            debug!(%address, name, service = self.service, "reverse record does not resolve back");
+            return Ok(None);
+        }
+        Ok(Some(name))
+    }
+
+    async fn text(&self, name: &str, key: &str) -> Result<Option<String>, ToolError> {
+        let node = namehash(name);
+        let mut calldata = TEXT_SELECTOR.to_vec();
+        calldata.extend(
+            DynSolValue::Tuple(vec![
+                DynSolValue::FixedBytes(node, 32),
+                DynSolValue::String(key.to_string()),
+            ])
+            .abi_encode_params(),
+        );
+        let value = self
+            .call_resolver(node, calldata, DynSolType::String)
+            .await?;
+        Ok(match value {
+            Some(DynSolValue::String(text)) if !text.is_empty() => Some(text),
+            _ => None,
+        })
+    }
+}
+
+fn with_node(selector: [u8; 4], node: B256) -> Vec<u8> {
+    let mut calldata = selector.to_vec();
+    calldata.extend_from_slice(node.as_slice());
+    calldata
+}
+
+fn decode_address(raw: &[u8]) -> Option<Address> {
+    match DynSolType::Address.abi_decode(raw).ok()? {
+        DynSolValue::Address(address) if !address.is_zero() => Some(address),
+        _ => None,
+    }
+}
+
+/// `eth_call` that treats reverts as "no answer" and transport failures as errors
+async fn eth_call(
+    client: &CastClient,
+    to: Address,
+    calldata: Vec<u8>,
+) -> Result<Option<Bytes>, ToolError> {
+    let tx = TransactionRequest::default()
+        .to(to)
+        .input(TransactionInput::new(Bytes::from(calldata)))
+        .with_input_and_data();
+    match client.provider.call(tx.into()).await {
+        Ok(raw) => Ok(Some(raw)),
+        Err(e) if e.as_error_resp().is_some() => {
+            debug!(%to, error = %e, "name service call reverted");
+            Ok(None)
+        }
+        Err(e) => Err(tool_error(format!("Name service call to {to} failed: {e}"))),
+    }
+}
+
+/// Lowercased, trimmed form used for hashing and cache keys
+fn normalize_name(name: &str) -> String {
+    name.trim().trim_end_matches('.').to_lowercase()
+}
+
+fn is_under(name: &str, suffix: &str) -> bool {
+    name == suffix
+        || name
+            .strip_suffix(suffix)
+            .is_some_and(|rest| rest.ends_with('.'))
+}
+
+/// A name or address together with the service that answered
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ResolvedName {
+    pub service: String,
+    pub name: String,
+    pub address: Address,
+}
+
+/// Routes lookups to [`NameResolver`]s and caches their answers
+pub struct NameService {
+    resolvers: Vec<Arc<dyn NameResolver>>,
+    store: Option<Arc<dyn NameStoreApi>>,
+    ttl: Duration,
+}
+
+impl NameService {
+    /// Reverse lookups try `resolvers` in order, so list the preferred service first
+    pub fn new(resolvers: Vec<Arc<dyn NameResolver>>) -> Self {
+        Self {
+            resolvers,
+            store: None,
+            ttl: DEFAULT_NAME_TTL,
+        }
+    }
+
+    /// ENS, then Basenames
+    pub fn default_resolvers() -> Vec<Arc<dyn NameResolver>> {
+        vec![
+            Arc::new(EnsCompatibleResolver::ens()),
+            Arc::new(EnsCompatibleResolver::basenames()),
+        ]
+    }
+
+    pub fn with_store(mut self, store: Arc<dyn NameStoreApi>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// The resolver with the longest suffix matching `name`, else a catch-all one
+    fn resolver_for(&self, name: &str) -> Option<&Arc<dyn NameResolver>> {
+        self.resolvers
+            .iter()
+            .filter(|resolver| {
+                resolver
+                    .suffix()
+                    .is_some_and(|suffix| is_under(name, suffix))
+            })
+            .max_by_key(|resolver| resolver.suffix().map(str::len))
+            .or_else(|| {
+                self.resolvers
+                    .iter()
+                    .find(|resolver| resolver.suffix().is_none())
+            })
+    }
+
+    pub async fn resolve_name(&self, name: &str) -> Result<Option<ResolvedName>, ToolError> {
+        let name = normalize_name(name);
+        let resolver = self
+            .resolver_for(&name)
+            .ok_or_else(|| tool_error(format!("No name service handles '{name}'")))?;
+
+        let value = self
+            .cached(resolver.service(), format!("name:{name}"), async {
+                Ok(resolver.resolve(&name).await?.map(|a| a.to_string()))
+            })
+            .await?;
+        Ok(value
+            .and_then(|value| value.parse().ok())
+            .map(|address| ResolvedName {
+                service: resolver.service().to_string(),
+                name,
+                address,
+            }))
+    }
+
+    /// Primary name of `address` from the first service that has one. A failing service is
+    /// skipped unless every service fails.
+    pub async fn lookup_address(
+        &self,
+        address: Address,
+    ) -> Result<Option<ResolvedName>, ToolError> {
+        let mut last_error = None;
+        let mut answered = false;
+        for resolver in &self.resolvers {
+            let lookup = format!("address:{address:#x}");
+            match self
+                .cached(resolver.service(), lookup, resolver.lookup(address))
+                .await
+            {
+                Ok(Some(name)) => {
+                    return Ok(Some(ResolvedName {
+                        service: resolver.service().to_string(),
+                        name,
+                        address,
+                    }));
+                }
+                Ok(None) => answered = true,
+                Err(e) => {
+                    warn!(%address, service = resolver.service(), error = %e, "reverse lookup failed");
+                    last_error = Some(e);
+                }
+            }
+        }
+        match last_error {
+            Some(e) if !answered => Err(e),
+            _ => Ok(None),
+        }
+    }
+
+    /// Text records of `name`; keys without a value map to None
+    pub async fn text_records(
+        &self,
+        name: &str,
+        keys: &[String],
+    ) -> Result<BTreeMap<String, Option<String>>, ToolError> {
+        let name = normalize_name(name);
+        let resolver = self
+            .resolver_for(&name)
+            .ok_or_else(|| tool_error(format!("No name service handles '{name}'")))?;
+
+        let mut records = BTreeMap::new();
+        for key in keys {
+            let value = self
+                .cached(
+                    resolver.service(),
+                    format!("text:{name}:{key}"),
+                    resolver.text(&name, key),
+                )
+                .await?;
+            records.insert(key.clone(), value);
+        }
+        Ok(records)
+    }
+
+    /// Drops expired cache entries; returns how many were removed
+    pub async fn purge_expired(&self) -> anyhow::Result<u64> {
+        match &self.store {
+            Some(store) => {
+                store
+                    .delete_expired_name_records(chrono::Utc::now().timestamp())
+                    .await
+            }
+            None => Ok(0),
+        }
+    }
+
+    /// Answers from the cache while fresh, else runs `fetch` and caches its answer. Cache
+    /// failures only cost a lookup.
+    async fn cached(
+        &self,
+        service: &str,
+        lookup: String,
+        fetch: impl Future<Output = Result<Option<String>, ToolError>>,
+    ) -> Result<Option<String>, ToolError> {
+        let now = chrono::Utc::now().timestamp();
+        if let Some(store) = &self.store {
+            match store.get_name_record(service, &lookup).await {
+                Ok(Some(record)) if record.expires_at > now => return Ok(record.value),
+                Ok(_) => {}
+                Err(e) => warn!(service, lookup, error = %e, "name cache read failed"),
+            }
+        }
+
+        let value = fetch.await?;
+
+        if let Some(store) = &self.store {
+            let record = NameRecord {
+                service: service.to_string(),
+                lookup,
+                value: value.clone(),
+                expires_at: now + self.ttl.as_secs() as i64,
+            };
+            if let Err(e) = store.upsert_name_record(&record).await {
+                warn!(service, lookup = record.lookup, error = %e, "name cache write failed");
+            }
+        }
+        Ok(value)
+    }
+}
+
+/// Text record keys to fetch: avatar first, then the requested ones
+fn text_keys(requested: Option<Vec<String>>) -> Vec<String> {
+    let mut keys = vec![AVATAR_KEY.to_string()];
+    for key in requested.unwrap_or_default() {
+        let key = key.trim().to_string();
+        if !key.is_empty() && !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    keys.truncate(MAX_TEXT_RECORDS);
+    keys
+}
+
+fn text_records_schema() -> serde_json::Value {
+    json!({
+        "type": "array",
+        "items": { "type": "string" },
+        "description": "Extra text records to fetch besides the avatar (e.g. [\"url\", \"com.twitter\", \"description\"])"
+    })
+}
+
+/// Resolves a name (e.g. vitalik.eth, jesse.base.eth) to an address
+#[derive(Debug, Clone)]
+pub struct ResolveName;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolveNameArgs {
+    pub name: String,
+    pub text_records: Option<Vec<String>>,
+}
+
+impl AomiToolArgs for ResolveNameArgs {
+    fn schema() -> serde_json::Value {
+        with_topic(json!({
+            "type": "object",
+            "properties": {
+                "name": {
+                    "type": "string",
+                    "description": "Name to resolve (e.g. \"vitalik.eth\", \"jesse.base.eth\")"
+                },
+                "text_records": text_records_schema()
+            },
+            "required": ["name"]
+        }))
+    }
+}
+
+async fn execute_resolve_name(args: ResolveNameArgs) -> Result<serde_json::Value, ToolError> {
+    let names = name_service();
+    let Some(resolved) = names.resolve_name(&args.name).await? else {
+        return Ok(json!({
+            "name": normalize_name(&args.name),
+            "address": null,
+            "message": "The name is not registered or has no address set",
+        }));
+    };
+    let records = names
+        .text_records(&resolved.name, &text_keys(args.text_records))
+        .await?;
+
+    Ok(json!({
+        "name": resolved.name,
+        "address": resolved.address.to_string(),
+        "service": resolved.service,
+        "text_records": records,
+    }))
+}
+
+impl AomiTool for ResolveName {
+    const NAME: &'static str = "resolve_name";
+
+    type Args = ResolveNameArgs;
+    type Output = serde_json::Value;
+    type Error = ToolError;
+
+    fn description(&self) -> &'static str {
+        "Resolve an ENS or Basenames name (e.g. vitalik.eth, jesse.base.eth) to its address, with its avatar and any requested text records. Use it whenever the user refers to an account by name."
+    }
+
+    fn read_only(&self) -> bool {
+        true
+    }
+
+    fn run_sync(
+        &self,
+        _ctx: ToolCallCtx,
+        args: Self::Args,
+    ) -> impl std::future::Future<Output = eyre::Result<serde_json::Value>> + Send {
+        async move {
+            execute_resolve_name(args)
+                .await
+                .map_err(|e| eyre::eyre!(e.to_string()))
+        }
+    }
+}
+
+/// Finds the primary name of an address
+#[derive(Debug, Clone)]
+pub struct LookupAddress;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LookupAddressArgs {
+    pub address: String,
+    pub text_records: Option<Vec<String>>,
+}
+
+impl AomiToolArgs for LookupAddressArgs {
+    fn schema() -> serde_json::Value {
+        with_topic(json!({
+            "type": "object",
+            "properties": {
+                "address": {
+                    "type": "string",
+                    "description": "Address to look up (0x-prefixed)"
+                },
+                "text_records": text_records_schema()
+            },
+            "required": ["address"]
+        }))
+    }
+}
+
+async fn execute_lookup_address(args: LookupAddressArgs) -> Result<serde_json::Value, ToolError> {
+    let address: Address = args
+        .address
+        .trim()
+        .parse()
+        .map_err(|e| tool_error(format!("Invalid address '{}': {e}", args.address)))?;
+
+    let names = name_service();
+    let Some(resolved) = names.lookup_address(address).await? else {
+        return Ok(json!({
+            "address": address.to_string(),
+            "name": null,
+            "message": "The address has no primary name",
+        }));
+    };
+    let records = names
+        .text_records(&resolved.name, &text_keys(args.text_records))
+        .await?;
+
+    Ok(json!({
+        "address": address.to_string(),
+        "name": resolved.name,
+        "service": resolved.service,
+        "text_records": records,
+    }))
+}
+
+impl AomiTool for LookupAddress {
+    const NAME: &'static str = "lookup_address";
+
+    type Args = LookupAddressArgs;
+    type Output = serde_json::Value;
+    type Error = ToolError;
+
+    fn description(&self) -> &'static str {
+        "Find the primary ENS or Basenames name of an address, with its avatar and any requested text records. Only names that resolve back to the address are returned."
+    }
+
+    fn read_only(&self) -> bool {
+        true
+    }
+
+    fn run_sync(
+        &self,
+        _ctx: ToolCallCtx,
+        args: Self::Args,
+    ) -> impl std::future::Future<Output = eyre::Result<serde_json::Value>> + Send {
+        async move {
+            execute_lookup_address(args)
+                .await
+                .map_err(|e| eyre::eyre!(e.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::NameStore;
+    use sqlx::any::AnyPoolOptions;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// In-memory name service counting how often it is asked
+    struct MockResolver {
+        service: &'static str,
+        suffix: Option<&'static str>,
+        names: HashMap<String, Address>,
+        calls: AtomicUsize,
+    }
+
+    impl MockResolver {
+        fn new(
+            service: &'static str,
+            suffix: Option<&'static str>,
+            names: &[(&str, Address)],
+        ) -> Self {
+            Self {
+                service,
+                suffix,
+                names: names.iter().map(|(n, a)| (n.to_string(), *a)).collect(),
+                calls: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl NameResolver for MockResolver {
+        fn service(&self) -> &str {
+            self.service
+        }
+
+        fn suffix(&self) -> Option<&str> {
+            self.suffix
+        }
+
+        async fn resolve(&self, name: &str) -> Result<Option<Address>, ToolError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(self.names.get(name).copied())
+        }
+
+        async fn lookup(&self, address: Address) -> Result<Option<String>, ToolError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(self
+                .names
+                .iter()
+                .find(|(_, a)| **a == address)
+                .map(|(name, _)| name.clone()))
+        }
+
+        async fn text(&self, name: &str, key: &str) -> Result<Option<String>, ToolError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok((key == AVATAR_KEY && self.names.contains_key(name))
+                .then(|| format!("https://avatars.example/{name}")))
+        }
+    }
+
+    async fn test_store() -> Arc<NameStore> {
+        sqlx::any::install_default_drivers();
+        let pool = AnyPoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::query(
+            "CREATE TABLE name_records (
+                service TEXT NOT NULL,
+                lookup TEXT NOT NULL,
+                value TEXT,
+                expires_at BIGINT NOT NULL,
+                PRIMARY KEY (service, lookup)
+            )",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        Arc::new(NameStore::new(pool))
+    }
+
+    const VITALIK: Address = address!("d8dA6BF26964aF9D7eEd9e03E53415D37aA96045");
+    const JESSE: Address = address!("849151d7D0bF1F34b70d5caD5149D28CC2308bf1");
+
+    #[test]
+    fn test_namehash_matches_ens() {
+        assert_eq!(namehash(""), B256::ZERO);
+        assert_eq!(
+            namehash("eth"),
+            "0x93cdeb708b7545dc668eb9280176169d1c33cfd8ed6f04690a0bcc88a93fc4ae"
+                .parse::<B256>()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_routes_names_by_suffix() {
+        let service = NameService::new(vec![
+            Arc::new(MockResolver::new("ens", None, &[])),
+            Arc::new(MockResolver::new("basenames", Some("base.eth"), &[])),
+        ]);
+        let route = |name: &str| service.resolver_for(name).map(|r| r.service().to_string());
+
+        assert_eq!(route("jesse.base.eth").as_deref(), Some("basenames"));
+        assert_eq!(route("base.eth").as_deref(), Some("basenames"));
+        assert_eq!(route("vitalik.eth").as_deref(), Some("ens"));
+        // Only whole labels match a suffix
+        assert_eq!(route("notbase.eth").as_deref(), Some("ens"));
+    }
+
+    #[test]
+    fn test_text_keys_always_include_avatar() {
+        assert_eq!(text_keys(None), vec!["avatar"]);
+        assert_eq!(
+            text_keys(Some(vec!["url".into(), "avatar".into(), " ".into()])),
+            vec!["avatar", "url"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolves_and_caches_including_misses() {
+        let ens = Arc::new(MockResolver::new("ens", None, &[("vitalik.eth", VITALIK)]));
+        let service = NameService::new(vec![ens.clone()]).with_store(test_store().await);
+
+        let resolved = service.resolve_name("Vitalik.eth").await.unwrap().unwrap();
+        assert_eq!(resolved.address, VITALIK);
+        assert_eq!(resolved.name, "vitalik.eth");
+        assert!(service.resolve_name("unknown.eth").await.unwrap().is_none());
+        assert_eq!(ens.calls.load(Ordering::SeqCst), 2);
+
+        // Hits and misses both come from the cache
+        service.resolve_name("vitalik.eth").await.unwrap();
+        service.resolve_name("unknown.eth").await.unwrap();
+        assert_eq!(ens.calls.load(Ordering::SeqCst), 2);
+
+        let records = service
+            .text_records("vitalik.eth", &[AVATAR_KEY.to_string(), "url".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(
+            records.get(AVATAR_KEY).cloned().flatten().as_deref(),
+            Some("https://avatars.example/vitalik.eth")
+        );
+        assert_eq!(records.get("url"), Some(&None));
+    }
+
+    #[tokio::test]
+    async fn test_expired_records_are_refreshed() {
+        let ens = Arc::new(MockResolver::new("ens", None, &[("vitalik.eth", VITALIK)]));
+        let service = NameService::new(vec![ens.clone()])
+            .with_store(test_store().await)
+            .with_ttl(Duration::ZERO);
+
+        service.resolve_name("vitalik.eth").await.unwrap();
+        service.resolve_name("vitalik.eth").await.unwrap();
+        assert_eq!(ens.calls.load(Ordering::SeqCst), 2);
+        assert!(service.purge_expired().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_lookup_falls_through_services_in_order() {
+        let ens = Arc::new(MockResolver::new("ens", None, &[("vitalik.eth", VITALIK)]));
+        let base = Arc::new(MockResolver::new(
+            "basenames",
+            Some("base.eth"),
+            &[("jesse.base.eth", JESSE)],
+        ));
+        let service = NameService::new(vec![ens, base]);
+
+        let vitalik = service.lookup_address(VITALIK).await.unwrap().unwrap();
+        assert_eq!(
+            (vitalik.service.as_str(), vitalik.name.as_str()),
+            ("ens", "vitalik.eth")
+        );
+        let jesse = service.lookup_address(JESSE).await.unwrap().unwrap();
+        assert_eq!(jesse.service, "basenames");
+        assert!(
+            service
+                .lookup_address(Address::ZERO)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
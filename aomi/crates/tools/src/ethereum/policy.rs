@@ -0,0 +1,1034 @@
+//! Transaction policy engine - guardrails enforced before any wallet handoff.
+//!
+//! Every `EvmGateway::send_transaction_to_wallet` call is checked against the
+//! policy resolved for the calling session. Policies are loaded from
+//! `policies.toml` and can be scoped per namespace or per API key:
+//!
+//! ```toml
+//! [default]
+//! max_value_wei = "1000000000000000000"          # 1 ETH per transaction
+//! block_unlimited_approvals = true
+//! block_set_approval_for_all = true
+//!
+//! [namespaces.forge]
+//! allowed_chains = [1, 8453]
+//! denied_contracts = ["0x722122dF12D4e14e13Ac3b6895a86e84145b6967"]
+//!
+//! [api_keys."tenant-key"]
+//! daily_spend_cap_wei = "5000000000000000000"    # 5 ETH per session per day
+//! allowed_contracts = ["0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"]
+//! blocked_selectors = ["0x23b872dd"]
+//! ```
+//!
+//! Sections are layered `default` < `namespaces.<ns>` < `api_keys.<key>`: a more
+//! specific section replaces only the fields it sets. When no policy file is
+//! found, every transaction is allowed.
+
+use alloy::primitives::{Address, U256, keccak256};
+use chrono::{NaiveDate, Utc};
+use dashmap::DashMap;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// `approve(address,uint256)`
+const APPROVE_SELECTOR: [u8; 4] = [0x09, 0x5e, 0xa7, 0xb3];
+
+/// `increaseAllowance(address,uint256)`
+const INCREASE_ALLOWANCE_SELECTOR: [u8; 4] = [0x39, 0x50, 0x93, 0x51];
+
+/// `setApprovalForAll(address,bool)`
+const SET_APPROVAL_FOR_ALL_SELECTOR: [u8; 4] = [0xa2, 0x2c, 0xb4, 0x65];
+
+/// Approval amounts at or above this (2^255 - 1) are treated as unlimited; wallets and dapps
+/// use values like `2**255` or `type(uint256).max - 1` as well as the exact maximum
+const UNLIMITED_APPROVAL_THRESHOLD: U256 =
+    U256::from_limbs([u64::MAX, u64::MAX, u64::MAX, u64::MAX >> 1]);
+
+/// Environment variable overriding the policy file location
+const POLICIES_TOML_ENV: &str = "POLICIES_TOML";
+
+// ============================================================================
+// Scope
+// ============================================================================
+
+/// Identifies who is sending a transaction, used to pick the applicable policy.
+///
+/// The scope travels inside serialized tool contexts, which may be persisted, so the raw
+/// API key is never serialized; only its [`api_key_id`] is.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyScope {
+    /// Public session identifier (daily spend caps are tracked per session)
+    pub session_id: Option<String>,
+    /// Namespace the session is running in (e.g. "default", "forge")
+    pub namespace: Option<String>,
+    /// API key the session was authorized with, kept in memory only
+    #[serde(skip)]
+    pub api_key: Option<String>,
+    /// Non-secret id of `api_key`, used to select its policy section
+    #[serde(default)]
+    pub api_key_id: Option<String>,
+    /// Wallet public key of the user, when known
+    pub public_key: Option<String>,
+}
+
+impl PolicyScope {
+    pub fn new(session_id: String, namespace: String, api_key: Option<String>) -> Self {
+        Self {
+            session_id: Some(session_id),
+            namespace: Some(namespace),
+            api_key_id: api_key.as_deref().map(api_key_id),
+            api_key,
+            public_key: None,
+        }
+    }
+
+    pub fn with_public_key(mut self, public_key: Option<String>) -> Self {
+        self.public_key = public_key;
+        self
+    }
+}
+
+/// Stable, non-secret identifier for an API key: a truncated keccak256 of the key.
+pub fn api_key_id(api_key: &str) -> String {
+    format!("key_{}", hex::encode(&keccak256(api_key.as_bytes())[..8]))
+}
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// A single policy section as written in `policies.toml`.
+///
+/// Every field is optional so sections can be layered on top of each other.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyRules {
+    /// Maximum native value per transaction, in wei
+    pub max_value_wei: Option<String>,
+    /// Maximum native value a session may send per UTC day, in wei
+    pub daily_spend_cap_wei: Option<String>,
+    /// Chain IDs transactions may target
+    pub allowed_chains: Option<Vec<u64>>,
+    /// If set, only these recipients are allowed
+    pub allowed_contracts: Option<Vec<String>>,
+    /// Recipients that are always rejected
+    pub denied_contracts: Option<Vec<String>>,
+    /// 4-byte function selectors that are always rejected (e.g. "0x23b872dd")
+    pub blocked_selectors: Option<Vec<String>>,
+    /// Reject `approve`/`increaseAllowance` with an effectively unlimited amount (2^255 - 1 or more)
+    pub block_unlimited_approvals: Option<bool>,
+    /// Reject `setApprovalForAll(operator, true)`
+    pub block_set_approval_for_all: Option<bool>,
+}
+
+impl PolicyRules {
+    /// Layer `other` on top of `self`; fields set in `other` win.
+    fn overlay(mut self, other: &PolicyRules) -> Self {
+        macro_rules! take {
+            ($($field:ident),*) => {
+                $(if other.$field.is_some() {
+                    self.$field = other.$field.clone();
+                })*
+            };
+        }
+        take!(
+            max_value_wei,
+            daily_spend_cap_wei,
+            allowed_chains,
+            allowed_contracts,
+            denied_contracts,
+            blocked_selectors,
+            block_unlimited_approvals,
+            block_set_approval_for_all
+        );
+        self
+    }
+
+    /// Parse the raw section into a policy that can be evaluated.
+    fn resolve(&self) -> eyre::Result<ResolvedPolicy> {
+        let parse_wei = |field: &str, raw: &Option<String>| -> eyre::Result<Option<U256>> {
+            raw.as_deref()
+                .map(|v| {
+                    U256::from_str(v.trim())
+                        .map_err(|e| eyre::eyre!("Invalid {} '{}': {}", field, v, e))
+                })
+                .transpose()
+        };
+        let parse_addresses =
+            |field: &str, raw: &Option<Vec<String>>| -> eyre::Result<Option<Vec<Address>>> {
+                raw.as_ref()
+                    .map(|list| {
+                        list.iter()
+                            .map(|a| {
+                                Address::from_str(a.trim()).map_err(|e| {
+                                    eyre::eyre!("Invalid address in {} '{}': {}", field, a, e)
+                                })
+                            })
+                            .collect()
+                    })
+                    .transpose()
+            };
+
+        let blocked_selectors = self
+            .blocked_selectors
+            .iter()
+            .flatten()
+            .map(|s| parse_selector(s))
+            .collect::<eyre::Result<Vec<_>>>()?;
+
+        Ok(ResolvedPolicy {
+            max_value: parse_wei("max_value_wei", &self.max_value_wei)?,
+            daily_spend_cap: parse_wei("daily_spend_cap_wei", &self.daily_spend_cap_wei)?,
+            allowed_chains: self.allowed_chains.clone(),
+            allowed_contracts: parse_addresses("allowed_contracts", &self.allowed_contracts)?,
+            denied_contracts: parse_addresses("denied_contracts", &self.denied_contracts)?
+                .unwrap_or_default(),
+            blocked_selectors,
+            block_unlimited_approvals: self.block_unlimited_approvals.unwrap_or(false),
+            block_set_approval_for_all: self.block_set_approval_for_all.unwrap_or(false),
+        })
+    }
+}
+
+/// Root of `policies.toml`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyConfig {
+    /// Rules applied to every session
+    #[serde(default)]
+    pub default: PolicyRules,
+    /// Rules keyed by namespace name (lowercased on load)
+    #[serde(default)]
+    pub namespaces: HashMap<String, PolicyRules>,
+    /// Rules keyed by API key (re-keyed by [`api_key_id`] on load)
+    #[serde(default)]
+    pub api_keys: HashMap<String, PolicyRules>,
+}
+
+impl PolicyConfig {
+    pub fn from_toml_str(content: &str) -> eyre::Result<Self> {
+        let mut config: PolicyConfig = toml::from_str(content)
+            .map_err(|e| eyre::eyre!("Failed to parse policy config: {}", e))?;
+        config.normalize_namespaces()?;
+        config.api_keys = config
+            .api_keys
+            .into_iter()
+            .map(|(key, rules)| (api_key_id(&key), rules))
+            .collect();
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> eyre::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| eyre::eyre!("Failed to read {}: {}", path.display(), e))?;
+        Self::from_toml_str(&content)
+    }
+
+    /// Namespaces are matched case-insensitively, so key the sections by lowercase name.
+    fn normalize_namespaces(&mut self) -> eyre::Result<()> {
+        let mut namespaces = HashMap::with_capacity(self.namespaces.len());
+        for (name, rules) in self.namespaces.drain() {
+            let key = name.to_lowercase();
+            if namespaces.insert(key, rules).is_some() {
+                eyre::bail!(
+                    "Duplicate policy section for namespace '{}' (names are case-insensitive)",
+                    name
+                );
+            }
+        }
+        self.namespaces = namespaces;
+        Ok(())
+    }
+
+    /// Reject malformed values at load time instead of on the first transaction.
+    fn validate(&self) -> eyre::Result<()> {
+        self.default.resolve()?;
+        for rules in self.namespaces.values().chain(self.api_keys.values()) {
+            self.default.clone().overlay(rules).resolve()?;
+        }
+        Ok(())
+    }
+
+    /// Merge the sections that apply to `scope`.
+    fn rules_for(&self, scope: &PolicyScope) -> PolicyRules {
+        let mut rules = self.default.clone();
+        if let Some(ns_rules) = scope
+            .namespace
+            .as_deref()
+            .and_then(|ns| self.namespaces.get(&ns.to_lowercase()))
+        {
+            rules = rules.overlay(ns_rules);
+        }
+        if let Some(key_rules) = scope
+            .api_key_id
+            .as_deref()
+            .and_then(|id| self.api_keys.get(id))
+        {
+            rules = rules.overlay(key_rules);
+        }
+        rules
+    }
+}
+
+/// Policy with parsed values, ready to evaluate.
+#[derive(Debug, Clone, Default)]
+struct ResolvedPolicy {
+    max_value: Option<U256>,
+    daily_spend_cap: Option<U256>,
+    allowed_chains: Option<Vec<u64>>,
+    allowed_contracts: Option<Vec<Address>>,
+    denied_contracts: Vec<Address>,
+    blocked_selectors: Vec<[u8; 4]>,
+    block_unlimited_approvals: bool,
+    block_set_approval_for_all: bool,
+}
+
+fn parse_selector(raw: &str) -> eyre::Result<[u8; 4]> {
+    let bytes = hex::decode(raw.trim().trim_start_matches("0x"))
+        .map_err(|e| eyre::eyre!("Invalid selector '{}': {}", raw, e))?;
+    bytes
+        .try_into()
+        .map_err(|_| eyre::eyre!("Invalid selector '{}': must be 4 bytes", raw))
+}
+
+// ============================================================================
+// Violations
+// ============================================================================
+
+/// A policy rule that rejected a transaction.
+///
+/// Serialized with a `rule` tag and a human-readable `message` so the agent can
+/// explain exactly why the transaction was not sent to the wallet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum PolicyViolation {
+    ChainNotAllowed {
+        chain_id: Option<u64>,
+        allowed: Vec<u64>,
+        message: String,
+    },
+    MaxValueExceeded {
+        value: String,
+        max: String,
+        message: String,
+    },
+    ContractDenied {
+        address: String,
+        message: String,
+    },
+    ContractNotAllowed {
+        address: String,
+        message: String,
+    },
+    SelectorBlocked {
+        selector: String,
+        message: String,
+    },
+    UnlimitedApproval {
+        spender: String,
+        message: String,
+    },
+    SetApprovalForAll {
+        operator: String,
+        message: String,
+    },
+    DailySpendCapExceeded {
+        spent: String,
+        value: String,
+        cap: String,
+        message: String,
+    },
+    /// A field the rules depend on could not be parsed, so the transaction cannot be checked
+    MalformedTransaction {
+        field: String,
+        value: String,
+        message: String,
+    },
+}
+
+impl PolicyViolation {
+    pub fn message(&self) -> &str {
+        match self {
+            PolicyViolation::ChainNotAllowed { message, .. }
+            | PolicyViolation::MaxValueExceeded { message, .. }
+            | PolicyViolation::ContractDenied { message, .. }
+            | PolicyViolation::ContractNotAllowed { message, .. }
+            | PolicyViolation::SelectorBlocked { message, .. }
+            | PolicyViolation::UnlimitedApproval { message, .. }
+            | PolicyViolation::SetApprovalForAll { message, .. }
+            | PolicyViolation::DailySpendCapExceeded { message, .. }
+            | PolicyViolation::MalformedTransaction { message, .. } => message,
+        }
+    }
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for PolicyViolation {}
+
+// ============================================================================
+// Engine
+// ============================================================================
+
+/// The transaction fields policies are evaluated against.
+#[derive(Debug, Clone, Copy)]
+pub struct PolicyTransaction<'a> {
+    pub chain_id: Option<u64>,
+    pub to: &'a str,
+    pub value: &'a str,
+    pub data: &'a str,
+}
+
+#[derive(Debug, Clone)]
+struct DailySpend {
+    day: NaiveDate,
+    /// Sent or reserved today; reservations are part of this until settled
+    spent: U256,
+    /// Reservations for requests still awaiting the wallet's answer, oldest first
+    pending: VecDeque<U256>,
+}
+
+impl DailySpend {
+    fn new(day: NaiveDate) -> Self {
+        Self {
+            day,
+            spent: U256::ZERO,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Remove the reservation of `amount` (or the oldest one) and return its amount
+    fn take_pending(&mut self, amount: Option<U256>) -> Option<U256> {
+        let index = amount
+            .and_then(|amount| self.pending.iter().position(|p| *p == amount))
+            .unwrap_or(0);
+        self.pending.remove(index)
+    }
+}
+
+pub struct PolicyEngine {
+    config: PolicyConfig,
+    /// Native value sent or reserved today, keyed by session id
+    daily_spend: DashMap<String, DailySpend>,
+}
+
+impl PolicyEngine {
+    pub fn new(config: PolicyConfig) -> Self {
+        Self {
+            config,
+            daily_spend: DashMap::new(),
+        }
+    }
+
+    /// Engine that allows every transaction.
+    pub fn permissive() -> Self {
+        Self::new(PolicyConfig::default())
+    }
+
+    fn resolve(&self, scope: &PolicyScope) -> ResolvedPolicy {
+        // Sections are validated on load, so resolution cannot fail here.
+        self.config
+            .rules_for(scope)
+            .resolve()
+            .expect("policy config validated on load")
+    }
+
+    /// Check a transaction against every rule and return all violations.
+    ///
+    /// Daily spend is only read here; call [`PolicyEngine::reserve_spend`] to
+    /// account for the transaction once it passes.
+    pub fn evaluate(
+        &self,
+        scope: &PolicyScope,
+        tx: &PolicyTransaction<'_>,
+    ) -> Vec<PolicyViolation> {
+        let policy = self.resolve(scope);
+        let mut violations = Vec::new();
+
+        if let Some(allowed) = &policy.allowed_chains
+            && !tx.chain_id.is_some_and(|id| allowed.contains(&id))
+        {
+            let target = tx
+                .chain_id
+                .map(|id| format!("chain {}", id))
+                .unwrap_or_else(|| "an unknown chain".to_string());
+            violations.push(PolicyViolation::ChainNotAllowed {
+                chain_id: tx.chain_id,
+                allowed: allowed.clone(),
+                message: format!(
+                    "Transactions on {} are not allowed. Allowed chains: {:?}",
+                    target, allowed
+                ),
+            });
+        }
+
+        let value = U256::from_str(tx.value).unwrap_or(U256::MAX);
+        if let Some(max) = policy.max_value
+            && value > max
+        {
+            violations.push(PolicyViolation::MaxValueExceeded {
+                value: tx.value.to_string(),
+                max: max.to_string(),
+                message: format!(
+                    "Transaction value {} wei exceeds the per-transaction limit of {} wei",
+                    tx.value, max
+                ),
+            });
+        }
+
+        if let Some(cap) = policy.daily_spend_cap {
+            let spent = self.spent_today(scope);
+            if spent.saturating_add(value) > cap {
+                violations.push(PolicyViolation::DailySpendCapExceeded {
+                    spent: spent.to_string(),
+                    value: tx.value.to_string(),
+                    cap: cap.to_string(),
+                    message: format!(
+                        "Sending {} wei would exceed the daily spend cap of {} wei ({} wei already sent today)",
+                        tx.value, cap, spent
+                    ),
+                });
+            }
+        }
+
+        // Fields that cannot be parsed fail closed: the rules reading them cannot be skipped
+        match Address::from_str(tx.to) {
+            Ok(to) => {
+                if policy.denied_contracts.contains(&to) {
+                    violations.push(PolicyViolation::ContractDenied {
+                        address: to.to_string(),
+                        message: format!("Recipient {} is on the deny list", to),
+                    });
+                }
+                if let Some(allowed) = &policy.allowed_contracts
+                    && !allowed.contains(&to)
+                {
+                    violations.push(PolicyViolation::ContractNotAllowed {
+                        address: to.to_string(),
+                        message: format!("Recipient {} is not on the allow list", to),
+                    });
+                }
+            }
+            Err(_) => violations.push(malformed("to", tx.to, "is not a valid address")),
+        }
+
+        let calldata = match hex::decode(tx.data.trim_start_matches("0x")) {
+            Ok(calldata) => calldata,
+            Err(_) => {
+                violations.push(malformed("data", tx.data, "is not valid hex calldata"));
+                Vec::new()
+            }
+        };
+        if calldata.len() >= 4 {
+            let selector: [u8; 4] = calldata[..4].try_into().expect("length checked");
+            let args = &calldata[4..];
+
+            if policy.blocked_selectors.contains(&selector) {
+                violations.push(PolicyViolation::SelectorBlocked {
+                    selector: format!("0x{}", hex::encode(selector)),
+                    message: format!(
+                        "Function selector 0x{} is blocked by policy",
+                        hex::encode(selector)
+                    ),
+                });
+            }
+
+            if policy.block_unlimited_approvals
+                && (selector == APPROVE_SELECTOR || selector == INCREASE_ALLOWANCE_SELECTOR)
+                && let Some((spender, amount)) = decode_address_word(args)
+                && amount >= UNLIMITED_APPROVAL_THRESHOLD
+            {
+                violations.push(PolicyViolation::UnlimitedApproval {
+                    spender: spender.to_string(),
+                    message: format!(
+                        "Unlimited token approval to {} is blocked; approve only the amount needed",
+                        spender
+                    ),
+                });
+            }
+
+            if policy.block_set_approval_for_all
+                && selector == SET_APPROVAL_FOR_ALL_SELECTOR
+                && let Some((operator, approved)) = decode_address_word(args)
+                && !approved.is_zero()
+            {
+                violations.push(PolicyViolation::SetApprovalForAll {
+                    operator: operator.to_string(),
+                    message: format!(
+                        "setApprovalForAll granting {} control of every token is blocked",
+                        operator
+                    ),
+                });
+            }
+        }
+
+        violations
+    }
+
+    /// Atomically add `value` to the session's daily spend if it stays under the cap.
+    ///
+    /// The reservation stays pending until [`PolicyEngine::settle_spend`] records the
+    /// wallet's answer, so concurrent requests cannot exceed the cap together.
+    pub fn reserve_spend(&self, scope: &PolicyScope, value: &str) -> Result<(), PolicyViolation> {
+        let Some(session_id) = scope.session_id.as_deref() else {
+            return Ok(());
+        };
+        let cap = self.resolve(scope).daily_spend_cap;
+        let amount = U256::from_str(value).unwrap_or(U256::MAX);
+        let today = Utc::now().date_naive();
+
+        let mut entry = self
+            .daily_spend
+            .entry(session_id.to_string())
+            .or_insert_with(|| DailySpend::new(today));
+        if entry.day != today {
+            *entry = DailySpend::new(today);
+        }
+
+        let next = entry.spent.saturating_add(amount);
+        if let Some(cap) = cap
+            && next > cap
+        {
+            return Err(PolicyViolation::DailySpendCapExceeded {
+                spent: entry.spent.to_string(),
+                value: value.to_string(),
+                cap: cap.to_string(),
+                message: format!(
+                    "Sending {} wei would exceed the daily spend cap of {} wei ({} wei already sent today)",
+                    value, cap, entry.spent
+                ),
+            });
+        }
+        entry.spent = next;
+        entry.pending.push_back(amount);
+        Ok(())
+    }
+
+    /// Undo a reservation when the wallet handoff fails.
+    pub fn release_spend(&self, scope: &PolicyScope, value: &str) {
+        self.settle_spend(scope, Some(value), false);
+    }
+
+    /// Record the outcome of a reserved transaction.
+    ///
+    /// A sent transaction keeps its reservation as spend; a rejected or failed one gives it
+    /// back. Only pass `sent = false` when the server knows the transaction never reached
+    /// the wallet: a client's own report of a rejection is not proof that nothing was sent. `value` picks the matching reservation; without it (or without a match) the
+    /// oldest pending one is settled.
+    pub fn settle_spend(&self, scope: &PolicyScope, value: Option<&str>, sent: bool) {
+        let Some(session_id) = scope.session_id.as_deref() else {
+            return;
+        };
+        let Some(mut entry) = self.daily_spend.get_mut(session_id) else {
+            return;
+        };
+        let amount = value.map(|v| U256::from_str(v).unwrap_or(U256::MAX));
+        // Reservations from a previous day were cleared with that day's spend
+        if let Some(reserved) = entry.take_pending(amount)
+            && !sent
+        {
+            entry.spent = entry.spent.saturating_sub(reserved);
+        }
+    }
+
+    fn spent_today(&self, scope: &PolicyScope) -> U256 {
+        let today = Utc::now().date_naive();
+        scope
+            .session_id
+            .as_deref()
+            .and_then(|id| self.daily_spend.get(id))
+            .filter(|entry| entry.day == today)
+            .map(|entry| entry.spent)
+            .unwrap_or(U256::ZERO)
+    }
+}
+
+fn malformed(field: &str, value: &str, problem: &str) -> PolicyViolation {
+    PolicyViolation::MalformedTransaction {
+        field: field.to_string(),
+        value: value.to_string(),
+        message: format!(
+            "Transaction field '{}' {}, so it cannot be checked against the policy",
+            field, problem
+        ),
+    }
+}
+
+/// Decode the leading `(address, uint256)` words of ABI-encoded arguments.
+fn decode_address_word(args: &[u8]) -> Option<(Address, U256)> {
+    if args.len() < 64 {
+        return None;
+    }
+    let address = Address::from_slice(&args[12..32]);
+    let word = U256::from_be_slice(&args[32..64]);
+    Some((address, word))
+}
+
+// ============================================================================
+// Engine Singleton
+// ============================================================================
+
+static POLICY_ENGINE: OnceCell<Arc<PolicyEngine>> = OnceCell::new();
+
+/// Get the global policy engine, loading `policies.toml` on first access.
+///
+/// Path resolution: `POLICIES_TOML` env var, then a walk up from the current
+/// directory. A missing file yields a permissive engine; an invalid file is an
+/// error so misconfigured guardrails never silently turn off.
+pub fn policy_engine() -> eyre::Result<Arc<PolicyEngine>> {
+    POLICY_ENGINE
+        .get_or_try_init(|| {
+            let engine = match resolve_policies_path()? {
+                Some(path) => {
+                    let config = PolicyConfig::from_file(&path)?;
+                    info!(
+                        path = %path.display(),
+                        namespaces = config.namespaces.len(),
+                        api_keys = config.api_keys.len(),
+                        "Loaded transaction policies"
+                    );
+                    PolicyEngine::new(config)
+                }
+                None => {
+                    warn!("No policies.toml found, wallet transactions are unrestricted");
+                    PolicyEngine::permissive()
+                }
+            };
+            Ok(Arc::new(engine))
+        })
+        .map(Arc::clone)
+}
+
+fn resolve_policies_path() -> eyre::Result<Option<PathBuf>> {
+    if let Ok(path) = std::env::var(POLICIES_TOML_ENV) {
+        let path = PathBuf::from(path);
+        if path.exists() {
+            return Ok(Some(path));
+        }
+        eyre::bail!(
+            "{} was set but not found: {}",
+            POLICIES_TOML_ENV,
+            path.display()
+        );
+    }
+
+    let mut dir = std::env::current_dir()?;
+    loop {
+        let candidate = dir.join("policies.toml");
+        if candidate.exists() {
+            return Ok(Some(candidate));
+        }
+        if !dir.pop() {
+            return Ok(None);
+        }
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USDC: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+    const SPENDER: &str = "000000000000000000000000742d35cc6634c0532925a3b844bc9e7595f33749";
+
+    fn engine(toml: &str) -> PolicyEngine {
+        PolicyEngine::new(PolicyConfig::from_toml_str(toml).unwrap())
+    }
+
+    fn scope(namespace: &str, api_key: Option<&str>) -> PolicyScope {
+        PolicyScope::new(
+            "session-1".to_string(),
+            namespace.to_string(),
+            api_key.map(String::from),
+        )
+    }
+
+    fn tx<'a>(chain_id: u64, to: &'a str, value: &'a str, data: &'a str) -> PolicyTransaction<'a> {
+        PolicyTransaction {
+            chain_id: Some(chain_id),
+            to,
+            value,
+            data,
+        }
+    }
+
+    #[test]
+    fn test_permissive_engine_allows_everything() {
+        let engine = PolicyEngine::permissive();
+        let violations = engine.evaluate(
+            &PolicyScope::default(),
+            &tx(1, USDC, "1000000000000000000000", "0x"),
+        );
+        assert!(violations.is_empty());
+    }
+
+    #[test]
+    fn test_max_value_and_chain_restrictions() {
+        let engine = engine(
+            r#"
+            [default]
+            max_value_wei = "1000"
+            allowed_chains = [1]
+            "#,
+        );
+        let violations = engine.evaluate(&scope("default", None), &tx(10, USDC, "1001", "0x"));
+        assert_eq!(violations.len(), 2);
+        assert!(matches!(
+            violations[0],
+            PolicyViolation::ChainNotAllowed { .. }
+        ));
+        assert!(matches!(
+            violations[1],
+            PolicyViolation::MaxValueExceeded { .. }
+        ));
+
+        let json = serde_json::to_value(&violations[1]).unwrap();
+        assert_eq!(json["rule"], "max_value_exceeded");
+        assert_eq!(json["max"], "1000");
+    }
+
+    #[test]
+    fn test_sections_layer_by_namespace_then_api_key() {
+        let engine = engine(
+            r#"
+            [default]
+            max_value_wei = "100"
+
+            [namespaces.forge]
+            max_value_wei = "1000"
+
+            [api_keys."tenant"]
+            denied_contracts = ["0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"]
+            "#,
+        );
+        let send = tx(1, USDC, "500", "0x");
+
+        assert_eq!(engine.evaluate(&scope("default", None), &send).len(), 1);
+        assert!(engine.evaluate(&scope("forge", None), &send).is_empty());
+
+        let violations = engine.evaluate(&scope("forge", Some("tenant")), &send);
+        assert_eq!(violations.len(), 1);
+        assert!(matches!(
+            violations[0],
+            PolicyViolation::ContractDenied { .. }
+        ));
+    }
+
+    #[test]
+    fn test_namespace_sections_match_case_insensitively() {
+        let engine = engine(
+            r#"
+            [namespaces.Polymarket]
+            max_value_wei = "10"
+            "#,
+        );
+        let send = tx(1, USDC, "500", "0x");
+        assert_eq!(engine.evaluate(&scope("polymarket", None), &send).len(), 1);
+        assert_eq!(engine.evaluate(&scope("POLYMARKET", None), &send).len(), 1);
+
+        let duplicate =
+            "[namespaces.Forge]\nmax_value_wei = \"1\"\n[namespaces.forge]\nmax_value_wei = \"2\"";
+        assert!(PolicyConfig::from_toml_str(duplicate).is_err());
+    }
+
+    #[test]
+    fn test_serialized_scope_omits_raw_api_key() {
+        let engine = engine(
+            r#"
+            [api_keys."tenant-secret"]
+            max_value_wei = "10"
+            "#,
+        );
+        let scope = scope("default", Some("tenant-secret"));
+        let json = serde_json::to_string(&scope).unwrap();
+        assert!(!json.contains("tenant-secret"));
+
+        // Policies still resolve from the id after a round trip
+        let restored: PolicyScope = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.api_key, None);
+        assert_eq!(restored.api_key_id, Some(api_key_id("tenant-secret")));
+        let violations = engine.evaluate(&restored, &tx(1, USDC, "500", "0x"));
+        assert_eq!(violations.len(), 1);
+    }
+
+    #[test]
+    fn test_allowlist_rejects_other_recipients() {
+        let engine = engine(
+            r#"
+            [default]
+            allowed_contracts = ["0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"]
+            "#,
+        );
+        let other = "0x742d35Cc6634C0532925a3b844Bc9e7595f33749";
+        assert!(
+            engine
+                .evaluate(&scope("default", None), &tx(1, USDC, "0", "0x"))
+                .is_empty()
+        );
+        let violations = engine.evaluate(&scope("default", None), &tx(1, other, "0", "0x"));
+        assert!(matches!(
+            violations[0],
+            PolicyViolation::ContractNotAllowed { .. }
+        ));
+    }
+
+    #[test]
+    fn test_blocks_unlimited_approval_and_set_approval_for_all() {
+        let engine = engine(
+            r#"
+            [default]
+            block_unlimited_approvals = true
+            block_set_approval_for_all = true
+            "#,
+        );
+        let scope = scope("default", None);
+
+        let unlimited = format!("0x095ea7b3{}{}", SPENDER, "f".repeat(64));
+        let violations = engine.evaluate(&scope, &tx(1, USDC, "0", &unlimited));
+        assert!(matches!(
+            violations[0],
+            PolicyViolation::UnlimitedApproval { .. }
+        ));
+
+        // approve(spender, 2**255)
+        let huge = format!("0x095ea7b3{}8{}", SPENDER, "0".repeat(63));
+        let violations = engine.evaluate(&scope, &tx(1, USDC, "0", &huge));
+        assert!(matches!(
+            violations[..],
+            [PolicyViolation::UnlimitedApproval { .. }]
+        ));
+
+        let bounded = format!("0x095ea7b3{}{:064x}", SPENDER, 1_000_000u64);
+        assert!(
+            engine
+                .evaluate(&scope, &tx(1, USDC, "0", &bounded))
+                .is_empty()
+        );
+
+        let approve_all = format!("0xa22cb465{}{:064x}", SPENDER, 1u8);
+        let violations = engine.evaluate(&scope, &tx(1, USDC, "0", &approve_all));
+        assert!(matches!(
+            violations[0],
+            PolicyViolation::SetApprovalForAll { .. }
+        ));
+
+        let revoke_all = format!("0xa22cb465{}{:064x}", SPENDER, 0u8);
+        assert!(
+            engine
+                .evaluate(&scope, &tx(1, USDC, "0", &revoke_all))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_blocked_selectors() {
+        let engine = engine(
+            r#"
+            [default]
+            blocked_selectors = ["0x23b872dd"]
+            "#,
+        );
+        let violations =
+            engine.evaluate(&scope("default", None), &tx(1, USDC, "0", "0x23b872dd0000"));
+        assert_eq!(
+            violations,
+            vec![PolicyViolation::SelectorBlocked {
+                selector: "0x23b872dd".to_string(),
+                message: "Function selector 0x23b872dd is blocked by policy".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_daily_spend_cap_accumulates_per_session() {
+        let engine = engine(
+            r#"
+            [default]
+            daily_spend_cap_wei = "1000"
+            "#,
+        );
+        let scope = scope("default", None);
+
+        assert!(engine.reserve_spend(&scope, "600").is_ok());
+        let violations = engine.evaluate(&scope, &tx(1, USDC, "500", "0x"));
+        assert!(matches!(
+            violations[0],
+            PolicyViolation::DailySpendCapExceeded { .. }
+        ));
+        assert!(engine.reserve_spend(&scope, "500").is_err());
+
+        engine.release_spend(&scope, "600");
+        assert!(engine.reserve_spend(&scope, "500").is_ok());
+
+        // The wallet rejected the 500 request: its budget comes back
+        engine.settle_spend(&scope, None, false);
+        assert!(engine.reserve_spend(&scope, "1000").is_ok());
+        // The 1000 request was sent: it stays spent, and settling twice is a no-op
+        engine.settle_spend(&scope, Some("1000"), true);
+        engine.settle_spend(&scope, Some("1000"), false);
+        assert!(engine.reserve_spend(&scope, "1").is_err());
+
+        let other = PolicyScope::new("session-2".to_string(), "default".to_string(), None);
+        assert!(
+            engine
+                .evaluate(&other, &tx(1, USDC, "900", "0x"))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_unparseable_fields_are_rejected() {
+        let engine = engine(
+            r#"
+            [default]
+            denied_contracts = ["0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"]
+            blocked_selectors = ["0x23b872dd"]
+            "#,
+        );
+        let scope = scope("default", None);
+
+        let violations = engine.evaluate(&scope, &tx(1, "usdc.eth", "0", "0x"));
+        assert!(matches!(
+            &violations[..],
+            [PolicyViolation::MalformedTransaction { field, .. }] if field == "to"
+        ));
+
+        let violations = engine.evaluate(&scope, &tx(1, USDC, "0", "0x23b872dzz"));
+        assert_eq!(violations.len(), 2);
+        assert!(matches!(
+            &violations[1],
+            PolicyViolation::MalformedTransaction { field, .. } if field == "data"
+        ));
+
+        // A permissive policy still refuses what it cannot read
+        let violations =
+            PolicyEngine::permissive().evaluate(&scope, &tx(1, USDC, "0", "0xnot-hex"));
+        assert_eq!(violations.len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_config_is_rejected() {
+        assert!(PolicyConfig::from_toml_str("[default]\nmax_value_wei = \"lots\"").is_err());
+        assert!(PolicyConfig::from_toml_str("[default]\nblocked_selectors = [\"0x12\"]").is_err());
+        assert!(PolicyConfig::from_toml_str("[default]\nunknown_rule = true").is_err());
+    }
+}
@@ -0,0 +1,570 @@
+//! Multi-chain portfolio aggregation.
+//!
+//! `get_portfolio` fans out over every chain the ProviderManager knows about and
+//! collects native and ERC20 balances into one normalized table, so the agent
+//! doesn't have to chain `get_account_info` and per-token calls by hand.
+
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::sync::Arc;
+
+use alloy::primitives::U256;
+use futures::future::join_all;
+use rig::tool::ToolError;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::task;
+use tracing::{debug, warn};
+
+use super::cast::ERC20;
+use super::gateway::{EvmGateway, get_gateway};
+use crate::clients::{CastClient, external_clients};
+use crate::{AomiTool, AomiToolArgs, ToolCallCtx, with_topic};
+
+/// Tokens checked on every chain unless `include_default_tokens` is false:
+/// (chain_id, address, symbol, decimals).
+const DEFAULT_TOKENS: &[(u64, &str, &str, u8)] = &[
+    // Ethereum
+    (1, "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48", "USDC", 6),
+    (1, "0xdAC17F958D2ee523a2206206994597C13D831ec7", "USDT", 6),
+    (1, "0x6B175474E89094C44Da98b954EedeAC495271d0F", "DAI", 18),
+    (1, "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2", "WETH", 18),
+    (1, "0x2260FAC5E5542a773Aa44fBCfeDf7C193bc2C599", "WBTC", 8),
+    // Optimism
+    (10, "0x0b2C639c533813f4Aa9D7837CAf62653d097Ff85", "USDC", 6),
+    (10, "0x4200000000000000000000000000000000000006", "WETH", 18),
+    // Polygon
+    (137, "0x3c499c542cEF5E3811e1192ce70d8cC03d5c3359", "USDC", 6),
+    (
+        137,
+        "0x7ceB23fD6bC0adD59E62ac25578270cFf1b9f619",
+        "WETH",
+        18,
+    ),
+    // Base
+    (
+        8453,
+        "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
+        "USDC",
+        6,
+    ),
+    (
+        8453,
+        "0x4200000000000000000000000000000000000006",
+        "WETH",
+        18,
+    ),
+    // Arbitrum One
+    (
+        42161,
+        "0xaf88d065e77c8cC2239327C5EDb3A432268e5831",
+        "USDC",
+        6,
+    ),
+    (
+        42161,
+        "0x82aF49447D8a07e3bd95BD0d56f35241523fBab1",
+        "WETH",
+        18,
+    ),
+];
+
+fn native_symbol(chain_id: u64) -> &'static str {
+    match chain_id {
+        56 | 97 => "BNB",
+        100 => "xDAI",
+        137 | 80002 => "POL",
+        43114 | 43113 => "AVAX",
+        _ => "ETH",
+    }
+}
+
+// ============================================================================
+// Tool Definition
+// ============================================================================
+
+/// Tool for aggregating native and ERC20 balances across all supported chains
+#[derive(Debug, Clone)]
+pub struct GetPortfolio;
+
+// ============================================================================
+// Argument Types
+// ============================================================================
+
+/// ERC20 token to include in the portfolio.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioToken {
+    pub chain_id: u64,
+    pub address: String,
+    /// Fallback symbol if the contract's symbol() call fails
+    #[serde(default)]
+    pub symbol: Option<String>,
+    /// Known decimals, skips the decimals() call
+    #[serde(default)]
+    pub decimals: Option<u8>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetPortfolioArgs {
+    pub address: String,
+    /// Restrict to these chains (default: every supported chain)
+    #[serde(default)]
+    pub chain_ids: Option<Vec<u64>>,
+    /// Extra tokens to check on top of the default list
+    #[serde(default)]
+    pub tokens: Vec<PortfolioToken>,
+    #[serde(default = "default_true")]
+    pub include_default_tokens: bool,
+    #[serde(default)]
+    pub include_zero_balances: bool,
+}
+
+impl AomiToolArgs for GetPortfolioArgs {
+    fn schema() -> serde_json::Value {
+        with_topic(json!({
+            "type": "object",
+            "properties": {
+                "address": {
+                    "type": "string",
+                    "description": "The wallet address to aggregate (0x-prefixed, 42 characters)"
+                },
+                "chain_ids": {
+                    "type": "array",
+                    "items": { "type": "number" },
+                    "description": "Only query these chain IDs. Omit to query every supported chain"
+                },
+                "tokens": {
+                    "type": "array",
+                    "description": "Additional ERC20 tokens to check",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "chain_id": { "type": "number" },
+                            "address": { "type": "string" },
+                            "symbol": { "type": "string" },
+                            "decimals": { "type": "number" }
+                        },
+                        "required": ["chain_id", "address"]
+                    }
+                },
+                "include_default_tokens": {
+                    "type": "boolean",
+                    "description": "Check common stablecoins and wrapped assets on each chain (default: true)"
+                },
+                "include_zero_balances": {
+                    "type": "boolean",
+                    "description": "Keep rows with a zero balance (default: false)"
+                }
+            },
+            "required": ["address"]
+        }))
+    }
+}
+
+// ============================================================================
+// Output Types
+// ============================================================================
+
+/// One row of the portfolio table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioRow {
+    pub chain_id: u64,
+    pub network: Option<String>,
+    pub asset: String,
+    /// `None` for the chain's native asset
+    pub token_address: Option<String>,
+    pub balance_raw: String,
+    pub decimals: Option<u8>,
+    /// Human-readable balance, when decimals are known
+    pub balance: Option<String>,
+}
+
+/// A balance that could not be fetched.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioError {
+    pub chain_id: u64,
+    pub token_address: Option<String>,
+    pub error: String,
+}
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+fn run_sync<F, T>(future: F) -> Result<T, ToolError>
+where
+    F: Future<Output = Result<T, ToolError>> + Send + 'static,
+    T: Send + 'static,
+{
+    task::block_in_place(|| tokio::runtime::Handle::current().block_on(future))
+}
+
+/// Tokens to check on `chain_id`: defaults (optionally) plus user tokens,
+/// deduplicated by address with user entries taking precedence.
+fn tokens_for_chain(
+    chain_id: u64,
+    extra: &[PortfolioToken],
+    include_defaults: bool,
+) -> Vec<PortfolioToken> {
+    let mut tokens: Vec<PortfolioToken> = extra
+        .iter()
+        .filter(|t| t.chain_id == chain_id)
+        .cloned()
+        .collect();
+
+    if include_defaults {
+        for &(id, address, symbol, decimals) in DEFAULT_TOKENS {
+            if id == chain_id
+                && !tokens
+                    .iter()
+                    .any(|t| t.address.eq_ignore_ascii_case(address))
+            {
+                tokens.push(PortfolioToken {
+                    chain_id,
+                    address: address.to_string(),
+                    symbol: Some(symbol.to_string()),
+                    decimals: Some(decimals),
+                });
+            }
+        }
+    }
+    tokens
+}
+
+/// Formats a raw integer amount with `decimals`, trimming trailing zeros.
+fn format_units(raw: U256, decimals: u8) -> String {
+    let divisor = U256::from(10u64).pow(U256::from(decimals));
+    let whole = raw / divisor;
+    let frac = raw % divisor;
+    if frac.is_zero() {
+        return whole.to_string();
+    }
+    let frac = format!("{:0>width$}", frac.to_string(), width = decimals as usize);
+    format!("{}.{}", whole, frac.trim_end_matches('0'))
+}
+
+fn row(
+    chain_id: u64,
+    network: Option<String>,
+    asset: String,
+    token_address: Option<String>,
+    raw: U256,
+    decimals: Option<u8>,
+) -> PortfolioRow {
+    PortfolioRow {
+        chain_id,
+        network,
+        asset,
+        token_address,
+        balance_raw: raw.to_string(),
+        decimals,
+        balance: decimals.map(|d| format_units(raw, d)),
+    }
+}
+
+struct ChainQuery {
+    chain_id: u64,
+    network: Option<String>,
+    cast: Option<Arc<CastClient>>,
+    address: String,
+    include_zero: bool,
+}
+
+impl ChainQuery {
+    async fn native(&self, gateway: &dyn EvmGateway) -> Result<Option<PortfolioRow>, String> {
+        let info = gateway
+            .get_account_info(self.chain_id, &self.address)
+            .await
+            .map_err(|e| e.to_string())?;
+        let raw = info
+            .balance
+            .parse::<U256>()
+            .map_err(|e| format!("Invalid native balance '{}': {e}", info.balance))?;
+        if raw.is_zero() && !self.include_zero {
+            return Ok(None);
+        }
+        Ok(Some(row(
+            self.chain_id,
+            self.network.clone(),
+            native_symbol(self.chain_id).to_string(),
+            None,
+            raw,
+            Some(18),
+        )))
+    }
+
+    async fn token(
+        &self,
+        gateway: &dyn EvmGateway,
+        token: PortfolioToken,
+    ) -> Result<Option<PortfolioRow>, String> {
+        let raw = match gateway
+            .get_erc20_balance(self.chain_id, &token.address, &self.address, None)
+            .await
+        {
+            Ok(result) => result
+                .balance
+                .parse::<U256>()
+                .map_err(|e| format!("Invalid token balance '{}': {e}", result.balance))?,
+            Err(gateway_err) => {
+                // Gateways without an RPC fallback (e.g. local mode) still have Cast
+                let cast = self.cast.as_ref().ok_or_else(|| gateway_err.to_string())?;
+                cast.get_balance_of(&token.address, &self.address)
+                    .await
+                    .ok_or_else(|| format!("{gateway_err}; balanceOf via RPC also failed"))?
+            }
+        };
+        if raw.is_zero() && !self.include_zero {
+            return Ok(None);
+        }
+
+        let (symbol, decimals) = match &self.cast {
+            Some(cast) => {
+                let symbol = cast.get_symbol(&token.address).await.or(token.symbol);
+                let decimals = match token.decimals {
+                    Some(d) => Some(d),
+                    None => cast.get_decimals(&token.address).await,
+                };
+                (symbol, decimals)
+            }
+            None => (token.symbol, token.decimals),
+        };
+
+        Ok(Some(row(
+            self.chain_id,
+            self.network.clone(),
+            symbol.unwrap_or_else(|| "UNKNOWN".to_string()),
+            Some(token.address.to_lowercase()),
+            raw,
+            decimals,
+        )))
+    }
+}
+
+// ============================================================================
+// Execute Function
+// ============================================================================
+
+/// Execute get_portfolio across all requested chains concurrently.
+///
+/// Failures on one chain or token are reported in `errors` and never fail the
+/// whole call.
+pub async fn execute_get_portfolio(args: GetPortfolioArgs) -> Result<serde_json::Value, ToolError> {
+    debug!("get_portfolio tool called with args: {:?}", args);
+
+    run_sync(async move {
+        let gateway = get_gateway().await.map_err(|e| {
+            ToolError::ToolCallError(format!("Failed to get gateway: {}", e).into())
+        })?;
+        let provider_manager = aomi_anvil::provider_manager().await.map_err(|e| {
+            ToolError::ToolCallError(format!("Failed to get provider manager: {}", e).into())
+        })?;
+        let clients = external_clients().await;
+
+        let supported: BTreeSet<u64> = provider_manager.supported_chain_ids().into_iter().collect();
+        let mut errors = Vec::new();
+        let chain_ids: Vec<u64> = match &args.chain_ids {
+            Some(requested) => requested
+                .iter()
+                .copied()
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .filter(|id| {
+                    let ok = supported.contains(id);
+                    if !ok {
+                        errors.push(PortfolioError {
+                            chain_id: *id,
+                            token_address: None,
+                            error: format!(
+                                "Chain {} is not supported. Supported chains: {:?}",
+                                id, supported
+                            ),
+                        });
+                    }
+                    ok
+                })
+                .collect(),
+            None => supported.iter().copied().collect(),
+        };
+
+        let mut queries = Vec::with_capacity(chain_ids.len());
+        for chain_id in &chain_ids {
+            let network = provider_manager.network_key_for_chain(*chain_id);
+            let cast = match &network {
+                Some(key) => clients
+                    .get_cast_client(key)
+                    .await
+                    .inspect_err(|e| warn!("No Cast client for chain {}: {}", chain_id, e))
+                    .ok(),
+                None => None,
+            };
+            queries.push(ChainQuery {
+                chain_id: *chain_id,
+                network,
+                cast,
+                address: args.address.clone(),
+                include_zero: args.include_zero_balances,
+            });
+        }
+
+        let gateway = gateway.as_ref();
+        let per_chain = join_all(queries.iter().map(|query| async move {
+            let tokens =
+                tokens_for_chain(query.chain_id, &args.tokens, args.include_default_tokens);
+            let addresses: Vec<Option<String>> = std::iter::once(None)
+                .chain(tokens.iter().map(|t| Some(t.address.to_lowercase())))
+                .collect();
+
+            let native = query.native(gateway);
+            let token_rows = join_all(tokens.into_iter().map(|t| query.token(gateway, t)));
+            let (native, token_rows) = futures::join!(native, token_rows);
+
+            std::iter::once(native)
+                .chain(token_rows)
+                .zip(addresses)
+                .map(|(result, token_address)| (query.chain_id, token_address, result))
+                .collect::<Vec<_>>()
+        }))
+        .await;
+
+        let mut holdings = Vec::new();
+        for (chain_id, token_address, result) in per_chain.into_iter().flatten() {
+            match result {
+                Ok(Some(row)) => holdings.push(row),
+                Ok(None) => {}
+                Err(error) => errors.push(PortfolioError {
+                    chain_id,
+                    token_address,
+                    error,
+                }),
+            }
+        }
+
+        debug!(
+            "get_portfolio: {} holdings, {} errors across {} chains",
+            holdings.len(),
+            errors.len(),
+            chain_ids.len()
+        );
+
+        Ok(json!({
+            "address": args.address,
+            "chains_queried": chain_ids,
+            "holdings": holdings,
+            "errors": errors,
+        }))
+    })
+}
+
+// ============================================================================
+// AomiTool Implementation
+// ============================================================================
+
+impl AomiTool for GetPortfolio {
+    const NAME: &'static str = "get_portfolio";
+
+    type Args = GetPortfolioArgs;
+    type Output = serde_json::Value;
+    type Error = ToolError;
+
+    fn support_async(&self) -> bool {
+        true
+    }
+
+    /// Balance scans are read-only, so a restart can safely run them again
+    fn is_resumable(&self) -> bool {
+        true
+    }
+
+    fn description(&self) -> &'static str {
+        "[Async Tool] Aggregate native and ERC20 balances for an address across all supported chains into one table."
+    }
+
+    fn run_sync(
+        &self,
+        _ctx: ToolCallCtx,
+        args: Self::Args,
+    ) -> impl std::future::Future<Output = eyre::Result<serde_json::Value>> + Send {
+        async move {
+            execute_get_portfolio(args)
+                .await
+                .map_err(|e| eyre::eyre!(e.to_string()))
+        }
+    }
+
+    fn run_async(
+        &self,
+        sender: mpsc::Sender<(eyre::Result<serde_json::Value>, bool)>,
+        _ctx: ToolCallCtx,
+        args: Self::Args,
+    ) -> impl std::future::Future<Output = ()> + Send {
+        async move {
+            let result = execute_get_portfolio(args)
+                .await
+                .map_err(|e| eyre::eyre!(e.to_string()));
+            let _ = sender.send((result, false)).await;
+        }
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_units() {
+        assert_eq!(format_units(U256::from(1_500_000u64), 6), "1.5");
+        assert_eq!(format_units(U256::from(42u64), 0), "42");
+        assert_eq!(format_units(U256::from(1u64), 18), "0.000000000000000001");
+        assert_eq!(
+            format_units(U256::from(2_000_000_000_000_000_000u128), 18),
+            "2"
+        );
+    }
+
+    #[test]
+    fn test_tokens_for_chain_merges_defaults() {
+        let extra = vec![
+            PortfolioToken {
+                chain_id: 1,
+                address: "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48".to_string(),
+                symbol: Some("USDC.e".to_string()),
+                decimals: None,
+            },
+            PortfolioToken {
+                chain_id: 8453,
+                address: "0x0000000000000000000000000000000000000001".to_string(),
+                symbol: None,
+                decimals: None,
+            },
+        ];
+
+        let mainnet = tokens_for_chain(1, &extra, true);
+        let defaults = DEFAULT_TOKENS.iter().filter(|t| t.0 == 1).count();
+        assert_eq!(mainnet.len(), defaults);
+        // User entry overrides the default with the same address
+        assert_eq!(mainnet[0].symbol.as_deref(), Some("USDC.e"));
+
+        let base = tokens_for_chain(8453, &extra, false);
+        assert_eq!(base.len(), 1);
+        assert!(tokens_for_chain(31337, &extra, true).is_empty());
+    }
+
+    #[test]
+    fn test_args_defaults() {
+        let args: GetPortfolioArgs = serde_json::from_value(json!({ "address": "0xabc" })).unwrap();
+        assert!(args.include_default_tokens);
+        assert!(!args.include_zero_balances);
+        assert!(args.chain_ids.is_none());
+        assert!(args.tokens.is_empty());
+    }
+}
@@ -0,0 +1,348 @@
+//! On-chain proxy detection.
+//!
+//! Etherscan only flags proxies it has verified, so unverified contracts and local forks
+//! never get an implementation address. [`detect_proxy`] reads the standard proxy storage
+//! slots directly through a [`CastClient`] and also recognises Safe singletons and
+//! EIP-2535 diamonds.
+
+use alloy::{
+    dyn_abi::{DynSolType, DynSolValue},
+    primitives::{Address, B256, Bytes, U256, b256},
+    rpc::types::{TransactionInput, TransactionRequest},
+};
+use alloy_provider::Provider;
+use rig::tool::ToolError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use tracing::debug;
+
+use crate::cast::tool_error;
+use crate::clients::CastClient;
+
+/// `keccak256("eip1967.proxy.implementation") - 1`
+pub const EIP1967_IMPLEMENTATION_SLOT: B256 =
+    b256!("360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc");
+/// `keccak256("eip1967.proxy.beacon") - 1`
+pub const EIP1967_BEACON_SLOT: B256 =
+    b256!("a3f0ad74e5423aebfd80d3ef4346578335a9a72aeaee59ff6cb3582b35133d50");
+/// `keccak256("PROXIABLE")`
+pub const EIP1822_PROXIABLE_SLOT: B256 =
+    b256!("c5f16f0fcc639fa48a6947836d9850f504798523bf8c9a3a87d5876cf622bcf7");
+
+/// `implementation()`, exposed by EIP-1967 beacons
+const IMPLEMENTATION_SELECTOR: [u8; 4] = [0x5c, 0x60, 0xda, 0x1b];
+/// `masterCopy()`, exposed by Safe proxies
+const MASTER_COPY_SELECTOR: [u8; 4] = [0xa6, 0x19, 0x48, 0x6e];
+/// `facets()`, the EIP-2535 loupe
+const FACETS_SELECTOR: [u8; 4] = [0x7a, 0x0e, 0xd6, 0x27];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyKind {
+    Eip1967,
+    Eip1967Beacon,
+    Eip1822,
+    Safe,
+    Diamond,
+}
+
+/// A facet of an EIP-2535 diamond and the selectors routed to it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Facet {
+    pub address: Address,
+    pub selectors: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxyInfo {
+    pub kind: ProxyKind,
+    /// Contract calls are delegated to; None for diamonds, which route per selector
+    pub implementation: Option<Address>,
+    /// Beacon the implementation was read from
+    pub beacon: Option<Address>,
+    pub facets: Vec<Facet>,
+}
+
+impl ProxyInfo {
+    /// Every contract whose ABI the proxy exposes
+    pub fn implementations(&self) -> Vec<Address> {
+        match self.implementation {
+            Some(implementation) => vec![implementation],
+            None => self.facets.iter().map(|facet| facet.address).collect(),
+        }
+    }
+}
+
+/// Detects whether `address` is a proxy and which contract(s) it delegates to.
+///
+/// Returns `Ok(None)` for contracts that match none of the supported patterns. Only storage
+/// reads are treated as errors; a failing probe call just means the pattern does not apply.
+pub async fn detect_proxy(
+    client: &CastClient,
+    address: Address,
+) -> Result<Option<ProxyInfo>, ToolError> {
+    if let Some(implementation) =
+        read_slot_address(client, address, EIP1967_IMPLEMENTATION_SLOT).await?
+        && has_code(client, implementation).await
+    {
+        return Ok(Some(single(ProxyKind::Eip1967, implementation)));
+    }
+
+    if let Some(beacon) = read_slot_address(client, address, EIP1967_BEACON_SLOT).await?
+        && let Some(implementation) = call_address(client, beacon, IMPLEMENTATION_SELECTOR).await
+        && has_code(client, implementation).await
+    {
+        return Ok(Some(ProxyInfo {
+            beacon: Some(beacon),
+            ..single(ProxyKind::Eip1967Beacon, implementation)
+        }));
+    }
+
+    if let Some(implementation) = read_slot_address(client, address, EIP1822_PROXIABLE_SLOT).await?
+        && has_code(client, implementation).await
+    {
+        return Ok(Some(single(ProxyKind::Eip1822, implementation)));
+    }
+
+    // Safe proxies keep the singleton in slot 0 and echo it from `masterCopy()`
+    if let Some(singleton) = read_slot_address(client, address, B256::ZERO).await?
+        && call_address(client, address, MASTER_COPY_SELECTOR).await == Some(singleton)
+        && has_code(client, singleton).await
+    {
+        return Ok(Some(single(ProxyKind::Safe, singleton)));
+    }
+
+    if let Some(facets) = call_facets(client, address).await {
+        return Ok(Some(ProxyInfo {
+            kind: ProxyKind::Diamond,
+            implementation: None,
+            beacon: None,
+            facets,
+        }));
+    }
+
+    Ok(None)
+}
+
+fn single(kind: ProxyKind, implementation: Address) -> ProxyInfo {
+    ProxyInfo {
+        kind,
+        implementation: Some(implementation),
+        beacon: None,
+        facets: Vec::new(),
+    }
+}
+
+async fn read_slot_address(
+    client: &CastClient,
+    address: Address,
+    slot: B256,
+) -> Result<Option<Address>, ToolError> {
+    let value = client
+        .provider
+        .get_storage_at(address, U256::from_be_bytes(slot.0))
+        .await
+        .map_err(|e| tool_error(format!("Failed to read storage of {address}: {e}")))?;
+    Ok(slot_address(value))
+}
+
+/// The address stored in the low 20 bytes of a storage word, if the word holds only that
+fn slot_address(value: U256) -> Option<Address> {
+    let bytes: [u8; 32] = value.to_be_bytes();
+    if bytes[..12].iter().any(|b| *b != 0) {
+        return None;
+    }
+    let address = Address::from_slice(&bytes[12..32]);
+    (!address.is_zero()).then_some(address)
+}
+
+async fn has_code(client: &CastClient, address: Address) -> bool {
+    match client.provider.get_code_at(address).await {
+        Ok(code) => !code.is_empty(),
+        Err(e) => {
+            debug!(%address, error = %e, "failed to fetch code while resolving proxy");
+            false
+        }
+    }
+}
+
+async fn call(client: &CastClient, to: Address, selector: [u8; 4]) -> Option<Bytes> {
+    let tx = TransactionRequest::default()
+        .to(to)
+        .input(TransactionInput::new(Bytes::from(selector.to_vec())))
+        .with_input_and_data();
+    client.provider.call(tx.into()).await.ok()
+}
+
+async fn call_address(client: &CastClient, to: Address, selector: [u8; 4]) -> Option<Address> {
+    let raw = call(client, to, selector).await?;
+    match DynSolType::Address.abi_decode(&raw).ok()? {
+        DynSolValue::Address(address) if !address.is_zero() => Some(address),
+        _ => None,
+    }
+}
+
+async fn call_facets(client: &CastClient, diamond: Address) -> Option<Vec<Facet>> {
+    let raw = call(client, diamond, FACETS_SELECTOR).await?;
+    let facets = decode_facets(&raw)?;
+    (!facets.is_empty()).then_some(facets)
+}
+
+/// Decodes the `(address facetAddress, bytes4[] functionSelectors)[]` returned by `facets()`
+fn decode_facets(raw: &[u8]) -> Option<Vec<Facet>> {
+    let ty = DynSolType::Array(Box::new(DynSolType::Tuple(vec![
+        DynSolType::Address,
+        DynSolType::Array(Box::new(DynSolType::FixedBytes(4))),
+    ])));
+    let DynSolValue::Array(entries) = ty.abi_decode(raw).ok()? else {
+        return None;
+    };
+
+    let mut facets = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let DynSolValue::Tuple(fields) = entry else {
+            return None;
+        };
+        let [DynSolValue::Address(address), DynSolValue::Array(selectors)] = fields.as_slice()
+        else {
+            return None;
+        };
+        let selectors = selectors
+            .iter()
+            .filter_map(|selector| match selector {
+                DynSolValue::FixedBytes(word, 4) => Some(format!("0x{}", hex::encode(&word[..4]))),
+                _ => None,
+            })
+            .collect();
+        facets.push(Facet {
+            address: *address,
+            selectors,
+        });
+    }
+    Some(facets)
+}
+
+/// Appends the implementation ABIs to the proxy's own, skipping entries the proxy already
+/// exposes and implementation constructors, which cannot be reached through the proxy.
+pub fn merge_abis(proxy_abi: &Value, implementation_abis: &[Value]) -> Value {
+    let mut merged = Vec::new();
+    let mut seen = HashSet::new();
+
+    let proxy_entries = proxy_abi.as_array().into_iter().flatten();
+    let implementation_entries = implementation_abis
+        .iter()
+        .flat_map(|abi| abi.as_array().into_iter().flatten())
+        .filter(|entry| entry.get("type").and_then(Value::as_str) != Some("constructor"));
+
+    for entry in proxy_entries.chain(implementation_entries) {
+        if seen.insert(abi_entry_key(entry)) {
+            merged.push(entry.clone());
+        }
+    }
+    Value::Array(merged)
+}
+
+/// Identifies an ABI entry by kind, name and input types
+fn abi_entry_key(entry: &Value) -> String {
+    let kind = entry
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or("function");
+    let name = entry.get("name").and_then(Value::as_str).unwrap_or("");
+    let inputs: Vec<&str> = entry
+        .get("inputs")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|input| input.get("type").and_then(Value::as_str).unwrap_or(""))
+        .collect();
+    format!("{kind}:{name}({})", inputs.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{address, keccak256};
+    use serde_json::json;
+
+    #[test]
+    fn test_slot_constants() {
+        let minus_one =
+            |label: &str| B256::from(U256::from_be_bytes(keccak256(label).0) - U256::from(1));
+        assert_eq!(
+            EIP1967_IMPLEMENTATION_SLOT,
+            minus_one("eip1967.proxy.implementation")
+        );
+        assert_eq!(EIP1967_BEACON_SLOT, minus_one("eip1967.proxy.beacon"));
+        assert_eq!(EIP1822_PROXIABLE_SLOT, keccak256("PROXIABLE"));
+
+        assert_eq!(IMPLEMENTATION_SELECTOR, keccak256("implementation()")[..4]);
+        assert_eq!(MASTER_COPY_SELECTOR, keccak256("masterCopy()")[..4]);
+        assert_eq!(FACETS_SELECTOR, keccak256("facets()")[..4]);
+    }
+
+    #[test]
+    fn test_slot_address() {
+        let implementation = address!("43506849d7c04f9138d1a2050bbf3a0c054402dd");
+        let word = U256::from_be_slice(implementation.as_slice());
+        assert_eq!(slot_address(word), Some(implementation));
+        assert_eq!(slot_address(U256::ZERO), None);
+        // Packed slots (e.g. an owner next to a flag) are not addresses
+        assert_eq!(slot_address(word | (U256::from(1) << 200)), None);
+    }
+
+    #[test]
+    fn test_decode_facets() {
+        let facet = address!("00000000000000000000000000000000000000aa");
+        let encoded = DynSolValue::Array(vec![DynSolValue::Tuple(vec![
+            DynSolValue::Address(facet),
+            DynSolValue::Array(vec![DynSolValue::FixedBytes(
+                B256::right_padding_from(&[0xcd, 0xff, 0xac, 0xc6]),
+                4,
+            )]),
+        ])])
+        .abi_encode();
+
+        let facets = decode_facets(&encoded).expect("decodes");
+        assert_eq!(
+            facets,
+            vec![Facet {
+                address: facet,
+                selectors: vec!["0xcdffacc6".to_string()],
+            }]
+        );
+        assert_eq!(decode_facets(&[0u8; 3]), None);
+    }
+
+    #[test]
+    fn test_merge_abis() {
+        let proxy = json!([
+            {"type": "constructor", "inputs": [{"type": "address"}]},
+            {"type": "function", "name": "upgradeTo", "inputs": [{"type": "address"}]},
+        ]);
+        let implementation = json!([
+            {"type": "constructor", "inputs": []},
+            {"type": "function", "name": "upgradeTo", "inputs": [{"type": "address"}]},
+            {"type": "function", "name": "transfer", "inputs": [{"type": "address"}, {"type": "uint256"}]},
+            {"type": "event", "name": "Transfer", "inputs": [{"type": "address"}, {"type": "address"}, {"type": "uint256"}]},
+        ]);
+
+        let merged = merge_abis(&proxy, &[implementation]);
+        let keys: Vec<_> = merged
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(abi_entry_key)
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                "constructor:(address)",
+                "function:upgradeTo(address)",
+                "function:transfer(address,uint256)",
+                "event:Transfer(address,address,uint256)",
+            ]
+        );
+    }
+}
@@ -0,0 +1,415 @@
+//! Transaction simulation on a forked chain.
+//!
+//! Used to build a preview of a wallet transaction before it is handed to the
+//! user for signing. The call is executed against a fork of the target chain
+//! obtained from `ProviderManager::get_backend`, and the outcome is summarized
+//! as success/revert, gas used, decoded logs and balance deltas for the sender.
+
+use alloy::primitives::{Address, B256, Bytes, Log, U256, keccak256};
+use aomi_anvil::ForkQuery;
+use foundry_evm::backend::Backend;
+use foundry_evm::executors::{ExecutorBuilder, RawCallResult};
+use foundry_evm::opts::EvmOpts;
+use foundry_evm::revm::Database;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use tracing::{debug, info};
+
+/// Gas limit used for simulated calls.
+const SIMULATION_GAS_LIMIT: u64 = 30_000_000;
+
+const TRANSFER_EVENT: &str = "Transfer(address,address,uint256)";
+const APPROVAL_EVENT: &str = "Approval(address,address,uint256)";
+const APPROVAL_FOR_ALL_EVENT: &str = "ApprovalForAll(address,address,bool)";
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Outcome of simulating a transaction on a fork of the target chain.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TransactionSimulation {
+    pub chain_id: u64,
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revert_reason: Option<String>,
+    pub gas_used: u64,
+    pub logs: Vec<SimulatedLog>,
+    pub balance_changes: BalanceChanges,
+}
+
+/// A log emitted during simulation, decoded when the event is well known.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SimulatedLog {
+    pub address: String,
+    /// Event signature if recognized (e.g. `Transfer(address,address,uint256)`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event: Option<String>,
+    /// Decoded event parameters keyed by name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+    pub topics: Vec<String>,
+    pub data: String,
+}
+
+/// Balance changes observed for the sender.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct BalanceChanges {
+    pub native: NativeBalanceChange,
+    pub erc20: Vec<TokenBalanceChange>,
+}
+
+/// Native (ETH) balance of the sender before and after the call, in wei.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct NativeBalanceChange {
+    pub before: String,
+    pub after: String,
+    /// Signed difference (`after - before`) in wei
+    pub delta: String,
+}
+
+/// Net ERC20 movement for the sender, derived from `Transfer` logs.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TokenBalanceChange {
+    pub token: String,
+    /// Signed net amount in base units (incoming minus outgoing)
+    pub delta: String,
+}
+
+// ============================================================================
+// Simulation
+// ============================================================================
+
+/// Simulate `from -> to` with `value` and `data` on a fork of `chain_id`.
+///
+/// Reverts are reported through `TransactionSimulation::success` and
+/// `revert_reason`; errors are only returned when the fork itself cannot be
+/// created or the call cannot be executed.
+pub async fn simulate_transaction(
+    chain_id: u64,
+    from: &str,
+    to: &str,
+    value: &str,
+    data: &str,
+) -> eyre::Result<TransactionSimulation> {
+    let from = Address::from_str(from)?;
+    let to = Address::from_str(to)?;
+    let value = U256::from_str(value)?;
+    let calldata = Bytes::from_str(if data.is_empty() { "0x" } else { data })?;
+
+    let manager = aomi_anvil::provider_manager()
+        .await
+        .map_err(|e| eyre::eyre!("Failed to get provider manager: {}", e))?;
+    let instance = manager
+        .get_instance_info_by_query(Some(chain_id), None)
+        .ok_or_else(|| eyre::eyre!("No provider configured for chain {}", chain_id))?;
+
+    debug!(
+        chain_id,
+        block = instance.block_number,
+        %from,
+        %to,
+        %value,
+        "Forking chain for transaction simulation"
+    );
+
+    // The backend forks the instance at its pinned block; build the env at the same block
+    let backend = manager
+        .get_backend(vec![
+            ForkQuery::new()
+                .with_chain_id(chain_id)
+                .with_block_number(instance.block_number),
+        ])
+        .await
+        .map_err(|e| eyre::eyre!("Failed to fork chain {}: {}", chain_id, e))?;
+
+    let evm_opts = EvmOpts {
+        fork_url: Some(instance.endpoint),
+        fork_block_number: Some(instance.block_number),
+        ..Default::default()
+    };
+    let env = evm_opts
+        .evm_env()
+        .await
+        .map_err(|e| eyre::eyre!("Failed to create EVM environment: {}", e))?;
+
+    // Fork database reads block on RPC, keep them off the async workers.
+    let simulation = tokio::task::spawn_blocking(move || {
+        let mut executor = ExecutorBuilder::new()
+            .gas_limit(SIMULATION_GAS_LIMIT)
+            .spec_id(foundry_config::Config::default().evm_spec_id())
+            .build(env, backend);
+
+        let native_before = native_balance(executor.backend_mut(), from)?;
+        let result = executor
+            .transact_raw(from, to, calldata, value)
+            .map_err(|e| eyre::eyre!("Simulation failed: {}", e))?;
+        let native_after = native_balance(executor.backend_mut(), from)?;
+
+        Ok::<_, eyre::Report>(summarize(
+            chain_id,
+            from,
+            result,
+            native_before,
+            native_after,
+        ))
+    })
+    .await??;
+
+    info!(
+        chain_id,
+        success = simulation.success,
+        gas_used = simulation.gas_used,
+        logs = simulation.logs.len(),
+        "Transaction simulation finished"
+    );
+
+    Ok(simulation)
+}
+
+fn native_balance(backend: &mut Backend, address: Address) -> eyre::Result<U256> {
+    Ok(backend
+        .basic(address)
+        .map_err(|e| eyre::eyre!("Failed to read balance of {}: {}", address, e))?
+        .map(|info| info.balance)
+        .unwrap_or_default())
+}
+
+fn summarize(
+    chain_id: u64,
+    sender: Address,
+    result: RawCallResult,
+    native_before: U256,
+    native_after: U256,
+) -> TransactionSimulation {
+    let revert_reason = result.reverted.then(|| {
+        alloy::sol_types::decode_revert_reason(&result.result)
+            .unwrap_or_else(|| format!("execution reverted (0x{})", hex::encode(&result.result)))
+    });
+
+    TransactionSimulation {
+        chain_id,
+        success: !result.reverted,
+        revert_reason,
+        gas_used: result.gas_used,
+        logs: result.logs.iter().map(decode_log).collect(),
+        balance_changes: BalanceChanges {
+            native: NativeBalanceChange {
+                before: native_before.to_string(),
+                after: native_after.to_string(),
+                delta: signed_delta(native_after, native_before),
+            },
+            erc20: erc20_deltas(sender, &result.logs),
+        },
+    }
+}
+
+// ============================================================================
+// Log Decoding
+// ============================================================================
+
+fn topic_address(topic: &B256) -> Address {
+    Address::from_word(*topic)
+}
+
+fn data_word(data: &[u8], index: usize) -> Option<U256> {
+    let start = index * 32;
+    data.get(start..start + 32).map(U256::from_be_slice)
+}
+
+/// Decode ERC20/ERC721 `Transfer`, `Approval` and `ApprovalForAll` events.
+fn decode_log(log: &Log) -> SimulatedLog {
+    let topics = log.data.topics();
+    let data = log.data.data.as_ref();
+
+    let decoded = topics.first().and_then(|topic0| {
+        if *topic0 == keccak256(TRANSFER_EVENT) || *topic0 == keccak256(APPROVAL_EVENT) {
+            let signature = if *topic0 == keccak256(TRANSFER_EVENT) {
+                TRANSFER_EVENT
+            } else {
+                APPROVAL_EVENT
+            };
+            let (first, second) = if signature == TRANSFER_EVENT {
+                ("from", "to")
+            } else {
+                ("owner", "spender")
+            };
+            match topics.len() {
+                // ERC20: amount in data
+                3 => data_word(data, 0).map(|amount| {
+                    (
+                        signature,
+                        json!({
+                            first: topic_address(&topics[1]).to_string(),
+                            second: topic_address(&topics[2]).to_string(),
+                            "value": amount.to_string(),
+                        }),
+                    )
+                }),
+                // ERC721: token id indexed
+                4 => Some((
+                    signature,
+                    json!({
+                        first: topic_address(&topics[1]).to_string(),
+                        second: topic_address(&topics[2]).to_string(),
+                        "tokenId": U256::from_be_bytes(topics[3].0).to_string(),
+                    }),
+                )),
+                _ => None,
+            }
+        } else if *topic0 == keccak256(APPROVAL_FOR_ALL_EVENT) && topics.len() == 3 {
+            data_word(data, 0).map(|approved| {
+                (
+                    APPROVAL_FOR_ALL_EVENT,
+                    json!({
+                        "owner": topic_address(&topics[1]).to_string(),
+                        "operator": topic_address(&topics[2]).to_string(),
+                        "approved": !approved.is_zero(),
+                    }),
+                )
+            })
+        } else {
+            None
+        }
+    });
+
+    let (event, params) = match decoded {
+        Some((signature, params)) => (Some(signature.to_string()), Some(params)),
+        None => (None, None),
+    };
+
+    SimulatedLog {
+        address: log.address.to_string(),
+        event,
+        params,
+        topics: topics.iter().map(|t| t.to_string()).collect(),
+        data: format!("0x{}", hex::encode(data)),
+    }
+}
+
+/// Net ERC20 transfers in and out of `sender`, grouped by token contract.
+fn erc20_deltas(sender: Address, logs: &[Log]) -> Vec<TokenBalanceChange> {
+    let transfer_topic = keccak256(TRANSFER_EVENT);
+    let mut flows: BTreeMap<Address, (U256, U256)> = BTreeMap::new();
+
+    for log in logs {
+        let topics = log.data.topics();
+        if topics.len() != 3 || topics[0] != transfer_topic {
+            continue;
+        }
+        let Some(amount) = data_word(log.data.data.as_ref(), 0) else {
+            continue;
+        };
+        let from = topic_address(&topics[1]);
+        let to = topic_address(&topics[2]);
+        if from != sender && to != sender {
+            continue;
+        }
+
+        let (incoming, outgoing) = flows.entry(log.address).or_default();
+        if to == sender {
+            *incoming = incoming.saturating_add(amount);
+        }
+        if from == sender {
+            *outgoing = outgoing.saturating_add(amount);
+        }
+    }
+
+    flows
+        .into_iter()
+        .filter(|(_, (incoming, outgoing))| incoming != outgoing)
+        .map(|(token, (incoming, outgoing))| TokenBalanceChange {
+            token: token.to_string(),
+            delta: signed_delta(incoming, outgoing),
+        })
+        .collect()
+}
+
+/// Format `a - b` as a signed decimal string.
+fn signed_delta(a: U256, b: U256) -> String {
+    if a >= b {
+        (a - b).to_string()
+    } else {
+        format!("-{}", b - a)
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::LogData;
+
+    fn transfer_log(token: Address, from: Address, to: Address, amount: u64) -> Log {
+        Log {
+            address: token,
+            data: LogData::new_unchecked(
+                vec![keccak256(TRANSFER_EVENT), from.into_word(), to.into_word()],
+                Bytes::from(U256::from(amount).to_be_bytes::<32>().to_vec()),
+            ),
+        }
+    }
+
+    #[test]
+    fn test_signed_delta() {
+        assert_eq!(signed_delta(U256::from(10), U256::from(3)), "7");
+        assert_eq!(signed_delta(U256::from(3), U256::from(10)), "-7");
+        assert_eq!(signed_delta(U256::ZERO, U256::ZERO), "0");
+    }
+
+    #[test]
+    fn test_erc20_deltas_net_sender_flows() {
+        let sender = Address::from([0x11; 20]);
+        let other = Address::from([0x22; 20]);
+        let usdc = Address::from([0xaa; 20]);
+        let weth = Address::from([0xbb; 20]);
+
+        let logs = vec![
+            transfer_log(usdc, sender, other, 100),
+            transfer_log(weth, other, sender, 5),
+            transfer_log(usdc, other, sender, 30),
+            // Unrelated transfer is ignored
+            transfer_log(weth, other, other, 1_000),
+        ];
+
+        let deltas = erc20_deltas(sender, &logs);
+        assert_eq!(deltas.len(), 2);
+        let usdc_delta = deltas.iter().find(|d| d.token == usdc.to_string()).unwrap();
+        assert_eq!(usdc_delta.delta, "-70");
+        let weth_delta = deltas.iter().find(|d| d.token == weth.to_string()).unwrap();
+        assert_eq!(weth_delta.delta, "5");
+    }
+
+    #[test]
+    fn test_decode_transfer_log() {
+        let sender = Address::from([0x11; 20]);
+        let other = Address::from([0x22; 20]);
+        let token = Address::from([0xaa; 20]);
+
+        let decoded = decode_log(&transfer_log(token, sender, other, 42));
+        assert_eq!(decoded.event.as_deref(), Some(TRANSFER_EVENT));
+        let params = decoded.params.unwrap();
+        assert_eq!(params["from"], sender.to_string());
+        assert_eq!(params["to"], other.to_string());
+        assert_eq!(params["value"], "42");
+    }
+
+    #[test]
+    fn test_decode_unknown_log_keeps_raw_fields() {
+        let log = Log {
+            address: Address::from([0xcc; 20]),
+            data: LogData::new_unchecked(vec![B256::repeat_byte(0x01)], Bytes::from(vec![0xde])),
+        };
+
+        let decoded = decode_log(&log);
+        assert!(decoded.event.is_none());
+        assert!(decoded.params.is_none());
+        assert_eq!(decoded.topics.len(), 1);
+        assert_eq!(decoded.data, "0xde");
+    }
+}
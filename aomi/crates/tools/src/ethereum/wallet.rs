@@ -4,7 +4,10 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tracing::{debug, info, warn};
 
+use super::abi_decoder::{ContractAbiResolver, SelectorOnly, decode_calldata};
+use super::fees::quote_transaction;
 use super::gateway::{WalletTransactionResult, get_gateway};
+use super::simulation::simulate_transaction;
 use crate::{AomiTool, AomiToolArgs, ToolCallCtx, with_topic};
 
 /// Parameters for SendTransactionToWallet
@@ -16,7 +19,7 @@ pub struct SendTransactionToWalletParameters {
     pub value: String,
     /// The encoded function call data (from encode_function_call tool). Use '0x' for simple ETH transfers
     pub data: String,
-    /// Optional gas limit for the transaction. If not provided, the wallet will estimate
+    /// Optional gas limit for the transaction. If not provided, it is estimated with a margin
     pub gas_limit: Option<String>,
     /// Human-readable description of what this transaction does, for user approval
     pub description: String,
@@ -41,7 +44,7 @@ impl AomiToolArgs for SendTransactionToWalletParameters {
                 },
                 "gas_limit": {
                     "type": "string",
-                    "description": "Optional gas limit. If omitted, wallet estimates"
+                    "description": "Optional gas limit. If omitted, it is estimated from the current chain state"
                 },
                 "description": {
                     "type": "string",
@@ -162,19 +165,94 @@ pub async fn execute_call(
         .await
         .map_err(|e| ToolError::ToolCallError(format!("Failed to get gateway: {}", e).into()))?;
 
-    let result = gateway
-        .send_transaction_to_wallet(from, &to, &value, &data, gas_limit.as_deref(), &description)
+    let mut result = gateway
+        .send_transaction_to_wallet(
+            &ctx.policy_scope,
+            ctx.user_chain_id,
+            from,
+            &to,
+            &value,
+            &data,
+            gas_limit.as_deref(),
+            &description,
+        )
         .await
         .map_err(|e| ToolError::ToolCallError(format!("Transaction failed: {}", e).into()))?;
 
     // Convert result to JSON
-    match &result {
+    match &mut result {
         WalletTransactionResult::Confirmed { tx_hash, .. } => {
             info!(tx_hash = %tx_hash, "Transaction auto-signed and confirmed");
         }
-        WalletTransactionResult::PendingApproval { .. } => {
+        WalletTransactionResult::PendingApproval {
+            gas,
+            simulation,
+            decoded,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            fees,
+            ..
+        } => {
+            // Attach a fork preview so the user sees the effects before signing.
+            // Simulation is best-effort: infrastructure failures never block the request.
+            match ctx.user_chain_id {
+                Some(chain_id) => {
+                    match simulate_transaction(chain_id, from, &to, &value, &data).await {
+                        Ok(preview) => {
+                            if !preview.success {
+                                warn!(
+                                    chain_id,
+                                    reason = ?preview.revert_reason,
+                                    "Transaction reverts in simulation"
+                                );
+                            }
+                            *simulation = Some(preview);
+                        }
+                        Err(e) => {
+                            warn!(chain_id, error = %e, "Failed to simulate wallet transaction");
+                        }
+                    }
+                }
+                None => {
+                    debug!("No chain id in context, skipping transaction simulation");
+                }
+            }
+            // Suggest EIP-1559 fees; the wallet falls back to its own when this fails
+            if let Some(chain_id) = ctx.user_chain_id {
+                match quote_transaction(chain_id, from, &to, &value, &data, gas.as_deref()).await {
+                    Ok(quote) => {
+                        if gas.is_none() {
+                            *gas = quote.gas_limit.clone();
+                        }
+                        *max_fee_per_gas = Some(quote.normal.max_fee_per_gas.clone());
+                        *max_priority_fee_per_gas =
+                            Some(quote.normal.max_priority_fee_per_gas.clone());
+                        *fees = Some(quote);
+                    }
+                    Err(e) => {
+                        warn!(chain_id, error = %e, "Failed to estimate transaction fees");
+                    }
+                }
+            }
+            // Show what the calldata does; without a chain only the selector table is used
+            if has_data && let Ok(calldata) = hex::decode(data.trim_start_matches("0x")) {
+                let call = match ctx.user_chain_id {
+                    Some(chain_id) => {
+                        let resolver = ContractAbiResolver::from_env().await;
+                        decode_calldata(&resolver, chain_id as u32, Some(&to), &calldata).await
+                    }
+                    None => decode_calldata(&SelectorOnly, 1, Some(&to), &calldata).await,
+                };
+                *decoded = Some(call);
+            }
             info!("Transaction request created, pending user approval");
         }
+        WalletTransactionResult::Rejected { violations, .. } => {
+            // Returned as a result (not an error) so the agent can explain each rule
+            for violation in violations.iter() {
+                warn!(violation = %violation, "Transaction blocked by policy");
+            }
+        }
     }
 
     serde_json::to_value(result)
@@ -5,31 +5,44 @@
 pub mod clients;
 pub mod db;
 pub mod ethereum;
+pub mod metrics;
 pub mod queries;
+pub mod resilience;
 pub mod scheduler;
 pub mod streams;
 pub mod types;
+pub mod usage;
 pub mod wrapper;
 
-pub use ethereum::{abi_encoder, account, cast, etherscan, wallet};
-pub use queries::{brave_search, context, db_tools, docs};
+pub use ethereum::{
+    abi_decoder, abi_encoder, account, cast, etherscan, fees, logs, names, portfolio, proxy, wallet,
+};
+pub use queries::{brave_search, context, db_tools, docs, session_search, tokens};
 
 // Re-export the tool types and their parameter types for convenience
+pub use abi_decoder::DecodeCalldata;
 pub use abi_encoder::{EncodeFunctionCall, EncodeFunctionCallParameters};
 pub use account::{GetAccountInfo, GetAccountTransactionHistory};
 pub use context::{GetTimeAndOnchainCtx, GetTimeAndOnchainCtxParameters};
 pub use db_tools::{GetContractABI, GetContractSourceCode};
 pub use etherscan::*;
+pub use logs::GetLogs;
+pub use names::{LookupAddress, ResolveName};
+pub use portfolio::GetPortfolio;
+pub use session_search::SearchSessions;
+pub use tokens::ResolveToken;
 pub use wallet::{SendTransactionToWallet, SendTransactionToWalletParameters};
 
 // Re-export scheduler types
+pub use resilience::{CallPolicy, RetryPolicy, ToolUnavailable, UpstreamFailure};
 pub use scheduler::ToolScheduler;
 pub use wrapper::AomiToolWrapper;
 
 // Re-export stream/future types
 pub use streams::{ToolCompletion, ToolReciever, ToolReturn};
 pub use types::{
-    AomiToolArgs, CallMetadata, RuntimeEnvelope, ToolCallCtx, ToolMetadata, WithTopic, with_topic,
+    AomiToolArgs, CallMetadata, RESUME_CURSOR_KEY, ResumableCall, RuntimeEnvelope, ToolCallCtx,
+    ToolMetadata, WithTopic, with_topic,
 };
 
 // Re-export types
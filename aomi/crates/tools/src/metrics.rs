@@ -0,0 +1,362 @@
+//! Process-wide telemetry for tool calls, LLM token usage and SSE fan-out.
+//!
+//! Everything is recorded into the global [`metrics()`] registry and rendered in the
+//! Prometheus text exposition format by the backend's `/api/metrics` route.
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use crate::ethereum::api_key_id;
+
+/// Upper bounds (seconds) of the tool latency histogram buckets
+pub const LATENCY_BUCKETS: [f64; 11] =
+    [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0];
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::default);
+
+/// The global metrics registry
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+/// How a tool call ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ToolOutcome {
+    Ok,
+    Error,
+    /// Timed out or rejected by an open circuit breaker
+    Unavailable,
+}
+
+impl ToolOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ToolOutcome::Ok => "ok",
+            ToolOutcome::Error => "error",
+            ToolOutcome::Unavailable => "unavailable",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// Non-cumulative counts per bucket, plus one overflow slot for `+Inf`
+    buckets: [u64; LATENCY_BUCKETS.len() + 1],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, secs: f64) {
+        let idx = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| secs <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.buckets[idx] += 1;
+        self.sum += secs;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct ToolStats {
+    outcomes: BTreeMap<ToolOutcome, u64>,
+    latency: Histogram,
+}
+
+#[derive(Debug, Clone, Default)]
+struct TokenStats {
+    requests: u64,
+    input_tokens: u64,
+    output_tokens: u64,
+}
+
+/// Counters and histograms shared by the scheduler, the completion loop and the backend.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    tools: Mutex<BTreeMap<String, ToolStats>>,
+    /// Keyed by (namespace, API key label); per-session usage lives in `usage_events`
+    llm_usage: Mutex<BTreeMap<(String, String), TokenStats>>,
+    sse_events_sent: AtomicU64,
+    sse_events_dropped: AtomicU64,
+    sse_subscribers: AtomicU64,
+}
+
+impl Metrics {
+    /// Records a finished tool call. `elapsed` is `None` for calls that never ran,
+    /// such as those rejected by an open circuit breaker.
+    pub fn record_tool_call(&self, tool: &str, outcome: ToolOutcome, elapsed: Option<Duration>) {
+        let mut tools = self.tools.lock().unwrap();
+        let stats = tools.entry(tool.to_string()).or_default();
+        *stats.outcomes.entry(outcome).or_default() += 1;
+        if let Some(elapsed) = elapsed {
+            stats.latency.observe(elapsed.as_secs_f64());
+        }
+    }
+
+    /// Records the token usage reported for one LLM completion.
+    pub fn record_llm_usage(
+        &self,
+        namespace: Option<&str>,
+        api_key: Option<&str>,
+        input_tokens: u64,
+        output_tokens: u64,
+    ) {
+        let key = (
+            namespace.unwrap_or("none").to_string(),
+            api_key_label(api_key),
+        );
+        let mut usage = self.llm_usage.lock().unwrap();
+        let stats = usage.entry(key).or_default();
+        stats.requests += 1;
+        stats.input_tokens += input_tokens;
+        stats.output_tokens += output_tokens;
+    }
+
+    /// Records one SSE broadcast and the number of subscribers it reached.
+    pub fn record_sse_broadcast(&self, subscribers: usize) {
+        self.sse_subscribers
+            .store(subscribers as u64, Ordering::Relaxed);
+        if subscribers == 0 {
+            self.sse_events_dropped.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.sse_events_sent.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Total calls recorded for `tool` with the given outcome
+    pub fn tool_calls(&self, tool: &str, outcome: ToolOutcome) -> u64 {
+        self.tools
+            .lock()
+            .unwrap()
+            .get(tool)
+            .and_then(|stats| stats.outcomes.get(&outcome).copied())
+            .unwrap_or(0)
+    }
+
+    /// Appends every metric in this registry to `out`.
+    pub fn render(&self, out: &mut PrometheusText) {
+        let tools = self.tools.lock().unwrap().clone();
+
+        out.family(
+            "aomi_tool_calls_total",
+            "counter",
+            "Tool calls by tool and outcome",
+        );
+        for (tool, stats) in &tools {
+            for (outcome, count) in &stats.outcomes {
+                out.sample(
+                    "aomi_tool_calls_total",
+                    &[("tool", tool), ("outcome", outcome.as_str())],
+                    count,
+                );
+            }
+        }
+
+        out.family(
+            "aomi_tool_call_duration_seconds",
+            "histogram",
+            "Tool call latency in seconds",
+        );
+        for (tool, stats) in &tools {
+            let latency = &stats.latency;
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(latency.buckets.iter()) {
+                cumulative += count;
+                out.sample(
+                    "aomi_tool_call_duration_seconds_bucket",
+                    &[("tool", tool), ("le", &bound.to_string())],
+                    cumulative,
+                );
+            }
+            out.sample(
+                "aomi_tool_call_duration_seconds_bucket",
+                &[("tool", tool), ("le", "+Inf")],
+                latency.count,
+            );
+            out.sample(
+                "aomi_tool_call_duration_seconds_sum",
+                &[("tool", tool)],
+                latency.sum,
+            );
+            out.sample(
+                "aomi_tool_call_duration_seconds_count",
+                &[("tool", tool)],
+                latency.count,
+            );
+        }
+
+        let usage = self.llm_usage.lock().unwrap().clone();
+        out.family(
+            "aomi_llm_requests_total",
+            "counter",
+            "LLM completions by namespace and API key",
+        );
+        for ((namespace, api_key), stats) in &usage {
+            out.sample(
+                "aomi_llm_requests_total",
+                &[("namespace", namespace), ("api_key", api_key)],
+                stats.requests,
+            );
+        }
+        out.family(
+            "aomi_llm_tokens_total",
+            "counter",
+            "LLM tokens by namespace, API key and direction",
+        );
+        for ((namespace, api_key), stats) in &usage {
+            for (kind, tokens) in [
+                ("input", stats.input_tokens),
+                ("output", stats.output_tokens),
+            ] {
+                out.sample(
+                    "aomi_llm_tokens_total",
+                    &[
+                        ("namespace", namespace),
+                        ("api_key", api_key),
+                        ("kind", kind),
+                    ],
+                    tokens,
+                );
+            }
+        }
+
+        out.family(
+            "aomi_sse_events_total",
+            "counter",
+            "SSE events broadcast, by whether any subscriber received them",
+        );
+        out.sample(
+            "aomi_sse_events_total",
+            &[("delivered", "true")],
+            self.sse_events_sent.load(Ordering::Relaxed),
+        );
+        out.sample(
+            "aomi_sse_events_total",
+            &[("delivered", "false")],
+            self.sse_events_dropped.load(Ordering::Relaxed),
+        );
+        out.family(
+            "aomi_sse_subscribers",
+            "gauge",
+            "SSE subscribers seen at the last broadcast",
+        );
+        out.sample(
+            "aomi_sse_subscribers",
+            &[],
+            self.sse_subscribers.load(Ordering::Relaxed),
+        );
+    }
+}
+
+/// Non-secret label for an API key: its hashed id, as used by transaction policies
+pub fn api_key_label(api_key: Option<&str>) -> String {
+    api_key.map_or_else(|| "none".to_string(), api_key_id)
+}
+
+/// Builder for the Prometheus text exposition format.
+#[derive(Debug, Default)]
+pub struct PrometheusText {
+    out: String,
+}
+
+impl PrometheusText {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes the `# HELP` and `# TYPE` lines of a metric family
+    pub fn family(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.out, "# HELP {name} {help}");
+        let _ = writeln!(self.out, "# TYPE {name} {kind}");
+    }
+
+    pub fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: impl Display) {
+        self.out.push_str(name);
+        if !labels.is_empty() {
+            self.out.push('{');
+            for (i, (key, val)) in labels.iter().enumerate() {
+                if i > 0 {
+                    self.out.push(',');
+                }
+                let _ = write!(self.out, "{key}=\"{}\"", escape_label(val));
+            }
+            self.out.push('}');
+        }
+        let _ = writeln!(self.out, " {value}");
+    }
+
+    pub fn finish(self) -> String {
+        self.out
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_tool_histogram_and_usage() {
+        let metrics = Metrics::default();
+        metrics.record_tool_call(
+            "brave_search",
+            ToolOutcome::Ok,
+            Some(Duration::from_millis(80)),
+        );
+        metrics.record_tool_call(
+            "brave_search",
+            ToolOutcome::Ok,
+            Some(Duration::from_secs(3)),
+        );
+        metrics.record_tool_call("brave_search", ToolOutcome::Unavailable, None);
+        metrics.record_llm_usage(Some("default"), Some("sk-live-abcdef123456"), 100, 20);
+        metrics.record_llm_usage(Some("default"), Some("sk-live-abcdef123456"), 50, 5);
+        metrics.record_sse_broadcast(2);
+
+        let mut out = PrometheusText::new();
+        metrics.render(&mut out);
+        let text = out.finish();
+
+        assert!(text.contains("aomi_tool_calls_total{tool=\"brave_search\",outcome=\"ok\"} 2"));
+        assert!(
+            text.contains("aomi_tool_calls_total{tool=\"brave_search\",outcome=\"unavailable\"} 1")
+        );
+        assert!(text.contains(
+            "aomi_tool_call_duration_seconds_bucket{tool=\"brave_search\",le=\"0.1\"} 1"
+        ));
+        assert!(
+            text.contains(
+                "aomi_tool_call_duration_seconds_bucket{tool=\"brave_search\",le=\"5\"} 2"
+            )
+        );
+        assert!(text.contains("aomi_tool_call_duration_seconds_count{tool=\"brave_search\"} 2"));
+        let key_id = api_key_id("sk-live-abcdef123456");
+        assert!(text.contains(&format!(
+            "aomi_llm_tokens_total{{namespace=\"default\",api_key=\"{key_id}\",kind=\"input\"}} 150"
+        )));
+        assert!(text.contains(&format!(
+            "aomi_llm_requests_total{{namespace=\"default\",api_key=\"{key_id}\"}} 2"
+        )));
+        assert!(text.contains("aomi_sse_events_total{delivered=\"true\"} 1"));
+        assert!(text.contains("aomi_sse_subscribers 2"));
+        // Full keys never reach the output
+        assert!(!text.contains("abcdef123456"));
+    }
+
+    #[test]
+    fn escapes_label_values() {
+        let mut out = PrometheusText::new();
+        out.sample("m", &[("k", "a\"b\\c\nd")], 1);
+        assert_eq!(out.finish(), "m{k=\"a\\\"b\\\\c\\nd\"} 1\n");
+    }
+}
@@ -3,8 +3,10 @@ use rig::tool::ToolError;
 use serde::{Deserialize, Serialize};
 use tracing::{info, warn};
 
-use crate::{AomiTool, AomiToolArgs, ToolCallCtx, with_topic};
+use crate::resilience::UpstreamFailure;
+use crate::{AomiTool, AomiToolArgs, CallPolicy, RetryPolicy, ToolCallCtx, with_topic};
 use serde_json::json;
+use std::time::Duration;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BraveSearchParameters {
     pub query: String,
@@ -113,7 +115,8 @@ pub async fn execute_call(args: BraveSearchParameters) -> Result<String, ToolErr
                 error = %e,
                 "Brave search request failed"
             );
-            ToolError::ToolCallError(format!("Failed to contact Brave Search API: {e}").into())
+            let failure = UpstreamFailure(format!("Failed to contact Brave Search API: {e}"));
+            ToolError::ToolCallError(Box::new(failure))
         })?;
 
     if !response.status().is_success() {
@@ -129,9 +132,13 @@ pub async fn execute_call(args: BraveSearchParameters) -> Result<String, ToolErr
             body = %body,
             "Brave search returned error response"
         );
-        return Err(ToolError::ToolCallError(
-            format!("Brave Search API error {status}: {body}").into(),
-        ));
+        let message = format!("Brave Search API error {status}: {body}");
+        // Only server-side errors count against the upstream's circuit breaker
+        return Err(if status.is_server_error() {
+            ToolError::ToolCallError(Box::new(UpstreamFailure(message)))
+        } else {
+            ToolError::ToolCallError(message.into())
+        });
     }
 
     let result: serde_json::Value = response.json().await.map_err(|e| {
@@ -201,6 +208,18 @@ impl AomiTool for BraveSearch {
         "Search the web using Brave Search."
     }
 
+    fn read_only(&self) -> bool {
+        true
+    }
+
+    fn call_policy(&self) -> CallPolicy {
+        CallPolicy::default()
+            .with_timeout(Duration::from_secs(15))
+            .with_retry(RetryPolicy::exponential(3, Duration::from_millis(500)))
+            .idempotent()
+            .with_upstream("brave")
+    }
+
     fn run_sync(
         &self,
         _ctx: ToolCallCtx,
@@ -210,7 +229,7 @@ impl AomiTool for BraveSearch {
             execute_call(args)
                 .await
                 .map(serde_json::Value::String)
-                .map_err(|e| eyre::eyre!(e.to_string()))
+                .map_err(eyre::Report::new)
         }
     }
 }
@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tracing::{info, warn};
 
+use crate::fees::recommend_fees;
 use crate::{AomiTool, AomiToolArgs, ToolCallCtx, WithTopic};
 
 // ============================================================================
@@ -42,7 +43,11 @@ impl AomiTool for GetTimeAndOnchainCtx {
     }
 
     fn description(&self) -> &'static str {
-        "Get the current time and on-chain context for the user's connected network. Returns chain name, chain ID, RPC endpoint, current time, block number, gas price, and list of all supported chains. IMPORTANT: Always call this tool at the start of a session or when you need to know which network the user is connected to. If the user is not connected, defaults to Ethereum mainnet."
+        "Get the current time and on-chain context for the user's connected network. Returns chain name, chain ID, RPC endpoint, current time, block number, gas price, EIP-1559 slow/normal/fast fee suggestions (null on chains without base fees), and list of all supported chains. IMPORTANT: Always call this tool at the start of a session or when you need to know which network the user is connected to. If the user is not connected, defaults to Ethereum mainnet."
+    }
+
+    fn read_only(&self) -> bool {
+        true
     }
 
     fn run_sync(
@@ -118,6 +123,18 @@ async fn fetch_onchain_context(user_chain_id: Option<u64>) -> eyre::Result<serde
             ("unknown".to_string(), 0, "unknown".to_string(), 0, None)
         };
 
+    let fees = if primary_instance.is_some() {
+        match recommend_fees(chain_id, None).await {
+            Ok(fees) => Some(fees),
+            Err(e) => {
+                warn!(chain_id, error = %e, "Failed to fetch fee recommendation");
+                None
+            }
+        }
+    } else {
+        None
+    };
+
     info!(
         target: "aomi_tools::context",
         chain_name = %chain_name,
@@ -134,6 +151,7 @@ async fn fetch_onchain_context(user_chain_id: Option<u64>) -> eyre::Result<serde
         "current_time_iso": chrono::Utc::now().to_rfc3339(),
         "block_number": block_number,
         "gas_price_wei": gas_price,
+        "fees": fees,
         "supported_chains": supported_chains
     }))
 }
@@ -196,6 +214,7 @@ mod tests {
             ),
             user_chain_id: Some(1),
             user_address: None,
+            policy_scope: Default::default(),
         };
 
         let result = tool
@@ -239,6 +258,7 @@ mod tests {
             ),
             user_chain_id: None,
             user_address: None,
+            policy_scope: Default::default(),
         };
 
         let result_none = tool
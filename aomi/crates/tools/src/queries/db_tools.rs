@@ -1,3 +1,5 @@
+use alloy::primitives::Address;
+use chrono::Utc;
 use rig::tool::ToolError;
 
 use serde::{Deserialize, Serialize};
@@ -7,8 +9,12 @@ use std::future::Future;
 use tokio::task;
 use tracing::{debug, error, info, warn};
 
-use crate::db::{ContractSearchParams, ContractStore, ContractStoreApi};
-use crate::etherscan::{fetch_and_store_contract, fetch_contract_from_etherscan};
+use crate::clients::external_clients;
+use crate::db::{
+    ContractProxyRecord, ContractSearchParams, ContractStore, ContractStoreApi, ContractUpdate,
+};
+use crate::etherscan::{chain_id_to_name, fetch_and_store_contract, fetch_contract_from_etherscan};
+use crate::proxy::{ProxyInfo, detect_proxy, merge_abis};
 use crate::{AomiTool, AomiToolArgs, ToolCallCtx, with_topic};
 
 /// Retrieves contract ABI from the database
@@ -87,30 +93,22 @@ async fn get_contract_inner(
     let args = normalize_address_args(args);
     let mut contracts = match (&args.chain_id, &args.address) {
         (Some(chain_id), Some(address)) => {
-            vec![get_or_fetch_contract(*chain_id, address.clone()).await?]
+            match get_or_fetch_contract(*chain_id, address.clone()).await {
+                Ok(contract) => vec![contract],
+                // Unverified proxies still expose their implementation's ABI
+                Err(err) if need_abi => match unverified_proxy(*chain_id, address).await {
+                    Some(contract) => vec![contract],
+                    None => return Err(err),
+                },
+                Err(err) => return Err(err),
+            }
         }
         _ => search_contracts(args).await?,
     };
 
     if need_abi {
         for contract in &mut contracts {
-            if contract.is_proxy.unwrap_or(false)
-                && let Some(ref impl_address) = contract.implementation_address
-            {
-                match get_or_fetch_contract(contract.chain_id, impl_address.clone()).await {
-                    Ok(impl_contract) => {
-                        contract.abi = impl_contract.abi;
-                    }
-                    Err(err) => {
-                        warn!(
-                            address = %contract.address,
-                            implementation = %impl_address,
-                            error = %err,
-                            "failed to load implementation ABI; using proxy ABI"
-                        );
-                    }
-                }
-            }
+            resolve_proxy_abi(contract).await;
         }
     }
 
@@ -121,6 +119,217 @@ async fn get_contract_inner(
     }))
 }
 
+/// How long an on-chain proxy check is trusted before the layout is read again
+const PROXY_CHECK_TTL_SECS: i64 = 6 * 60 * 60;
+
+/// Proxy layout of `address`. None if the chain has no RPC client or the contract is not a
+/// proxy we recognise.
+///
+/// Probing takes several RPC calls, so the result (including "not a proxy") is kept in the
+/// contract store and only re-read on-chain once it is older than `PROXY_CHECK_TTL_SECS`.
+async fn detect_onchain_proxy(chain_id: u32, address: &str) -> Option<ProxyInfo> {
+    let address = address.to_lowercase();
+    let store = connect_contract_store().await;
+    let now = Utc::now().timestamp();
+
+    if let Some(store) = &store {
+        match store.get_proxy_record(chain_id, &address).await {
+            Ok(Some(record)) if now - record.checked_at < PROXY_CHECK_TTL_SECS => {
+                match record
+                    .layout
+                    .as_deref()
+                    .map(serde_json::from_str)
+                    .transpose()
+                {
+                    Ok(proxy) => return proxy,
+                    Err(err) => {
+                        warn!(%address, chain_id, error = %err, "ignoring unreadable cached proxy layout")
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(err) => {
+                warn!(%address, chain_id, error = %err, "failed to read cached proxy layout")
+            }
+        }
+    }
+
+    let parsed = address.parse::<Address>().ok()?;
+    let client = match external_clients()
+        .await
+        .get_cast_client(&chain_id_to_name(chain_id))
+        .await
+    {
+        Ok(client) => client,
+        Err(err) => {
+            debug!(chain_id, error = %err, "no RPC client for on-chain proxy detection");
+            return None;
+        }
+    };
+
+    // Failed probes are not cached so the next lookup retries them
+    let proxy = match detect_proxy(&client, parsed).await {
+        Ok(info) => info,
+        Err(err) => {
+            warn!(%address, chain_id, error = %err, "on-chain proxy detection failed");
+            return None;
+        }
+    };
+
+    if let Some(store) = &store {
+        let record = ContractProxyRecord {
+            chain_id,
+            address: address.clone(),
+            layout: proxy
+                .as_ref()
+                .and_then(|info| serde_json::to_string(info).ok()),
+            checked_at: now,
+        };
+        if let Err(err) = store.upsert_proxy_record(&record).await {
+            warn!(%address, chain_id, error = %err, "failed to cache proxy layout");
+        }
+    }
+
+    proxy
+}
+
+/// Placeholder for a contract Etherscan has no source for but which is a proxy on-chain
+async fn unverified_proxy(chain_id: u32, address: &str) -> Option<ContractData> {
+    let address = address.to_lowercase();
+    let proxy = detect_onchain_proxy(chain_id, &address).await?;
+
+    Some(ContractData {
+        address,
+        chain: chain_id_to_name(chain_id),
+        chain_id,
+        source_code: String::new(),
+        abi: json!([]),
+        name: None,
+        symbol: None,
+        is_proxy: Some(true),
+        implementation_address: proxy.implementation.map(|a| a.to_string().to_lowercase()),
+        fetched_from_etherscan: false,
+        proxy: Some(proxy),
+    })
+}
+
+/// ABI of the contract at `address`, with the implementation ABIs merged in for proxies.
+pub async fn get_contract_abi(
+    chain_id: u32,
+    address: &str,
+) -> Result<serde_json::Value, ToolError> {
+    let mut contract = match get_or_fetch_contract(chain_id, address.to_string()).await {
+        Ok(contract) => contract,
+        Err(err) => unverified_proxy(chain_id, address).await.ok_or(err)?,
+    };
+    resolve_proxy_abi(&mut contract).await;
+    Ok(contract.abi)
+}
+
+/// Merges the implementation ABIs into a proxy's ABI.
+///
+/// The proxy layout is read on-chain (at most once per cache TTL) so upgrades are picked up; if
+/// it changed since the contract was stored the stored record is refreshed. Without an RPC client we fall back to
+/// the implementation Etherscan reported.
+async fn resolve_proxy_abi(contract: &mut ContractData) {
+    if contract.proxy.is_none() {
+        contract.proxy = detect_onchain_proxy(contract.chain_id, &contract.address).await;
+    }
+
+    let implementations: Vec<String> = match &contract.proxy {
+        Some(proxy) => {
+            let implementation = proxy.implementation.map(|a| a.to_string().to_lowercase());
+            if contract.is_proxy != Some(true) || contract.implementation_address != implementation
+            {
+                refresh_stored_proxy(contract, implementation.clone()).await;
+                contract.is_proxy = Some(true);
+                contract.implementation_address = implementation;
+            }
+            proxy
+                .implementations()
+                .iter()
+                .map(|a| a.to_string().to_lowercase())
+                .collect()
+        }
+        None if contract.is_proxy.unwrap_or(false) => {
+            contract.implementation_address.iter().cloned().collect()
+        }
+        None => return,
+    };
+
+    let mut implementation_abis = Vec::with_capacity(implementations.len());
+    for implementation in implementations {
+        match get_or_fetch_contract(contract.chain_id, implementation.clone()).await {
+            Ok(impl_contract) => implementation_abis.push(impl_contract.abi),
+            Err(err) => {
+                warn!(
+                    address = %contract.address,
+                    implementation = %implementation,
+                    error = %err,
+                    "failed to load implementation ABI; using proxy ABI"
+                );
+            }
+        }
+    }
+
+    if !implementation_abis.is_empty() {
+        contract.abi = merge_abis(&contract.abi, &implementation_abis);
+    }
+}
+
+/// Records a proxy's current implementation on its stored contract row
+async fn refresh_stored_proxy(contract: &ContractData, implementation: Option<String>) {
+    let Some(store) = connect_contract_store().await else {
+        return;
+    };
+
+    info!(
+        address = %contract.address,
+        previous = ?contract.implementation_address,
+        current = ?implementation,
+        "proxy implementation changed; updating stored contract"
+    );
+    let update = ContractUpdate {
+        chain_id: contract.chain_id,
+        address: contract.address.clone(),
+        name: None,
+        symbol: None,
+        clear_symbol: false,
+        protocol: None,
+        clear_protocol: false,
+        contract_type: None,
+        clear_contract_type: false,
+        version: None,
+        clear_version: false,
+        is_proxy: Some(true),
+        clear_implementation_address: implementation.is_none(),
+        implementation_address: implementation,
+        description: None,
+        clear_description: false,
+    };
+    if let Err(err) = store.update_contract(update).await {
+        warn!(address = %contract.address, error = %err, "failed to update stored proxy");
+    }
+}
+
+/// Single-connection contract store for best-effort proxy bookkeeping; None without a database
+async fn connect_contract_store() -> Option<ContractStore> {
+    sqlx::any::install_default_drivers();
+    let database_url = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| "postgres://aomi@localhost:5432/chatbot".to_string());
+    match AnyPoolOptions::new()
+        .max_connections(1)
+        .connect(&database_url)
+        .await
+    {
+        Ok(pool) => Some(ContractStore::new(pool)),
+        Err(err) => {
+            debug!(error = %err, "no database available for proxy bookkeeping");
+            None
+        }
+    }
+}
+
 fn normalize_address_args(mut args: GetContractArgs) -> GetContractArgs {
     if let Some(address) = args.address.as_mut() {
         if address
@@ -201,7 +410,11 @@ impl AomiTool for GetContractABI {
     type Error = ToolError;
 
     fn description(&self) -> &'static str {
-        "Retrieve contract ABI from the database (or Etherscan fallback)."
+        "Retrieve contract ABI from the database (or Etherscan fallback). For proxies (EIP-1967, beacon, EIP-1822, Safe, EIP-2535 diamonds) the implementation ABI is merged in."
+    }
+
+    fn read_only(&self) -> bool {
+        true
     }
 
     fn run_sync(
@@ -228,6 +441,10 @@ impl AomiTool for GetContractSourceCode {
         "Retrieve contract source code from the database (or Etherscan fallback)."
     }
 
+    fn read_only(&self) -> bool {
+        true
+    }
+
     fn run_sync(
         &self,
         _ctx: ToolCallCtx,
@@ -252,6 +469,8 @@ pub struct ContractData {
     pub is_proxy: Option<bool>,
     pub implementation_address: Option<String>,
     pub fetched_from_etherscan: bool,
+    /// Proxy layout read on-chain, if resolved
+    pub proxy: Option<ProxyInfo>,
 }
 
 impl ContractData {
@@ -267,6 +486,9 @@ impl ContractData {
             "implementation_address": self.implementation_address,
             "fetched_from_etherscan": self.fetched_from_etherscan,
         });
+        if let Some(proxy) = &self.proxy {
+            contract_json["proxy"] = json!(proxy);
+        }
 
         if need_abi {
             contract_json["abi"] = self.abi.clone();
@@ -330,6 +552,7 @@ async fn search_contracts(args: GetContractArgs) -> Result<Vec<ContractData>, To
             is_proxy: c.is_proxy,
             implementation_address: c.implementation_address,
             fetched_from_etherscan: false,
+            proxy: None,
         })
         .collect())
 }
@@ -384,6 +607,7 @@ pub async fn get_or_fetch_contract(
                     is_proxy: c.is_proxy,
                     implementation_address: c.implementation_address,
                     fetched_from_etherscan: false,
+                    proxy: None,
                 });
             }
             Ok(None) => {
@@ -445,5 +669,6 @@ pub async fn get_or_fetch_contract(
         is_proxy: fetched_contract.is_proxy,
         implementation_address: fetched_contract.implementation_address,
         fetched_from_etherscan: true,
+        proxy: None,
     })
 }
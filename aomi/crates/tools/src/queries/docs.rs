@@ -106,6 +106,10 @@ impl AomiTool for SharedDocuments {
         "Search documentation sources for relevant passages."
     }
 
+    fn read_only(&self) -> bool {
+        true
+    }
+
     fn run_sync(
         &self,
         _ctx: ToolCallCtx,
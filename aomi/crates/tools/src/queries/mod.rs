@@ -2,3 +2,5 @@ pub mod brave_search;
 pub mod context;
 pub mod db_tools;
 pub mod docs;
+pub mod session_search;
+pub mod tokens;
@@ -0,0 +1,350 @@
+//! Search over a user's past sessions.
+//!
+//! Keyword matches come from the database's full-text index (Postgres `tsvector`, SQLite
+//! FTS5). Semantic search embeds messages lazily with [`EmbeddingClient`] and fuses both
+//! rankings. The backend installs a [`SessionSearch`] at startup with
+//! [`install_session_search`]; without one the `search_sessions` tool reports that search
+//! is unavailable.
+
+use aomi_rag::EmbeddingClient;
+use rig::tool::ToolError;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, OnceLock};
+use tokio::sync::OnceCell;
+use tracing::warn;
+
+use crate::db::{SessionSearchHit, SessionStoreApi};
+use crate::{AomiTool, AomiToolArgs, ToolCallCtx, with_topic};
+
+static SESSION_SEARCH: OnceLock<Arc<SessionSearch>> = OnceLock::new();
+
+/// Upper bound on sessions returned by one search
+pub const MAX_SEARCH_LIMIT: usize = 50;
+/// Messages embedded per semantic search; older backlog is indexed by later searches
+const EMBED_BATCH: i64 = 32;
+/// Most recent embedded messages compared against the query
+const SEMANTIC_CANDIDATES: i64 = 2000;
+/// Cosine similarity below which a message is not considered related
+const MIN_SIMILARITY: f64 = 0.6;
+/// Rank offset of reciprocal rank fusion
+const RRF_K: f64 = 60.0;
+
+/// Makes `search` available to the `search_sessions` tool. Returns false if one was already
+/// installed.
+pub fn install_session_search(search: Arc<SessionSearch>) -> bool {
+    SESSION_SEARCH.set(search).is_ok()
+}
+
+/// The installed session search, if any.
+pub fn session_search() -> Option<Arc<SessionSearch>> {
+    SESSION_SEARCH.get().cloned()
+}
+
+pub struct SessionSearch {
+    store: Arc<dyn SessionStoreApi>,
+    /// Loaded on the first semantic search; None if the model failed to load
+    embeddings: OnceCell<Option<Arc<EmbeddingClient>>>,
+}
+
+impl SessionSearch {
+    pub fn new(store: Arc<dyn SessionStoreApi>) -> Self {
+        Self {
+            store,
+            embeddings: OnceCell::new(),
+        }
+    }
+
+    /// Sessions of `public_key` matching `query`, best first, with their best matching
+    /// message. Semantic failures fall back to keyword results.
+    pub async fn search(
+        &self,
+        public_key: &str,
+        query: &str,
+        limit: usize,
+        semantic: bool,
+    ) -> anyhow::Result<Vec<SessionSearchHit>> {
+        let limit = limit.clamp(1, MAX_SEARCH_LIMIT);
+        // Several hits may come from one session, so over-fetch before collapsing
+        let fetch = (limit * 4) as i64;
+
+        let keyword = self.store.search_messages(public_key, query, fetch).await?;
+        if !semantic {
+            return Ok(best_per_session(keyword, limit));
+        }
+
+        let similar = match self.semantic_search(public_key, query, fetch).await {
+            Ok(hits) => hits,
+            Err(e) => {
+                warn!("Semantic session search failed: {e}");
+                Vec::new()
+            }
+        };
+        Ok(best_per_session(fuse(vec![keyword, similar]), limit))
+    }
+
+    async fn embedding_client(&self) -> Option<Arc<EmbeddingClient>> {
+        self.embeddings
+            .get_or_init(|| async {
+                match EmbeddingClient::new().await {
+                    Ok(client) => Some(Arc::new(client)),
+                    Err(e) => {
+                        warn!("Embedding model unavailable, semantic search disabled: {e}");
+                        None
+                    }
+                }
+            })
+            .await
+            .clone()
+    }
+
+    async fn semantic_search(
+        &self,
+        public_key: &str,
+        query: &str,
+        limit: i64,
+    ) -> anyhow::Result<Vec<SessionSearchHit>> {
+        let Some(client) = self.embedding_client().await else {
+            return Ok(Vec::new());
+        };
+
+        for hit in self
+            .store
+            .get_unembedded_messages(public_key, EMBED_BATCH)
+            .await?
+        {
+            let embedding = client.embed(&hit.text).await?;
+            let vector: Vec<f32> = embedding.vec.iter().map(|x| *x as f32).collect();
+            self.store
+                .save_message_embedding(hit.message_id, &vector)
+                .await?;
+        }
+
+        let query_vec = client.embed(query).await?.vec;
+        let mut hits: Vec<_> = self
+            .store
+            .get_message_embeddings(public_key, SEMANTIC_CANDIDATES)
+            .await?
+            .into_iter()
+            .map(|(mut hit, vector)| {
+                hit.score = cosine_similarity(&query_vec, &vector);
+                hit
+            })
+            .filter(|hit| hit.score >= MIN_SIMILARITY)
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        hits.truncate(limit as usize);
+        Ok(hits)
+    }
+}
+
+fn cosine_similarity(a: &[f64], b: &[f32]) -> f64 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0.0, 0.0, 0.0);
+    for (x, y) in a.iter().zip(b) {
+        let y = f64::from(*y);
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+/// Merges ranked lists with reciprocal rank fusion; `score` becomes the fused score.
+fn fuse(rankings: Vec<Vec<SessionSearchHit>>) -> Vec<SessionSearchHit> {
+    let mut fused: HashMap<i64, SessionSearchHit> = HashMap::new();
+    for ranking in rankings {
+        for (rank, hit) in ranking.into_iter().enumerate() {
+            let score = 1.0 / (RRF_K + rank as f64 + 1.0);
+            fused
+                .entry(hit.message_id)
+                .and_modify(|existing| existing.score += score)
+                .or_insert(SessionSearchHit { score, ..hit });
+        }
+    }
+
+    let mut hits: Vec<_> = fused.into_values().collect();
+    hits.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(b.timestamp.cmp(&a.timestamp))
+    });
+    hits
+}
+
+/// Keeps the best hit of each session, preserving order.
+fn best_per_session(hits: Vec<SessionSearchHit>, limit: usize) -> Vec<SessionSearchHit> {
+    let mut seen = HashSet::new();
+    hits.into_iter()
+        .filter(|hit| seen.insert(hit.session_id.clone()))
+        .take(limit)
+        .collect()
+}
+
+/// Lets the agent recall the user's earlier conversations
+#[derive(Debug, Clone)]
+pub struct SearchSessions;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchSessionsArgs {
+    pub query: String,
+    pub limit: Option<usize>,
+    pub semantic: Option<bool>,
+}
+
+impl AomiToolArgs for SearchSessionsArgs {
+    fn schema() -> serde_json::Value {
+        with_topic(json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "What to look for in past conversations (e.g. \"Aave position health factor\")"
+                },
+                "limit": {
+                    "type": "number",
+                    "description": "Maximum number of sessions to return (default 5)"
+                },
+                "semantic": {
+                    "type": "boolean",
+                    "description": "Also match by meaning, not only by keywords (default true)"
+                }
+            },
+            "required": ["query"]
+        }))
+    }
+}
+
+/// Caps the matched message shown to the model
+const MAX_SNIPPET_CHARS: usize = 400;
+
+async fn execute_search_sessions(
+    ctx: ToolCallCtx,
+    args: SearchSessionsArgs,
+) -> Result<serde_json::Value, ToolError> {
+    let Some(public_key) = ctx.policy_scope.public_key.clone() else {
+        return Err(ToolError::ToolCallError(
+            "Past sessions can only be searched once the user has connected a wallet".into(),
+        ));
+    };
+    let Some(search) = session_search() else {
+        return Err(ToolError::ToolCallError(
+            "Session search is not available".into(),
+        ));
+    };
+
+    let hits = search
+        .search(
+            &public_key,
+            &args.query,
+            args.limit.unwrap_or(5),
+            args.semantic.unwrap_or(true),
+        )
+        .await
+        .map_err(|e| ToolError::ToolCallError(e.to_string().into()))?;
+
+    // The current conversation is already in context
+    let current = ctx.policy_scope.session_id.as_deref();
+    let sessions: Vec<_> = hits
+        .into_iter()
+        .filter(|hit| Some(hit.session_id.as_str()) != current)
+        .map(|hit| {
+            let snippet: String = hit.text.chars().take(MAX_SNIPPET_CHARS).collect();
+            json!({
+                "session_id": hit.session_id,
+                "title": hit.title,
+                "matched_message": {
+                    "sender": hit.sender,
+                    "text": snippet,
+                    "timestamp": hit.timestamp,
+                },
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "count": sessions.len(),
+        "sessions": sessions,
+    }))
+}
+
+impl AomiTool for SearchSessions {
+    const NAME: &'static str = "search_sessions";
+
+    type Args = SearchSessionsArgs;
+    type Output = serde_json::Value;
+    type Error = ToolError;
+
+    fn description(&self) -> &'static str {
+        "Search the user's earlier chat sessions by keyword and meaning. Use it when the user refers to something discussed in a previous conversation (e.g. \"the Aave position I asked about last week\"). Returns matching sessions with their title and best matching message."
+    }
+
+    fn read_only(&self) -> bool {
+        true
+    }
+
+    fn run_sync(
+        &self,
+        ctx: ToolCallCtx,
+        args: Self::Args,
+    ) -> impl std::future::Future<Output = eyre::Result<serde_json::Value>> + Send {
+        async move {
+            execute_search_sessions(ctx, args)
+                .await
+                .map_err(|e| eyre::eyre!(e.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(session_id: &str, message_id: i64, score: f64) -> SessionSearchHit {
+        SessionSearchHit {
+            session_id: session_id.to_string(),
+            title: None,
+            message_id,
+            sender: "user".to_string(),
+            text: format!("message {message_id}"),
+            timestamp: message_id,
+            score,
+        }
+    }
+
+    #[test]
+    fn test_cosine_similarity() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-9);
+        assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]).abs() < 1e-9);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 0.0]), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+    }
+
+    #[test]
+    fn test_fuse_prefers_hits_in_both_rankings() {
+        let keyword = vec![hit("a", 1, 3.0), hit("b", 2, 2.0)];
+        let semantic = vec![hit("c", 3, 0.9), hit("b", 2, 0.8)];
+
+        let fused = fuse(vec![keyword, semantic]);
+        let ids: Vec<_> = fused.iter().map(|h| h.message_id).collect();
+        assert_eq!(ids[0], 2, "a hit found by both searches ranks first");
+        assert_eq!(fused.len(), 3);
+    }
+
+    #[test]
+    fn test_best_per_session() {
+        let hits = vec![hit("a", 1, 3.0), hit("a", 2, 2.0), hit("b", 3, 1.0)];
+        let best = best_per_session(hits.clone(), 10);
+        assert_eq!(
+            best.iter().map(|h| h.message_id).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+        assert_eq!(best_per_session(hits, 1).len(), 1);
+    }
+}
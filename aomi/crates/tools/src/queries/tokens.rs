@@ -0,0 +1,233 @@
+//! Token registry lookups: symbol/address → (address, decimals) per chain.
+
+use alloy::primitives::utils::parse_units;
+use rig::tool::ToolError;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::any::AnyPoolOptions;
+use tracing::{debug, error, warn};
+
+use crate::cast::ERC20;
+use crate::clients::external_clients;
+use crate::db::{Token, TokenStore, TokenStoreApi};
+use crate::db_tools::run_sync;
+use crate::etherscan::chain_id_to_name;
+use crate::{AomiTool, AomiToolArgs, ToolCallCtx, with_topic};
+
+/// Tool for resolving token symbols to contract addresses and decimals
+#[derive(Debug, Clone)]
+pub struct ResolveToken;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolveTokenArgs {
+    /// Token symbol (e.g. "USDC") or contract address
+    pub token: String,
+    pub chain_id: Option<u32>,
+    /// Human-readable amount to convert into base units (e.g. "100.5")
+    pub amount: Option<String>,
+}
+
+impl AomiToolArgs for ResolveTokenArgs {
+    fn schema() -> serde_json::Value {
+        with_topic(json!({
+            "type": "object",
+            "properties": {
+                "token": {
+                    "type": "string",
+                    "description": "Token symbol (e.g. \"USDC\", \"WETH\") or contract address"
+                },
+                "chain_id": {
+                    "type": "number",
+                    "description": "Chain ID to resolve on (e.g. 1 for Ethereum, 8453 for Base). Required when token is an address"
+                },
+                "amount": {
+                    "type": "string",
+                    "description": "Optional human-readable amount (e.g. \"100\" or \"0.25\") to convert into the token's smallest unit"
+                }
+            },
+            "required": ["token"]
+        }))
+    }
+}
+
+fn looks_like_address(value: &str) -> bool {
+    value.len() == 42
+        && value.starts_with("0x")
+        && value[2..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Converts a human amount into base units, e.g. ("100.5", 6) → "100500000".
+pub fn to_base_units(amount: &str, decimals: u8) -> Result<String, String> {
+    let amount = amount.trim();
+    if amount.starts_with('-') {
+        return Err(format!("Invalid amount '{amount}': must not be negative"));
+    }
+    parse_units(amount, decimals)
+        .map(|units| units.get_absolute().to_string())
+        .map_err(|e| format!("Invalid amount '{amount}': {e}"))
+}
+
+async fn token_store() -> Result<TokenStore, ToolError> {
+    sqlx::any::install_default_drivers();
+    let database_url = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| "postgres://aomi@localhost:5432/chatbot".to_string());
+
+    let pool = AnyPoolOptions::new()
+        .max_connections(5)
+        .connect(&database_url)
+        .await
+        .map_err(|e| {
+            let error_msg = format!("Database connection error: {}", e);
+            error!("{}", error_msg);
+            ToolError::ToolCallError(error_msg.into())
+        })?;
+
+    Ok(TokenStore::new(pool))
+}
+
+/// Fills in decimals for auto-registered tokens via decimals() and writes them back.
+async fn backfill_decimals(store: &TokenStore, token: &mut Token) {
+    if token.decimals.is_some() {
+        return;
+    }
+    let network = chain_id_to_name(token.chain_id);
+    let cast_client = match external_clients().await.get_cast_client(&network).await {
+        Ok(client) => client,
+        Err(e) => {
+            warn!(
+                "Could not get RPC client for {} decimals lookup: {:?}",
+                network, e
+            );
+            return;
+        }
+    };
+    let Some(decimals) = cast_client.get_decimals(&token.address).await else {
+        return;
+    };
+    token.decimals = Some(decimals);
+    if let Err(e) = store
+        .set_token_decimals(token.chain_id, &token.address, decimals)
+        .await
+    {
+        warn!("Failed to persist decimals for {}: {}", token.address, e);
+    }
+}
+
+pub async fn execute_resolve_token(args: ResolveTokenArgs) -> Result<serde_json::Value, ToolError> {
+    debug!("resolve_token tool called with args: {:?}", args);
+
+    run_sync(async move {
+        let store = token_store().await?;
+        let query = args.token.trim().to_string();
+
+        let lookup = if looks_like_address(&query) {
+            let chain_id = args.chain_id.ok_or_else(|| {
+                ToolError::ToolCallError("chain_id is required when token is an address".into())
+            })?;
+            store
+                .get_token(chain_id, &query)
+                .await
+                .map(|token| token.into_iter().collect::<Vec<_>>())
+        } else {
+            store.resolve_symbol(&query, args.chain_id).await
+        };
+        let mut tokens = lookup.map_err(|e| {
+            let error_msg = format!("Failed to query token registry: {}", e);
+            error!("{}", error_msg);
+            ToolError::ToolCallError(error_msg.into())
+        })?;
+
+        if tokens.is_empty() {
+            let scope = args
+                .chain_id
+                .map(|id| format!(" on chain {id}"))
+                .unwrap_or_default();
+            return Err(ToolError::ToolCallError(
+                format!(
+                    "Token '{query}' is not in the registry{scope}. Look up the contract with get_contract_abi or fetch_contract_from_etherscan instead"
+                )
+                .into(),
+            ));
+        }
+
+        let mut matches = Vec::with_capacity(tokens.len());
+        for token in tokens.iter_mut() {
+            backfill_decimals(&store, token).await;
+
+            let mut entry = json!({
+                "chain_id": token.chain_id,
+                "address": token.address,
+                "symbol": token.symbol,
+                "name": token.name,
+                "decimals": token.decimals,
+                "source": token.source,
+            });
+            if let Some(amount) = &args.amount {
+                match token.decimals.map(|d| to_base_units(amount, d)) {
+                    Some(Ok(units)) => entry["amount_base_units"] = json!(units),
+                    Some(Err(e)) => entry["amount_error"] = json!(e),
+                    None => entry["amount_error"] = json!("Token decimals are unknown"),
+                }
+            }
+            matches.push(entry);
+        }
+
+        Ok(json!({
+            "token": query,
+            "chain_id": args.chain_id,
+            "ambiguous": matches.len() > 1,
+            "matches": matches,
+        }))
+    })
+}
+
+impl AomiTool for ResolveToken {
+    const NAME: &'static str = "resolve_token";
+
+    type Args = ResolveTokenArgs;
+    type Output = serde_json::Value;
+    type Error = ToolError;
+
+    fn description(&self) -> &'static str {
+        "Resolve a token symbol (or address) to its contract address and decimals on a chain using the token registry. Pass an amount to get it converted into base units. Prefer this over web search for token addresses."
+    }
+
+    fn read_only(&self) -> bool {
+        true
+    }
+
+    fn run_sync(
+        &self,
+        _ctx: ToolCallCtx,
+        args: Self::Args,
+    ) -> impl std::future::Future<Output = eyre::Result<serde_json::Value>> + Send {
+        async move {
+            execute_resolve_token(args)
+                .await
+                .map_err(|e| eyre::eyre!(e.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_base_units() {
+        assert_eq!(to_base_units("100", 6).unwrap(), "100000000");
+        assert_eq!(to_base_units("100.5", 6).unwrap(), "100500000");
+        assert_eq!(to_base_units("0.000000000000000001", 18).unwrap(), "1");
+        assert!(to_base_units("-1", 6).is_err());
+        assert!(to_base_units("abc", 18).is_err());
+    }
+
+    #[test]
+    fn test_looks_like_address() {
+        assert!(looks_like_address(
+            "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+        ));
+        assert!(!looks_like_address("USDC"));
+        assert!(!looks_like_address("0x1234"));
+    }
+}
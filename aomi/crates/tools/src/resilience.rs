@@ -0,0 +1,598 @@
+//! Timeouts, retries and per-upstream circuit breaking for tool calls.
+//!
+//! A tool declares its [`CallPolicy`] through `AomiTool::call_policy`. The wrapper enforces the
+//! timeout and retries on every call, and the scheduler keeps one [`CircuitBreaker`] per upstream
+//! so a failing service is skipped quickly instead of stalling every turn that touches it.
+//! Only failures that point at the service itself count against its breaker: timeouts,
+//! transport errors and 5xx responses (see [`CallFailure::is_upstream_failure`]).
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tracing::debug;
+
+/// Consecutive failures after which an upstream's breaker opens
+pub const BREAKER_FAILURE_THRESHOLD: u32 = 5;
+
+/// How long an open breaker rejects calls before letting a trial call through
+pub const BREAKER_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Error kind carried by the structured result of an unavailable tool
+pub const TOOL_UNAVAILABLE: &str = "tool_unavailable";
+
+/// How failed attempts are repeated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for each later retry
+    pub initial_backoff_ms: u64,
+    /// Upper bound for the delay between attempts
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl RetryPolicy {
+    /// A single attempt, no retries
+    pub const fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_backoff_ms: 0,
+            max_backoff_ms: 0,
+        }
+    }
+
+    /// `max_attempts` tries with exponential backoff starting at `initial_backoff`
+    pub fn exponential(max_attempts: u32, initial_backoff: Duration) -> Self {
+        let initial_backoff_ms = initial_backoff.as_millis() as u64;
+        Self {
+            max_attempts: max_attempts.max(1),
+            initial_backoff_ms,
+            max_backoff_ms: initial_backoff_ms.saturating_mul(8),
+        }
+    }
+
+    /// Delay before retry number `retry` (1-based)
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 1u64
+            .checked_shl(retry.saturating_sub(1))
+            .unwrap_or(u64::MAX);
+        Duration::from_millis(
+            self.initial_backoff_ms
+                .saturating_mul(factor)
+                .min(self.max_backoff_ms),
+        )
+    }
+}
+
+/// Timeout, retry and upstream settings a tool declares for its calls.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallPolicy {
+    /// Per-attempt timeout; `None` lets an attempt run until it finishes
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// Retries for failed or timed-out attempts, honored only for idempotent tools
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    /// Whether repeating a call with the same arguments is safe
+    #[serde(default)]
+    pub idempotent: bool,
+    /// Upstream service whose circuit breaker guards the tool
+    #[serde(default)]
+    pub upstream: Option<String>,
+}
+
+impl CallPolicy {
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout_ms = Some(timeout.as_millis() as u64);
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn idempotent(mut self) -> Self {
+        self.idempotent = true;
+        self
+    }
+
+    pub fn with_upstream(mut self, upstream: impl Into<String>) -> Self {
+        self.upstream = Some(upstream.into());
+        self
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout_ms.map(Duration::from_millis)
+    }
+
+    /// Attempts a call may make; non-idempotent calls never repeat
+    pub fn max_attempts(&self) -> u32 {
+        if self.idempotent {
+            self.retry.max_attempts.max(1)
+        } else {
+            1
+        }
+    }
+}
+
+/// Why the last attempt of a call did not produce a value.
+#[derive(Debug)]
+pub enum CallFailure {
+    TimedOut(Duration),
+    Failed(eyre::Report),
+}
+
+impl CallFailure {
+    /// Whether the failure points at the upstream service rather than the call itself
+    pub fn is_upstream_failure(&self) -> bool {
+        match self {
+            CallFailure::TimedOut(_) => true,
+            CallFailure::Failed(err) => is_upstream_error(err),
+        }
+    }
+}
+
+impl fmt::Display for CallFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallFailure::TimedOut(timeout) => {
+                write!(f, "timed out after {}s", timeout.as_secs_f32())
+            }
+            CallFailure::Failed(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+/// Error for an upstream that could not be reached or answered with a 5xx status.
+/// Tools return it (wrapped in their own error) so the failure counts against the
+/// upstream's circuit breaker; other errors, like a 4xx for bad input, do not.
+#[derive(Debug)]
+pub struct UpstreamFailure(pub String);
+
+impl fmt::Display for UpstreamFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for UpstreamFailure {}
+
+/// Whether any cause in `err` is a transport error, a timeout or a 5xx response.
+pub fn is_upstream_error(err: &eyre::Report) -> bool {
+    err.chain().any(|cause| {
+        if cause.is::<UpstreamFailure>() {
+            return true;
+        }
+        if let Some(err) = cause.downcast_ref::<reqwest::Error>() {
+            return err.is_timeout()
+                || err.is_connect()
+                || err.is_request()
+                || err.status().is_some_and(|status| status.is_server_error());
+        }
+        if let Some(err) = cause.downcast_ref::<std::io::Error>() {
+            return matches!(
+                err.kind(),
+                std::io::ErrorKind::TimedOut
+                    | std::io::ErrorKind::ConnectionRefused
+                    | std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::NotConnected
+                    | std::io::ErrorKind::BrokenPipe
+            );
+        }
+        false
+    })
+}
+
+/// Runs `attempt` under `policy`: each attempt is bounded by the timeout and idempotent calls
+/// are retried with backoff. Returns the failure of the last attempt.
+pub async fn run_with_policy<F, Fut>(
+    policy: &CallPolicy,
+    mut attempt: F,
+) -> Result<Value, CallFailure>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = eyre::Result<Value>>,
+{
+    let max_attempts = policy.max_attempts();
+    let mut tries = 0;
+    loop {
+        tries += 1;
+        let outcome = match policy.timeout() {
+            Some(timeout) => match tokio::time::timeout(timeout, attempt()).await {
+                Ok(result) => result.map_err(CallFailure::Failed),
+                Err(_) => Err(CallFailure::TimedOut(timeout)),
+            },
+            None => attempt().await.map_err(CallFailure::Failed),
+        };
+        match outcome {
+            Ok(value) => return Ok(value),
+            Err(failure) if tries >= max_attempts => return Err(failure),
+            Err(failure) => {
+                let backoff = policy.retry.backoff(tries);
+                debug!(attempt = tries, error = %failure, ?backoff, "Retrying tool call");
+                tokio::time::sleep(backoff).await;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// Calls flow normally
+    Closed,
+    /// Calls are rejected until the cooldown elapses
+    Open,
+    /// Cooldown elapsed; a single trial call decides whether it closes or re-opens
+    HalfOpen,
+}
+
+/// Consecutive-failure circuit breaker for a single upstream.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    /// When the half-open trial call was admitted, while it is outstanding
+    probe_started: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            consecutive_failures: 0,
+            opened_at: None,
+            probe_started: None,
+        }
+    }
+
+    pub fn state(&self, now: Instant) -> BreakerState {
+        match self.opened_at {
+            None => BreakerState::Closed,
+            Some(opened_at) if now.duration_since(opened_at) < self.cooldown => BreakerState::Open,
+            Some(_) => BreakerState::HalfOpen,
+        }
+    }
+
+    /// Admits a call, or returns how long until the upstream may be tried again.
+    /// Half-open admits one trial call at a time; a trial that never reports back
+    /// (e.g. its turn was cancelled) stops blocking others after another cooldown.
+    pub fn check(&mut self, now: Instant) -> Result<(), Duration> {
+        match (self.state(now), self.opened_at) {
+            (BreakerState::Open, Some(opened_at)) => {
+                Err(self.cooldown.saturating_sub(now.duration_since(opened_at)))
+            }
+            (BreakerState::HalfOpen, _) => match self.probe_started {
+                Some(started) if now.duration_since(started) < self.cooldown => {
+                    Err(self.cooldown.saturating_sub(now.duration_since(started)))
+                }
+                _ => {
+                    self.probe_started = Some(now);
+                    Ok(())
+                }
+            },
+            _ => Ok(()),
+        }
+    }
+
+    pub fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.opened_at = None;
+        self.probe_started = None;
+    }
+
+    /// Counts a failure; a failed half-open trial re-opens the breaker immediately
+    pub fn record_failure(&mut self, now: Instant) {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        self.probe_started = None;
+        if self.consecutive_failures >= self.failure_threshold {
+            self.opened_at = Some(now);
+        }
+    }
+
+    /// Ends a trial call whose outcome says nothing about the upstream
+    pub fn release_probe(&mut self) {
+        self.probe_started = None;
+    }
+}
+
+/// Circuit breakers keyed by upstream name.
+#[derive(Debug)]
+pub struct CircuitBreakers {
+    failure_threshold: u32,
+    cooldown: Duration,
+    breakers: Mutex<HashMap<String, CircuitBreaker>>,
+}
+
+impl Default for CircuitBreakers {
+    fn default() -> Self {
+        Self::new(BREAKER_FAILURE_THRESHOLD, BREAKER_COOLDOWN)
+    }
+}
+
+impl CircuitBreakers {
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold,
+            cooldown,
+            breakers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Admits a call to `upstream`, or returns how long until it may be tried again
+    pub fn check(&self, upstream: &str) -> Result<(), Duration> {
+        let mut breakers = self.breakers.lock().unwrap();
+        match breakers.get_mut(upstream) {
+            Some(breaker) => breaker.check(Instant::now()),
+            None => Ok(()),
+        }
+    }
+
+    pub fn record_success(&self, upstream: &str) {
+        if let Some(breaker) = self.breakers.lock().unwrap().get_mut(upstream) {
+            breaker.record_success();
+        }
+    }
+
+    /// Counts a failure of the upstream itself (see [`CallFailure::is_upstream_failure`])
+    pub fn record_failure(&self, upstream: &str) {
+        self.breakers
+            .lock()
+            .unwrap()
+            .entry(upstream.to_string())
+            .or_insert_with(|| CircuitBreaker::new(self.failure_threshold, self.cooldown))
+            .record_failure(Instant::now());
+    }
+
+    /// Records the outcome of a call admitted by [`Self::check`]. Failures that do not
+    /// point at the upstream leave its failure streak untouched.
+    pub fn record_result(&self, upstream: &str, result: &Result<Value, CallFailure>) {
+        match result {
+            Ok(_) => self.record_success(upstream),
+            Err(failure) if failure.is_upstream_failure() => self.record_failure(upstream),
+            Err(_) => self.release_probe(upstream),
+        }
+    }
+
+    /// Records the final result an async tool sent on its stream, with the same rules as
+    /// [`Self::record_result`].
+    pub fn record_stream_result(&self, upstream: &str, result: &eyre::Result<Value>) {
+        match result {
+            Ok(_) => self.record_success(upstream),
+            Err(err) if is_upstream_error(err) => self.record_failure(upstream),
+            Err(_) => self.release_probe(upstream),
+        }
+    }
+
+    /// Ends an admitted call whose outcome says nothing about the upstream
+    pub fn release_probe(&self, upstream: &str) {
+        if let Some(breaker) = self.breakers.lock().unwrap().get_mut(upstream) {
+            breaker.release_probe();
+        }
+    }
+
+    pub fn state(&self, upstream: &str) -> BreakerState {
+        self.breakers
+            .lock()
+            .unwrap()
+            .get(upstream)
+            .map(|breaker| breaker.state(Instant::now()))
+            .unwrap_or(BreakerState::Closed)
+    }
+}
+
+/// Structured result returned in place of a tool's output when it cannot be reached.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolUnavailable {
+    pub tool: String,
+    pub upstream: Option<String>,
+    pub reason: String,
+    pub retry_after_secs: Option<u64>,
+}
+
+impl ToolUnavailable {
+    /// The upstream's breaker is open
+    pub fn circuit_open(tool: &str, upstream: &str, retry_after: Duration) -> Self {
+        Self {
+            tool: tool.to_string(),
+            upstream: Some(upstream.to_string()),
+            reason: format!("{upstream} is failing repeatedly"),
+            retry_after_secs: Some(retry_after.as_secs().max(1)),
+        }
+    }
+
+    /// Every attempt of the call timed out
+    pub fn timed_out(tool: &str, upstream: Option<&str>, timeout: Duration, attempts: u32) -> Self {
+        Self {
+            tool: tool.to_string(),
+            upstream: upstream.map(str::to_string),
+            reason: format!(
+                "no response within {}s after {attempts} attempt(s)",
+                timeout.as_secs_f32()
+            ),
+            retry_after_secs: None,
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        json!({
+            "error": TOOL_UNAVAILABLE,
+            "tool": self.tool,
+            "upstream": self.upstream,
+            "reason": self.reason,
+            "retry_after_secs": self.retry_after_secs,
+            "message": format!(
+                "{} is temporarily unavailable ({}). Do not call it again right away; tell the user or continue without it.",
+                self.tool, self.reason
+            ),
+        })
+    }
+}
+
+/// Whether `value` is the structured result of an unavailable tool.
+pub fn is_unavailable(value: &Value) -> bool {
+    value.get("error").and_then(Value::as_str) == Some(TOOL_UNAVAILABLE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn backoff_doubles_up_to_cap() {
+        let retry = RetryPolicy::exponential(5, Duration::from_millis(100));
+        assert_eq!(retry.backoff(1), Duration::from_millis(100));
+        assert_eq!(retry.backoff(2), Duration::from_millis(200));
+        assert_eq!(retry.backoff(3), Duration::from_millis(400));
+        assert_eq!(retry.backoff(10), Duration::from_millis(800));
+        assert_eq!(retry.backoff(100), Duration::from_millis(800));
+    }
+
+    #[test]
+    fn breaker_opens_and_recovers() {
+        let start = Instant::now();
+        let mut breaker = CircuitBreaker::new(2, Duration::from_secs(10));
+        breaker.record_failure(start);
+        assert_eq!(breaker.state(start), BreakerState::Closed);
+        breaker.record_failure(start);
+        assert_eq!(breaker.state(start), BreakerState::Open);
+        assert!(breaker.check(start + Duration::from_secs(4)).is_err());
+
+        let later = start + Duration::from_secs(11);
+        assert_eq!(breaker.state(later), BreakerState::HalfOpen);
+        assert!(breaker.check(later).is_ok());
+        // Only one trial call at a time
+        assert!(breaker.check(later).is_err());
+
+        // A failed trial re-opens right away
+        breaker.record_failure(later);
+        assert_eq!(breaker.state(later), BreakerState::Open);
+
+        breaker.record_success();
+        assert_eq!(breaker.state(later), BreakerState::Closed);
+    }
+
+    #[test]
+    fn half_open_probe_is_released_or_expires() {
+        let start = Instant::now();
+        let mut breaker = CircuitBreaker::new(1, Duration::from_secs(10));
+        breaker.record_failure(start);
+
+        let later = start + Duration::from_secs(11);
+        assert!(breaker.check(later).is_ok());
+        breaker.release_probe();
+        assert!(breaker.check(later).is_ok());
+
+        // A trial that never reports back stops blocking after another cooldown
+        assert!(breaker.check(later + Duration::from_secs(5)).is_err());
+        assert!(breaker.check(later + Duration::from_secs(10)).is_ok());
+    }
+
+    #[test]
+    fn only_upstream_failures_count() {
+        let breakers = CircuitBreakers::new(1, Duration::from_secs(10));
+        let bad_input = Err(CallFailure::Failed(eyre::eyre!("400 Bad Request")));
+        breakers.record_result("brave", &bad_input);
+        assert_eq!(breakers.state("brave"), BreakerState::Closed);
+
+        let server_error = Err(CallFailure::Failed(eyre::Report::new(UpstreamFailure(
+            "Brave Search API error 503".to_string(),
+        ))));
+        breakers.record_result("brave", &server_error);
+        assert_eq!(breakers.state("brave"), BreakerState::Open);
+
+        let timed_out = Err(CallFailure::TimedOut(Duration::from_secs(1)));
+        breakers.record_result("etherscan", &timed_out);
+        assert_eq!(breakers.state("etherscan"), BreakerState::Open);
+
+        // Async tools report through their final stream result
+        breakers.record_stream_result("rpc", &Ok(json!({ "ok": true })));
+        assert_eq!(breakers.state("rpc"), BreakerState::Closed);
+        breakers.record_stream_result("rpc", &Err(eyre::eyre!("invalid block tag")));
+        assert_eq!(breakers.state("rpc"), BreakerState::Closed);
+        breakers.record_stream_result(
+            "rpc",
+            &Err(eyre::Report::new(UpstreamFailure(
+                "RPC error 502".to_string(),
+            ))),
+        );
+        assert_eq!(breakers.state("rpc"), BreakerState::Open);
+
+        let refused = std::io::Error::from(std::io::ErrorKind::ConnectionRefused);
+        assert!(is_upstream_error(
+            &eyre::Report::new(refused).wrap_err("fetch failed")
+        ));
+    }
+
+    #[tokio::test]
+    async fn retries_only_idempotent_calls() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let failing = || {
+            let calls = calls.clone();
+            async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err::<Value, _>(eyre::eyre!("boom"))
+            }
+        };
+
+        let retry = RetryPolicy::exponential(3, Duration::from_millis(1));
+        let policy = CallPolicy::default().with_retry(retry);
+        assert!(run_with_policy(&policy, failing).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        calls.store(0, Ordering::SeqCst);
+        let policy = policy.idempotent();
+        assert!(run_with_policy(&policy, failing).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn timed_out_attempts_are_retried() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let policy = CallPolicy::default()
+            .with_timeout(Duration::from_millis(20))
+            .with_retry(RetryPolicy::exponential(2, Duration::from_millis(1)))
+            .idempotent();
+
+        let result = run_with_policy(&policy, || {
+            let calls = calls.clone();
+            async move {
+                // Only the first attempt hangs
+                if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+                Ok(json!({ "ok": true }))
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), json!({ "ok": true }));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn unavailable_result_is_structured() {
+        let value = ToolUnavailable::circuit_open("brave_search", "brave", Duration::from_secs(12))
+            .to_value();
+        assert!(is_unavailable(&value));
+        assert_eq!(value["upstream"], "brave");
+        assert_eq!(value["retry_after_secs"], 12);
+        assert!(!is_unavailable(&json!({ "error": "boom" })));
+    }
+}
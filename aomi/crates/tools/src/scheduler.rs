@@ -1,15 +1,21 @@
 use crate::clients::{ExternalClients, init_external_clients};
+use crate::metrics::{ToolOutcome, metrics};
+use crate::resilience::{CircuitBreakers, is_unavailable};
 use crate::streams::{ToolCompletion, ToolReciever};
-use crate::types::ToolMetadata;
+use crate::types::{ResumableCall, ToolMetadata};
 use eyre::Result;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
-use tokio::sync::{Mutex, OnceCell};
-use tracing::warn;
+use tokio::sync::{Mutex, OnceCell, Semaphore, mpsc};
+use tracing::{info, warn};
 
 static SCHEDULER: OnceCell<Arc<ToolScheduler>> = OnceCell::const_new();
 
+/// Maximum number of sync tool calls a single session runs at once
+pub const MAX_CONCURRENT_SYNC_CALLS: usize = 4;
+
 // AnyApiTool trait + impl now live in types.rs for reuse
 
 /// Runtime handle that may or may not own its runtime
@@ -49,7 +55,6 @@ impl SchedulerRuntime {
         Ok(Self::Owned(rt))
     }
 
-    #[allow(dead_code)]
     fn handle(&self) -> &tokio::runtime::Handle {
         match self {
             Self::Borrowed(h) => h,
@@ -58,12 +63,49 @@ impl SchedulerRuntime {
     }
 }
 
+/// Re-launches a persisted async call for a specific tool type.
+trait ToolResumer: Send + Sync {
+    fn launch(&self, runtime: &tokio::runtime::Handle, call: ResumableCall)
+    -> Result<ToolReciever>;
+}
+
+struct TypedResumer<T: crate::AomiTool>(T);
+
+impl<T: crate::AomiTool> ToolResumer for TypedResumer<T> {
+    fn launch(
+        &self,
+        runtime: &tokio::runtime::Handle,
+        call: ResumableCall,
+    ) -> Result<ToolReciever> {
+        let args: T::Args = serde_json::from_value(call.args.clone())
+            .map_err(|e| eyre::eyre!("Invalid persisted args for {}: {}", T::NAME, e))?;
+        let (tx, rx) = mpsc::channel::<(Result<Value>, bool)>(100);
+        let tool = self.0.clone();
+        let ctx = call.ctx.clone();
+        let cursor = call.cursor.clone();
+
+        let task = runtime.spawn(async move {
+            tool.resume_async(tx, ctx, args, cursor).await;
+        });
+
+        Ok(ToolReciever::new_async(call.metadata.clone(), rx)
+            .with_task(task.abort_handle())
+            .with_resume(call))
+    }
+}
+
 /// Unified scheduler that manages session handlers and tool metadata
 pub struct ToolScheduler {
     /// Metadata about registered tools (namespace, description, async support)
     tool_metadata: Arc<RwLock<HashMap<String, ToolMetadata>>>,
+    /// Launchers for tools whose in-flight calls can be resumed, keyed by tool name
+    resumers: Arc<RwLock<HashMap<String, Arc<dyn ToolResumer>>>>,
     /// Session handlers - one per active session
     session_handlers: Arc<RwLock<HashMap<String, Arc<Mutex<ToolHandler>>>>>, // Alice with delta API KEY -> ToolHandler for Alice ->  tool sets allowed for alice
+    /// Per-session permits bounding concurrent sync tool calls
+    session_permits: Arc<RwLock<HashMap<String, Arc<Semaphore>>>>,
+    /// Circuit breakers shared by all sessions, keyed by upstream service
+    circuit_breakers: Arc<CircuitBreakers>,
     #[allow(dead_code)]
     runtime: Arc<SchedulerRuntime>,
 }
@@ -79,7 +121,10 @@ impl ToolScheduler {
 
         let scheduler = ToolScheduler {
             tool_metadata: Arc::new(RwLock::new(HashMap::new())),
+            resumers: Arc::new(RwLock::new(HashMap::new())),
             session_handlers: Arc::new(RwLock::new(HashMap::new())),
+            session_permits: Arc::new(RwLock::new(HashMap::new())),
+            circuit_breakers: Arc::new(CircuitBreakers::default()),
             runtime: Arc::new(runtime),
         };
 
@@ -97,6 +142,11 @@ impl ToolScheduler {
         Ok(scheduler.clone())
     }
 
+    /// The global scheduler, if it has been initialized
+    pub fn get() -> Option<Arc<ToolScheduler>> {
+        SCHEDULER.get().cloned()
+    }
+
     /// Helper to spawn an isolated scheduler on the current runtime without touching the global OnceCell.
     pub async fn new_for_test() -> Result<Arc<ToolScheduler>> {
         let runtime = SchedulerRuntime::new()?;
@@ -105,7 +155,10 @@ impl ToolScheduler {
 
         let scheduler = Arc::new(ToolScheduler {
             tool_metadata: Arc::new(RwLock::new(HashMap::new())),
+            resumers: Arc::new(RwLock::new(HashMap::new())),
             session_handlers: Arc::new(RwLock::new(HashMap::new())),
+            session_permits: Arc::new(RwLock::new(HashMap::new())),
+            circuit_breakers: Arc::new(CircuitBreakers::default()),
             runtime: Arc::new(runtime),
         });
 
@@ -123,6 +176,14 @@ impl ToolScheduler {
             .write()
             .map_err(|_| eyre::eyre!("Failed to acquire write lock"))?;
         metadata.insert(meta.name.clone(), meta);
+        drop(metadata);
+
+        if tool.is_async() && tool.is_resumable() {
+            self.resumers
+                .write()
+                .map_err(|_| eyre::eyre!("Failed to acquire write lock"))?
+                .insert(T::NAME.to_string(), Arc::new(TypedResumer(tool.clone())));
+        }
         Ok(())
     }
 
@@ -143,27 +204,51 @@ impl ToolScheduler {
 
         // Create new handler with filtered tool set
         let mut handler = ToolHandler::new(namespaces.clone());
-
-        // Filter tools by namespace and populate available_tools
-        let metadata_guard = self.tool_metadata.read().unwrap();
-
-        for (name, meta) in metadata_guard.iter() {
-            if namespaces.contains(&meta.namespace) {
-                handler.avaliable_tools.insert(name.clone(), meta.clone());
-            }
-        }
-
-        drop(metadata_guard);
+        handler.avaliable_tools = self.namespace_tools(&namespaces);
 
         let handler_arc = Arc::new(Mutex::new(handler));
         handlers.insert(session_id.clone(), Arc::clone(&handler_arc));
         handler_arc
     }
 
+    /// Registered tools that belong to one of `namespaces`
+    fn namespace_tools(&self, namespaces: &[String]) -> HashMap<String, ToolMetadata> {
+        self.tool_metadata
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, meta)| namespaces.contains(&meta.namespace))
+            .map(|(name, meta)| (name.clone(), meta.clone()))
+            .collect()
+    }
+
+    /// Permits bounding how many sync tool calls of a session run concurrently
+    pub fn session_permits(&self, session_id: &str) -> Arc<Semaphore> {
+        if let Some(permits) = self.session_permits.read().unwrap().get(session_id) {
+            return Arc::clone(permits);
+        }
+        let mut permits = self.session_permits.write().unwrap();
+        Arc::clone(
+            permits
+                .entry(session_id.to_string())
+                .or_insert_with(|| Arc::new(Semaphore::new(MAX_CONCURRENT_SYNC_CALLS))),
+        )
+    }
+
+    /// Circuit breakers for the upstream services tools declare in their call policy
+    pub fn circuit_breakers(&self) -> &CircuitBreakers {
+        &self.circuit_breakers
+    }
+
     /// Cleanup session when user logs off
     ///
-    /// Phase 5: Now persists incomplete calls before removing from memory
-    pub async fn cleanup_session(&self, session_id: &str) -> Result<Option<PersistedHandlerState>> {
+    /// Phase 5: Now persists incomplete calls before removing from memory.
+    /// Non-resumable calls get `timeout_secs` to finish before they are dropped.
+    pub async fn cleanup_session(
+        &self,
+        session_id: &str,
+        timeout_secs: u64,
+    ) -> Result<Option<PersistedHandlerState>> {
         // Persist incomplete calls if handler exists
         let handler = {
             self.session_handlers
@@ -175,13 +260,14 @@ impl ToolScheduler {
 
         let persisted_state = if let Some(handler) = handler {
             let mut guard = handler.lock().await;
-            Some(guard.sanitized_persist(3600).await?)
+            Some(guard.sanitized_persist(timeout_secs).await?)
         } else {
             None
         };
 
         // Remove from memory
         self.session_handlers.write().unwrap().remove(session_id);
+        self.session_permits.write().unwrap().remove(session_id);
         eprintln!("Cleaned up session: {}", session_id);
         Ok(persisted_state)
     }
@@ -212,13 +298,48 @@ impl ToolScheduler {
 
     /// Phase 5: Restore session handler from persisted state
     ///
-    /// Creates a new handler with the persisted state
+    /// Creates a new handler with the persisted state and re-launches any resumable
+    /// calls that were still in flight, so their callbacks reach this session.
     pub fn restore_session(
         &self,
         session_id: String,
-        state: PersistedHandlerState,
+        mut state: PersistedHandlerState,
     ) -> SessionToolHandler {
-        let handler = ToolHandler::from_persisted(state);
+        let pending_calls = std::mem::take(&mut state.pending_calls);
+        let mut handler = ToolHandler::from_persisted(state);
+
+        for mut call in pending_calls {
+            call.ctx.session_id = session_id.clone();
+            let metadata = call.metadata.clone();
+            let resumer = self.resumers.read().unwrap().get(&metadata.name).cloned();
+
+            let launched = match resumer {
+                Some(resumer) => resumer.launch(self.runtime.handle(), call),
+                None => Err(eyre::eyre!(
+                    "Tool {} is not registered as resumable",
+                    metadata.name
+                )),
+            };
+
+            match launched {
+                Ok(receiver) => {
+                    info!(session_id = %session_id, tool = %metadata.name, id = %metadata.id, "Resumed in-flight tool call");
+                    handler.register_receiver(receiver);
+                }
+                Err(e) => {
+                    warn!(session_id = %session_id, tool = %metadata.name, error = %e, "Failed to resume tool call");
+                    handler.completed_calls.push(ToolCompletion {
+                        metadata,
+                        result: Err(format!(
+                            "Tool call could not be resumed after restart: {}",
+                            e
+                        )),
+                        has_more: false,
+                    });
+                }
+            }
+        }
+
         let handler_arc = Arc::new(Mutex::new(handler));
         self.session_handlers
             .write()
@@ -227,6 +348,20 @@ impl ToolScheduler {
         eprintln!("Restored session: {}", session_id);
         handler_arc
     }
+
+    /// Restore `state` under `session_id` with the tool set of `namespaces` instead of the
+    /// persisted one. Used when a session switches backend, so its unconsumed results and
+    /// resumable calls carry over to the new tool set.
+    pub fn restore_session_with_namespaces(
+        &self,
+        session_id: String,
+        namespaces: Vec<String>,
+        mut state: PersistedHandlerState,
+    ) -> SessionToolHandler {
+        state.available_tools = self.namespace_tools(&namespaces);
+        state.namespaces = namespaces;
+        self.restore_session(session_id, state)
+    }
 }
 
 // ============================================================================
@@ -241,6 +376,9 @@ pub struct PersistedHandlerState {
     pub namespaces: Vec<String>,
     pub available_tools: HashMap<String, ToolMetadata>,
     pub completed_calls: Vec<ToolCompletion>,
+    /// In-flight resumable calls to re-launch on restore
+    #[serde(default)]
+    pub pending_calls: Vec<ResumableCall>,
 }
 
 /// Handler for managing tool execution lifecycle (2-phase: ongoing_calls → completed_calls)
@@ -291,6 +429,18 @@ impl ToolHandler {
 
             match receiver.poll_next(&mut cx) {
                 Poll::Ready(Some((metadata, result, has_more))) => {
+                    if !has_more || !is_async {
+                        let outcome = match &result {
+                            Ok(value) if is_unavailable(value) => ToolOutcome::Unavailable,
+                            Ok(_) => ToolOutcome::Ok,
+                            Err(_) => ToolOutcome::Error,
+                        };
+                        metrics().record_tool_call(
+                            &metadata.name,
+                            outcome,
+                            Some(receiver.elapsed()),
+                        );
+                    }
                     self.completed_calls.push(ToolCompletion {
                         metadata,
                         result,
@@ -361,20 +511,33 @@ impl ToolHandler {
         self.ongoing_calls.clear();
     }
 
+    /// Descriptors of ongoing calls that can be re-launched after a restart.
+    pub fn pending_resumable_calls(&self) -> Vec<ResumableCall> {
+        self.ongoing_calls
+            .iter()
+            .filter_map(|receiver| receiver.resumable().cloned())
+            .collect()
+    }
+
     /// Poll all pending calls to completion with timeout, then serialize state.
     ///
     /// This method:
-    /// 1. Polls all calls until completion or timeout
-    /// 2. On timeout, gracefully closes remaining calls
-    /// 3. Returns serialized state with only completed calls
+    /// 1. Polls non-resumable calls until completion or timeout
+    /// 2. Saves a descriptor for every resumable call still in flight
+    /// 3. Aborts the tasks behind remaining calls, so a restore doesn't run them twice
+    /// 4. Returns serialized state with completed calls and resumable descriptors
     pub async fn sanitized_persist(&mut self, timeout_secs: u64) -> Result<PersistedHandlerState> {
         use tokio::time::{Duration, timeout};
 
-        // Poll calls until completion or timeout
+        // Resumable calls don't need to finish here - they are re-launched on restore
         let poll_result = timeout(Duration::from_secs(timeout_secs), async {
             loop {
                 let completed_count = self.poll_once();
-                if completed_count == 0 && self.ongoing_calls.is_empty() {
+                let blocking = self
+                    .ongoing_calls
+                    .iter()
+                    .any(|receiver| receiver.resumable().is_none());
+                if completed_count == 0 && !blocking {
                     break;
                 }
                 // Small delay between polls
@@ -383,26 +546,31 @@ impl ToolHandler {
         })
         .await;
 
-        if poll_result.is_err() {
+        let pending_calls = self.pending_resumable_calls();
+        let dropped = self.ongoing_calls.len() - pending_calls.len();
+        if poll_result.is_err() && dropped > 0 {
             warn!(
-                "Persistence timeout after {} seconds with {} ongoing calls",
-                timeout_secs,
-                self.ongoing_calls.len()
+                "Persistence timeout after {} seconds, dropping {} non-resumable calls",
+                timeout_secs, dropped
             );
-            self.close_ongoing_calls();
         }
+        for receiver in self.ongoing_calls.iter_mut() {
+            receiver.abort();
+        }
+        self.close_ongoing_calls();
 
-        // Return state with completed calls only
         Ok(PersistedHandlerState {
             namespaces: self.namespaces.clone(),
             available_tools: self.avaliable_tools.clone(),
             completed_calls: self.completed_calls.clone(),
+            pending_calls,
         })
     }
 
     /// Restore handler from persisted state
     ///
-    /// Creates a new handler with the persisted completed calls and tool metadata
+    /// Creates a new handler with the persisted completed calls and tool metadata.
+    /// Pending calls are re-launched by `ToolScheduler::restore_session`.
     pub fn from_persisted(state: PersistedHandlerState) -> Self {
         Self {
             namespaces: state.namespaces,
@@ -418,6 +586,7 @@ impl ToolHandler {
             namespaces: self.namespaces.clone(),
             available_tools: self.avaliable_tools.clone(),
             completed_calls: self.completed_calls.clone(),
+            pending_calls: self.pending_resumable_calls(),
         }
     }
 }
@@ -1,11 +1,14 @@
-use crate::CallMetadata;
+use crate::types::RESUME_CURSOR_KEY;
+use crate::{CallMetadata, ResumableCall};
 use eyre::Result as EyreResult;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::fmt::{Debug, Display};
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, oneshot};
+use tokio::task::AbortHandle;
 
 type ToolResult = EyreResult<Value>;
 type AsyncToolResult = (ToolResult, bool);
@@ -50,6 +53,12 @@ pub struct ToolReciever {
     async_rx: Option<mpsc::Receiver<AsyncToolResult>>,
     /// Single-result tools use oneshot receiver
     oneshot_rx: Option<oneshot::Receiver<ToolResult>>,
+    /// Descriptor for re-launching this call after a restart (resumable async tools only)
+    resume: Option<ResumableCall>,
+    /// Handle to the background task producing results, so it can be stopped on persist
+    task: Option<AbortHandle>,
+    /// When the call was launched, for latency metrics
+    started_at: Instant,
 }
 
 impl ToolReciever {
@@ -59,6 +68,9 @@ impl ToolReciever {
             finished: false,
             async_rx: None,
             oneshot_rx: Some(single_rx),
+            resume: None,
+            task: None,
+            started_at: Instant::now(),
         }
     }
 
@@ -68,9 +80,36 @@ impl ToolReciever {
             finished: false,
             async_rx: Some(async_rx),
             oneshot_rx: None,
+            resume: None,
+            task: None,
+            started_at: Instant::now(),
         }
     }
 
+    /// Attach a resumable descriptor so the call survives persistence.
+    pub fn with_resume(mut self, call: ResumableCall) -> Self {
+        self.resume = Some(call);
+        self
+    }
+
+    /// Attach the handle of the task producing this call's results.
+    pub fn with_task(mut self, task: AbortHandle) -> Self {
+        self.task = Some(task);
+        self
+    }
+
+    /// Stop the background task, e.g. before its descriptor is persisted for re-launch.
+    pub fn abort(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+
+    /// Descriptor with the latest progress cursor, if this call is resumable.
+    pub fn resumable(&self) -> Option<&ResumableCall> {
+        self.resume.as_ref()
+    }
+
     pub fn metadata(&self) -> &CallMetadata {
         &self.metadata
     }
@@ -79,10 +118,20 @@ impl ToolReciever {
         self.async_rx.is_some()
     }
 
+    /// Time since the call was launched
+    pub fn elapsed(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
     pub fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<ToolStreamItem>> {
         if let Some(rx) = self.async_rx.as_mut() {
             match rx.poll_recv(cx) {
                 Poll::Ready(Some((result, has_more))) => {
+                    if let (Some(resume), Ok(value)) = (self.resume.as_mut(), result.as_ref())
+                        && let Some(cursor) = value.get(RESUME_CURSOR_KEY)
+                    {
+                        resume.cursor = Some(cursor.clone());
+                    }
                     let mapped = result.map_err(|e| e.to_string());
                     return Poll::Ready(Some((self.metadata.clone(), mapped, has_more)));
                 }
@@ -1,7 +1,7 @@
-use crate::CallMetadata;
-use crate::scheduler::ToolScheduler;
-use crate::streams::ToolReciever;
-use crate::test_utils::{MockAsyncTool, MockSingleTool};
+use crate::scheduler::{PersistedHandlerState, ToolScheduler};
+use crate::streams::{ToolCompletion, ToolReciever};
+use crate::test_utils::{MockAsyncTool, MockResumableTool, MockSingleTool};
+use crate::{AomiTool, CallMetadata, ResumableCall, ToolCallCtx};
 use serde_json::json;
 use tokio::sync::{mpsc, oneshot};
 
@@ -160,3 +160,304 @@ async fn test_close_ongoing_calls() {
     let send_result = tx.send((Ok(json!({ "after_close": true })), false)).await;
     assert!(send_result.is_err(), "send should fail after close");
 }
+
+/// Test that in-flight resumable calls are persisted and re-launched on restore
+#[tokio::test(flavor = "multi_thread")]
+async fn test_persist_and_resume_inflight_call() {
+    let scheduler = ToolScheduler::new_for_test().await.unwrap();
+    scheduler.register_tool(&MockResumableTool).unwrap();
+    let handler =
+        scheduler.get_session_handler("session_resume".to_string(), vec!["default".to_string()]);
+
+    let metadata = CallMetadata::new(
+        MockResumableTool::NAME.to_string(),
+        "default".to_string(),
+        "resume_1".to_string(),
+        Some("call_resume_1".to_string()),
+        true,
+    );
+    let ctx = ToolCallCtx {
+        session_id: "session_resume".to_string(),
+        metadata: metadata.clone(),
+        user_chain_id: None,
+        user_address: None,
+        policy_scope: Default::default(),
+    };
+    let args = json!({ "topic": "scan", "input": "logs" });
+
+    let (tx, rx) = mpsc::channel(4);
+    let tool_ctx = ctx.clone();
+    let task = tokio::spawn(async move {
+        let request = serde_json::from_value(json!({ "input": "logs" })).unwrap();
+        MockResumableTool.run_async(tx, tool_ctx, request).await;
+    });
+
+    let mut guard = handler.lock().await;
+    guard.register_receiver(
+        ToolReciever::new_async(metadata.clone(), rx)
+            .with_task(task.abort_handle())
+            .with_resume(ResumableCall {
+                metadata: metadata.clone(),
+                ctx,
+                args,
+                cursor: None,
+            }),
+    );
+
+    // Wait for the progress chunk carrying the cursor
+    for _ in 0..50 {
+        if guard.poll_once() > 0 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+
+    // Resumable calls don't block persistence; the latest cursor is captured
+    let state = guard.sanitized_persist(1).await.unwrap();
+    drop(guard);
+    assert_eq!(state.completed_calls.len(), 1);
+    assert_eq!(state.pending_calls.len(), 1);
+    assert_eq!(state.pending_calls[0].cursor, Some(json!({ "block": 100 })));
+    // The original task is stopped so the restore below is the only run
+    assert!(task.await.unwrap_err().is_cancelled());
+
+    // Round-trip through serialization as a restart would
+    let encoded = serde_json::to_string(&state).unwrap();
+    let decoded: PersistedHandlerState = serde_json::from_str(&encoded).unwrap();
+
+    let restored = scheduler.restore_session("session_restored".to_string(), decoded);
+    let mut results = Vec::new();
+    for _ in 0..50 {
+        let mut guard = restored.lock().await;
+        guard.poll_once();
+        results.extend(guard.take_completed_calls());
+        if results.len() >= 2 {
+            break;
+        }
+        drop(guard);
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+
+    let resumed = results
+        .iter()
+        .find(|c| !c.has_more)
+        .expect("resumed call should deliver a final result");
+    assert_eq!(resumed.metadata.call_id.as_deref(), Some("call_resume_1"));
+    let value = resumed.result.as_ref().unwrap();
+    assert_eq!(value["resumed_from"], json!({ "block": 100 }));
+    assert_eq!(value["session_id"], "session_restored");
+}
+
+/// Test that pending calls for unknown tools surface an error on restore
+#[tokio::test(flavor = "multi_thread")]
+async fn test_restore_unknown_resumable_tool_reports_error() {
+    let scheduler = ToolScheduler::new_for_test().await.unwrap();
+    let metadata = CallMetadata::new(
+        "missing_tool".to_string(),
+        "default".to_string(),
+        "missing_1".to_string(),
+        None,
+        true,
+    );
+    let state = PersistedHandlerState {
+        namespaces: vec!["default".to_string()],
+        available_tools: Default::default(),
+        completed_calls: Vec::new(),
+        pending_calls: vec![ResumableCall {
+            metadata: metadata.clone(),
+            ctx: ToolCallCtx {
+                session_id: "old".to_string(),
+                metadata,
+                user_chain_id: None,
+                user_address: None,
+                policy_scope: Default::default(),
+            },
+            args: json!({ "input": "x" }),
+            cursor: None,
+        }],
+    };
+
+    let handler = scheduler.restore_session("session_missing".to_string(), state);
+    let mut guard = handler.lock().await;
+    assert!(!guard.has_ongoing_calls());
+    let completed = guard.take_completed_calls();
+    assert_eq!(completed.len(), 1);
+    assert!(completed[0].result.is_err());
+}
+
+/// Test that a backend switch keeps unconsumed results but takes the new tool set
+#[tokio::test(flavor = "multi_thread")]
+async fn test_restore_with_namespaces_keeps_results() {
+    let scheduler = ToolScheduler::new_for_test().await.unwrap();
+    scheduler.register_tool(&MockSingleTool).unwrap();
+
+    let metadata = CallMetadata::new(
+        "finished_tool".to_string(),
+        "other".to_string(),
+        "finished_1".to_string(),
+        None,
+        false,
+    );
+    let state = PersistedHandlerState {
+        namespaces: vec!["other".to_string()],
+        available_tools: Default::default(),
+        completed_calls: vec![ToolCompletion {
+            metadata,
+            result: Ok(json!({ "done": true })),
+            has_more: false,
+        }],
+        pending_calls: Vec::new(),
+    };
+
+    let handler = scheduler.restore_session_with_namespaces(
+        "session_switched".to_string(),
+        vec!["default".to_string()],
+        state,
+    );
+    let mut guard = handler.lock().await;
+    assert_eq!(
+        guard.get_description("mock_single"),
+        "Mock single-result tool for testing"
+    );
+    let completed = guard.take_completed_calls();
+    assert_eq!(completed.len(), 1);
+    assert_eq!(completed[0].result, Ok(json!({ "done": true })));
+}
+
+/// Test that sync-call permits are shared within a session and bounded
+#[tokio::test(flavor = "multi_thread")]
+async fn test_session_permits_bound_concurrency() {
+    use crate::scheduler::MAX_CONCURRENT_SYNC_CALLS;
+    use std::sync::Arc;
+
+    let scheduler = ToolScheduler::new_for_test().await.unwrap();
+    let permits = scheduler.session_permits("session_permits");
+    assert!(Arc::ptr_eq(
+        &permits,
+        &scheduler.session_permits("session_permits")
+    ));
+    assert!(!Arc::ptr_eq(
+        &permits,
+        &scheduler.session_permits("other_session")
+    ));
+
+    let held: Vec<_> = (0..MAX_CONCURRENT_SYNC_CALLS)
+        .map(|_| permits.clone().try_acquire_owned().unwrap())
+        .collect();
+    assert!(permits.clone().try_acquire_owned().is_err());
+    drop(held);
+    assert_eq!(permits.available_permits(), MAX_CONCURRENT_SYNC_CALLS);
+
+    scheduler
+        .cleanup_session("session_permits", 0)
+        .await
+        .unwrap();
+    assert!(!Arc::ptr_eq(
+        &permits,
+        &scheduler.session_permits("session_permits")
+    ));
+}
+
+/// Test that the scheduler's breakers open per upstream after repeated failures
+#[tokio::test(flavor = "multi_thread")]
+async fn test_circuit_breakers_open_per_upstream() {
+    use crate::resilience::{BREAKER_FAILURE_THRESHOLD, BreakerState};
+
+    let scheduler = ToolScheduler::new_for_test().await.unwrap();
+    let breakers = scheduler.circuit_breakers();
+
+    for _ in 0..BREAKER_FAILURE_THRESHOLD - 1 {
+        breakers.record_failure("etherscan");
+    }
+    assert!(breakers.check("etherscan").is_ok());
+
+    // A success resets the streak
+    breakers.record_success("etherscan");
+    for _ in 0..BREAKER_FAILURE_THRESHOLD {
+        breakers.record_failure("etherscan");
+    }
+    assert_eq!(breakers.state("etherscan"), BreakerState::Open);
+    assert!(breakers.check("etherscan").is_err());
+    assert_eq!(breakers.state("brave"), BreakerState::Closed);
+    assert!(breakers.check("brave").is_ok());
+}
+
+/// Test that a hung sync tool yields a structured unavailable result after its retries
+#[tokio::test(flavor = "multi_thread")]
+async fn test_wrapper_times_out_hung_tool() {
+    use crate::resilience::is_unavailable;
+    use crate::test_utils::MockHangingTool;
+    use crate::{AomiToolWrapper, RuntimeEnvelope};
+    use rig::tool::Tool;
+    use std::time::{Duration, Instant};
+
+    let wrapper = AomiToolWrapper::new(MockHangingTool);
+    let ctx = ToolCallCtx {
+        session_id: "session_timeout".to_string(),
+        metadata: CallMetadata::new(
+            MockHangingTool::NAME.to_string(),
+            "default".to_string(),
+            "hang_1".to_string(),
+            None,
+            false,
+        ),
+        user_chain_id: None,
+        user_address: None,
+        policy_scope: Default::default(),
+    };
+
+    let started = Instant::now();
+    let result = wrapper
+        .call(RuntimeEnvelope {
+            ctx,
+            args: json!({ "topic": "hang", "input": "x" }),
+        })
+        .await
+        .unwrap();
+
+    // Two 50ms attempts plus backoff, far below the tool's own 30s sleep
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert!(is_unavailable(&result));
+    assert_eq!(result["tool"], "mock_hanging");
+    assert_eq!(result["upstream"], "mock_upstream");
+}
+
+/// Test that final results polled by the handler are counted per tool and outcome
+#[tokio::test(flavor = "multi_thread")]
+async fn test_handler_records_tool_metrics() {
+    use crate::metrics::{ToolOutcome, metrics};
+
+    let scheduler = ToolScheduler::new_for_test().await.unwrap();
+    let handler =
+        scheduler.get_session_handler("session_metrics".to_string(), vec!["default".to_string()]);
+    let metadata = CallMetadata::new(
+        "mock_metrics_async".to_string(),
+        "default".to_string(),
+        "metrics_1".to_string(),
+        None,
+        true,
+    );
+
+    let (tx, rx) = mpsc::channel(10);
+    tx.send((Ok(json!({ "step": 1 })), true)).await.unwrap();
+    tx.send((Err(eyre::eyre!("upstream failed")), false))
+        .await
+        .unwrap();
+    drop(tx);
+
+    let mut guard = handler.lock().await;
+    guard.register_receiver(ToolReciever::new_async(metadata, rx));
+    while guard.has_ongoing_calls() {
+        guard.poll_once();
+    }
+
+    // Only the final chunk counts as a finished call
+    assert_eq!(
+        metrics().tool_calls("mock_metrics_async", ToolOutcome::Error),
+        1
+    );
+    assert_eq!(
+        metrics().tool_calls("mock_metrics_async", ToolOutcome::Ok),
+        0
+    );
+}
@@ -1,4 +1,6 @@
-use crate::{AomiTool, AomiToolArgs, ToolCallCtx, ToolScheduler, with_topic};
+use crate::{
+    AomiTool, AomiToolArgs, CallPolicy, RetryPolicy, ToolCallCtx, ToolScheduler, with_topic,
+};
 use rig::tool::ToolError;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
@@ -107,6 +109,44 @@ impl AomiTool for MockSlowSingleTool {
     }
 }
 
+// ============================================================================
+// MockHangingTool - Never answers within its call policy's timeout
+// ============================================================================
+
+#[derive(Debug, Clone)]
+pub struct MockHangingTool;
+
+impl AomiTool for MockHangingTool {
+    const NAME: &'static str = "mock_hanging";
+
+    type Args = MockToolParameters;
+    type Output = Value;
+    type Error = ToolError;
+
+    fn description(&self) -> &'static str {
+        "Mock tool whose upstream never responds"
+    }
+
+    fn call_policy(&self) -> CallPolicy {
+        CallPolicy::default()
+            .with_timeout(Duration::from_millis(50))
+            .with_retry(RetryPolicy::exponential(2, Duration::from_millis(10)))
+            .idempotent()
+            .with_upstream("mock_upstream")
+    }
+
+    fn run_sync(
+        &self,
+        _ctx: ToolCallCtx,
+        _args: Self::Args,
+    ) -> impl std::future::Future<Output = eyre::Result<Value>> + Send {
+        async move {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            Ok(json!({ "result": "too late" }))
+        }
+    }
+}
+
 // ============================================================================
 // MockErrorTool - Tool that returns an error
 // ============================================================================
@@ -212,6 +252,64 @@ impl AomiTool for MockAsyncTool {
     }
 }
 
+// ============================================================================
+// MockResumableTool - Reports a cursor, then stalls until resumed
+// ============================================================================
+
+#[derive(Debug, Clone)]
+pub struct MockResumableTool;
+
+impl AomiTool for MockResumableTool {
+    const NAME: &'static str = "mock_resumable";
+
+    type Args = MockToolParameters;
+    type Output = Value;
+    type Error = ToolError;
+
+    fn support_async(&self) -> bool {
+        true
+    }
+
+    fn is_resumable(&self) -> bool {
+        true
+    }
+
+    fn description(&self) -> &'static str {
+        "Mock long-running tool that can be resumed from a cursor"
+    }
+
+    fn run_async(
+        &self,
+        sender: Sender<(eyre::Result<Value>, bool)>,
+        _ctx: ToolCallCtx,
+        request: Self::Args,
+    ) -> impl std::future::Future<Output = ()> + Send {
+        async move {
+            let progress = json!({ "input": request.input, "resume_cursor": { "block": 100 } });
+            let _ = sender.send((Ok(progress), true)).await;
+            // Never finishes on its own; only a resume completes the call
+            sender.closed().await;
+        }
+    }
+
+    fn resume_async(
+        &self,
+        sender: Sender<(eyre::Result<Value>, bool)>,
+        ctx: ToolCallCtx,
+        request: Self::Args,
+        cursor: Option<Value>,
+    ) -> impl std::future::Future<Output = ()> + Send {
+        async move {
+            let result = json!({
+                "input": request.input,
+                "session_id": ctx.session_id,
+                "resumed_from": cursor,
+            });
+            let _ = sender.send((Ok(result), false)).await;
+        }
+    }
+}
+
 // ============================================================================
 // Registration helpers
 // ============================================================================
@@ -6,6 +6,9 @@ use std::future::Future;
 use std::hash::{Hash, Hasher};
 use tokio::sync::mpsc;
 
+use crate::ethereum::policy::PolicyScope;
+use crate::resilience::CallPolicy;
+
 /// Metadata about a tool call.
 #[derive(Debug, Clone, Default, Serialize, Deserialize, Eq)]
 pub struct CallMetadata {
@@ -74,6 +77,15 @@ pub struct ToolMetadata {
     pub description: String,
     /// Whether this tool supports async execution
     pub is_async: bool,
+    /// Whether calls must be approved by the user before running
+    #[serde(default)]
+    pub requires_approval: bool,
+    /// Whether calls have no side effects and may run alongside other read-only calls
+    #[serde(default)]
+    pub read_only: bool,
+    /// Timeout, retry and upstream settings enforced on every call
+    #[serde(default)]
+    pub call_policy: CallPolicy,
 }
 
 impl ToolMetadata {
@@ -83,8 +95,26 @@ impl ToolMetadata {
             namespace,
             description,
             is_async,
+            requires_approval: false,
+            read_only: false,
+            call_policy: CallPolicy::default(),
         }
     }
+
+    pub fn with_approval(mut self, requires_approval: bool) -> Self {
+        self.requires_approval = requires_approval;
+        self
+    }
+
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    pub fn with_call_policy(mut self, call_policy: CallPolicy) -> Self {
+        self.call_policy = call_policy;
+        self
+    }
 }
 
 /// Runtime context for a tool call.
@@ -96,6 +126,26 @@ pub struct ToolCallCtx {
     pub user_chain_id: Option<u64>,
     /// Address from user's connected wallet (None if not connected)
     pub user_address: Option<String>,
+    /// Session namespace and API key id used to select transaction policies
+    #[serde(default)]
+    pub policy_scope: PolicyScope,
+}
+
+/// Key an async tool can include in an intermediate result to record how far it got.
+/// The latest value is persisted with the call and handed back on resume.
+pub const RESUME_CURSOR_KEY: &str = "resume_cursor";
+
+/// Descriptor of an in-flight async call that can be re-launched after a restart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResumableCall {
+    /// Tool name, call id and LLM call_id used to route the eventual callback
+    pub metadata: CallMetadata,
+    /// Context the call was originally launched with
+    pub ctx: ToolCallCtx,
+    /// Raw tool arguments as sent by the LLM
+    pub args: Value,
+    /// Last progress cursor reported by the tool
+    pub cursor: Option<Value>,
 }
 
 /// Envelope passed to tools from the completion layer.
@@ -285,6 +335,25 @@ pub trait AomiTool: Send + Sync + Clone + 'static {
         self.support_async()
     }
 
+    /// Whether each call must be confirmed by the user before it runs.
+    /// Flag tools with side effects the user should see first (orders, fills, deletions).
+    fn requires_approval(&self) -> bool {
+        false
+    }
+
+    /// Whether calls only read state, so the LLM's calls to it in one turn may run
+    /// concurrently. Calls to other tools run one at a time, in the order requested.
+    fn read_only(&self) -> bool {
+        false
+    }
+
+    /// Timeout, retries and upstream circuit breaker applied to each call.
+    /// Tools backed by remote services should bound their calls and name the upstream;
+    /// retries only happen for tools marked idempotent.
+    fn call_policy(&self) -> CallPolicy {
+        CallPolicy::default()
+    }
+
     /// Get tool description for LLM (displayed in tool definition)
     fn description(&self) -> &'static str;
 
@@ -301,6 +370,9 @@ pub trait AomiTool: Send + Sync + Clone + 'static {
             self.description().to_string(),
             self.support_async(),
         )
+        .with_approval(self.requires_approval())
+        .with_read_only(self.read_only())
+        .with_call_policy(self.call_policy())
     }
 
     /// Execute synchronously - returns a single result directly.
@@ -345,6 +417,26 @@ pub trait AomiTool: Send + Sync + Clone + 'static {
             // Async tools must override this
         }
     }
+
+    /// Whether an in-flight async call can be persisted and re-launched after a restart.
+    /// Resumable tools should tolerate being run again from their last cursor.
+    fn is_resumable(&self) -> bool {
+        false
+    }
+
+    /// Re-launch a persisted async call.
+    ///
+    /// `cursor` is the last `resume_cursor` the tool reported before the restart.
+    /// Default implementation ignores the cursor and runs the call from the start.
+    fn resume_async(
+        &self,
+        results_sender: mpsc::Sender<(EyreResult<Value>, bool)>,
+        ctx: ToolCallCtx,
+        args: Self::Args,
+        _cursor: Option<Value>,
+    ) -> impl Future<Output = ()> + Send {
+        self.run_async(results_sender, ctx, args)
+    }
 }
 
 /// Format tool name for display.
@@ -0,0 +1,77 @@
+//! Persistent usage metering for LLM tokens and tool invocations.
+//!
+//! The completion loop reports usage through [`record_usage`]. Events are queued and written
+//! to the `usage_events` table by a background task once the backend has called
+//! [`install_usage_store`]; processes without a store (CLI, tests) drop them.
+
+use crate::db::{UsageEvent, UsageKind, UsageStoreApi};
+use crate::ethereum::PolicyScope;
+use std::sync::{Arc, OnceLock};
+use tokio::sync::mpsc;
+
+static USAGE_SINK: OnceLock<mpsc::UnboundedSender<UsageEvent>> = OnceLock::new();
+
+/// Starts writing recorded usage to `store`. Returns false if a store was already installed.
+pub fn install_usage_store(store: Arc<dyn UsageStoreApi>) -> bool {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<UsageEvent>();
+    if USAGE_SINK.set(sender).is_err() {
+        return false;
+    }
+    tokio::spawn(async move {
+        while let Some(event) = receiver.recv().await {
+            if let Err(e) = store.record_usage(&event).await {
+                tracing::warn!(
+                    session_id = %event.session_id,
+                    kind = event.kind.as_str(),
+                    "Failed to record usage: {e}"
+                );
+            }
+        }
+    });
+    true
+}
+
+/// Queues a usage event for persistence (no-op without an installed store).
+pub fn record_usage(event: UsageEvent) {
+    if let Some(sink) = USAGE_SINK.get() {
+        let _ = sink.send(event);
+    }
+}
+
+impl UsageEvent {
+    fn scoped(session_id: &str, scope: &PolicyScope, kind: UsageKind) -> Self {
+        Self {
+            api_key: scope.api_key.clone(),
+            public_key: scope.public_key.clone(),
+            session_id: session_id.to_string(),
+            namespace: scope.namespace.clone(),
+            kind,
+            tool_name: None,
+            input_tokens: 0,
+            output_tokens: 0,
+            created_at: chrono::Utc::now().timestamp(),
+        }
+    }
+
+    /// Token usage of one LLM completion
+    pub fn llm(
+        session_id: &str,
+        scope: &PolicyScope,
+        input_tokens: u64,
+        output_tokens: u64,
+    ) -> Self {
+        Self {
+            input_tokens: i64::try_from(input_tokens).unwrap_or(i64::MAX),
+            output_tokens: i64::try_from(output_tokens).unwrap_or(i64::MAX),
+            ..Self::scoped(session_id, scope, UsageKind::Llm)
+        }
+    }
+
+    /// One invocation of `tool`
+    pub fn tool(session_id: &str, scope: &PolicyScope, tool: &str) -> Self {
+        Self {
+            tool_name: Some(tool.to_string()),
+            ..Self::scoped(session_id, scope, UsageKind::Tool)
+        }
+    }
+}
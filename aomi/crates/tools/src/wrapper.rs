@@ -1,11 +1,24 @@
+use crate::metrics::{ToolOutcome, metrics};
+use crate::resilience::{CallFailure, ToolUnavailable, run_with_policy};
 use crate::scheduler::ToolScheduler;
 use crate::streams::ToolReciever;
-use crate::{AomiTool, CallMetadata, RuntimeEnvelope, ToolCallCtx};
+use crate::{AomiTool, CallMetadata, ResumableCall, RuntimeEnvelope, ToolCallCtx};
 use eyre::Result as EyreResult;
 use rig::completion::ToolDefinition;
 use rig::tool::{Tool, ToolError};
 use serde_json::{Value, json};
+use std::time::Instant;
 use tokio::sync::mpsc;
+use tracing::Instrument;
+
+/// Aborts a spawned tool task when the caller stops waiting for it (e.g. on interrupt).
+struct AbortOnDrop(tokio::task::AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
 
 #[derive(Clone)]
 pub struct AomiToolWrapper<T: AomiTool> {
@@ -21,7 +34,8 @@ impl<T: AomiTool> AomiToolWrapper<T> {
 impl<T: AomiTool> Tool for AomiToolWrapper<T> {
     const NAME: &'static str = T::NAME;
 
-    type Args = RuntimeEnvelope<T::Args>;
+    // Raw args are kept so resumable calls can be persisted verbatim
+    type Args = RuntimeEnvelope<Value>;
     type Output = Value;
     type Error = ToolError;
 
@@ -37,8 +51,10 @@ impl<T: AomiTool> Tool for AomiToolWrapper<T> {
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
         let RuntimeEnvelope {
             ctx,
-            args: tool_args,
+            args: raw_args,
         } = args;
+        let tool_args: T::Args =
+            serde_json::from_value(raw_args.clone()).map_err(ToolError::JsonError)?;
         let session_id = ctx.session_id.clone();
         let metadata = CallMetadata::new(
             T::NAME.to_string(),
@@ -52,39 +68,177 @@ impl<T: AomiTool> Tool for AomiToolWrapper<T> {
             metadata: metadata.clone(),
             user_chain_id: ctx.user_chain_id,
             user_address: ctx.user_address.clone(),
+            policy_scope: ctx.policy_scope.clone(),
         };
 
+        let policy = self.inner.call_policy();
+        let upstream = policy.upstream.clone();
+
         if metadata.is_async {
             // Async tools: register receiver for polling, return immediate ACK
             let scheduler = ToolScheduler::get_or_init()
                 .await
                 .map_err(|e| ToolError::ToolCallError(e.to_string().into()))?;
+            if let Some(upstream) = upstream.as_deref()
+                && let Err(retry_after) = scheduler.circuit_breakers().check(upstream)
+            {
+                metrics().record_tool_call(T::NAME, ToolOutcome::Unavailable, None);
+                return Ok(
+                    ToolUnavailable::circuit_open(T::NAME, upstream, retry_after).to_value(),
+                );
+            }
             let handler = scheduler.get_session_handler(session_id, vec![T::NAMESPACE.to_string()]);
 
             let (tx, rx) = mpsc::channel::<(EyreResult<Value>, bool)>(100);
             let tool = self.inner.clone();
-            let ctx = ctx.clone();
+            let task_ctx = ctx.clone();
+            let task_scheduler = scheduler.clone();
 
-            tokio::spawn(async move {
-                tool.run_async(tx, ctx, tool_args).await;
-            });
+            // The background task keeps reporting under the caller's tool call span
+            let span = tracing::Span::current();
+            let task = tokio::spawn(
+                async move {
+                    // Results pass through here so the final one can be recorded for the breaker
+                    let (run_tx, mut run_rx) = mpsc::channel::<(EyreResult<Value>, bool)>(100);
+                    let timeout = policy.timeout();
+                    let run = async {
+                        match timeout {
+                            Some(timeout) => tokio::time::timeout(
+                                timeout,
+                                tool.run_async(run_tx, task_ctx, tool_args),
+                            )
+                            .await
+                            .is_err(),
+                            None => {
+                                tool.run_async(run_tx, task_ctx, tool_args).await;
+                                false
+                            }
+                        }
+                    };
+                    let forward = async {
+                        let mut finished = false;
+                        while let Some((result, has_more)) = run_rx.recv().await {
+                            if !has_more {
+                                finished = true;
+                                if let Some(upstream) = upstream.as_deref() {
+                                    task_scheduler
+                                        .circuit_breakers()
+                                        .record_stream_result(upstream, &result);
+                                }
+                            }
+                            let _ = tx.send((result, has_more)).await;
+                        }
+                        finished
+                    };
+                    let (timed_out, finished) = tokio::join!(run, forward);
 
-            handler
-                .lock()
-                .await
-                .register_receiver(ToolReciever::new_async(metadata.clone(), rx));
+                    if timed_out && let Some(timeout) = timeout {
+                        // Close the stream with a structured result instead of leaving it hanging
+                        let unavailable =
+                            ToolUnavailable::timed_out(T::NAME, upstream.as_deref(), timeout, 1);
+                        let _ = tx.send((Ok(unavailable.to_value()), false)).await;
+                    }
+                    if let Some(upstream) = upstream.as_deref() {
+                        let breakers = task_scheduler.circuit_breakers();
+                        if timed_out {
+                            breakers.record_failure(upstream);
+                        } else if !finished {
+                            breakers.release_probe(upstream);
+                        }
+                    }
+                }
+                .instrument(span),
+            );
+
+            let mut receiver =
+                ToolReciever::new_async(metadata.clone(), rx).with_task(task.abort_handle());
+            if self.inner.is_resumable() {
+                receiver = receiver.with_resume(ResumableCall {
+                    metadata: metadata.clone(),
+                    ctx,
+                    args: raw_args,
+                    cursor: None,
+                });
+            }
+            handler.lock().await.register_receiver(receiver);
 
             Ok(json!({
                 "status": "queued",
                 "id": metadata.id,
             }))
         } else {
-            // Sync tools: spawn to avoid Sync requirement on future, then await
-            let tool = self.inner.clone();
-            tokio::spawn(async move { tool.run_sync(ctx, tool_args).await })
-                .await
-                .map_err(|e| ToolError::ToolCallError(e.to_string().into()))?
-                .map_err(|e| ToolError::ToolCallError(e.to_string().into()))
+            let scheduler = ToolScheduler::get();
+            if let Some(scheduler) = &scheduler
+                && let Some(upstream) = upstream.as_deref()
+                && let Err(retry_after) = scheduler.circuit_breakers().check(upstream)
+            {
+                metrics().record_tool_call(T::NAME, ToolOutcome::Unavailable, None);
+                return Ok(
+                    ToolUnavailable::circuit_open(T::NAME, upstream, retry_after).to_value(),
+                );
+            }
+
+            // Sync tools share the session's concurrency budget once the scheduler is up
+            let _permit = match &scheduler {
+                Some(scheduler) => Some(
+                    scheduler
+                        .session_permits(&session_id)
+                        .acquire_owned()
+                        .await
+                        .map_err(|e| ToolError::ToolCallError(e.to_string().into()))?,
+                ),
+                None => None,
+            };
+
+            // Args are re-parsed for retries since tool args need not be Clone
+            let started_at = Instant::now();
+            let mut first_args = Some(tool_args);
+            let result = run_with_policy(&policy, || {
+                let tool = self.inner.clone();
+                let ctx = ctx.clone();
+                let args = match first_args.take() {
+                    Some(args) => Ok(args),
+                    None => serde_json::from_value::<T::Args>(raw_args.clone()),
+                };
+                async move {
+                    let args = args?;
+                    // Sync tools: spawn to avoid Sync requirement on future, then await
+                    let task = tokio::spawn(
+                        async move { tool.run_sync(ctx, args).await }
+                            .instrument(tracing::Span::current()),
+                    );
+                    let _abort = AbortOnDrop(task.abort_handle());
+                    task.await?
+                }
+            })
+            .await;
+
+            if let Some(scheduler) = &scheduler
+                && let Some(upstream) = upstream.as_deref()
+            {
+                scheduler
+                    .circuit_breakers()
+                    .record_result(upstream, &result);
+            }
+
+            let outcome = match &result {
+                Ok(_) => ToolOutcome::Ok,
+                Err(CallFailure::TimedOut(_)) => ToolOutcome::Unavailable,
+                Err(CallFailure::Failed(_)) => ToolOutcome::Error,
+            };
+            metrics().record_tool_call(T::NAME, outcome, Some(started_at.elapsed()));
+
+            match result {
+                Ok(value) => Ok(value),
+                Err(CallFailure::TimedOut(timeout)) => Ok(ToolUnavailable::timed_out(
+                    T::NAME,
+                    upstream.as_deref(),
+                    timeout,
+                    policy.max_attempts(),
+                )
+                .to_value()),
+                Err(CallFailure::Failed(e)) => Err(ToolError::ToolCallError(e.to_string().into())),
+            }
         }
     }
 }
@@ -37,6 +37,30 @@ CREATE INDEX IF NOT EXISTS idx_contracts_protocol ON contracts(protocol) WHERE p
 CREATE INDEX IF NOT EXISTS idx_contracts_type ON contracts(contract_type) WHERE contract_type IS NOT NULL;
 CREATE INDEX IF NOT EXISTS idx_contracts_version ON contracts(version) WHERE version IS NOT NULL;
 
+-- On-chain proxy layouts (NULL layout = not a proxy when checked)
+CREATE TABLE IF NOT EXISTS contract_proxies (
+    chain_id INTEGER NOT NULL,
+    address TEXT NOT NULL,
+    layout TEXT,
+    checked_at BIGINT NOT NULL,
+    PRIMARY KEY (chain_id, address)
+);
+
+-- Token registry (symbol -> address/decimals per chain)
+CREATE TABLE IF NOT EXISTS tokens (
+    chain_id INTEGER NOT NULL,
+    address TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    name TEXT,
+    decimals INTEGER,
+    source TEXT NOT NULL,
+    created_at BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
+    updated_at BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
+    PRIMARY KEY (chain_id, address)
+);
+
+CREATE INDEX IF NOT EXISTS idx_tokens_symbol ON tokens(LOWER(symbol));
+
 -- Transaction fetch tracking per address
 CREATE TABLE IF NOT EXISTS transaction_records (
     chain_id INTEGER NOT NULL,
@@ -73,6 +97,17 @@ CREATE INDEX IF NOT EXISTS idx_tx_chain_address_block ON transactions(chain_id,
 CREATE INDEX IF NOT EXISTS idx_tx_hash ON transactions(hash);
 CREATE INDEX IF NOT EXISTS idx_tx_timestamp ON transactions(chain_id, address, timestamp DESC);
 
+-- Cached name service lookups (NULL value = nothing found)
+CREATE TABLE IF NOT EXISTS name_records (
+    service TEXT NOT NULL,
+    lookup TEXT NOT NULL,
+    value TEXT,
+    expires_at BIGINT NOT NULL,
+    PRIMARY KEY (service, lookup)
+);
+
+CREATE INDEX IF NOT EXISTS idx_name_records_expires ON name_records(expires_at);
+
 -- ============================================================================
 -- USERS & SESSIONS (Application state)
 -- ============================================================================
@@ -95,11 +130,16 @@ CREATE TABLE IF NOT EXISTS sessions (
     last_active_at BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
     title TEXT,
     pending_transaction JSONB,
-    messages_persisted BOOLEAN NOT NULL DEFAULT FALSE
+    messages_persisted BOOLEAN NOT NULL DEFAULT FALSE,
+    context_summary TEXT,
+    tool_state TEXT,
+    parent_session_id TEXT REFERENCES sessions(id) ON DELETE SET NULL,
+    fork_message_index BIGINT
 );
 
 CREATE INDEX IF NOT EXISTS idx_sessions_public_key ON sessions(public_key);
 CREATE INDEX IF NOT EXISTS idx_sessions_last_active ON sessions(last_active_at DESC);
+CREATE INDEX IF NOT EXISTS idx_sessions_parent ON sessions(parent_session_id);
 
 -- Chat and agent messages
 CREATE TABLE IF NOT EXISTS messages (
@@ -112,6 +152,16 @@ CREATE TABLE IF NOT EXISTS messages (
 );
 
 CREATE INDEX IF NOT EXISTS idx_messages_session_type ON messages(session_id, message_type, timestamp ASC);
+CREATE INDEX IF NOT EXISTS idx_messages_fts ON messages
+    USING GIN (to_tsvector('english', content->>'text'))
+    WHERE message_type = 'chat';
+
+-- Message embeddings (JSON float arrays), filled lazily by semantic search
+CREATE TABLE IF NOT EXISTS message_embeddings (
+    message_id BIGINT PRIMARY KEY REFERENCES messages(id) ON DELETE CASCADE,
+    embedding TEXT NOT NULL,
+    created_at BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT
+);
 
 -- ============================================================================
 -- AUTH & ACCESS CONTROL
@@ -132,6 +182,34 @@ CREATE INDEX IF NOT EXISTS idx_api_keys_active ON api_keys(is_active);
 CREATE INDEX IF NOT EXISTS idx_api_keys_api_key ON api_keys(api_key);
 CREATE INDEX IF NOT EXISTS idx_api_keys_namespace ON api_keys(namespace);
 
+-- Daily/monthly usage quotas per API key (NULL limit = unlimited)
+CREATE TABLE IF NOT EXISTS api_key_quotas (
+    api_key TEXT PRIMARY KEY,
+    daily_token_limit BIGINT,
+    monthly_token_limit BIGINT,
+    daily_tool_call_limit BIGINT,
+    monthly_tool_call_limit BIGINT,
+    updated_at BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT
+);
+
+-- Metered LLM tokens and tool invocations
+CREATE TABLE IF NOT EXISTS usage_events (
+    id BIGSERIAL PRIMARY KEY,
+    api_key TEXT,
+    public_key TEXT,
+    session_id TEXT NOT NULL,
+    namespace TEXT,
+    kind TEXT NOT NULL,
+    tool_name TEXT,
+    input_tokens BIGINT NOT NULL DEFAULT 0,
+    output_tokens BIGINT NOT NULL DEFAULT 0,
+    created_at BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT
+);
+
+CREATE INDEX IF NOT EXISTS idx_usage_events_api_key ON usage_events(api_key, created_at) WHERE api_key IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_usage_events_public_key ON usage_events(public_key, created_at) WHERE public_key IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_usage_events_session ON usage_events(session_id, created_at);
+
 -- Wallet signature challenges for session binding
 CREATE TABLE IF NOT EXISTS signup_challenges (
     session_id VARCHAR(255) PRIMARY KEY REFERENCES sessions(id) ON DELETE CASCADE,